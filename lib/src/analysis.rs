@@ -0,0 +1,497 @@
+use std::collections::VecDeque;
+
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+use crate::streaming::ReferenceModel;
+
+/// Axis-aligned bounding box in canvas coordinates, inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoundingBox {
+    pub min_x: usize,
+    pub min_y: usize,
+    pub max_x: usize,
+    pub max_y: usize,
+}
+
+impl BoundingBox {
+    fn around(pixels: &[(usize, usize)]) -> Option<Self> {
+        let (&(first_y, first_x), rest) = pixels.split_first()?;
+        let mut bounds = Self {
+            min_x: first_x,
+            min_y: first_y,
+            max_x: first_x,
+            max_y: first_y,
+        };
+        for &(y, x) in rest {
+            bounds.min_x = bounds.min_x.min(x);
+            bounds.min_y = bounds.min_y.min(y);
+            bounds.max_x = bounds.max_x.max(x);
+            bounds.max_y = bounds.max_y.max(y);
+        }
+        Some(bounds)
+    }
+}
+
+/// One eight-connected blob of reference pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComponentInfo {
+    pub pixel_count: u64,
+    pub bounding_box: BoundingBox,
+}
+
+/// Difficulty classification derived from the analysis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+/// Structural statistics of a reference image, used to classify exercise
+/// difficulty and pick sensible tolerance defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReferenceAnalysis {
+    pub pixel_count: u64,
+    pub components: Vec<ComponentInfo>,
+    /// Bounding box of all content, `None` for a blank mask.
+    pub bounding_box: Option<BoundingBox>,
+    /// Centre of mass of the content as `(x, y)` canvas coordinates,
+    /// `None` for a blank mask.
+    #[serde(default)]
+    pub centroid: Option<(f64, f64)>,
+    /// Approximate total stroke length: the pixel count of the thinned
+    /// skeleton.
+    pub stroke_length_estimate: f64,
+    /// Mean turning angle (radians) at skeleton pixels; 0 for straight
+    /// strokes.
+    pub mean_curvature: f64,
+    pub max_curvature: f64,
+    pub difficulty: Difficulty,
+    pub suggested_tolerance: i32,
+}
+
+impl ReferenceAnalysis {
+    /// Analyzes an extracted reference mask (1 = stroke, 0 = background).
+    pub fn analyze(pixels: &Array2<u8>) -> Self {
+        let pixel_count = pixels.iter().filter(|&&p| p != 0).count() as u64;
+        let components = connected_components(pixels);
+        let all_pixels: Vec<(usize, usize)> = pixels
+            .indexed_iter()
+            .filter(|(_, &on)| on != 0)
+            .map(|(pos, _)| pos)
+            .collect();
+        let bounding_box = BoundingBox::around(&all_pixels);
+        let centroid = (!all_pixels.is_empty()).then(|| {
+            let count = all_pixels.len() as f64;
+            let (sum_y, sum_x) = all_pixels
+                .iter()
+                .fold((0.0, 0.0), |(sy, sx), &(y, x)| (sy + y as f64, sx + x as f64));
+            (sum_x / count, sum_y / count)
+        });
+
+        let skeleton = skeletonize(pixels);
+        let stroke_length_estimate = skeleton.iter().filter(|&&p| p != 0).count() as f64;
+        let (mean_curvature, max_curvature) = curvature_stats(&skeleton);
+
+        let difficulty = if stroke_length_estimate > 1500.0
+            || components.len() > 5
+            || mean_curvature > 0.5
+        {
+            Difficulty::Hard
+        } else if stroke_length_estimate < 400.0 && components.len() <= 2 && mean_curvature < 0.25
+        {
+            Difficulty::Easy
+        } else {
+            Difficulty::Medium
+        };
+        let suggested_tolerance = match difficulty {
+            Difficulty::Easy => 3,
+            Difficulty::Medium => 4,
+            Difficulty::Hard => 6,
+        };
+
+        Self {
+            pixel_count,
+            components,
+            bounding_box,
+            centroid,
+            stroke_length_estimate,
+            mean_curvature,
+            max_curvature,
+            difficulty,
+            suggested_tolerance,
+        }
+    }
+}
+
+impl ReferenceModel {
+    /// Analyzes this model's reference content.
+    pub fn analyze(&self) -> ReferenceAnalysis {
+        ReferenceAnalysis::analyze(&self.pixels)
+    }
+}
+
+/// Fewer reference pixels than this is not enough to grade against.
+const MIN_REFERENCE_PIXELS: u64 = 50;
+/// Components smaller than this are export dust, not strokes.
+pub(crate) const SPECK_PIXELS: u64 = 5;
+/// More ink than this fraction of the canvas drowns the heatmap.
+const MAX_DENSITY: f64 = 0.3;
+/// Content should keep this fraction of each dimension clear of the
+/// canvas edges.
+const MARGIN_FRACTION: f64 = 0.02;
+
+/// A problem found in a candidate reference by [`validate_reference`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReferenceWarning {
+    /// Stable machine-readable code, e.g. `too-few-pixels`.
+    pub code: String,
+    pub message: String,
+}
+
+impl ReferenceWarning {
+    fn new(code: &str, message: String) -> Self {
+        Self {
+            code: code.to_string(),
+            message,
+        }
+    }
+}
+
+/// Checks a candidate reference mask for authoring problems before an
+/// exercise is published. An empty result means publishable; each
+/// warning carries a stable code for tooling to filter on.
+pub fn validate_reference(pixels: &Array2<u8>) -> Vec<ReferenceWarning> {
+    let (height, width) = pixels.dim();
+    let mut warnings = Vec::new();
+    let analysis = ReferenceAnalysis::analyze(pixels);
+
+    if analysis.pixel_count < MIN_REFERENCE_PIXELS {
+        warnings.push(ReferenceWarning::new(
+            "too-few-pixels",
+            format!(
+                "reference has {} stroke pixels, at least {MIN_REFERENCE_PIXELS} are needed",
+                analysis.pixel_count
+            ),
+        ));
+    }
+
+    let density = analysis.pixel_count as f64 / (width * height).max(1) as f64;
+    if density > MAX_DENSITY {
+        warnings.push(ReferenceWarning::new(
+            "too-dense",
+            format!(
+                "{:.0}% of the canvas is ink; dense references drown the distance heatmap",
+                density * 100.0
+            ),
+        ));
+    }
+
+    let specks = analysis
+        .components
+        .iter()
+        .filter(|component| component.pixel_count < SPECK_PIXELS)
+        .count();
+    if specks > 0 {
+        warnings.push(ReferenceWarning::new(
+            "disconnected-specks",
+            format!("{specks} component(s) smaller than {SPECK_PIXELS} pixels look like export dust"),
+        ));
+    }
+
+    if let Some(bounds) = analysis.bounding_box {
+        if bounds.min_x == 0
+            || bounds.min_y == 0
+            || bounds.max_x == width - 1
+            || bounds.max_y == height - 1
+        {
+            warnings.push(ReferenceWarning::new(
+                "touches-border",
+                "content touches the canvas border and may be clipped on export".to_string(),
+            ));
+        } else {
+            let margin_x = ((width as f64 * MARGIN_FRACTION) as usize).max(1);
+            let margin_y = ((height as f64 * MARGIN_FRACTION) as usize).max(1);
+            if bounds.min_x < margin_x
+                || bounds.min_y < margin_y
+                || bounds.max_x >= width - margin_x
+                || bounds.max_y >= height - margin_y
+            {
+                warnings.push(ReferenceWarning::new(
+                    "content-in-margin",
+                    format!(
+                        "content enters the {margin_x}px margin; leave the edges clear for drawing slack"
+                    ),
+                ));
+            }
+        }
+    }
+
+    warnings
+}
+
+/// Labels eight-connected components with a breadth-first search.
+pub(crate) fn connected_components(pixels: &Array2<u8>) -> Vec<ComponentInfo> {
+    let (height, width) = pixels.dim();
+    let mut visited = Array2::<u8>::zeros((height, width));
+    let mut components = Vec::new();
+    for ((y, x), &on) in pixels.indexed_iter() {
+        if on == 0 || visited[(y, x)] != 0 {
+            continue;
+        }
+        let mut member_pixels = Vec::new();
+        let mut queue = VecDeque::from([(y, x)]);
+        visited[(y, x)] = 1;
+        while let Some((cy, cx)) = queue.pop_front() {
+            member_pixels.push((cy, cx));
+            for dy in -1i32..=1 {
+                for dx in -1i32..=1 {
+                    let ny = cy as i32 + dy;
+                    let nx = cx as i32 + dx;
+                    if ny < 0 || nx < 0 || ny >= height as i32 || nx >= width as i32 {
+                        continue;
+                    }
+                    let pos = (ny as usize, nx as usize);
+                    if pixels[pos] != 0 && visited[pos] == 0 {
+                        visited[pos] = 1;
+                        queue.push_back(pos);
+                    }
+                }
+            }
+        }
+        components.push(ComponentInfo {
+            pixel_count: member_pixels.len() as u64,
+            bounding_box: BoundingBox::around(&member_pixels)
+                .expect("component has at least one pixel"),
+        });
+    }
+    components
+}
+
+/// Thins a mask to a one-pixel-wide skeleton (Zhang-Suen).
+pub(crate) fn skeletonize(pixels: &Array2<u8>) -> Array2<u8> {
+    let mut skeleton = pixels.mapv(|p| u8::from(p != 0));
+    let (height, width) = skeleton.dim();
+    if height < 3 || width < 3 {
+        return skeleton;
+    }
+    let mut to_remove = Vec::new();
+    loop {
+        let mut changed = false;
+        for step in 0..2 {
+            for y in 1..height - 1 {
+                for x in 1..width - 1 {
+                    if skeleton[(y, x)] == 0 {
+                        continue;
+                    }
+                    // Neighbours p2..p9 clockwise from the one above.
+                    let p = |dy: i32, dx: i32| {
+                        skeleton[((y as i32 + dy) as usize, (x as i32 + dx) as usize)]
+                    };
+                    let n = [
+                        p(-1, 0),
+                        p(-1, 1),
+                        p(0, 1),
+                        p(1, 1),
+                        p(1, 0),
+                        p(1, -1),
+                        p(0, -1),
+                        p(-1, -1),
+                    ];
+                    let neighbour_count: u8 = n.iter().sum();
+                    if !(2..=6).contains(&neighbour_count) {
+                        continue;
+                    }
+                    let transitions = (0..8)
+                        .filter(|&i| n[i] == 0 && n[(i + 1) % 8] == 1)
+                        .count();
+                    if transitions != 1 {
+                        continue;
+                    }
+                    let passes = if step == 0 {
+                        n[0] * n[2] * n[4] == 0 && n[2] * n[4] * n[6] == 0
+                    } else {
+                        n[0] * n[2] * n[6] == 0 && n[0] * n[4] * n[6] == 0
+                    };
+                    if passes {
+                        to_remove.push((y, x));
+                    }
+                }
+            }
+            for pos in to_remove.drain(..) {
+                skeleton[pos] = 0;
+                changed = true;
+            }
+        }
+        if !changed {
+            return skeleton;
+        }
+    }
+}
+
+/// Mean and max turning angle at skeleton pixels with exactly two
+/// skeleton neighbours.
+fn curvature_stats(skeleton: &Array2<u8>) -> (f64, f64) {
+    let (height, width) = skeleton.dim();
+    let mut sum = 0.0;
+    let mut max = 0.0f64;
+    let mut samples = 0u64;
+    for ((y, x), &on) in skeleton.indexed_iter() {
+        if on == 0 {
+            continue;
+        }
+        let mut neighbours = Vec::with_capacity(2);
+        for dy in -1i32..=1 {
+            for dx in -1i32..=1 {
+                if dy == 0 && dx == 0 {
+                    continue;
+                }
+                let ny = y as i32 + dy;
+                let nx = x as i32 + dx;
+                if ny < 0 || nx < 0 || ny >= height as i32 || nx >= width as i32 {
+                    continue;
+                }
+                if skeleton[(ny as usize, nx as usize)] != 0 {
+                    neighbours.push((dy as f64, dx as f64));
+                }
+            }
+        }
+        let [(ay, ax), (by, bx)] = neighbours[..] else {
+            continue;
+        };
+        // Turning angle: 0 when the two arms point in opposite directions.
+        let dot = ay * by + ax * bx;
+        let norms = (ay * ay + ax * ax).sqrt() * (by * by + bx * bx).sqrt();
+        let turning = std::f64::consts::PI - (dot / norms).clamp(-1.0, 1.0).acos();
+        sum += turning;
+        max = max.max(turning);
+        samples += 1;
+    }
+    if samples == 0 {
+        (0.0, 0.0)
+    } else {
+        (sum / samples as f64, max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_centered_line_passes_validation() {
+        let mut pixels = Array2::zeros((500, 500));
+        for x in 100..300 {
+            pixels[(250, x)] = 1;
+        }
+        assert!(validate_reference(&pixels).is_empty());
+    }
+
+    #[test]
+    fn sparse_dusty_references_are_flagged() {
+        let mut pixels = Array2::zeros((500, 500));
+        for x in 100..130 {
+            pixels[(250, x)] = 1;
+        }
+        pixels[(400, 400)] = 1;
+        let warnings = validate_reference(&pixels);
+        let codes: Vec<&str> = warnings.iter().map(|w| w.code.as_str()).collect();
+        assert!(codes.contains(&"too-few-pixels"), "{codes:?}");
+        assert!(codes.contains(&"disconnected-specks"), "{codes:?}");
+    }
+
+    #[test]
+    fn border_and_margin_content_are_distinguished() {
+        let mut touching = Array2::zeros((500, 500));
+        for x in 0..200 {
+            touching[(250, x)] = 1;
+        }
+        let codes: Vec<String> = validate_reference(&touching)
+            .into_iter()
+            .map(|w| w.code)
+            .collect();
+        assert!(codes.contains(&"touches-border".to_string()), "{codes:?}");
+
+        let mut in_margin = Array2::zeros((500, 500));
+        for x in 5..205 {
+            in_margin[(250, x)] = 1;
+        }
+        let codes: Vec<String> = validate_reference(&in_margin)
+            .into_iter()
+            .map(|w| w.code)
+            .collect();
+        assert!(codes.contains(&"content-in-margin".to_string()), "{codes:?}");
+        assert!(!codes.contains(&"touches-border".to_string()), "{codes:?}");
+    }
+
+    #[test]
+    fn a_flooded_canvas_is_too_dense() {
+        let mut pixels = Array2::zeros((100, 100));
+        for y in 0..70 {
+            for x in 10..90 {
+                pixels[(y + 10, x)] = 1;
+            }
+        }
+        let warnings = validate_reference(&pixels);
+        assert!(warnings.iter().any(|w| w.code == "too-dense"));
+    }
+
+    #[test]
+    fn straight_line_is_one_easy_component() {
+        let mut pixels = Array2::zeros((500, 500));
+        for x in 100..300 {
+            pixels[(250, x)] = 1;
+        }
+        let analysis = ReferenceAnalysis::analyze(&pixels);
+        assert_eq!(analysis.pixel_count, 200);
+        assert_eq!(analysis.components.len(), 1);
+        assert_eq!(
+            analysis.bounding_box,
+            Some(BoundingBox {
+                min_x: 100,
+                min_y: 250,
+                max_x: 299,
+                max_y: 250,
+            })
+        );
+        assert_eq!(analysis.centroid, Some((199.5, 250.0)));
+        assert!(analysis.mean_curvature < 0.05);
+        assert_eq!(analysis.difficulty, Difficulty::Easy);
+        assert_eq!(analysis.suggested_tolerance, 3);
+    }
+
+    #[test]
+    fn separate_blobs_are_separate_components() {
+        let mut pixels = Array2::zeros((500, 500));
+        for d in 0..10 {
+            pixels[(100, 100 + d)] = 1;
+            pixels[(300, 300 + d)] = 1;
+        }
+        let analysis = ReferenceAnalysis::analyze(&pixels);
+        assert_eq!(analysis.components.len(), 2);
+    }
+
+    #[test]
+    fn blank_mask_analyzes_to_zeroes() {
+        let analysis = ReferenceAnalysis::analyze(&Array2::zeros((500, 500)));
+        assert_eq!(analysis.pixel_count, 0);
+        assert!(analysis.bounding_box.is_none());
+        assert!(analysis.centroid.is_none());
+        assert_eq!(analysis.stroke_length_estimate, 0.0);
+    }
+
+    #[test]
+    fn skeleton_of_thick_line_is_thin() {
+        let mut pixels = Array2::zeros((100, 100));
+        for y in 45..55 {
+            for x in 20..80 {
+                pixels[(y, x)] = 1;
+            }
+        }
+        let skeleton = skeletonize(&pixels);
+        let count = skeleton.iter().filter(|&&p| p != 0).count();
+        assert!(count < 120, "skeleton still has {count} pixels");
+        assert!(count >= 50);
+    }
+}
@@ -0,0 +1,221 @@
+//! Reproducibility bundles for disputed grades.
+//!
+//! A score is only defensible if it can be recomputed later, possibly
+//! by a different build. [`EvaluationResult::export_bundle`] writes a
+//! zip archive holding everything a re-run needs: the panes exactly as
+//! they were scored (after flooding, filtering and scaling), the full
+//! evaluator configuration, the schema-versioned result JSON and the
+//! library version that produced it. Entries are stored uncompressed
+//! with zeroed timestamps, so the same inputs produce a byte-identical
+//! archive.
+
+use std::io::Cursor;
+use std::path::Path;
+
+use image::RgbaImage;
+use ndarray::Array2;
+
+use crate::error::EvaluationError;
+use crate::evaluator::{EvaluationResult, EvaluatorConfig};
+use crate::render::render_mask;
+
+impl EvaluationResult {
+    /// Writes a reproducibility bundle next to the score: a zip with
+    /// `reference.png` and `observation.png` (the masks as scored),
+    /// `config.json`, `result.json` and `VERSION`. Pass the same pane
+    /// masks and configuration the result was computed from — the
+    /// bundle records, it does not verify; [`Self::integrity`] covers
+    /// tamper detection.
+    pub fn export_bundle(
+        &self,
+        path: impl AsRef<Path>,
+        reference: &Array2<u8>,
+        observation: &Array2<u8>,
+        config: &EvaluatorConfig,
+    ) -> Result<(), EvaluationError> {
+        let path = path.as_ref();
+        let mut zip = ZipWriter::default();
+        zip.add(
+            "reference.png",
+            &encode_png(&render_mask(reference, config.transparent_background)),
+        );
+        zip.add(
+            "observation.png",
+            &encode_png(&render_mask(observation, config.transparent_background)),
+        );
+        zip.add(
+            "config.json",
+            &serde_json::to_vec_pretty(config).expect("config serializes to JSON"),
+        );
+        zip.add(
+            "result.json",
+            &serde_json::to_vec_pretty(self).expect("result serializes to JSON"),
+        );
+        zip.add("VERSION", env!("CARGO_PKG_VERSION").as_bytes());
+        std::fs::write(path, zip.finish()).map_err(|source| EvaluationError::Io {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+}
+
+fn encode_png(image: &RgbaImage) -> Vec<u8> {
+    let mut png = Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, image::ImageOutputFormat::Png)
+        .expect("encoding to an in-memory PNG cannot fail");
+    png.into_inner()
+}
+
+/// A minimal store-only (method 0) zip writer. Every archiver reads
+/// stored entries, timestamps are zeroed for reproducibility, and
+/// keeping it here avoids a compression dependency for what is mostly
+/// already-compressed PNG data.
+#[derive(Default)]
+struct ZipWriter {
+    bytes: Vec<u8>,
+    /// Central directory records accumulated for [`Self::finish`].
+    directory: Vec<u8>,
+    entries: u16,
+}
+
+impl ZipWriter {
+    fn add(&mut self, name: &str, data: &[u8]) {
+        let offset = self.bytes.len() as u32;
+        let crc = crc32(data);
+        let size = data.len() as u32;
+
+        // Local file header.
+        self.bytes.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        self.bytes.extend_from_slice(&20u16.to_le_bytes()); // version needed
+        self.bytes.extend_from_slice(&0u16.to_le_bytes()); // flags
+        self.bytes.extend_from_slice(&0u16.to_le_bytes()); // method: stored
+        self.bytes.extend_from_slice(&0u32.to_le_bytes()); // time + date
+        self.bytes.extend_from_slice(&crc.to_le_bytes());
+        self.bytes.extend_from_slice(&size.to_le_bytes()); // compressed
+        self.bytes.extend_from_slice(&size.to_le_bytes()); // uncompressed
+        self.bytes.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        self.bytes.extend_from_slice(&0u16.to_le_bytes()); // extra length
+        self.bytes.extend_from_slice(name.as_bytes());
+        self.bytes.extend_from_slice(data);
+
+        // Matching central directory record.
+        self.directory.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        self.directory.extend_from_slice(&20u16.to_le_bytes()); // made by
+        self.directory.extend_from_slice(&20u16.to_le_bytes()); // needed
+        self.directory.extend_from_slice(&0u16.to_le_bytes()); // flags
+        self.directory.extend_from_slice(&0u16.to_le_bytes()); // method
+        self.directory.extend_from_slice(&0u32.to_le_bytes()); // time + date
+        self.directory.extend_from_slice(&crc.to_le_bytes());
+        self.directory.extend_from_slice(&size.to_le_bytes());
+        self.directory.extend_from_slice(&size.to_le_bytes());
+        self.directory.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        self.directory.extend_from_slice(&0u16.to_le_bytes()); // extra
+        self.directory.extend_from_slice(&0u16.to_le_bytes()); // comment
+        self.directory.extend_from_slice(&0u16.to_le_bytes()); // disk
+        self.directory.extend_from_slice(&0u16.to_le_bytes()); // internal attrs
+        self.directory.extend_from_slice(&0u32.to_le_bytes()); // external attrs
+        self.directory.extend_from_slice(&offset.to_le_bytes());
+        self.directory.extend_from_slice(name.as_bytes());
+
+        self.entries += 1;
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let directory_offset = self.bytes.len() as u32;
+        let directory_size = self.directory.len() as u32;
+        self.bytes.extend_from_slice(&self.directory);
+        // End of central directory.
+        self.bytes.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        self.bytes.extend_from_slice(&0u16.to_le_bytes()); // this disk
+        self.bytes.extend_from_slice(&0u16.to_le_bytes()); // directory disk
+        self.bytes.extend_from_slice(&self.entries.to_le_bytes());
+        self.bytes.extend_from_slice(&self.entries.to_le_bytes());
+        self.bytes.extend_from_slice(&directory_size.to_le_bytes());
+        self.bytes.extend_from_slice(&directory_offset.to_le_bytes());
+        self.bytes.extend_from_slice(&0u16.to_le_bytes()); // comment length
+        self.bytes
+    }
+}
+
+/// Standard reflected CRC-32 (polynomial `0xEDB88320`), as zip requires.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (0u32.wrapping_sub(crc & 1)));
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::ImageEvaluator;
+    use image::RgbaImage;
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("evaluator-{name}-test"));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn composite(config: &EvaluatorConfig) -> RgbaImage {
+        let mut image = RgbaImage::new(
+            config.composite_width() as u32,
+            config.canvas_height as u32,
+        );
+        for x in 100..400 {
+            image.put_pixel(x, 250, image::Rgba([0, 0, 0, 255]));
+            image.put_pixel(x + 510, 250, image::Rgba([0, 0, 0, 255]));
+        }
+        image
+    }
+
+    #[test]
+    fn crc32_matches_the_reference_vector() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn bundles_are_valid_zips_with_every_entry() {
+        let config = EvaluatorConfig::default();
+        let evaluator = ImageEvaluator::new(config.clone());
+        let result = evaluator.evaluate_image(&composite(&config)).unwrap();
+        let mask = Array2::zeros((10, 10));
+        let dir = temp_dir("audit");
+        let path = dir.join("grade.zip");
+        result.export_bundle(&path, &mask, &mask, &config).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(&bytes[..4], &0x0403_4b50u32.to_le_bytes());
+        assert_eq!(&bytes[bytes.len() - 22..bytes.len() - 18], &0x0605_4b50u32.to_le_bytes());
+        let entries = u16::from_le_bytes([bytes[bytes.len() - 12], bytes[bytes.len() - 11]]);
+        assert_eq!(entries, 5);
+        for name in ["reference.png", "observation.png", "config.json", "result.json", "VERSION"] {
+            assert!(
+                bytes.windows(name.len()).any(|window| window == name.as_bytes()),
+                "missing entry {name}"
+            );
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn identical_inputs_produce_byte_identical_bundles() {
+        let config = EvaluatorConfig::default();
+        let evaluator = ImageEvaluator::new(config.clone());
+        let result = evaluator.evaluate_image(&composite(&config)).unwrap();
+        let mask = Array2::zeros((10, 10));
+        let dir = temp_dir("audit-repro");
+        let first = dir.join("first.zip");
+        let second = dir.join("second.zip");
+        result.export_bundle(&first, &mask, &mask, &config).unwrap();
+        result.export_bundle(&second, &mask, &mask, &config).unwrap();
+        assert_eq!(std::fs::read(&first).unwrap(), std::fs::read(&second).unwrap());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
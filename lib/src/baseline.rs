@@ -0,0 +1,165 @@
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+use crate::evaluator::ImageEvaluator;
+use crate::metrics::ErrorMetrics;
+use crate::streaming::ReferenceModel;
+
+/// Weight of the uncovered-reference penalty inside [`badness`]; chosen
+/// so a blank observation rates comparably to aimless scribbling.
+const UNCOVERED_PENALTY: f64 = 10.0;
+
+/// No-skill scores for a reference, used to normalize raw metrics into
+/// an intuitive skill scale.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BaselineScores {
+    /// Badness of submitting nothing at all.
+    pub empty_score: f64,
+    /// Badness of a deterministic random scribble with as many pixels as
+    /// the reference.
+    pub random_scribble_score: f64,
+    /// The baseline used for normalization: the better (lower) of the
+    /// two, so any genuine attempt must beat both no-skill strategies.
+    pub baseline: f64,
+}
+
+impl BaselineScores {
+    /// Computes both no-skill baselines for a reference. The scribble is
+    /// seeded from the reference content, so results are reproducible
+    /// and cacheable.
+    pub fn compute(model: &ReferenceModel) -> Self {
+        let evaluator = ImageEvaluator::new(model.config.clone());
+        let (height, width) = model.pixels.dim();
+
+        let empty = Array2::zeros((height, width));
+        let empty_score = evaluator
+            .evaluate_arrays(&model.pixels, &empty)
+            .map(|r| badness(&r.metrics))
+            .unwrap_or(0.0);
+
+        let scribble = random_scribble(&model.pixels);
+        let random_scribble_score = evaluator
+            .evaluate_arrays(&model.pixels, &scribble)
+            .map(|r| badness(&r.metrics))
+            .unwrap_or(0.0);
+
+        Self {
+            empty_score,
+            random_scribble_score,
+            baseline: empty_score.min(random_scribble_score),
+        }
+    }
+}
+
+/// Collapses metrics into one scalar where higher is worse: the top-5
+/// error plus a penalty for uncovered reference, so both a blank canvas
+/// and aimless scribbling rate poorly.
+pub fn badness(metrics: &ErrorMetrics) -> f64 {
+    metrics.top_5_error + (1.0 - metrics.coverage) * UNCOVERED_PENALTY
+}
+
+/// Normalized skill on a roughly 0..1 scale: 1 for a perfect drawing,
+/// around 0 for no-skill input, negative when worse than the baseline.
+pub fn normalized_skill(metrics: &ErrorMetrics, baselines: &BaselineScores) -> f64 {
+    if baselines.baseline <= 0.0 {
+        return 0.0;
+    }
+    (baselines.baseline - badness(metrics)) / baselines.baseline
+}
+
+/// A deterministic random walk with as many pixels as the reference,
+/// seeded from the reference content.
+fn random_scribble(reference: &Array2<u8>) -> Array2<u8> {
+    let (height, width) = reference.dim();
+    let target: usize = reference.iter().filter(|&&p| p != 0).count();
+    let mut scribble = Array2::zeros((height, width));
+    let mut seed = reference
+        .indexed_iter()
+        .filter(|(_, &on)| on != 0)
+        .fold(0x9e37_79b9_u64, |acc, ((y, x), _)| {
+            acc.wrapping_mul(31).wrapping_add((y * width + x) as u64)
+        });
+    let mut next = move || {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        seed >> 33
+    };
+    let mut placed = 0usize;
+    let mut y = (next() as usize) % height.max(1);
+    let mut x = (next() as usize) % width.max(1);
+    while placed < target {
+        if scribble[(y, x)] == 0 {
+            scribble[(y, x)] = 1;
+            placed += 1;
+        }
+        // Occasionally jump to a fresh spot so the walk spreads out.
+        if next() % 64 == 0 {
+            y = (next() as usize) % height.max(1);
+            x = (next() as usize) % width.max(1);
+        } else {
+            let dy = (next() % 3) as i32 - 1;
+            let dx = (next() % 3) as i32 - 1;
+            y = (y as i32 + dy).clamp(0, height as i32 - 1) as usize;
+            x = (x as i32 + dx).clamp(0, width as i32 - 1) as usize;
+        }
+    }
+    scribble
+}
+
+impl ReferenceModel {
+    /// The cached no-skill baselines for this reference, computed on
+    /// first use.
+    pub fn baselines(&self) -> &BaselineScores {
+        self.baselines.get_or_init(|| BaselineScores::compute(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::EvaluatorConfig;
+    use crate::streaming::StreamingEvaluator;
+
+    fn model() -> ReferenceModel {
+        let mut pixels = Array2::zeros((500, 500));
+        for x in 100..400 {
+            pixels[(250, x)] = 1;
+        }
+        ReferenceModel::new(pixels, EvaluatorConfig::default()).unwrap()
+    }
+
+    #[test]
+    fn baselines_rate_no_skill_input_as_bad() {
+        let model = model();
+        let baselines = model.baselines();
+        assert!(baselines.empty_score > 0.0);
+        assert!(baselines.random_scribble_score > 0.0);
+        assert!(baselines.baseline > 0.0);
+    }
+
+    #[test]
+    fn baselines_are_deterministic_per_reference() {
+        let a = BaselineScores::compute(&model());
+        let b = BaselineScores::compute(&model());
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn perfect_tracing_scores_near_full_skill() {
+        let model = model();
+        let baselines = *model.baselines();
+        let mut streaming = StreamingEvaluator::new(model);
+        let pixels: Vec<(usize, usize)> = (100..400).map(|x| (250, x)).collect();
+        streaming.add_observation_pixels(&pixels).unwrap();
+        let skill = normalized_skill(&streaming.get_full_evaluation(), &baselines);
+        assert!(skill > 0.95, "skill {skill}");
+    }
+
+    #[test]
+    fn empty_drawing_scores_near_zero_skill() {
+        let model = model();
+        let baselines = *model.baselines();
+        let streaming = StreamingEvaluator::new(model);
+        let skill = normalized_skill(&streaming.get_full_evaluation(), &baselines);
+        assert!(skill.abs() < 1e-9, "skill {skill}");
+    }
+}
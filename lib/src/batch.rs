@@ -0,0 +1,737 @@
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+
+use crate::baseline::badness;
+use crate::decode::{Decoder, ImageCrateDecoder};
+use crate::error::EvaluationError;
+use crate::evaluator::{EvaluationResult, ImageEvaluator};
+use crate::metrics::{ErrorMetrics, GRID_SIZE};
+use crate::streaming::{ReferenceModel, StreamingEvaluator};
+use crate::timelapse::mask_from_frame;
+
+/// Outcome of evaluating a single image within a batch.
+#[derive(Debug)]
+pub struct BatchItemResult {
+    pub path: PathBuf,
+    pub result: Result<EvaluationResult, EvaluationError>,
+    /// Wall-clock time spent on this image, including load and decode.
+    pub duration_ms: u64,
+}
+
+/// Policies controlling how a batch run reacts to failing images.
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    /// Keep going after a failed image instead of stopping the batch.
+    pub continue_on_error: bool,
+    /// Additional attempts per image after the first failure.
+    pub max_retries: u32,
+    /// Abort a single image's evaluation after this long.
+    pub per_image_timeout: Option<Duration>,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            continue_on_error: true,
+            max_retries: 0,
+            per_image_timeout: None,
+        }
+    }
+}
+
+/// One image that ultimately failed, with its final error and how often
+/// it was attempted.
+#[derive(Debug)]
+pub struct BatchFailure {
+    pub path: PathBuf,
+    pub reason: String,
+    pub attempts: u32,
+}
+
+/// Outcome of a whole batch run.
+#[derive(Debug)]
+pub struct BatchSummary {
+    pub items: Vec<BatchItemResult>,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub failures: Vec<BatchFailure>,
+    /// Paths never attempted because the batch stopped early.
+    pub skipped: Vec<PathBuf>,
+}
+
+/// Evaluates every image in `paths` in order with default options:
+/// individual failures are recorded rather than aborting the batch.
+pub fn evaluate_batch(evaluator: &ImageEvaluator, paths: &[PathBuf]) -> Vec<BatchItemResult> {
+    evaluate_batch_with_options(evaluator, paths, &BatchOptions::default()).items
+}
+
+/// Evaluates a batch under the given failure policies, retrying failed
+/// images and optionally stopping at the first exhausted failure.
+pub fn evaluate_batch_with_options(
+    evaluator: &ImageEvaluator,
+    paths: &[PathBuf],
+    options: &BatchOptions,
+) -> BatchSummary {
+    let mut summary = BatchSummary {
+        items: Vec::new(),
+        succeeded: 0,
+        failed: 0,
+        failures: Vec::new(),
+        skipped: Vec::new(),
+    };
+    for (index, path) in paths.iter().enumerate() {
+        let started = Instant::now();
+        let mut attempts = 0;
+        let result = loop {
+            attempts += 1;
+            let result = evaluate_one(evaluator, path, options.per_image_timeout);
+            if result.is_ok() || attempts > options.max_retries {
+                break result;
+            }
+        };
+        match &result {
+            Ok(_) => summary.succeeded += 1,
+            Err(error) => {
+                summary.failed += 1;
+                summary.failures.push(BatchFailure {
+                    path: path.clone(),
+                    reason: error.to_string(),
+                    attempts,
+                });
+            }
+        }
+        let stop = result.is_err() && !options.continue_on_error;
+        summary.items.push(BatchItemResult {
+            path: path.clone(),
+            result,
+            duration_ms: started.elapsed().as_millis() as u64,
+        });
+        if stop {
+            summary.skipped.extend(paths[index + 1..].iter().cloned());
+            break;
+        }
+    }
+    summary
+}
+
+fn evaluate_one(
+    evaluator: &ImageEvaluator,
+    path: &PathBuf,
+    timeout: Option<Duration>,
+) -> Result<EvaluationResult, EvaluationError> {
+    let Some(timeout) = timeout else {
+        return evaluator.evaluate_file(path);
+    };
+    // Run the evaluation on a helper thread so a pathological image can be
+    // abandoned; the thread finishes in the background after a timeout.
+    let (sender, receiver) = mpsc::channel();
+    let evaluator = evaluator.clone();
+    let path = path.clone();
+    std::thread::spawn(move || {
+        let _ = sender.send(evaluator.evaluate_file(&path));
+    });
+    receiver
+        .recv_timeout(timeout)
+        .unwrap_or(Err(EvaluationError::Timeout {
+            timeout_ms: timeout.as_millis() as u64,
+        }))
+}
+
+/// Knobs for [`evaluate_archive`], the low-memory pipeline for
+/// re-scoring very large corpora.
+#[derive(Debug, Clone)]
+pub struct ArchiveOptions {
+    /// Evaluator threads. Peak memory scales with this — each thread
+    /// holds at most one decoded composite — not with the corpus size.
+    pub threads: usize,
+    /// Progress manifest: completed paths, one per line, appended and
+    /// flushed as each image finishes. Paths already listed are skipped
+    /// on start, so an interrupted run resumes where it stopped; failed
+    /// images are not recorded and are retried on resume. `None`
+    /// disables resumability.
+    pub progress_path: Option<PathBuf>,
+}
+
+impl Default for ArchiveOptions {
+    fn default() -> Self {
+        Self {
+            threads: 4,
+            progress_path: None,
+        }
+    }
+}
+
+/// Totals from an [`evaluate_archive`] run.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ArchiveStats {
+    /// Images evaluated in this run.
+    pub processed: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// Images skipped because the progress manifest already listed them.
+    pub already_completed: usize,
+}
+
+/// Re-scores a huge corpus without holding it in memory: images stream
+/// through a bounded thread pool, one NDJSON record per image goes to
+/// `output` as it finishes (completion order, not input order), and an
+/// optional progress manifest makes an interrupted run resumable. Only
+/// the path list and per-thread working images are resident, so
+/// hundreds of thousands of images run in constant memory.
+pub fn evaluate_archive(
+    evaluator: &ImageEvaluator,
+    paths: &[PathBuf],
+    output: impl Write,
+    options: &ArchiveOptions,
+) -> Result<ArchiveStats, EvaluationError> {
+    let completed: HashSet<PathBuf> = match &options.progress_path {
+        Some(path) if path.exists() => std::fs::read_to_string(path)
+            .map_err(|source| EvaluationError::Io {
+                path: path.clone(),
+                source,
+            })?
+            .lines()
+            .map(PathBuf::from)
+            .collect(),
+        _ => HashSet::new(),
+    };
+    let pending: Vec<PathBuf> = paths
+        .iter()
+        .filter(|path| !completed.contains(*path))
+        .cloned()
+        .collect();
+    let mut stats = ArchiveStats {
+        already_completed: paths.len() - pending.len(),
+        ..ArchiveStats::default()
+    };
+    let mut progress = match &options.progress_path {
+        Some(path) => Some(
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|source| EvaluationError::Io {
+                    path: path.clone(),
+                    source,
+                })?,
+        ),
+        None => None,
+    };
+
+    let (job_sender, job_receiver) = mpsc::channel();
+    for path in pending {
+        let _ = job_sender.send(path);
+    }
+    drop(job_sender);
+    let job_receiver = Arc::new(Mutex::new(job_receiver));
+    let (result_sender, result_receiver) = mpsc::channel();
+    let evaluators: Vec<_> = (0..options.threads.max(1))
+        .map(|_| {
+            let jobs = Arc::clone(&job_receiver);
+            let results = result_sender.clone();
+            let evaluator = evaluator.clone();
+            std::thread::spawn(move || loop {
+                let path = match jobs.lock().expect("archive queue lock").recv() {
+                    Ok(path) => path,
+                    Err(_) => break,
+                };
+                let started = Instant::now();
+                let result = evaluator.evaluate_file(&path);
+                let item = BatchItemResult {
+                    path,
+                    result,
+                    duration_ms: started.elapsed().as_millis() as u64,
+                };
+                if results.send(item).is_err() {
+                    break;
+                }
+            })
+        })
+        .collect();
+    drop(result_sender);
+
+    let mut writer = BatchReportWriter::new(output, ReportFormat::Ndjson);
+    for item in result_receiver {
+        stats.processed += 1;
+        match &item.result {
+            Ok(_) => stats.succeeded += 1,
+            Err(_) => stats.failed += 1,
+        }
+        // Like the worker pool, a closed output stops the run early;
+        // the progress manifest keeps the completed work durable.
+        if writer.write_item(&item).is_err() {
+            break;
+        }
+        if item.result.is_ok() {
+            if let Some(progress) = &mut progress {
+                if writeln!(progress, "{}", item.path.display())
+                    .and_then(|()| progress.flush())
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        }
+    }
+    for evaluator in evaluators {
+        let _ = evaluator.join();
+    }
+    let _ = writer.finish();
+    Ok(stats)
+}
+
+/// One observation image evaluated by [`evaluate_directory`].
+#[derive(Debug)]
+pub struct DirectoryItem {
+    pub path: PathBuf,
+    pub result: Result<ErrorMetrics, EvaluationError>,
+    /// Badness-style score (lower is better); `None` for failed files.
+    pub score: Option<f64>,
+}
+
+/// Outcome of a directory run, with class-level score statistics over
+/// the files that evaluated successfully.
+#[derive(Debug)]
+pub struct DirectorySummary {
+    pub items: Vec<DirectoryItem>,
+    pub failed: usize,
+    pub mean_score: f64,
+    pub median_score: f64,
+    pub min_score: f64,
+    pub max_score: f64,
+}
+
+/// Evaluates every observation-only image in `directory` whose file
+/// name matches `pattern` (a glob with `*` wildcards, e.g. `*.png`)
+/// against one reference. The reference heatmap is computed once in the
+/// model, so grading a class of N submissions costs N mask ingestions
+/// instead of N composite flood fills. Files are evaluated in name
+/// order; individual failures are recorded, not fatal.
+pub fn evaluate_directory(
+    reference: &ReferenceModel,
+    directory: impl AsRef<Path>,
+    pattern: &str,
+) -> Result<DirectorySummary, EvaluationError> {
+    let directory = directory.as_ref();
+    let entries = std::fs::read_dir(directory).map_err(|source| EvaluationError::Io {
+        path: directory.to_path_buf(),
+        source,
+    })?;
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| matches_pattern(name, pattern))
+        })
+        .collect();
+    paths.sort();
+
+    let mut items = Vec::with_capacity(paths.len());
+    let mut scores = Vec::new();
+    for path in paths {
+        let result = evaluate_observation_file(reference, &path, &ImageCrateDecoder);
+        let score = result.as_ref().ok().map(badness);
+        if let Some(score) = score {
+            scores.push(score);
+        }
+        items.push(DirectoryItem {
+            path,
+            result,
+            score,
+        });
+    }
+
+    scores.sort_by(f64::total_cmp);
+    let failed = items.iter().filter(|item| item.result.is_err()).count();
+    Ok(DirectorySummary {
+        failed,
+        mean_score: if scores.is_empty() {
+            0.0
+        } else {
+            scores.iter().sum::<f64>() / scores.len() as f64
+        },
+        median_score: if scores.is_empty() {
+            0.0
+        } else {
+            scores[scores.len() / 2]
+        },
+        min_score: scores.first().copied().unwrap_or(0.0),
+        max_score: scores.last().copied().unwrap_or(0.0),
+        items,
+    })
+}
+
+/// Scores one observation-only image against the precomputed reference.
+fn evaluate_observation_file(
+    reference: &ReferenceModel,
+    path: &Path,
+    decoder: &dyn Decoder,
+) -> Result<ErrorMetrics, EvaluationError> {
+    let bytes = std::fs::read(path).map_err(|source| EvaluationError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let frame = decoder.decode(&bytes)?;
+    let mask = mask_from_frame(&frame, reference.config.transparent_background);
+    let mut evaluator = StreamingEvaluator::new(reference.clone());
+    let pixels: Vec<(usize, usize)> = mask
+        .indexed_iter()
+        .filter(|(_, &on)| on != 0)
+        .map(|(pos, _)| pos)
+        .collect();
+    evaluator.add_observation_pixels(&pixels)?;
+    evaluator.flush();
+    Ok(evaluator.get_full_evaluation())
+}
+
+/// Matches a file name against a glob where `*` matches any run of
+/// characters; everything else matches literally.
+fn matches_pattern(name: &str, pattern: &str) -> bool {
+    let mut pieces = pattern.split('*');
+    let Some(first) = pieces.next() else {
+        return name.is_empty();
+    };
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let mut last_piece = "";
+    let mut open_ended = false;
+    for piece in pieces {
+        open_ended = true;
+        last_piece = piece;
+        match rest.find(piece) {
+            Some(index) => rest = &rest[index + piece.len()..],
+            None => return false,
+        }
+    }
+    if open_ended {
+        // The final `*piece` must reach the end of the name.
+        last_piece.is_empty() || name.ends_with(last_piece)
+    } else {
+        rest.is_empty()
+    }
+}
+
+/// Output format for [`BatchReportWriter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Csv,
+    Ndjson,
+}
+
+/// Writes batch results as one CSV row or NDJSON record per image, so
+/// analysis tooling no longer has to re-parse pretty-printed JSON.
+pub struct BatchReportWriter<W: Write> {
+    writer: W,
+    format: ReportFormat,
+    header_written: bool,
+}
+
+impl<W: Write> BatchReportWriter<W> {
+    pub fn new(writer: W, format: ReportFormat) -> Self {
+        Self {
+            writer,
+            format,
+            header_written: false,
+        }
+    }
+
+    /// Writes one record. The CSV header is emitted before the first row.
+    pub fn write_item(&mut self, item: &BatchItemResult) -> io::Result<()> {
+        match self.format {
+            ReportFormat::Csv => self.write_csv(item),
+            ReportFormat::Ndjson => self.write_ndjson(item),
+        }
+    }
+
+    /// Flushes and returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_csv(&mut self, item: &BatchItemResult) -> io::Result<()> {
+        if !self.header_written {
+            let mut header =
+                String::from("path,mean_error,top_5_error,coverage,duration_ms,error");
+            for row in 0..GRID_SIZE {
+                for column in 0..GRID_SIZE {
+                    header.push_str(&format!(",cell_{row}_{column}"));
+                }
+            }
+            writeln!(self.writer, "{header}")?;
+            self.header_written = true;
+        }
+        let mut fields = vec![csv_field(&item.path.display().to_string())];
+        match &item.result {
+            Ok(result) => {
+                fields.push(result.metrics.mean_error.to_string());
+                fields.push(result.metrics.top_5_error.to_string());
+                fields.push(result.metrics.coverage.to_string());
+                fields.push(item.duration_ms.to_string());
+                fields.push(String::new());
+                for row in &result.metrics.grid {
+                    for cell in row {
+                        fields.push(cell.to_string());
+                    }
+                }
+            }
+            Err(error) => {
+                fields.extend([String::new(), String::new(), String::new()]);
+                fields.push(item.duration_ms.to_string());
+                fields.push(csv_field(&error.to_string()));
+                fields.extend(std::iter::repeat_n(String::new(), GRID_SIZE * GRID_SIZE));
+            }
+        }
+        writeln!(self.writer, "{}", fields.join(","))
+    }
+
+    fn write_ndjson(&mut self, item: &BatchItemResult) -> io::Result<()> {
+        let record = match &item.result {
+            Ok(result) => json!({
+                "path": item.path.display().to_string(),
+                "metrics": result.metrics,
+                "duration_ms": item.duration_ms,
+                "error": null,
+            }),
+            Err(error) => json!({
+                "path": item.path.display().to_string(),
+                "metrics": null,
+                "duration_ms": item.duration_ms,
+                "error": error.to_string(),
+            }),
+        };
+        writeln!(self.writer, "{record}")
+    }
+}
+
+/// Quotes a CSV field when it contains a delimiter, quote or newline.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::{ErrorMetrics, Normalization};
+
+    fn sample_item() -> BatchItemResult {
+        BatchItemResult {
+            path: PathBuf::from("exercises/cat.png"),
+            result: Ok(EvaluationResult {
+                schema_version: crate::schema::SCHEMA_VERSION,
+                metrics: ErrorMetrics {
+                    mean_error: 1.5,
+                    top_5_error: 2.0,
+                    coverage: 0.75,
+                    grid: vec![vec![0.0; GRID_SIZE]; GRID_SIZE],
+                    normalization: Normalization::default(),
+                    cell_tolerance_multipliers: None,
+                    normalized: None,
+                },
+                duration_ms: 12,
+                reference_scale: 1.0,
+                observation_scale: 1.0,
+                reference_stroke_width: None,
+                observation_stroke_width: None,
+                overtime_ms: None,
+                filtered_pixels: 0,
+                panes_swapped: false,
+                problem_regions: Vec::new(),
+                correction_vectors: Vec::new(),
+                integrity: None,
+                perf: None,
+            }),
+            duration_ms: 14,
+        }
+    }
+
+    #[test]
+    fn csv_emits_header_once_and_flattens_the_grid() {
+        let mut writer = BatchReportWriter::new(Vec::new(), ReportFormat::Csv);
+        writer.write_item(&sample_item()).unwrap();
+        writer.write_item(&sample_item()).unwrap();
+        let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("path,mean_error,top_5_error,coverage,duration_ms,error"));
+        assert!(lines[0].ends_with("cell_9_9"));
+        assert_eq!(lines[1].matches(',').count(), lines[0].matches(',').count());
+        assert!(lines[1].starts_with("exercises/cat.png,1.5,2,0.75,14,"));
+    }
+
+    #[test]
+    fn csv_records_failures_with_empty_metric_fields() {
+        let item = BatchItemResult {
+            path: PathBuf::from("broken.png"),
+            result: Err(EvaluationError::EmptyReference),
+            duration_ms: 3,
+        };
+        let mut writer = BatchReportWriter::new(Vec::new(), ReportFormat::Csv);
+        writer.write_item(&item).unwrap();
+        let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+        assert!(output
+            .lines()
+            .nth(1)
+            .unwrap()
+            .starts_with("broken.png,,,,3,reference pane contains no pixels"));
+    }
+
+    #[test]
+    fn retries_are_counted_per_failing_image() {
+        let paths = vec![PathBuf::from("does-not-exist.png")];
+        let options = BatchOptions {
+            max_retries: 2,
+            ..BatchOptions::default()
+        };
+        let summary =
+            evaluate_batch_with_options(&ImageEvaluator::default(), &paths, &options);
+        assert_eq!(summary.failed, 1);
+        assert_eq!(summary.failures[0].attempts, 3);
+    }
+
+    #[test]
+    fn fail_fast_skips_the_remaining_paths() {
+        let paths = vec![
+            PathBuf::from("missing-a.png"),
+            PathBuf::from("missing-b.png"),
+        ];
+        let options = BatchOptions {
+            continue_on_error: false,
+            ..BatchOptions::default()
+        };
+        let summary =
+            evaluate_batch_with_options(&ImageEvaluator::default(), &paths, &options);
+        assert_eq!(summary.items.len(), 1);
+        assert_eq!(summary.skipped, vec![PathBuf::from("missing-b.png")]);
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn directory_evaluation_scores_every_matching_file() {
+        use crate::evaluator::EvaluatorConfig;
+        use image::{Rgba, RgbaImage};
+        use ndarray::Array2;
+
+        let directory = std::env::temp_dir().join("evaluator-directory-test");
+        std::fs::create_dir_all(&directory).unwrap();
+        let mut reference = Array2::<u8>::zeros((500, 500));
+        for x in 100..400 {
+            reference[(250, x)] = 1;
+        }
+        let model = ReferenceModel::new(reference, EvaluatorConfig::default()).unwrap();
+
+        // One perfect tracing, one that misses the line entirely.
+        let mut perfect = RgbaImage::new(500, 500);
+        let mut offset = RgbaImage::new(500, 500);
+        for x in 100..400u32 {
+            perfect.put_pixel(x, 250, Rgba([0, 0, 0, 255]));
+            offset.put_pixel(x, 100, Rgba([0, 0, 0, 255]));
+        }
+        perfect.save(directory.join("ana.png")).unwrap();
+        offset.save(directory.join("bo.png")).unwrap();
+        std::fs::write(directory.join("notes.txt"), "ignored").unwrap();
+
+        let summary = evaluate_directory(&model, &directory, "*.png").unwrap();
+        assert_eq!(summary.items.len(), 2);
+        assert_eq!(summary.failed, 0);
+        assert_eq!(summary.items[0].path.file_name().unwrap(), "ana.png");
+        assert_eq!(summary.items[0].score, Some(0.0));
+        assert_eq!(summary.min_score, 0.0);
+        assert!(summary.max_score > 0.0);
+        assert!((summary.mean_score - summary.max_score / 2.0).abs() < 1e-9);
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn archive_runs_resume_from_the_progress_manifest() {
+        use crate::evaluator::EvaluatorConfig;
+        use image::{Rgba, RgbaImage};
+
+        let directory = std::env::temp_dir().join("evaluator-archive-test");
+        std::fs::create_dir_all(&directory).unwrap();
+        let config = EvaluatorConfig::default();
+        let mut composite =
+            RgbaImage::new(config.composite_width() as u32, config.canvas_height as u32);
+        for x in 100..400u32 {
+            composite.put_pixel(x, 250, Rgba([0, 0, 0, 255]));
+            composite.put_pixel(x + 510, 250, Rgba([0, 0, 0, 255]));
+        }
+        composite.save(directory.join("ana.png")).unwrap();
+        composite.save(directory.join("bo.png")).unwrap();
+        let paths = vec![directory.join("ana.png"), directory.join("bo.png")];
+        let options = ArchiveOptions {
+            threads: 2,
+            progress_path: Some(directory.join("progress")),
+        };
+
+        let mut output = Vec::new();
+        let stats = evaluate_archive(&ImageEvaluator::default(), &paths, &mut output, &options)
+            .unwrap();
+        assert_eq!(stats.processed, 2);
+        assert_eq!(stats.succeeded, 2);
+        assert_eq!(stats.already_completed, 0);
+        assert_eq!(String::from_utf8(output).unwrap().lines().count(), 2);
+
+        // A second run skips everything the manifest already lists.
+        let mut output = Vec::new();
+        let resumed =
+            evaluate_archive(&ImageEvaluator::default(), &paths, &mut output, &options).unwrap();
+        assert_eq!(resumed.processed, 0);
+        assert_eq!(resumed.already_completed, 2);
+        assert!(output.is_empty());
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn archive_failures_are_reported_but_retried_on_resume() {
+        let directory = std::env::temp_dir().join("evaluator-archive-retry-test");
+        std::fs::create_dir_all(&directory).unwrap();
+        let paths = vec![directory.join("missing.png")];
+        let options = ArchiveOptions {
+            threads: 1,
+            progress_path: Some(directory.join("progress")),
+        };
+        let stats =
+            evaluate_archive(&ImageEvaluator::default(), &paths, Vec::new(), &options).unwrap();
+        assert_eq!((stats.processed, stats.failed), (1, 1));
+        // The failure was not recorded as completed.
+        let again =
+            evaluate_archive(&ImageEvaluator::default(), &paths, Vec::new(), &options).unwrap();
+        assert_eq!((again.processed, again.already_completed), (1, 0));
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn glob_patterns_match_literal_text_around_wildcards() {
+        assert!(matches_pattern("cat.png", "*.png"));
+        assert!(matches_pattern("cat.png", "cat.*"));
+        assert!(matches_pattern("cat-01-final.png", "cat-*-final.png"));
+        assert!(!matches_pattern("cat.jpeg", "*.png"));
+        assert!(!matches_pattern("cat.png.bak", "*.png"));
+        assert!(matches_pattern("cat.png", "cat.png"));
+        assert!(!matches_pattern("cat.png", "dog.png"));
+    }
+
+    #[test]
+    fn ndjson_emits_one_parseable_record_per_line() {
+        let mut writer = BatchReportWriter::new(Vec::new(), ReportFormat::Ndjson);
+        writer.write_item(&sample_item()).unwrap();
+        let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+        let record: serde_json::Value = serde_json::from_str(output.trim()).unwrap();
+        assert_eq!(record["path"], "exercises/cat.png");
+        assert_eq!(record["metrics"]["coverage"], 0.75);
+        assert!(record["error"].is_null());
+    }
+}
@@ -0,0 +1,179 @@
+//! Fluent construction for evaluator configuration.
+//!
+//! [`EvaluatorConfig`] has grown a field at a time and integrations
+//! that only tweak one or two knobs end up spelling out a struct
+//! literal or mutating a default. The builder gives them a chained
+//! entry point instead, with the same defaults as
+//! `EvaluatorConfig::default()`.
+
+use crate::decode::InkChannel;
+use crate::evaluator::{EvaluatorConfig, ImageEvaluator, OutlierFilter};
+use crate::metrics::{CellAggregator, Normalization};
+use crate::scale::ResampleMode;
+
+/// What the canvas behind the ink looks like in exported composites.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Background {
+    /// Ink carried in the alpha channel over a transparent canvas.
+    #[default]
+    Transparent,
+    /// Dark ink on a white canvas.
+    Opaque,
+}
+
+/// Builds an [`EvaluatorConfig`] (or an [`ImageEvaluator`] directly)
+/// one setting at a time.
+///
+/// ```
+/// use evaluator::prelude::*;
+///
+/// let evaluator = EvaluatorBuilder::new()
+///     .canvas(500, 500)
+///     .background(Background::Transparent)
+///     .tolerance(3)
+///     .build();
+/// # let _ = evaluator;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EvaluatorBuilder {
+    config: EvaluatorConfig,
+}
+
+impl EvaluatorBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pixel dimensions of each pane.
+    pub fn canvas(mut self, width: usize, height: usize) -> Self {
+        self.config.canvas_width = width;
+        self.config.canvas_height = height;
+        self
+    }
+
+    /// Pixel gap between the reference and observation panes.
+    pub fn pane_gap(mut self, gap: usize) -> Self {
+        self.config.pane_gap = gap;
+        self
+    }
+
+    pub fn background(mut self, background: Background) -> Self {
+        self.config.transparent_background = background == Background::Transparent;
+        self
+    }
+
+    /// Which channel(s) mark ink on opaque exports; see
+    /// [`InkChannel`]. Transparent exports always read alpha.
+    pub fn ink_channel(mut self, channel: InkChannel) -> Self {
+        self.config.ink_channel = channel;
+        self
+    }
+
+    /// Distance (in pixels) within which a stroke counts as covering
+    /// the reference.
+    pub fn tolerance(mut self, tolerance: i32) -> Self {
+        self.config.tolerance = tolerance;
+        self
+    }
+
+    /// Thin both masks to single-pixel skeletons before scoring.
+    pub fn skeletonize(mut self, enabled: bool) -> Self {
+        self.config.skeletonize = enabled;
+        self
+    }
+
+    /// Cap the flood-fill distance field at `limit` pixels.
+    pub fn max_distance(mut self, limit: i32) -> Self {
+        self.config.max_distance = Some(limit);
+        self
+    }
+
+    /// How mismatched pane resolutions are reconciled.
+    pub fn resample(mut self, mode: ResampleMode) -> Self {
+        self.config.resample = mode;
+        self
+    }
+
+    pub fn normalization(mut self, normalization: Normalization) -> Self {
+        self.config.normalization = normalization;
+        self
+    }
+
+    /// Filter stray observation marks before aggregation.
+    pub fn outlier_filter(mut self, filter: OutlierFilter) -> Self {
+        self.config.outlier_filter = Some(filter);
+        self
+    }
+
+    /// Translate the observation onto the reference centroid before
+    /// scoring.
+    pub fn auto_center(mut self, enabled: bool) -> Self {
+        self.config.auto_center = enabled;
+        self
+    }
+
+    /// Per-cell tolerance multipliers, row-major over the scoring grid.
+    pub fn cell_tolerance_multipliers(mut self, multipliers: Vec<Vec<f64>>) -> Self {
+        self.config.cell_tolerance_multipliers = Some(multipliers);
+        self
+    }
+
+    /// How each grid cell's pixel errors collapse into its score.
+    pub fn cell_aggregator(mut self, aggregator: CellAggregator) -> Self {
+        self.config.cell_aggregator = aggregator;
+        self
+    }
+
+    /// Fit the scoring grid to the reference's bounding box.
+    pub fn fit_grid_to_reference(mut self, enabled: bool) -> Self {
+        self.config.fit_grid_to_reference = enabled;
+        self
+    }
+
+    /// The configuration accumulated so far.
+    pub fn config(self) -> EvaluatorConfig {
+        self.config
+    }
+
+    /// A one-shot evaluator with the accumulated configuration.
+    pub fn build(self) -> ImageEvaluator {
+        ImageEvaluator::new(self.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn an_untouched_builder_matches_the_default_config() {
+        assert_eq!(EvaluatorBuilder::new().config(), EvaluatorConfig::default());
+    }
+
+    #[test]
+    fn chained_settings_land_in_the_config() {
+        let config = EvaluatorBuilder::new()
+            .canvas(800, 600)
+            .pane_gap(20)
+            .background(Background::Opaque)
+            .tolerance(5)
+            .max_distance(40)
+            .auto_center(true)
+            .fit_grid_to_reference(true)
+            .config();
+        assert_eq!(config.canvas_width, 800);
+        assert_eq!(config.canvas_height, 600);
+        assert_eq!(config.pane_gap, 20);
+        assert!(!config.transparent_background);
+        assert_eq!(config.tolerance, 5);
+        assert_eq!(config.max_distance, Some(40));
+        assert!(config.auto_center);
+        assert!(config.fit_grid_to_reference);
+    }
+
+    #[test]
+    fn build_hands_the_config_to_the_evaluator() {
+        let evaluator = EvaluatorBuilder::new().tolerance(7).build();
+        assert_eq!(evaluator.config().tolerance, 7);
+    }
+}
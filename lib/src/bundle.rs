@@ -0,0 +1,209 @@
+//! Persistent precomputed reference bundles.
+//!
+//! Building a [`ReferenceModel`] runs a full flood fill, which is the
+//! expensive part of serving an exercise. A bundle file captures the
+//! finished model — reference pixels, distance heatmap and analysis
+//! metadata — so deployments can ship precomputed references next to
+//! their manifests and skip the flood fill entirely at runtime. The
+//! bundle records a hash of the evaluator configuration it was built
+//! with; loading under a different configuration is rejected instead of
+//! silently scoring with a stale heatmap.
+
+use std::path::Path;
+
+use ndarray::Array2;
+
+use crate::analysis::ReferenceAnalysis;
+use crate::error::EvaluationError;
+use crate::evaluator::EvaluatorConfig;
+use crate::streaming::ReferenceModel;
+
+/// File magic identifying a reference bundle.
+const MAGIC: &[u8; 5] = b"VAREF";
+/// Bundle layout version written by this build.
+const BUNDLE_VERSION: u16 = 1;
+
+/// A precomputed reference read back from a bundle file.
+#[derive(Debug, Clone)]
+pub struct ReferenceBundle {
+    pub model: ReferenceModel,
+    /// Analysis computed when the bundle was written.
+    pub analysis: ReferenceAnalysis,
+}
+
+impl ReferenceModel {
+    /// Writes this model as a binary bundle: magic, layout version,
+    /// config hash, mask dimensions, reference pixels, heatmap and the
+    /// reference analysis.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), EvaluationError> {
+        let path = path.as_ref();
+        let (height, width) = self.pixels.dim();
+        let analysis_json =
+            serde_json::to_vec(&self.analyze()).expect("analysis serializes to JSON");
+
+        let mut bytes = Vec::with_capacity(width * height * 5 + analysis_json.len() + 32);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&BUNDLE_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&config_hash(&self.config).to_le_bytes());
+        bytes.extend_from_slice(&(width as u32).to_le_bytes());
+        bytes.extend_from_slice(&(height as u32).to_le_bytes());
+        bytes.extend(self.pixels.iter());
+        for &distance in &self.heatmap {
+            bytes.extend_from_slice(&distance.to_le_bytes());
+        }
+        bytes.extend_from_slice(&(analysis_json.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&analysis_json);
+
+        std::fs::write(path, bytes).map_err(|source| EvaluationError::Io {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Reads a bundle written by [`Self::save`], skipping the flood
+    /// fill. `config` must hash to the configuration the bundle was
+    /// built with; a mismatch is an error, because the stored heatmap
+    /// would not match the scoring knobs in effect.
+    pub fn load(
+        path: impl AsRef<Path>,
+        config: EvaluatorConfig,
+    ) -> Result<ReferenceBundle, EvaluationError> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|source| EvaluationError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let mut reader = Reader { bytes: &bytes };
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(EvaluationError::InvalidState(
+                "not a reference bundle (bad magic)".to_string(),
+            ));
+        }
+        let version = u16::from_le_bytes(reader.array()?);
+        if version != BUNDLE_VERSION {
+            return Err(EvaluationError::InvalidState(format!(
+                "unsupported bundle version {version}; this build reads version {BUNDLE_VERSION}"
+            )));
+        }
+        let stored_hash = u64::from_le_bytes(reader.array()?);
+        let expected_hash = config_hash(&config);
+        if stored_hash != expected_hash {
+            return Err(EvaluationError::InvalidState(format!(
+                "bundle was built with a different evaluator configuration \
+                 (hash {stored_hash:016x}, expected {expected_hash:016x}); rebuild the bundle"
+            )));
+        }
+        let width = u32::from_le_bytes(reader.array()?) as usize;
+        let height = u32::from_le_bytes(reader.array()?) as usize;
+
+        let pixels = reader.take(width * height)?.to_vec();
+        let mut heatmap = Vec::with_capacity(width * height);
+        for _ in 0..width * height {
+            heatmap.push(i32::from_le_bytes(reader.array()?));
+        }
+        let analysis_len = u32::from_le_bytes(reader.array()?) as usize;
+        let analysis: ReferenceAnalysis = serde_json::from_slice(reader.take(analysis_len)?)
+            .map_err(|e| EvaluationError::InvalidState(format!("bad bundle analysis: {e}")))?;
+
+        let pixels = Array2::from_shape_vec((height, width), pixels)
+            .expect("pixel buffer length was checked by the reader");
+        let heatmap = Array2::from_shape_vec((height, width), heatmap)
+            .expect("heatmap buffer length was checked by the reader");
+        Ok(ReferenceBundle {
+            model: ReferenceModel {
+                pixels,
+                heatmap,
+                config,
+                baselines: std::sync::OnceLock::new(),
+            },
+            analysis,
+        })
+    }
+}
+
+/// FNV-1a hash of the configuration's JSON form; stable across builds so
+/// bundles survive redeployments with an unchanged configuration.
+fn config_hash(config: &EvaluatorConfig) -> u64 {
+    let json = serde_json::to_vec(config).expect("config serializes to JSON");
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for byte in json {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+/// Bounds-checked sequential reader over the bundle bytes.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], EvaluationError> {
+        if self.bytes.len() < count {
+            return Err(EvaluationError::InvalidState(
+                "truncated reference bundle".to_string(),
+            ));
+        }
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], EvaluationError> {
+        Ok(self.take(N)?.try_into().expect("take returned N bytes"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line_model() -> ReferenceModel {
+        let mut pixels = Array2::<u8>::zeros((500, 500));
+        for x in 100..400 {
+            pixels[(250, x)] = 1;
+        }
+        ReferenceModel::new(pixels, EvaluatorConfig::default()).unwrap()
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        let directory = std::env::temp_dir().join("evaluator-bundle-test");
+        std::fs::create_dir_all(&directory).unwrap();
+        directory.join(name)
+    }
+
+    #[test]
+    fn bundles_round_trip_the_model_and_its_analysis() {
+        let model = line_model();
+        let path = temp_path("round-trip.bundle");
+        model.save(&path).unwrap();
+        let bundle = ReferenceModel::load(&path, EvaluatorConfig::default()).unwrap();
+        assert_eq!(bundle.model.pixels, model.pixels);
+        assert_eq!(bundle.model.heatmap, model.heatmap);
+        assert_eq!(bundle.analysis, model.analyze());
+    }
+
+    #[test]
+    fn a_different_configuration_is_rejected_on_load() {
+        let path = temp_path("config-mismatch.bundle");
+        line_model().save(&path).unwrap();
+        let other = EvaluatorConfig {
+            tolerance: 9,
+            ..EvaluatorConfig::default()
+        };
+        let error = ReferenceModel::load(&path, other).unwrap_err();
+        assert!(error.to_string().contains("different evaluator configuration"));
+    }
+
+    #[test]
+    fn truncated_bundles_are_rejected() {
+        let path = temp_path("truncated.bundle");
+        line_model().save(&path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() / 2]).unwrap();
+        let error = ReferenceModel::load(&path, EvaluatorConfig::default()).unwrap_err();
+        assert!(error.to_string().contains("truncated"));
+    }
+}
@@ -0,0 +1,228 @@
+//! Color similarity scoring for painting exercises.
+//!
+//! Spatial metrics only say where ink landed, not whether it is the
+//! right color. This module compares the average ink color per scoring
+//! grid cell between the reference and observation panes as a CIE76
+//! delta-E in Lab space, and blends the result with the placement
+//! badness into one combined score.
+
+use image::RgbaImage;
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+use crate::baseline::badness;
+use crate::error::EvaluationError;
+use crate::metrics::{ErrorMetrics, GRID_SIZE};
+
+/// Divisor bringing delta-E (just-noticeable around 2.3, hue mismatches
+/// around 30+) onto the same scale as placement badness.
+const DELTA_E_DIVISOR: f64 = 10.0;
+
+/// Blend weights for [`combined_badness`]. They need not sum to one.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ColorWeights {
+    pub placement: f64,
+    pub color: f64,
+}
+
+impl Default for ColorWeights {
+    fn default() -> Self {
+        Self {
+            placement: 0.7,
+            color: 0.3,
+        }
+    }
+}
+
+/// Per-region color comparison between the two panes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColorMetrics {
+    /// Mean delta-E over the cells where both panes have ink.
+    pub mean_delta_e: f64,
+    /// Per-cell delta-E, row-major; `-1.0` where a cell lacks ink in
+    /// either pane and no comparison was possible.
+    pub grid: Vec<Vec<f64>>,
+    /// Number of cells that had ink in both panes.
+    pub compared_cells: usize,
+}
+
+/// Compares the average ink color of each scoring grid cell between two
+/// equally sized RGBA panes. Ink pixels are found with the composite ink
+/// rule: alpha on transparent exports, darkness on opaque ones.
+pub fn color_metrics(
+    reference: &RgbaImage,
+    observation: &RgbaImage,
+    transparent_background: bool,
+) -> Result<ColorMetrics, EvaluationError> {
+    if reference.dimensions() != observation.dimensions() {
+        return Err(EvaluationError::InvalidBuffer(format!(
+            "pane dimensions differ: reference {:?}, observation {:?}",
+            reference.dimensions(),
+            observation.dimensions()
+        )));
+    }
+    let reference_cells = cell_average_colors(reference, transparent_background);
+    let observation_cells = cell_average_colors(observation, transparent_background);
+    let mut grid = vec![vec![-1.0f64; GRID_SIZE]; GRID_SIZE];
+    let mut sum = 0.0;
+    let mut compared_cells = 0;
+    for row in 0..GRID_SIZE {
+        for column in 0..GRID_SIZE {
+            if let (Some(reference), Some(observation)) = (
+                reference_cells[(row, column)],
+                observation_cells[(row, column)],
+            ) {
+                let delta = delta_e_76(srgb_to_lab(reference), srgb_to_lab(observation));
+                grid[row][column] = delta;
+                sum += delta;
+                compared_cells += 1;
+            }
+        }
+    }
+    let mean_delta_e = if compared_cells == 0 {
+        0.0
+    } else {
+        sum / compared_cells as f64
+    };
+    Ok(ColorMetrics {
+        mean_delta_e,
+        grid,
+        compared_cells,
+    })
+}
+
+/// Blends placement badness with the color mismatch into one scalar
+/// where higher is worse, per the configured weights.
+pub fn combined_badness(
+    placement: &ErrorMetrics,
+    color: &ColorMetrics,
+    weights: &ColorWeights,
+) -> f64 {
+    weights.placement * badness(placement) + weights.color * color.mean_delta_e / DELTA_E_DIVISOR
+}
+
+/// Average sRGB ink color per grid cell; `None` for cells without ink.
+fn cell_average_colors(
+    pane: &RgbaImage,
+    transparent_background: bool,
+) -> Array2<Option<[f64; 3]>> {
+    let (width, height) = (pane.width() as usize, pane.height() as usize);
+    let cell_height = height.div_ceil(GRID_SIZE);
+    let cell_width = width.div_ceil(GRID_SIZE);
+    let mut sums = Array2::from_elem((GRID_SIZE, GRID_SIZE), [0.0f64; 3]);
+    let mut counts = Array2::from_elem((GRID_SIZE, GRID_SIZE), 0u64);
+    for (x, y, pixel) in pane.enumerate_pixels() {
+        let on = if transparent_background {
+            pixel[3] >= 128
+        } else {
+            pixel[0] < 128
+        };
+        if !on {
+            continue;
+        }
+        let cell = (y as usize / cell_height, x as usize / cell_width);
+        for channel in 0..3 {
+            sums[cell][channel] += f64::from(pixel[channel]);
+        }
+        counts[cell] += 1;
+    }
+    let mut averages = Array2::from_elem((GRID_SIZE, GRID_SIZE), None);
+    for (cell, average) in averages.indexed_iter_mut() {
+        if counts[cell] > 0 {
+            let count = counts[cell] as f64;
+            *average = Some([
+                sums[cell][0] / count,
+                sums[cell][1] / count,
+                sums[cell][2] / count,
+            ]);
+        }
+    }
+    averages
+}
+
+/// Converts an sRGB color (0..=255 per channel) to CIE Lab under D65.
+fn srgb_to_lab([r, g, b]: [f64; 3]) -> [f64; 3] {
+    let linear = |c: f64| {
+        let c = c / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let (r, g, b) = (linear(r), linear(g), linear(b));
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+    let f = |t: f64| {
+        if t > 0.008856 {
+            t.cbrt()
+        } else {
+            7.787 * t + 16.0 / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+/// CIE76 delta-E: Euclidean distance in Lab space.
+fn delta_e_76(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a.iter()
+        .zip(&b)
+        .map(|(x, y)| (x - y).powi(2))
+        .sum::<f64>()
+        .sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn pane_with_line(color: [u8; 4]) -> RgbaImage {
+        let mut pane = RgbaImage::new(100, 100);
+        for x in 20..80 {
+            pane.put_pixel(x, 50, Rgba(color));
+        }
+        pane
+    }
+
+    #[test]
+    fn identical_panes_have_zero_delta_e() {
+        let pane = pane_with_line([200, 30, 30, 255]);
+        let metrics = color_metrics(&pane, &pane, true).unwrap();
+        assert_eq!(metrics.mean_delta_e, 0.0);
+        assert_eq!(metrics.compared_cells, 6);
+    }
+
+    #[test]
+    fn wrong_hue_in_the_right_place_still_scores_badly() {
+        let reference = pane_with_line([200, 30, 30, 255]);
+        let observation = pane_with_line([30, 30, 200, 255]);
+        let color = color_metrics(&reference, &observation, true).unwrap();
+        assert!(color.mean_delta_e > 20.0, "{}", color.mean_delta_e);
+        let perfect_placement = ErrorMetrics {
+            mean_error: 0.0,
+            top_5_error: 0.0,
+            coverage: 1.0,
+            grid: vec![vec![0.0; GRID_SIZE]; GRID_SIZE],
+            normalization: Default::default(),
+            cell_tolerance_multipliers: None,
+            normalized: None,
+        };
+        let combined = combined_badness(&perfect_placement, &color, &ColorWeights::default());
+        assert!(combined > 0.0);
+    }
+
+    #[test]
+    fn cells_without_ink_in_both_panes_are_skipped() {
+        let reference = pane_with_line([0, 0, 0, 255]);
+        let mut observation = RgbaImage::new(100, 100);
+        for x in 20..30 {
+            observation.put_pixel(x, 50, Rgba([0, 0, 0, 255]));
+        }
+        let metrics = color_metrics(&reference, &observation, true).unwrap();
+        assert_eq!(metrics.compared_cells, 1);
+        assert_eq!(metrics.grid[5][7], -1.0);
+    }
+}
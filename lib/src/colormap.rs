@@ -0,0 +1,133 @@
+use crate::error::EvaluationError;
+
+/// A gradient mapping normalized `[0, 1]` values to RGBA colors, shared
+/// by every visual export so they stay consistent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Colormap {
+    /// Gradient stops sorted by position in `[0, 1]`.
+    stops: Vec<(f64, [u8; 4])>,
+}
+
+impl Colormap {
+    /// Builds a colormap from user-defined gradient stops. Stops are
+    /// sorted by position; at least one stop is required and positions
+    /// must lie in `[0, 1]`.
+    pub fn from_stops(mut stops: Vec<(f64, [u8; 4])>) -> Result<Self, EvaluationError> {
+        if stops.is_empty() {
+            return Err(EvaluationError::InvalidColormap(
+                "at least one gradient stop is required".into(),
+            ));
+        }
+        if stops.iter().any(|(t, _)| !(0.0..=1.0).contains(t)) {
+            return Err(EvaluationError::InvalidColormap(
+                "stop positions must lie in [0, 1]".into(),
+            ));
+        }
+        stops.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(Self { stops })
+    }
+
+    /// Samples the gradient at `t`, clamped to `[0, 1]`, with linear
+    /// interpolation between the surrounding stops.
+    pub fn sample(&self, t: f64) -> [u8; 4] {
+        let t = t.clamp(0.0, 1.0);
+        let mut previous = self.stops[0];
+        if t <= previous.0 {
+            return previous.1;
+        }
+        for &stop in &self.stops[1..] {
+            if t <= stop.0 {
+                let span = stop.0 - previous.0;
+                let weight = if span == 0.0 { 1.0 } else { (t - previous.0) / span };
+                return std::array::from_fn(|i| {
+                    let a = f64::from(previous.1[i]);
+                    let b = f64::from(stop.1[i]);
+                    (a + (b - a) * weight).round() as u8
+                });
+            }
+            previous = stop;
+        }
+        previous.1
+    }
+
+    /// Looks up a built-in palette by its CLI name.
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "viridis" => Some(Self::viridis()),
+            "turbo" => Some(Self::turbo()),
+            "coolwarm" => Some(Self::coolwarm()),
+            "grayscale" => Some(Self::grayscale()),
+            _ => None,
+        }
+    }
+
+    pub fn viridis() -> Self {
+        Self::from_stops(vec![
+            (0.0, [68, 1, 84, 255]),
+            (0.25, [59, 82, 139, 255]),
+            (0.5, [33, 145, 140, 255]),
+            (0.75, [94, 201, 98, 255]),
+            (1.0, [253, 231, 37, 255]),
+        ])
+        .expect("built-in palette is valid")
+    }
+
+    pub fn turbo() -> Self {
+        Self::from_stops(vec![
+            (0.0, [48, 18, 59, 255]),
+            (0.25, [70, 134, 251, 255]),
+            (0.45, [164, 252, 60, 255]),
+            (0.7, [252, 150, 38, 255]),
+            (1.0, [122, 4, 3, 255]),
+        ])
+        .expect("built-in palette is valid")
+    }
+
+    pub fn coolwarm() -> Self {
+        Self::from_stops(vec![
+            (0.0, [59, 76, 192, 255]),
+            (0.5, [221, 221, 221, 255]),
+            (1.0, [180, 4, 38, 255]),
+        ])
+        .expect("built-in palette is valid")
+    }
+
+    pub fn grayscale() -> Self {
+        Self::from_stops(vec![(0.0, [0, 0, 0, 255]), (1.0, [255, 255, 255, 255])])
+            .expect("built-in palette is valid")
+    }
+}
+
+impl Default for Colormap {
+    fn default() -> Self {
+        Self::viridis()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sampling_interpolates_between_stops() {
+        let colormap =
+            Colormap::from_stops(vec![(0.0, [0, 0, 0, 255]), (1.0, [200, 100, 50, 255])]).unwrap();
+        assert_eq!(colormap.sample(0.0), [0, 0, 0, 255]);
+        assert_eq!(colormap.sample(0.5), [100, 50, 25, 255]);
+        assert_eq!(colormap.sample(2.0), [200, 100, 50, 255]);
+    }
+
+    #[test]
+    fn built_in_palettes_resolve_by_name() {
+        for name in ["viridis", "turbo", "coolwarm", "grayscale"] {
+            assert!(Colormap::by_name(name).is_some(), "missing palette {name}");
+        }
+        assert!(Colormap::by_name("plasma").is_none());
+    }
+
+    #[test]
+    fn invalid_stops_are_rejected() {
+        assert!(Colormap::from_stops(vec![]).is_err());
+        assert!(Colormap::from_stops(vec![(1.5, [0, 0, 0, 255])]).is_err());
+    }
+}
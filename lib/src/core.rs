@@ -0,0 +1,283 @@
+//! The pure scoring math, free of `std` and the image stack.
+//!
+//! Embedded stylus hardware wants to run the distance and score math on
+//! device, where neither the operating system nor an image decoder
+//! exists. Everything here works on flat row-major byte masks with only
+//! `core` and `alloc`: pane extraction from a composite mask, the
+//! breadth-first distance transform, and the 10x10 grid scoring. With
+//! `--no-default-features` the crate compiles as `no_std + alloc` and
+//! exposes exactly this module; image IO, serde and the streaming
+//! session live in the std layer, which builds on the same math.
+
+use alloc::collections::VecDeque;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// The evaluation grid is 10x10 cells regardless of canvas size.
+pub const GRID_SIZE: usize = 10;
+
+/// Eight-connected neighbour offsets used by the chessboard flood fill.
+const NEIGHBOURS_8: [(i32, i32); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// Four-connected neighbour offsets used by the Manhattan flood fill.
+const NEIGHBOURS_4: [(i32, i32); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+
+/// Splits a flat row-major composite mask into its two panes: the
+/// reference on the left, the observation on the right, separated by
+/// `pane_gap` columns. Returns `None` when two `pane_width` panes plus
+/// the gap do not add up to the composite width, or the buffer does not
+/// match the dimensions.
+pub fn extract_panes(
+    composite: &[u8],
+    width: usize,
+    height: usize,
+    pane_width: usize,
+    pane_gap: usize,
+) -> Option<(Vec<u8>, Vec<u8>)> {
+    if composite.len() != width * height || pane_width * 2 + pane_gap != width {
+        return None;
+    }
+    let mut reference = Vec::with_capacity(pane_width * height);
+    let mut observation = Vec::with_capacity(pane_width * height);
+    for row in composite.chunks_exact(width) {
+        reference.extend_from_slice(&row[..pane_width]);
+        observation.extend_from_slice(&row[pane_width + pane_gap..]);
+    }
+    Some((reference, observation))
+}
+
+/// Chessboard (eight-connected) distance to the nearest "on" pixel, for
+/// every position of a flat `width * height` mask. This is the metric
+/// the evaluators grade with. Every position is `-1` when the mask has
+/// no pixels; with a `max_distance` clamp, positions further out read
+/// as the clamp value.
+pub fn chessboard_distances(
+    mask: &[u8],
+    width: usize,
+    height: usize,
+    max_distance: Option<i32>,
+) -> Vec<i32> {
+    flood_fill(mask, width, height, &NEIGHBOURS_8, max_distance)
+}
+
+/// [`chessboard_distances`] with the Manhattan (four-connected) metric,
+/// where diagonal steps cost 2.
+pub fn manhattan_distances(
+    mask: &[u8],
+    width: usize,
+    height: usize,
+    max_distance: Option<i32>,
+) -> Vec<i32> {
+    flood_fill(mask, width, height, &NEIGHBOURS_4, max_distance)
+}
+
+/// Multi-source breadth-first flood fill from every "on" pixel.
+fn flood_fill(
+    mask: &[u8],
+    width: usize,
+    height: usize,
+    neighbours: &[(i32, i32)],
+    max_distance: Option<i32>,
+) -> Vec<i32> {
+    let mut distances = vec![-1i32; width * height];
+    let mut queue = VecDeque::new();
+    for (index, &on) in mask.iter().enumerate() {
+        if on != 0 {
+            distances[index] = 0;
+            queue.push_back(index);
+        }
+    }
+    let has_sources = !queue.is_empty();
+    while let Some(index) = queue.pop_front() {
+        let next = distances[index] + 1;
+        if max_distance.is_some_and(|max| next > max) {
+            continue;
+        }
+        let (y, x) = ((index / width) as i32, (index % width) as i32);
+        for &(dy, dx) in neighbours {
+            let (ny, nx) = (y + dy, x + dx);
+            if ny < 0 || nx < 0 || ny >= height as i32 || nx >= width as i32 {
+                continue;
+            }
+            let neighbour = ny as usize * width + nx as usize;
+            if distances[neighbour] < 0 {
+                distances[neighbour] = next;
+                queue.push_back(neighbour);
+            }
+        }
+    }
+    if let (Some(max), true) = (max_distance, has_sources) {
+        for distance in &mut distances {
+            if *distance < 0 {
+                *distance = max;
+            }
+        }
+    }
+    distances
+}
+
+/// Grid scores of one observation mask against a reference mask, the
+/// flat counterpart of the std layer's default metrics: each cell keeps
+/// the worst error of its observation pixels, the mean error averages
+/// over every observation pixel, the top-5 sums the five worst cells,
+/// and coverage is the fraction of reference pixels with an observation
+/// pixel within `tolerance` chessboard steps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridScores {
+    /// Mean distance of observation pixels to the reference, divided by
+    /// the mean-error divisor.
+    pub mean_error: f64,
+    /// Sum of the five worst grid cells, divided by the top-5 divisor.
+    pub top_5_error: f64,
+    /// Fraction of the reference covered, in `0..=1`.
+    pub coverage: f64,
+    /// Worst error per grid cell, row-major from the top-left.
+    pub grid: [[f64; GRID_SIZE]; GRID_SIZE],
+}
+
+/// Computes [`GridScores`] for flat `width * height` masks. The
+/// divisors play the role of the std layer's normalization (5.0 and
+/// 25.0 historically). Returns `None` when either buffer does not match
+/// the dimensions.
+pub fn grid_scores(
+    reference: &[u8],
+    observation: &[u8],
+    width: usize,
+    height: usize,
+    tolerance: i32,
+    mean_error_divisor: f64,
+    top_5_divisor: f64,
+) -> Option<GridScores> {
+    if reference.len() != width * height || observation.len() != width * height {
+        return None;
+    }
+    let reference_heatmap = chessboard_distances(reference, width, height, None);
+    let observation_heatmap = chessboard_distances(observation, width, height, None);
+
+    let cell_height = height.div_ceil(GRID_SIZE).max(1);
+    let cell_width = width.div_ceil(GRID_SIZE).max(1);
+    let mut grid = [[0.0f64; GRID_SIZE]; GRID_SIZE];
+    let mut error_sum = 0.0f64;
+    let mut observation_count = 0u64;
+    for (index, &on) in observation.iter().enumerate() {
+        if on == 0 {
+            continue;
+        }
+        let (y, x) = (index / width, index % width);
+        let distance = f64::from(reference_heatmap[index].max(0));
+        error_sum += distance;
+        observation_count += 1;
+        let cell = &mut grid[(y / cell_height).min(GRID_SIZE - 1)][(x / cell_width)
+            .min(GRID_SIZE - 1)];
+        *cell = cell.max(distance);
+    }
+    let mean_error = if observation_count == 0 {
+        0.0
+    } else {
+        error_sum / observation_count as f64 / mean_error_divisor
+    };
+
+    let mut reference_count = 0u64;
+    let mut covered = 0u64;
+    for (index, &on) in reference.iter().enumerate() {
+        if on == 0 {
+            continue;
+        }
+        reference_count += 1;
+        if (0..=tolerance).contains(&observation_heatmap[index]) {
+            covered += 1;
+        }
+    }
+    let coverage = if reference_count == 0 {
+        0.0
+    } else {
+        covered as f64 / reference_count as f64
+    };
+
+    let mut cells: Vec<f64> = grid.iter().flatten().copied().collect();
+    cells.sort_by(f64::total_cmp);
+    let top_5_error = cells.iter().rev().take(5).sum::<f64>() / top_5_divisor;
+
+    Some(GridScores {
+        mean_error,
+        top_5_error,
+        coverage,
+        grid,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 100x100 mask with a horizontal line of ink at `y`.
+    fn line(y: usize) -> Vec<u8> {
+        let mut mask = vec![0u8; 100 * 100];
+        for x in 20..80 {
+            mask[y * 100 + x] = 1;
+        }
+        mask
+    }
+
+    #[test]
+    fn panes_split_around_the_gap() {
+        // 2x3 panes with a 1-column gap; the gap column never leaks.
+        let composite = [
+            1, 0, 9, 0, 1, //
+            0, 1, 9, 1, 0, //
+            1, 1, 9, 0, 0, //
+        ];
+        let (reference, observation) = extract_panes(&composite, 5, 3, 2, 1).unwrap();
+        assert_eq!(reference, [1, 0, 0, 1, 1, 1]);
+        assert_eq!(observation, [0, 1, 1, 0, 0, 0]);
+    }
+
+    #[test]
+    fn mismatched_pane_dimensions_are_rejected() {
+        assert!(extract_panes(&[0; 15], 5, 3, 3, 1).is_none());
+        assert!(extract_panes(&[0; 10], 5, 3, 2, 1).is_none());
+    }
+
+    #[test]
+    fn flat_distances_match_the_std_transform() {
+        let mask = line(50);
+        let flat = chessboard_distances(&mask, 100, 100, Some(12));
+        let std_layer =
+            crate::heatmap::distance_transform(&mask, 100, 100, Default::default(), Some(12))
+                .unwrap();
+        assert_eq!(flat, std_layer.into_raw_vec_and_offset().0);
+    }
+
+    #[test]
+    fn perfect_tracing_scores_zero_error_and_full_coverage() {
+        let mask = line(50);
+        let scores = grid_scores(&mask, &mask, 100, 100, 3, 5.0, 25.0).unwrap();
+        assert_eq!(scores.mean_error, 0.0);
+        assert_eq!(scores.top_5_error, 0.0);
+        assert_eq!(scores.coverage, 1.0);
+    }
+
+    #[test]
+    fn an_offset_stroke_reports_its_distance() {
+        let scores = grid_scores(&line(50), &line(60), 100, 100, 3, 5.0, 25.0).unwrap();
+        assert!((scores.mean_error - 10.0 / 5.0).abs() < 1e-9);
+        assert_eq!(scores.coverage, 0.0);
+        // The stroke crosses six cells of row 6, all ten pixels off.
+        assert_eq!(scores.grid[6][2], 10.0);
+        assert!((scores.top_5_error - 50.0 / 25.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn mismatched_score_buffers_are_rejected() {
+        assert!(grid_scores(&[0; 10], &[0; 100], 10, 10, 3, 5.0, 25.0).is_none());
+    }
+}
@@ -0,0 +1,292 @@
+//! Golden-score regression corpus.
+//!
+//! Algorithm changes can shift the whole grading distribution without
+//! failing a single unit test. A corpus directory pins that down: it
+//! holds curated composite images plus a `corpus.toml` spec giving the
+//! metric ranges each image is expected to score in, and the runner
+//! fails any case that drifts outside its range. The spec is a small
+//! TOML subset — `[cases.<name>]` sections with `key = value` lines —
+//! parsed here directly so the crate stays dependency-free.
+//!
+//! ```toml
+//! # corpus.toml
+//! [cases.perfect-line]
+//! image = "perfect-line.png"
+//! mean_error_max = 0.1
+//! coverage_min = 0.95
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use crate::error::EvaluationError;
+use crate::evaluator::ImageEvaluator;
+use crate::metrics::ErrorMetrics;
+
+/// File name the runner looks for inside a corpus directory.
+pub const CORPUS_SPEC_FILE: &str = "corpus.toml";
+
+/// An inclusive expected range for one metric; unset ends are unchecked.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MetricRange {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl MetricRange {
+    /// Failure message when `value` falls outside the range.
+    fn check(&self, metric: &str, value: f64) -> Option<String> {
+        if let Some(min) = self.min {
+            if value < min {
+                return Some(format!("{metric} {value:.4} below the expected min {min}"));
+            }
+        }
+        if let Some(max) = self.max {
+            if value > max {
+                return Some(format!("{metric} {value:.4} above the expected max {max}"));
+            }
+        }
+        None
+    }
+}
+
+/// One curated image and the score ranges it must stay within.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorpusCase {
+    pub name: String,
+    /// Image path, relative to the corpus directory.
+    pub image: PathBuf,
+    pub mean_error: MetricRange,
+    pub top_5_error: MetricRange,
+    pub coverage: MetricRange,
+}
+
+impl CorpusCase {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            image: PathBuf::new(),
+            mean_error: MetricRange::default(),
+            top_5_error: MetricRange::default(),
+            coverage: MetricRange::default(),
+        }
+    }
+
+    /// Every way the metrics drift outside this case's ranges.
+    fn drift(&self, metrics: &ErrorMetrics) -> Vec<String> {
+        [
+            self.mean_error.check("mean_error", metrics.mean_error),
+            self.top_5_error.check("top_5_error", metrics.top_5_error),
+            self.coverage.check("coverage", metrics.coverage),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+/// The result of running one corpus case.
+#[derive(Debug, Clone)]
+pub struct CorpusOutcome {
+    pub name: String,
+    /// `None` when the image failed to evaluate at all.
+    pub metrics: Option<ErrorMetrics>,
+    /// Drift or evaluation failures; empty means the case passed.
+    pub failures: Vec<String>,
+}
+
+/// All case outcomes from one corpus run.
+#[derive(Debug, Clone)]
+pub struct CorpusReport {
+    pub cases: Vec<CorpusOutcome>,
+}
+
+impl CorpusReport {
+    /// Whether every case scored within its expected ranges.
+    pub fn passed(&self) -> bool {
+        self.cases.iter().all(|case| case.failures.is_empty())
+    }
+
+    /// One `name: message` line per failure, for assertion messages.
+    pub fn failures(&self) -> Vec<String> {
+        self.cases
+            .iter()
+            .flat_map(|case| {
+                case.failures
+                    .iter()
+                    .map(|message| format!("{}: {message}", case.name))
+            })
+            .collect()
+    }
+}
+
+/// Parses a corpus spec. Only the subset shown in the module docs is
+/// supported; unknown sections or keys are errors so typos cannot
+/// silently skip a check.
+pub fn parse_corpus(text: &str) -> Result<Vec<CorpusCase>, EvaluationError> {
+    let invalid = |message: String| EvaluationError::InvalidCorpus(message);
+    let mut cases: Vec<CorpusCase> = Vec::new();
+    for (index, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        let position = index + 1;
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('[') {
+            let name = header
+                .strip_suffix(']')
+                .and_then(|section| section.strip_prefix("cases."))
+                .ok_or_else(|| {
+                    invalid(format!("line {position}: expected a [cases.<name>] section"))
+                })?;
+            if name.is_empty() {
+                return Err(invalid(format!("line {position}: case name must not be empty")));
+            }
+            cases.push(CorpusCase::new(name));
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| invalid(format!("line {position}: expected key = value")))?;
+        let (key, value) = (key.trim(), value.trim());
+        let case = cases
+            .last_mut()
+            .ok_or_else(|| invalid(format!("line {position}: {key} outside any case section")))?;
+        if key == "image" {
+            let path = value
+                .strip_prefix('"')
+                .and_then(|rest| rest.strip_suffix('"'))
+                .ok_or_else(|| invalid(format!("line {position}: image must be a quoted path")))?;
+            case.image = PathBuf::from(path);
+            continue;
+        }
+        let number: f64 = value
+            .parse()
+            .map_err(|_| invalid(format!("line {position}: {key} must be a number")))?;
+        match key {
+            "mean_error_min" => case.mean_error.min = Some(number),
+            "mean_error_max" => case.mean_error.max = Some(number),
+            "top_5_error_min" => case.top_5_error.min = Some(number),
+            "top_5_error_max" => case.top_5_error.max = Some(number),
+            "coverage_min" => case.coverage.min = Some(number),
+            "coverage_max" => case.coverage.max = Some(number),
+            _ => return Err(invalid(format!("line {position}: unknown key {key}"))),
+        }
+    }
+    for case in &cases {
+        if case.image.as_os_str().is_empty() {
+            return Err(invalid(format!("case {} has no image", case.name)));
+        }
+    }
+    Ok(cases)
+}
+
+/// Runs every case in `directory` (which must contain
+/// [`CORPUS_SPEC_FILE`]) through `evaluator`. A spec that fails to load
+/// or parse is an error; an image that fails to evaluate becomes a
+/// failing case, so one broken file does not hide drift in the rest.
+pub fn run_corpus(
+    directory: impl AsRef<Path>,
+    evaluator: &ImageEvaluator,
+) -> Result<CorpusReport, EvaluationError> {
+    let directory = directory.as_ref();
+    let spec_path = directory.join(CORPUS_SPEC_FILE);
+    let text = std::fs::read_to_string(&spec_path).map_err(|source| EvaluationError::Io {
+        path: spec_path,
+        source,
+    })?;
+    let cases = parse_corpus(&text)?;
+    let outcomes = cases
+        .iter()
+        .map(|case| {
+            let (metrics, failures) = match evaluator.evaluate_file(directory.join(&case.image)) {
+                Ok(result) => {
+                    let failures = case.drift(&result.metrics);
+                    (Some(result.metrics), failures)
+                }
+                Err(error) => (None, vec![error.to_string()]),
+            };
+            CorpusOutcome {
+                name: case.name.clone(),
+                metrics,
+                failures,
+            }
+        })
+        .collect();
+    Ok(CorpusReport { cases: outcomes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn specs_parse_into_cases_with_ranges() {
+        let spec = r#"
+            # Curated drift fixtures.
+            [cases.perfect-line]
+            image = "perfect-line.png"
+            mean_error_max = 0.1
+            coverage_min = 0.95
+
+            [cases.offset-line]
+            image = "offset-line.png"
+            mean_error_min = 1.5
+            mean_error_max = 2.5
+        "#;
+        let cases = parse_corpus(spec).unwrap();
+        assert_eq!(cases.len(), 2);
+        assert_eq!(cases[0].name, "perfect-line");
+        assert_eq!(cases[0].image, PathBuf::from("perfect-line.png"));
+        assert_eq!(cases[0].mean_error.max, Some(0.1));
+        assert_eq!(cases[0].coverage.min, Some(0.95));
+        assert_eq!(cases[1].mean_error, MetricRange { min: Some(1.5), max: Some(2.5) });
+    }
+
+    #[test]
+    fn typos_and_missing_images_are_rejected() {
+        let unknown = parse_corpus("[cases.a]\nimage = \"a.png\"\nmean_eror_max = 1.0");
+        assert!(unknown.unwrap_err().to_string().contains("unknown key"));
+        let missing = parse_corpus("[cases.a]\ncoverage_min = 0.5");
+        assert!(missing.unwrap_err().to_string().contains("has no image"));
+        let stray = parse_corpus("coverage_min = 0.5");
+        assert!(stray.unwrap_err().to_string().contains("outside any case"));
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn drifting_scores_fail_their_corpus_case() {
+        use crate::evaluator::EvaluatorConfig;
+        use image::{Rgba, RgbaImage};
+
+        let directory = std::env::temp_dir().join("evaluator-corpus-test");
+        std::fs::create_dir_all(&directory).unwrap();
+        let config = EvaluatorConfig::default();
+        let mut image =
+            RgbaImage::new(config.composite_width() as u32, config.canvas_height as u32);
+        for x in 100..400u32 {
+            image.put_pixel(x, 250, Rgba([0, 0, 0, 255]));
+            image.put_pixel(x + 510, 250, Rgba([0, 0, 0, 255]));
+        }
+        image.save(directory.join("perfect.png")).unwrap();
+        let spec = "\
+            [cases.in-range]\n\
+            image = \"perfect.png\"\n\
+            mean_error_max = 0.1\n\
+            coverage_min = 0.95\n\
+            [cases.drifted]\n\
+            image = \"perfect.png\"\n\
+            mean_error_min = 1.0\n\
+            [cases.missing]\n\
+            image = \"absent.png\"\n";
+        std::fs::write(directory.join(CORPUS_SPEC_FILE), spec).unwrap();
+
+        let report = run_corpus(&directory, &ImageEvaluator::new(config)).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert!(!report.passed());
+        assert!(report.cases[0].failures.is_empty());
+        let failures = report.failures();
+        assert_eq!(failures.len(), 2);
+        assert!(failures[0].contains("drifted: mean_error 0.0000 below the expected min 1"));
+        assert!(failures[1].contains("missing: failed to read"));
+    }
+}
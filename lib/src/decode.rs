@@ -0,0 +1,321 @@
+//! Pluggable image decoding.
+//!
+//! The built-in decoder uses whatever formats this crate's Cargo
+//! features enable (PNG by default; jpeg/webp/tiff opt-in). Embedders
+//! that already decode elsewhere — the browser decodes via canvas —
+//! can implement [`Decoder`] themselves and build with
+//! `--no-default-features` to drop the decoders from the bundle.
+
+use image::RgbaImage;
+use ndarray::{Array2, ArrayView2, ShapeBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::error::EvaluationError;
+
+/// How opaque (white-background) exports decide which samples count as
+/// ink. Transparent exports always read the alpha channel; this only
+/// affects `transparent_background: false`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InkChannel {
+    /// The red channel — the historical default. Pure-red strokes
+    /// (255, 0, 0) on white are invisible under it.
+    #[default]
+    Red,
+    Green,
+    Blue,
+    /// The darkest of red, green and blue, so any saturated stroke
+    /// color reads as ink.
+    MinRgb,
+    /// Rec. 601 luminance, weighting green heaviest like the eye does.
+    Luminance,
+}
+
+impl InkChannel {
+    /// The single plane this selection reads zero-copy, `None` for the
+    /// combinations that must be computed per pixel.
+    pub(crate) fn plane(self) -> Option<usize> {
+        match self {
+            Self::Red => Some(0),
+            Self::Green => Some(1),
+            Self::Blue => Some(2),
+            Self::MinRgb | Self::Luminance => None,
+        }
+    }
+
+    /// Collapses one RGB triple into the value mask extraction
+    /// thresholds (ink is dark, below 128).
+    fn value(self, [red, green, blue]: [u8; 3]) -> u8 {
+        match self {
+            Self::Red => red,
+            Self::Green => green,
+            Self::Blue => blue,
+            Self::MinRgb => red.min(green).min(blue),
+            Self::Luminance => ((299 * u32::from(red)
+                + 587 * u32::from(green)
+                + 114 * u32::from(blue))
+                / 1000) as u8,
+        }
+    }
+}
+
+/// The combined ink value plane of an image under `channel` — the
+/// owned fallback for the combinations [`InkChannel::plane`] cannot
+/// view zero-copy.
+pub fn ink_values(image: &RgbaImage, channel: InkChannel) -> Array2<u8> {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let raw = image.as_raw();
+    Array2::from_shape_fn((height, width), |(y, x)| {
+        let pixel = 4 * (y * width + x);
+        channel.value([raw[pixel], raw[pixel + 1], raw[pixel + 2]])
+    })
+}
+
+/// Turns encoded image bytes into RGBA pixels.
+pub trait Decoder {
+    fn decode(&self, bytes: &[u8]) -> Result<RgbaImage, EvaluationError>;
+}
+
+/// Decoder backed by the `image` crate's enabled format features.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ImageCrateDecoder;
+
+impl Decoder for ImageCrateDecoder {
+    fn decode(&self, bytes: &[u8]) -> Result<RgbaImage, EvaluationError> {
+        Ok(normalize_decoded(image::load_from_memory(bytes)?))
+    }
+}
+
+/// Collapses a decoded image to the 8-bit RGBA working format.
+///
+/// Scans arrive as whatever the authoring tool saved — indexed-palette
+/// PNGs (expanded to RGB by the decoder), grayscale, 16-bit grayscale —
+/// and the generic conversion is surprising for some of them. The
+/// grayscale variants are handled explicitly: the luma sample is
+/// replicated across RGB directly, with 16-bit samples scaled to 8 bits
+/// by rounding, so a stroke reads identically whichever color type the
+/// tool picked.
+fn normalize_decoded(image: image::DynamicImage) -> RgbaImage {
+    use image::DynamicImage;
+    match image {
+        DynamicImage::ImageLuma8(gray) => RgbaImage::from_fn(gray.width(), gray.height(), |x, y| {
+            let luma = gray.get_pixel(x, y)[0];
+            image::Rgba([luma, luma, luma, 255])
+        }),
+        DynamicImage::ImageLumaA8(gray) => {
+            RgbaImage::from_fn(gray.width(), gray.height(), |x, y| {
+                let image::LumaA([luma, alpha]) = *gray.get_pixel(x, y);
+                image::Rgba([luma, luma, luma, alpha])
+            })
+        }
+        DynamicImage::ImageLuma16(gray) => {
+            RgbaImage::from_fn(gray.width(), gray.height(), |x, y| {
+                let luma = scale_16_to_8(gray.get_pixel(x, y)[0]);
+                image::Rgba([luma, luma, luma, 255])
+            })
+        }
+        DynamicImage::ImageLumaA16(gray) => {
+            RgbaImage::from_fn(gray.width(), gray.height(), |x, y| {
+                let image::LumaA([luma, alpha]) = *gray.get_pixel(x, y);
+                let luma = scale_16_to_8(luma);
+                image::Rgba([luma, luma, luma, scale_16_to_8(alpha)])
+            })
+        }
+        other => other.to_rgba8(),
+    }
+}
+
+/// Scales a 16-bit sample to 8 bits, rounding to nearest, so 0x8000
+/// lands on 128 rather than truncating to 127.
+fn scale_16_to_8(value: u16) -> u8 {
+    ((u32::from(value) * 255 + 32_767) / 65_535) as u8
+}
+
+/// A zero-copy 2D view of one channel of a decoded RGBA buffer, built
+/// with stride math (4 samples per pixel, `4 * width` per row) instead
+/// of copying the plane out. `channel` is clamped to the alpha channel.
+pub fn channel_view(image: &RgbaImage, channel: usize) -> ArrayView2<'_, u8> {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let raw = image.as_raw();
+    let offset = if raw.is_empty() { 0 } else { channel.min(3) };
+    ArrayView2::from_shape((height, width).strides((4 * width, 4)), &raw[offset..])
+        .expect("RGBA buffer holds height x width x 4 samples")
+}
+
+/// Thresholds a channel view into a stroke mask without an
+/// intermediate copy of the frame: with a transparent background the
+/// alpha channel marks covered pixels, on opaque exports the red
+/// channel marks dark ink.
+pub fn mask_from_view(view: &ArrayView2<'_, u8>, transparent_background: bool) -> Array2<u8> {
+    if transparent_background {
+        view.mapv(|alpha| u8::from(alpha >= 128))
+    } else {
+        view.mapv(|red| u8::from(red < 128))
+    }
+}
+
+/// The channel [`mask_from_view`] thresholds under this background
+/// convention.
+pub fn mask_channel(transparent_background: bool) -> usize {
+    if transparent_background {
+        3
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    /// Minimal PNG writer for the color types these tests exercise —
+    /// the image crate's encoder cannot produce indexed output, so the
+    /// fixtures are assembled by hand with stored zlib blocks.
+    fn png_bytes(
+        width: u32,
+        height: u32,
+        bit_depth: u8,
+        color_type: u8,
+        palette: Option<&[u8]>,
+        rows: &[&[u8]],
+    ) -> Vec<u8> {
+        let mut bytes = vec![137, 80, 78, 71, 13, 10, 26, 10];
+        let mut ihdr = Vec::new();
+        ihdr.extend_from_slice(&width.to_be_bytes());
+        ihdr.extend_from_slice(&height.to_be_bytes());
+        ihdr.extend_from_slice(&[bit_depth, color_type, 0, 0, 0]);
+        push_chunk(&mut bytes, b"IHDR", &ihdr);
+        if let Some(palette) = palette {
+            push_chunk(&mut bytes, b"PLTE", palette);
+        }
+        // A filter byte of 0 before each row, in one stored zlib block.
+        let mut raw = Vec::new();
+        for row in rows {
+            raw.push(0);
+            raw.extend_from_slice(row);
+        }
+        let mut idat = vec![0x78, 0x01, 0x01];
+        idat.extend_from_slice(&(raw.len() as u16).to_le_bytes());
+        idat.extend_from_slice(&(!(raw.len() as u16)).to_le_bytes());
+        idat.extend_from_slice(&raw);
+        idat.extend_from_slice(&adler32(&raw).to_be_bytes());
+        push_chunk(&mut bytes, b"IDAT", &idat);
+        push_chunk(&mut bytes, b"IEND", &[]);
+        bytes
+    }
+
+    fn push_chunk(bytes: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+        bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(kind);
+        bytes.extend_from_slice(data);
+        let mut checked = kind.to_vec();
+        checked.extend_from_slice(data);
+        bytes.extend_from_slice(&crc32(&checked).to_be_bytes());
+    }
+
+    fn crc32(bytes: &[u8]) -> u32 {
+        let mut crc = 0xFFFF_FFFFu32;
+        for &byte in bytes {
+            crc ^= u32::from(byte);
+            for _ in 0..8 {
+                crc = if crc & 1 != 0 {
+                    (crc >> 1) ^ 0xEDB8_8320
+                } else {
+                    crc >> 1
+                };
+            }
+        }
+        !crc
+    }
+
+    fn adler32(bytes: &[u8]) -> u32 {
+        let (mut a, mut b) = (1u32, 0u32);
+        for &byte in bytes {
+            a = (a + u32::from(byte)) % 65_521;
+            b = (b + a) % 65_521;
+        }
+        (b << 16) | a
+    }
+
+    #[test]
+    fn eight_bit_grayscale_pngs_replicate_luma() {
+        let png = png_bytes(2, 1, 8, 0, None, &[&[0, 255]]);
+        let image = ImageCrateDecoder.decode(&png).unwrap();
+        assert_eq!(*image.get_pixel(0, 0), Rgba([0, 0, 0, 255]));
+        assert_eq!(*image.get_pixel(1, 0), Rgba([255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn sixteen_bit_grayscale_pngs_round_to_eight_bits() {
+        let png = png_bytes(3, 1, 16, 0, None, &[&[0, 0, 0x80, 0x00, 0xFF, 0xFF]]);
+        let image = ImageCrateDecoder.decode(&png).unwrap();
+        assert_eq!(*image.get_pixel(0, 0), Rgba([0, 0, 0, 255]));
+        // The midpoint sample rounds to 128 instead of truncating.
+        assert_eq!(*image.get_pixel(1, 0), Rgba([128, 128, 128, 255]));
+        assert_eq!(*image.get_pixel(2, 0), Rgba([255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn indexed_palette_pngs_expand_to_their_palette_colors() {
+        // Two-entry palette: black ink, white background.
+        let palette = [0, 0, 0, 255, 255, 255];
+        let png = png_bytes(2, 1, 8, 3, Some(&palette), &[&[0, 1]]);
+        let image = ImageCrateDecoder.decode(&png).unwrap();
+        assert_eq!(*image.get_pixel(0, 0), Rgba([0, 0, 0, 255]));
+        assert_eq!(*image.get_pixel(1, 0), Rgba([255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn grayscale_alpha_pngs_keep_their_alpha_channel() {
+        let png = png_bytes(2, 1, 8, 4, None, &[&[0, 255, 200, 0]]);
+        let image = ImageCrateDecoder.decode(&png).unwrap();
+        assert_eq!(*image.get_pixel(0, 0), Rgba([0, 0, 0, 255]));
+        assert_eq!(*image.get_pixel(1, 0), Rgba([200, 200, 200, 0]));
+    }
+
+    #[test]
+    fn undecodable_bytes_surface_a_decode_error() {
+        let error = ImageCrateDecoder.decode(&[0, 1, 2, 3]).unwrap_err();
+        assert!(matches!(error, EvaluationError::Decode(_)));
+    }
+
+    #[test]
+    fn channel_views_read_the_requested_plane_in_place() {
+        let mut image = RgbaImage::new(3, 2);
+        image.put_pixel(2, 1, Rgba([10, 20, 30, 40]));
+        let red = channel_view(&image, 0);
+        let alpha = channel_view(&image, 3);
+        assert_eq!(red.dim(), (2, 3));
+        assert_eq!(red[(1, 2)], 10);
+        assert_eq!(alpha[(1, 2)], 40);
+        assert_eq!(alpha[(0, 0)], 0);
+    }
+
+    #[test]
+    fn masks_threshold_the_channel_for_either_background() {
+        let mut image = RgbaImage::new(2, 1);
+        image.put_pixel(0, 0, Rgba([0, 0, 0, 255]));
+        image.put_pixel(1, 0, Rgba([255, 255, 255, 0]));
+        let transparent =
+            mask_from_view(&channel_view(&image, mask_channel(true)), true);
+        assert_eq!((transparent[(0, 0)], transparent[(0, 1)]), (1, 0));
+        let opaque = mask_from_view(&channel_view(&image, mask_channel(false)), false);
+        assert_eq!((opaque[(0, 0)], opaque[(0, 1)]), (1, 0));
+    }
+
+    #[test]
+    fn channel_combinations_see_pure_colored_strokes() {
+        let mut image = RgbaImage::from_pixel(2, 1, Rgba([255, 255, 255, 255]));
+        image.put_pixel(0, 0, Rgba([255, 0, 0, 255])); // pure red stroke
+        // The red plane reads the stroke as background...
+        assert_eq!(ink_values(&image, InkChannel::Red)[(0, 0)], 255);
+        // ...while the combinations and other planes read it as ink.
+        assert_eq!(ink_values(&image, InkChannel::Green)[(0, 0)], 0);
+        assert_eq!(ink_values(&image, InkChannel::MinRgb)[(0, 0)], 0);
+        assert_eq!(ink_values(&image, InkChannel::Luminance)[(0, 0)], 76);
+        // White background stays background under every selection.
+        assert_eq!(ink_values(&image, InkChannel::MinRgb)[(0, 1)], 255);
+        assert_eq!(ink_values(&image, InkChannel::Luminance)[(0, 1)], 255);
+    }
+}
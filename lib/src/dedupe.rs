@@ -0,0 +1,169 @@
+//! Duplicate submission detection.
+//!
+//! Copied screenshots resubmitted under another name score identically,
+//! which leaderboards and graded batches should flag rather than
+//! reward. This module computes a dHash-style perceptual hash of the
+//! observation pane — ink density over a coarse grid, each bit
+//! comparing a cell to its right neighbour — so near-identical
+//! submissions land within a few bits of each other while genuinely
+//! different drawings do not. [`find_duplicates`] compares a batch of
+//! hashes pairwise; the `dedupe` CLI subcommand wraps it for
+//! directories of composites.
+
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+/// Density grid rows the hash compares.
+const HASH_ROWS: usize = 8;
+/// Density grid columns; one more than rows so each row yields eight
+/// horizontal comparisons, giving 64 bits total.
+const HASH_COLUMNS: usize = 9;
+
+/// Hamming distance at or below which two submissions are flagged as
+/// near-duplicates by default. Resized or slightly shifted copies land
+/// within a few bits; different drawings of the same reference do not.
+pub const DEFAULT_MAX_DISTANCE: u32 = 5;
+
+/// A 64-bit perceptual hash of an observation pane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PerceptualHash {
+    pub bits: u64,
+}
+
+impl PerceptualHash {
+    /// Bits on which the two hashes disagree; 0 for identical panes.
+    pub fn distance(&self, other: &PerceptualHash) -> u32 {
+        (self.bits ^ other.bits).count_ones()
+    }
+
+    /// `1 - distance / 64`: 1.0 for identical panes, toward 0 as they
+    /// diverge.
+    pub fn similarity(&self, other: &PerceptualHash) -> f64 {
+        1.0 - f64::from(self.distance(other)) / 64.0
+    }
+}
+
+/// Hashes an observation mask: ink density over an
+/// [`HASH_ROWS`]x[`HASH_COLUMNS`] grid, one bit per horizontal
+/// neighbour comparison. Gradient comparisons make the hash robust to
+/// uniform density changes (brush width, export scaling) that would
+/// break an exact pixel hash.
+pub fn observation_hash(mask: &Array2<u8>) -> PerceptualHash {
+    let (height, width) = mask.dim();
+    let mut ink = [[0u64; HASH_COLUMNS]; HASH_ROWS];
+    let mut total = [[0u64; HASH_COLUMNS]; HASH_ROWS];
+    for ((y, x), &on) in mask.indexed_iter() {
+        let row = (y * HASH_ROWS / height.max(1)).min(HASH_ROWS - 1);
+        let column = (x * HASH_COLUMNS / width.max(1)).min(HASH_COLUMNS - 1);
+        total[row][column] += 1;
+        if on != 0 {
+            ink[row][column] += 1;
+        }
+    }
+    let density = |row: usize, column: usize| {
+        if total[row][column] == 0 {
+            0.0
+        } else {
+            ink[row][column] as f64 / total[row][column] as f64
+        }
+    };
+    let mut bits = 0u64;
+    for row in 0..HASH_ROWS {
+        for column in 0..HASH_COLUMNS - 1 {
+            bits <<= 1;
+            if density(row, column) > density(row, column + 1) {
+                bits |= 1;
+            }
+        }
+    }
+    PerceptualHash { bits }
+}
+
+/// Two submissions flagged as near-identical, as indices into the
+/// hash slice handed to [`find_duplicates`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DuplicatePair {
+    pub first: usize,
+    pub second: usize,
+    pub distance: u32,
+}
+
+/// Flags every pair of hashes within `max_distance` bits, closest
+/// pairs first. Quadratic in the batch size, which stays trivial at
+/// grading-batch scale.
+pub fn find_duplicates(hashes: &[PerceptualHash], max_distance: u32) -> Vec<DuplicatePair> {
+    let mut pairs = Vec::new();
+    for (first, a) in hashes.iter().enumerate() {
+        for (offset, b) in hashes[first + 1..].iter().enumerate() {
+            let distance = a.distance(b);
+            if distance <= max_distance {
+                pairs.push(DuplicatePair {
+                    first,
+                    second: first + 1 + offset,
+                    distance,
+                });
+            }
+        }
+    }
+    pairs.sort_by_key(|pair| pair.distance);
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line_mask(y: usize, x0: usize, x1: usize) -> Array2<u8> {
+        let mut mask = Array2::zeros((500, 500));
+        for x in x0..x1 {
+            mask[(y, x)] = 1;
+        }
+        mask
+    }
+
+    #[test]
+    fn identical_panes_hash_identically() {
+        let a = observation_hash(&line_mask(250, 100, 400));
+        let b = observation_hash(&line_mask(250, 100, 400));
+        assert_eq!(a.distance(&b), 0);
+        assert_eq!(a.similarity(&b), 1.0);
+    }
+
+    #[test]
+    fn a_slightly_shifted_copy_stays_within_the_default_distance() {
+        let original = observation_hash(&line_mask(250, 100, 400));
+        let shifted = observation_hash(&line_mask(253, 103, 403));
+        assert!(
+            original.distance(&shifted) <= DEFAULT_MAX_DISTANCE,
+            "distance {}",
+            original.distance(&shifted)
+        );
+    }
+
+    #[test]
+    fn different_drawings_hash_far_apart() {
+        let horizontal = observation_hash(&line_mask(250, 100, 400));
+        let mut vertical = Array2::zeros((500, 500));
+        for y in 100..400 {
+            vertical[(y, 250)] = 1;
+        }
+        let distance = horizontal.distance(&observation_hash(&vertical));
+        assert!(distance > DEFAULT_MAX_DISTANCE, "distance {distance}");
+    }
+
+    #[test]
+    fn batches_flag_only_the_near_identical_pairs() {
+        let mut vertical = Array2::zeros((500, 500));
+        for y in 100..400 {
+            vertical[(y, 250)] = 1;
+        }
+        let hashes = [
+            observation_hash(&line_mask(250, 100, 400)),
+            observation_hash(&vertical),
+            observation_hash(&line_mask(252, 101, 401)),
+        ];
+        let pairs = find_duplicates(&hashes, DEFAULT_MAX_DISTANCE);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!((pairs[0].first, pairs[0].second), (0, 2));
+    }
+}
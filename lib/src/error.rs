@@ -0,0 +1,57 @@
+use std::path::PathBuf;
+
+/// Errors produced while loading or scoring an image.
+#[derive(Debug, thiserror::Error)]
+pub enum EvaluationError {
+    #[error("failed to read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("failed to decode image: {0}")]
+    Decode(#[from] image::ImageError),
+
+    #[error("expected a {expected_width}x{expected_height} composite, got {width}x{height}")]
+    InvalidDimensions {
+        expected_width: usize,
+        expected_height: usize,
+        width: usize,
+        height: usize,
+    },
+
+    #[error("reference pane contains no pixels")]
+    EmptyReference,
+
+    #[error("observation pixel ({x}, {y}) is outside the {width}x{height} canvas")]
+    OutOfBounds {
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    },
+
+    #[error("invalid rgba buffer: {0}")]
+    InvalidBuffer(String),
+
+    #[error("invalid data URL: {0}")]
+    InvalidDataUrl(String),
+
+    #[error("invalid colormap: {0}")]
+    InvalidColormap(String),
+
+    #[error("invalid serialized state: {0}")]
+    InvalidState(String),
+
+    #[error("invalid exercise manifest: {0}")]
+    InvalidManifest(String),
+
+    #[error("invalid corpus spec: {0}")]
+    InvalidCorpus(String),
+
+    #[error("invalid phrase book: {0}")]
+    InvalidPhraseBook(String),
+
+    #[error("evaluation timed out after {timeout_ms}ms")]
+    Timeout { timeout_ms: u64 },
+}
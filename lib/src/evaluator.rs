@@ -0,0 +1,1241 @@
+use std::path::Path;
+use std::time::Instant;
+
+use image::RgbaImage;
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::skeletonize;
+use crate::decode::{Decoder, ImageCrateDecoder, InkChannel};
+use crate::error::EvaluationError;
+use crate::heatmap::flood_fill_distances;
+use crate::metrics::{compute_metrics, CellAggregator, ErrorMetrics, Normalization};
+use crate::regions::{compute_problem_regions, correction_vectors, CorrectionVector, ProblemRegion};
+use crate::scale::{resample_mask, ResampleMode};
+
+/// Configuration shared by the one-shot and streaming evaluators.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvaluatorConfig {
+    /// Width of a single pane in pixels.
+    pub canvas_width: usize,
+    /// Height of a single pane in pixels.
+    pub canvas_height: usize,
+    /// Gap between the reference and observation panes in the composite.
+    pub pane_gap: usize,
+    /// Read strokes from the alpha channel (transparent canvas exports)
+    /// instead of the red channel.
+    pub transparent_background: bool,
+    /// Which channel(s) mark ink on opaque exports, where the default
+    /// red channel cannot see pure-red strokes. Ignored for
+    /// transparent exports, which always read alpha.
+    #[serde(default)]
+    pub ink_channel: InkChannel,
+    /// Distance in pixels within which a reference pixel counts as covered.
+    pub tolerance: i32,
+    /// Thin both panes to one-pixel skeletons before comparison, so a
+    /// thick brush against a thin reference is scored on placement
+    /// rather than punished for its width. The measured mean stroke
+    /// widths are reported in the result.
+    #[serde(default)]
+    pub skeletonize: bool,
+    /// Clamp applied to heatmap distances: flood-fill propagation stops
+    /// here and further positions read as this value, bounding how much
+    /// one stray stroke can dominate the metrics. `None` leaves
+    /// distances unbounded.
+    #[serde(default)]
+    pub max_distance: Option<i32>,
+    /// Resampling used when a pane arrives at a different resolution
+    /// than the evaluation canvas (e.g. devicePixelRatio 2 tablets).
+    #[serde(default)]
+    pub resample: ResampleMode,
+    /// Display-scale constants applied to the raw distance metrics.
+    #[serde(default)]
+    pub normalization: Normalization,
+    /// Optional stray-mark filter applied to the observation before
+    /// metric aggregation, so one accidental dot cannot dominate the
+    /// grid error. The number of dropped pixels is reported in the
+    /// result.
+    #[serde(default)]
+    pub outlier_filter: Option<OutlierFilter>,
+    /// Translate both panes so their content centroids sit at the
+    /// canvas centre before comparison, so a reference authored
+    /// off-centre does not systematically penalize centred drawings.
+    #[serde(default)]
+    pub auto_center: bool,
+    /// Per-grid-cell tolerance multipliers, row-major over the scoring
+    /// grid: values above 1 score a cell leniently (background
+    /// texture), below 1 strictly (focal areas). `None` keeps every
+    /// cell at the base tolerance. The map used is echoed in the
+    /// metrics.
+    #[serde(default)]
+    pub cell_tolerance_multipliers: Option<Vec<Vec<f64>>>,
+    /// Fit the scoring grid to the reference's bounding box instead of
+    /// the full canvas, so a drawing confined to one corner still
+    /// exercises every grid cell in the top-5 selection.
+    #[serde(default)]
+    pub fit_grid_to_reference: bool,
+    /// How each scoring grid cell's pixel errors collapse into the
+    /// cell's score; the historical max unless overridden.
+    #[serde(default)]
+    pub cell_aggregator: CellAggregator,
+    /// Collect per-stage timings, pixel counts and a peak-memory
+    /// estimate into [`EvaluationResult::perf`], for capacity planning
+    /// on the grading server. Off by default; the accounting itself is
+    /// cheap but the extra result payload is not always wanted.
+    #[serde(default)]
+    pub collect_perf_stats: bool,
+}
+
+/// How stray observation marks are filtered out before aggregation.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutlierFilter {
+    /// Drop eight-connected observation components smaller than this
+    /// many pixels.
+    MinComponentSize(usize),
+    /// Drop up to this fraction (`0..=1`) of the observation pixels
+    /// with the worst distances, never touching pixels within the
+    /// coverage tolerance.
+    TrimWorstFraction(f64),
+}
+
+impl Default for EvaluatorConfig {
+    fn default() -> Self {
+        Self {
+            canvas_width: 500,
+            canvas_height: 500,
+            pane_gap: 10,
+            transparent_background: true,
+            ink_channel: InkChannel::default(),
+            tolerance: 3,
+            skeletonize: false,
+            max_distance: None,
+            resample: ResampleMode::default(),
+            normalization: Normalization::default(),
+            outlier_filter: None,
+            auto_center: false,
+            cell_tolerance_multipliers: None,
+            fit_grid_to_reference: false,
+            cell_aggregator: CellAggregator::default(),
+            collect_perf_stats: false,
+        }
+    }
+}
+
+impl EvaluatorConfig {
+    /// Expected pixel width of a composite image (two panes plus the gap).
+    pub fn composite_width(&self) -> usize {
+        self.canvas_width * 2 + self.pane_gap
+    }
+}
+
+/// One-shot evaluator for finished composite reference/observation images.
+#[derive(Debug, Clone, Default)]
+pub struct ImageEvaluator {
+    config: EvaluatorConfig,
+}
+
+impl ImageEvaluator {
+    pub fn new(config: EvaluatorConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &EvaluatorConfig {
+        &self.config
+    }
+
+    /// Evaluates a composite PNG on disk.
+    pub fn evaluate_file(&self, path: impl AsRef<Path>) -> Result<EvaluationResult, EvaluationError> {
+        self.evaluate_file_with(path, &ImageCrateDecoder)
+    }
+
+    /// [`Self::evaluate_file`] decoding through a caller-supplied
+    /// [`Decoder`], for embedders built without the default decoders.
+    pub fn evaluate_file_with(
+        &self,
+        path: impl AsRef<Path>,
+        decoder: &dyn Decoder,
+    ) -> Result<EvaluationResult, EvaluationError> {
+        let path = path.as_ref();
+        let span = tracing::debug_span!("load_image", path = %path.display()).entered();
+        let started = Instant::now();
+        let bytes = std::fs::read(path).map_err(|source| EvaluationError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let image = decoder.decode(&bytes)?;
+        let load_ms = started.elapsed().as_millis() as u64;
+        tracing::debug!(elapsed_ms = load_ms, bytes = bytes.len(), "image loaded");
+        drop(span);
+        let mut result = self.evaluate_image(&image)?;
+        if let Some(perf) = result.perf.as_mut() {
+            perf.load_ms = Some(load_ms);
+            perf.peak_memory_bytes += bytes.len() as u64;
+        }
+        Ok(result)
+    }
+
+    /// Evaluates a raw RGBA composite buffer straight from a canvas
+    /// (`width * height * 4` bytes), skipping the PNG round trip.
+    pub fn evaluate_rgba_buffer(
+        &self,
+        buffer: &[u8],
+        width: usize,
+        height: usize,
+    ) -> Result<EvaluationResult, EvaluationError> {
+        if buffer.len() != width * height * 4 {
+            return Err(EvaluationError::InvalidBuffer(format!(
+                "buffer has {} bytes, expected {} for {width}x{height} RGBA",
+                buffer.len(),
+                width * height * 4
+            )));
+        }
+        let image = RgbaImage::from_raw(width as u32, height as u32, buffer.to_vec())
+            .ok_or_else(|| EvaluationError::InvalidBuffer("dimension overflow".into()))?;
+        self.evaluate_image(&image)
+    }
+
+    /// Evaluates a base64 `data:` URL as produced by `canvas.toDataURL()`.
+    pub fn evaluate_data_url(&self, data_url: &str) -> Result<EvaluationResult, EvaluationError> {
+        self.evaluate_data_url_with(data_url, &ImageCrateDecoder)
+    }
+
+    /// [`Self::evaluate_data_url`] decoding through a caller-supplied
+    /// [`Decoder`].
+    pub fn evaluate_data_url_with(
+        &self,
+        data_url: &str,
+        decoder: &dyn Decoder,
+    ) -> Result<EvaluationResult, EvaluationError> {
+        let payload = data_url
+            .strip_prefix("data:")
+            .ok_or_else(|| EvaluationError::InvalidDataUrl("missing data: scheme".into()))?;
+        let (metadata, data) = payload
+            .split_once(',')
+            .ok_or_else(|| EvaluationError::InvalidDataUrl("missing comma separator".into()))?;
+        if !metadata.ends_with(";base64") {
+            return Err(EvaluationError::InvalidDataUrl(
+                "only base64 data URLs are supported".into(),
+            ));
+        }
+        let started = Instant::now();
+        let bytes = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, data)
+            .map_err(|e| EvaluationError::InvalidDataUrl(e.to_string()))?;
+        let image = decoder.decode(&bytes)?;
+        let load_ms = started.elapsed().as_millis() as u64;
+        let mut result = self.evaluate_image(&image)?;
+        if let Some(perf) = result.perf.as_mut() {
+            perf.load_ms = Some(load_ms);
+            perf.peak_memory_bytes += bytes.len() as u64;
+        }
+        Ok(result)
+    }
+
+    /// Evaluates an in-memory composite image.
+    pub fn evaluate_image(&self, image: &RgbaImage) -> Result<EvaluationResult, EvaluationError> {
+        let started = Instant::now();
+        let (reference, observation) = self.extract_panes(image)?;
+        let extract_ms = started.elapsed().as_millis() as u64;
+        let mut result = self.evaluate_arrays(&reference, &observation)?;
+        attach_extract_stats(&mut result, extract_ms, image);
+        Ok(result)
+    }
+
+    /// [`Self::evaluate_image`] with swap detection against the
+    /// exercise's known reference mask. Composites exported with the
+    /// panes swapped silently produce nonsense scores; when the
+    /// observation pane matches `expected_reference` clearly better
+    /// than the reference pane does, the panes are evaluated swapped
+    /// and the result carries `panes_swapped: true`.
+    pub fn evaluate_image_checked(
+        &self,
+        image: &RgbaImage,
+        expected_reference: &Array2<u8>,
+    ) -> Result<EvaluationResult, EvaluationError> {
+        let started = Instant::now();
+        let (reference, observation) = self.extract_panes(image)?;
+        let extract_ms = started.elapsed().as_millis() as u64;
+        let target = (self.config.canvas_height, self.config.canvas_width);
+        let (expected, _) = self.normalize_pane(expected_reference, target);
+        let (reference_mask, _) = self.normalize_pane(&reference, target);
+        let (observation_mask, _) = self.normalize_pane(&observation, target);
+        let swapped = panes_look_swapped(&expected, &reference_mask, &observation_mask);
+        let mut result = if swapped {
+            self.evaluate_arrays(&observation, &reference)?
+        } else {
+            self.evaluate_arrays(&reference, &observation)?
+        };
+        result.panes_swapped = swapped;
+        attach_extract_stats(&mut result, extract_ms, image);
+        Ok(result)
+    }
+
+    /// Splits a composite into its (reference, observation) stroke masks
+    /// after validating its dimensions. Composites exported at an integer
+    /// devicePixelRatio multiple of the expected size are accepted; their
+    /// panes are rescaled during evaluation.
+    pub fn extract_panes(
+        &self,
+        image: &RgbaImage,
+    ) -> Result<(Array2<u8>, Array2<u8>), EvaluationError> {
+        let _span = tracing::debug_span!("extract_panes").entered();
+        let started = Instant::now();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let expected_width = self.config.composite_width();
+        let scale = width / expected_width.max(1);
+        if scale == 0
+            || width != expected_width * scale
+            || height != self.config.canvas_height * scale
+        {
+            return Err(EvaluationError::InvalidDimensions {
+                expected_width,
+                expected_height: self.config.canvas_height,
+                width,
+                height,
+            });
+        }
+        let pane_width = self.config.canvas_width * scale;
+        let pane_height = self.config.canvas_height * scale;
+        let reference = self.extract_pane(image, 0, pane_width, pane_height);
+        let observation = self.extract_pane(
+            image,
+            (self.config.canvas_width + self.config.pane_gap) * scale,
+            pane_width,
+            pane_height,
+        );
+        tracing::debug!(
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            scale,
+            "panes extracted"
+        );
+        Ok((reference, observation))
+    }
+
+    /// Evaluates already-extracted pixel masks (1 = stroke, 0 = background).
+    /// Panes at a different resolution than the panes' common canvas are
+    /// rescaled to it first; the applied scale factors are recorded in
+    /// the result.
+    pub fn evaluate_arrays(
+        &self,
+        reference: &Array2<u8>,
+        observation: &Array2<u8>,
+    ) -> Result<EvaluationResult, EvaluationError> {
+        self.evaluate_arrays_weighted(reference, observation, None)
+    }
+
+    /// Scores the observation against every candidate reference and
+    /// returns the full evaluation against the best match (lowest
+    /// [`crate::baseline::badness`]) plus each candidate's metrics.
+    /// For exercises that accept any of several valid solutions.
+    ///
+    /// The scan reuses each candidate's precomputed heatmap and floods
+    /// the observation only once; the winner then goes through the
+    /// normal [`Self::evaluate_arrays`] pipeline. Candidates must match
+    /// the observation's resolution.
+    pub fn evaluate_against_any(
+        &self,
+        observation: &Array2<u8>,
+        candidates: &[crate::streaming::ReferenceModel],
+    ) -> Result<BestMatch, EvaluationError> {
+        if candidates.is_empty() {
+            return Err(EvaluationError::EmptyReference);
+        }
+        let observation_heatmap = flood_fill_distances(observation, self.config.max_distance);
+        let mut candidate_metrics = Vec::with_capacity(candidates.len());
+        for candidate in candidates {
+            if candidate.pixels.dim() != observation.dim() {
+                return Err(EvaluationError::InvalidDimensions {
+                    expected_width: candidate.pixels.dim().1,
+                    expected_height: candidate.pixels.dim().0,
+                    width: observation.dim().1,
+                    height: observation.dim().0,
+                });
+            }
+            candidate_metrics.push(compute_metrics(
+                &candidate.pixels,
+                &candidate.heatmap,
+                observation,
+                &observation_heatmap,
+                None,
+                self.config.tolerance,
+                self.config.cell_tolerance_multipliers.as_deref(),
+                self.config.fit_grid_to_reference,
+                self.config.cell_aggregator,
+                self.config.normalization,
+            ));
+        }
+        let best_index = candidate_metrics
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| {
+                crate::baseline::badness(a)
+                    .partial_cmp(&crate::baseline::badness(b))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .map(|(index, _)| index)
+            .expect("candidates is non-empty");
+        let result = self.evaluate_arrays(&candidates[best_index].pixels, observation)?;
+        Ok(BestMatch {
+            best_index,
+            result,
+            candidate_metrics,
+        })
+    }
+
+    /// [`Self::evaluate_arrays`] with optional per-pixel observation
+    /// weights (e.g. stylus pressure, 0..=1): lighter pixels contribute
+    /// proportionally less error and coverage. Weights must match the
+    /// evaluation canvas; pass `None` to weight every pixel fully.
+    pub fn evaluate_arrays_weighted(
+        &self,
+        reference: &Array2<u8>,
+        observation: &Array2<u8>,
+        observation_weights: Option<&Array2<f64>>,
+    ) -> Result<EvaluationResult, EvaluationError> {
+        let started = Instant::now();
+        if reference.iter().all(|&p| p == 0) {
+            return Err(EvaluationError::EmptyReference);
+        }
+        let integrity = crate::integrity::InputDigests::of(reference, observation, &self.config);
+        let target = (self.config.canvas_height, self.config.canvas_width);
+        if let Some(weights) = observation_weights {
+            if weights.dim() != target {
+                return Err(EvaluationError::InvalidBuffer(format!(
+                    "observation weights are {}x{}, expected the {}x{} canvas",
+                    weights.dim().1,
+                    weights.dim().0,
+                    target.1,
+                    target.0
+                )));
+            }
+        }
+        let (mut reference, reference_scale) = self.normalize_pane(reference, target);
+        let (mut observation, observation_scale) = self.normalize_pane(observation, target);
+        let mut stroke_widths = None;
+        if self.config.skeletonize {
+            let reference_skeleton = skeletonize(&reference);
+            let observation_skeleton = skeletonize(&observation);
+            stroke_widths = Some((
+                mean_stroke_width(&reference, &reference_skeleton),
+                mean_stroke_width(&observation, &observation_skeleton),
+            ));
+            reference = reference_skeleton;
+            observation = observation_skeleton;
+        }
+        let mut centered_weights = None;
+        if self.config.auto_center {
+            reference = shift_array(&reference, centering_shift(&reference));
+            let shift = centering_shift(&observation);
+            observation = shift_array(&observation, shift);
+            if let Some(weights) = observation_weights {
+                centered_weights = Some(shift_array(weights, shift));
+            }
+        }
+        let observation_weights = centered_weights.as_ref().or(observation_weights);
+        let fill_span = tracing::debug_span!("heatmap_fill").entered();
+        let fill_started = Instant::now();
+        let reference_heatmap = flood_fill_distances(&reference, self.config.max_distance);
+        let filtered_pixels = match self.config.outlier_filter {
+            Some(filter) => {
+                apply_outlier_filter(&mut observation, &reference_heatmap, filter, self.config.tolerance)
+            }
+            None => 0,
+        };
+        let observation_heatmap =
+            flood_fill_distances(&observation, self.config.max_distance);
+        let heatmap_ms = fill_started.elapsed().as_millis() as u64;
+        tracing::debug!(elapsed_ms = heatmap_ms, "heatmaps filled");
+        drop(fill_span);
+        let metrics_span = tracing::debug_span!("compute_metrics").entered();
+        let metrics_started = Instant::now();
+        let metrics = compute_metrics(
+            &reference,
+            &reference_heatmap,
+            &observation,
+            &observation_heatmap,
+            observation_weights,
+            self.config.tolerance,
+            self.config.cell_tolerance_multipliers.as_deref(),
+            self.config.fit_grid_to_reference,
+            self.config.cell_aggregator,
+            self.config.normalization,
+        );
+        let problem_regions = compute_problem_regions(&metrics.grid, &reference);
+        let correction_vectors = correction_vectors(&problem_regions, &reference);
+        let metrics_ms = metrics_started.elapsed().as_millis() as u64;
+        tracing::debug!(elapsed_ms = metrics_ms, "metrics computed");
+        drop(metrics_span);
+        let perf = self.config.collect_perf_stats.then(|| {
+            let canvas_pixels = (target.0 * target.1) as u64;
+            PerfStats {
+                load_ms: None,
+                extract_ms: None,
+                heatmap_ms,
+                metrics_ms,
+                reference_pixels: reference.iter().filter(|&&p| p != 0).count() as u64,
+                observation_pixels: observation.iter().filter(|&&p| p != 0).count() as u64,
+                canvas_pixels,
+                // Two u8 masks and two i32 heatmaps per pane pair, plus
+                // the f64 weight plane when one was supplied.
+                peak_memory_bytes: canvas_pixels
+                    * (2 + 2 * 4 + if observation_weights.is_some() { 8 } else { 0 }),
+            }
+        });
+        Ok(EvaluationResult {
+            schema_version: crate::schema::SCHEMA_VERSION,
+            metrics,
+            duration_ms: started.elapsed().as_millis() as u64,
+            reference_scale,
+            observation_scale,
+            reference_stroke_width: stroke_widths.map(|(reference, _)| reference),
+            observation_stroke_width: stroke_widths.map(|(_, observation)| observation),
+            overtime_ms: None,
+            filtered_pixels,
+            panes_swapped: false,
+            problem_regions,
+            correction_vectors,
+            integrity: Some(integrity),
+            perf,
+        })
+    }
+
+    /// Rescales a pane to `target` if needed, returning the scale factor
+    /// that was applied (source width over target width).
+    fn normalize_pane(
+        &self,
+        pane: &Array2<u8>,
+        target: (usize, usize),
+    ) -> (Array2<u8>, f64) {
+        if pane.dim() == target {
+            return (pane.clone(), 1.0);
+        }
+        let scale = pane.dim().1 as f64 / target.1 as f64;
+        (
+            resample_mask(pane, target.0, target.1, self.config.resample),
+            scale,
+        )
+    }
+
+    /// Extracts one pane of the composite into a binary stroke mask.
+    fn extract_pane(
+        &self,
+        image: &RgbaImage,
+        x_offset: usize,
+        pane_width: usize,
+        pane_height: usize,
+    ) -> Array2<u8> {
+        let transparent = self.config.transparent_background;
+        let plane = if transparent {
+            Some(crate::decode::mask_channel(true))
+        } else {
+            self.config.ink_channel.plane()
+        };
+        match plane {
+            Some(plane) => {
+                let channel = crate::decode::channel_view(image, plane);
+                let pane =
+                    channel.slice(ndarray::s![..pane_height, x_offset..x_offset + pane_width]);
+                crate::decode::mask_from_view(&pane, transparent)
+            }
+            // Channel combinations cannot be viewed zero-copy; compute
+            // the combined plane and threshold it like the red path.
+            None => {
+                let values = crate::decode::ink_values(image, self.config.ink_channel);
+                let pane =
+                    values.slice(ndarray::s![..pane_height, x_offset..x_offset + pane_width]);
+                pane.mapv(|value| u8::from(value < 128))
+            }
+        }
+    }
+}
+
+/// The result of scoring one composite image.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvaluationResult {
+    /// Layout version of this JSON document; see [`crate::schema`].
+    /// Results stored before versioning deserialize as version 1.
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32,
+    pub metrics: ErrorMetrics,
+    /// Wall-clock time spent evaluating, in milliseconds.
+    pub duration_ms: u64,
+    /// Factor by which the reference pane was scaled down to the
+    /// evaluation resolution (1.0 when it already matched).
+    #[serde(default = "unit_scale")]
+    pub reference_scale: f64,
+    /// Factor by which the observation pane was scaled down.
+    #[serde(default = "unit_scale")]
+    pub observation_scale: f64,
+    /// Mean reference stroke width in pixels, measured when
+    /// skeletonization is enabled.
+    #[serde(default)]
+    pub reference_stroke_width: Option<f64>,
+    /// Mean observation stroke width in pixels.
+    #[serde(default)]
+    pub observation_stroke_width: Option<f64>,
+    /// How long the drawing ran past the exercise time limit, when one
+    /// was enforced.
+    #[serde(default)]
+    pub overtime_ms: Option<u64>,
+    /// Observation pixels dropped by the configured outlier filter.
+    #[serde(default)]
+    pub filtered_pixels: u64,
+    /// The panes looked swapped against the expected reference and were
+    /// evaluated in the corrected order.
+    #[serde(default)]
+    pub panes_swapped: bool,
+    /// Clusters of adjacent high-error grid cells, worst first.
+    #[serde(default)]
+    pub problem_regions: Vec<ProblemRegion>,
+    /// Corrective arrows from misplaced clusters toward the nearest
+    /// reference content, in region order.
+    #[serde(default)]
+    pub correction_vectors: Vec<CorrectionVector>,
+    /// SHA-256 digests of the panes and configuration this score was
+    /// computed from, for later re-verification. Older results omit
+    /// them.
+    #[serde(default)]
+    pub integrity: Option<crate::integrity::InputDigests>,
+    /// Resource accounting of this evaluation, when
+    /// [`EvaluatorConfig::collect_perf_stats`] was set.
+    #[serde(default)]
+    pub perf: Option<PerfStats>,
+}
+
+/// Resource accounting of one evaluation, collected when
+/// [`EvaluatorConfig::collect_perf_stats`] is set. The numbers mirror
+/// what the tracing spans log, but travel with the result so the
+/// grading server can do capacity planning without external profilers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PerfStats {
+    /// Reading and decoding the input, when the evaluation started from
+    /// a file or data URL.
+    #[serde(default)]
+    pub load_ms: Option<u64>,
+    /// Splitting the composite into panes, when the evaluation started
+    /// from an image rather than pre-extracted masks.
+    #[serde(default)]
+    pub extract_ms: Option<u64>,
+    /// Flood-filling the reference and observation heatmaps.
+    pub heatmap_ms: u64,
+    /// Aggregating the heatmaps into metrics, regions and vectors.
+    pub metrics_ms: u64,
+    /// "On" pixels in the reference pane, after normalization.
+    pub reference_pixels: u64,
+    /// "On" pixels in the observation pane, after normalization and
+    /// outlier filtering.
+    pub observation_pixels: u64,
+    /// Pixels per pane at the evaluation resolution.
+    pub canvas_pixels: u64,
+    /// Estimated peak working set in bytes: the pane masks and both
+    /// heatmaps, plus the decoded composite and raw input bytes when
+    /// the evaluation started from one.
+    pub peak_memory_bytes: u64,
+}
+
+/// The outcome of scoring one observation against several candidate
+/// references; see [`ImageEvaluator::evaluate_against_any`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BestMatch {
+    /// Index of the winning candidate in the slice passed in.
+    pub best_index: usize,
+    /// The full evaluation against the winning candidate.
+    pub result: EvaluationResult,
+    /// Every candidate's metrics, in candidate order.
+    pub candidate_metrics: Vec<ErrorMetrics>,
+}
+
+/// Folds the pane-extraction stage into a result's perf stats: its
+/// timing, plus the decoded composite that stays alive for the whole
+/// evaluation.
+fn attach_extract_stats(result: &mut EvaluationResult, extract_ms: u64, image: &RgbaImage) {
+    if let Some(perf) = result.perf.as_mut() {
+        perf.extract_ms = Some(extract_ms);
+        perf.peak_memory_bytes += 4 * u64::from(image.width()) * u64::from(image.height());
+    }
+}
+
+fn unit_scale() -> f64 {
+    1.0
+}
+
+fn legacy_schema_version() -> u32 {
+    1
+}
+
+/// How much better the observation pane must match the expected
+/// reference before panes are considered swapped; the margin keeps a
+/// very faithful drawing from triggering a false positive.
+const SWAP_MARGIN: f64 = 1.2;
+
+/// Heuristic swap check: the panes of a composite look swapped when the
+/// observation pane overlaps the expected reference clearly better than
+/// the reference pane does. All three masks must share one canvas size.
+pub fn panes_look_swapped(
+    expected: &Array2<u8>,
+    reference: &Array2<u8>,
+    observation: &Array2<u8>,
+) -> bool {
+    let observation_match = mask_iou(expected, observation);
+    observation_match > 0.0 && observation_match > mask_iou(expected, reference) * SWAP_MARGIN
+}
+
+/// Offset that moves a mask's content centroid onto the canvas centre;
+/// zero for an empty mask.
+fn centering_shift(mask: &Array2<u8>) -> (isize, isize) {
+    let mut count = 0usize;
+    let mut sum_y = 0.0;
+    let mut sum_x = 0.0;
+    for ((y, x), &on) in mask.indexed_iter() {
+        if on != 0 {
+            count += 1;
+            sum_y += y as f64;
+            sum_x += x as f64;
+        }
+    }
+    if count == 0 {
+        return (0, 0);
+    }
+    let (height, width) = mask.dim();
+    let dy = height as f64 / 2.0 - sum_y / count as f64;
+    let dx = width as f64 / 2.0 - sum_x / count as f64;
+    (dy.round() as isize, dx.round() as isize)
+}
+
+/// Translates an array by `(dy, dx)`, dropping content shifted off the
+/// canvas and zero-filling the vacated cells.
+fn shift_array<T: Copy + Default>(array: &Array2<T>, (dy, dx): (isize, isize)) -> Array2<T> {
+    let (height, width) = array.dim();
+    let mut shifted = Array2::default((height, width));
+    for ((y, x), &value) in array.indexed_iter() {
+        let ny = y as isize + dy;
+        let nx = x as isize + dx;
+        if ny >= 0 && nx >= 0 && (ny as usize) < height && (nx as usize) < width {
+            shifted[(ny as usize, nx as usize)] = value;
+        }
+    }
+    shifted
+}
+
+/// Intersection-over-union of two equally sized stroke masks.
+fn mask_iou(a: &Array2<u8>, b: &Array2<u8>) -> f64 {
+    let mut intersection = 0u64;
+    let mut union = 0u64;
+    for (&left, &right) in a.iter().zip(b.iter()) {
+        match (left != 0, right != 0) {
+            (true, true) => {
+                intersection += 1;
+                union += 1;
+            }
+            (false, false) => {}
+            _ => union += 1,
+        }
+    }
+    if union == 0 {
+        0.0
+    } else {
+        intersection as f64 / union as f64
+    }
+}
+
+/// Applies the configured stray-mark filter to the observation mask,
+/// returning how many pixels were dropped.
+fn apply_outlier_filter(
+    observation: &mut Array2<u8>,
+    reference_heatmap: &Array2<i32>,
+    filter: OutlierFilter,
+    tolerance: i32,
+) -> u64 {
+    match filter {
+        OutlierFilter::MinComponentSize(min_size) => drop_small_components(observation, min_size),
+        OutlierFilter::TrimWorstFraction(fraction) => {
+            trim_worst_pixels(observation, reference_heatmap, fraction.clamp(0.0, 1.0), tolerance)
+        }
+    }
+}
+
+/// Zeroes eight-connected components smaller than `min_size` pixels.
+fn drop_small_components(observation: &mut Array2<u8>, min_size: usize) -> u64 {
+    let (height, width) = observation.dim();
+    let mut visited = Array2::<u8>::zeros((height, width));
+    let mut dropped = 0u64;
+    for y in 0..height {
+        for x in 0..width {
+            if observation[(y, x)] == 0 || visited[(y, x)] != 0 {
+                continue;
+            }
+            let mut members = Vec::new();
+            let mut queue = std::collections::VecDeque::from([(y, x)]);
+            visited[(y, x)] = 1;
+            while let Some((cy, cx)) = queue.pop_front() {
+                members.push((cy, cx));
+                for dy in -1i32..=1 {
+                    for dx in -1i32..=1 {
+                        let ny = cy as i32 + dy;
+                        let nx = cx as i32 + dx;
+                        if ny < 0 || nx < 0 || ny >= height as i32 || nx >= width as i32 {
+                            continue;
+                        }
+                        let pos = (ny as usize, nx as usize);
+                        if observation[pos] != 0 && visited[pos] == 0 {
+                            visited[pos] = 1;
+                            queue.push_back(pos);
+                        }
+                    }
+                }
+            }
+            if members.len() < min_size {
+                dropped += members.len() as u64;
+                for pos in members {
+                    observation[pos] = 0;
+                }
+            }
+        }
+    }
+    dropped
+}
+
+/// Zeroes up to `fraction` of the observation pixels, worst distances
+/// first, never touching pixels within the coverage tolerance.
+fn trim_worst_pixels(
+    observation: &mut Array2<u8>,
+    reference_heatmap: &Array2<i32>,
+    fraction: f64,
+    tolerance: i32,
+) -> u64 {
+    let mut pixels: Vec<((usize, usize), i32)> = observation
+        .indexed_iter()
+        .filter(|(_, &on)| on != 0)
+        .map(|(pos, _)| (pos, reference_heatmap[pos].max(0)))
+        .collect();
+    let budget = (pixels.len() as f64 * fraction).round() as usize;
+    pixels.sort_by_key(|&(_, distance)| std::cmp::Reverse(distance));
+    let mut dropped = 0u64;
+    for &(pos, distance) in pixels.iter().take(budget) {
+        if distance <= tolerance {
+            break;
+        }
+        observation[pos] = 0;
+        dropped += 1;
+    }
+    dropped
+}
+
+/// Mean stroke width estimated as stroke area over skeleton length.
+fn mean_stroke_width(mask: &Array2<u8>, skeleton: &Array2<u8>) -> f64 {
+    let area = mask.iter().filter(|&&p| p != 0).count() as f64;
+    let length = skeleton.iter().filter(|&&p| p != 0).count() as f64;
+    if length == 0.0 {
+        0.0
+    } else {
+        area / length
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn the_best_matching_candidate_wins_and_all_are_scored() {
+        use crate::streaming::ReferenceModel;
+
+        let config = EvaluatorConfig::default();
+        let evaluator = ImageEvaluator::new(config.clone());
+        let mut top = Array2::zeros((500, 500));
+        let mut bottom = Array2::zeros((500, 500));
+        let mut observation = Array2::zeros((500, 500));
+        for x in 100..400 {
+            top[(100, x)] = 1;
+            bottom[(400, x)] = 1;
+            observation[(402, x)] = 1;
+        }
+        let candidates = [
+            ReferenceModel::new(top, config.clone()).unwrap(),
+            ReferenceModel::new(bottom, config).unwrap(),
+        ];
+        let best = evaluator
+            .evaluate_against_any(&observation, &candidates)
+            .unwrap();
+        assert_eq!(best.best_index, 1);
+        assert_eq!(best.candidate_metrics.len(), 2);
+        assert!(best.candidate_metrics[1].mean_error < best.candidate_metrics[0].mean_error);
+        assert_eq!(best.result.metrics, best.candidate_metrics[1]);
+        assert!(evaluator.evaluate_against_any(&observation, &[]).is_err());
+    }
+
+    fn composite_with_strokes() -> RgbaImage {
+        let config = EvaluatorConfig::default();
+        let mut image = RgbaImage::new(config.composite_width() as u32, config.canvas_height as u32);
+        let ink = Rgba([0, 0, 0, 255]);
+        for x in 100..400u32 {
+            image.put_pixel(x, 250, ink);
+            image.put_pixel(x + 510, 250, ink);
+        }
+        image
+    }
+
+    #[test]
+    fn min_rgb_ink_channel_sees_pure_red_strokes_on_white() {
+        let config = EvaluatorConfig {
+            transparent_background: false,
+            ..EvaluatorConfig::default()
+        };
+        let mut image = RgbaImage::from_pixel(
+            config.composite_width() as u32,
+            config.canvas_height as u32,
+            Rgba([255, 255, 255, 255]),
+        );
+        // The same stroke in both panes, drawn in pure red.
+        for x in 100..400u32 {
+            image.put_pixel(x, 250, Rgba([255, 0, 0, 255]));
+            image.put_pixel(x + 510, 250, Rgba([255, 0, 0, 255]));
+        }
+        // The red channel reads the strokes as background: nothing to
+        // score, so the reference comes up empty.
+        let invisible = ImageEvaluator::new(config.clone()).evaluate_image(&image);
+        assert!(matches!(invisible, Err(EvaluationError::EmptyReference)));
+        let result = ImageEvaluator::new(EvaluatorConfig {
+            ink_channel: InkChannel::MinRgb,
+            ..config
+        })
+        .evaluate_image(&image)
+        .unwrap();
+        assert_eq!(result.metrics.top_5_error, 0.0);
+        assert_eq!(result.metrics.coverage, 1.0);
+    }
+
+    #[test]
+    fn identical_panes_evaluate_perfectly() {
+        let result = ImageEvaluator::default()
+            .evaluate_image(&composite_with_strokes())
+            .unwrap();
+        assert_eq!(result.metrics.top_5_error, 0.0);
+        assert_eq!(result.metrics.coverage, 1.0);
+    }
+
+    #[test]
+    fn double_resolution_composite_is_normalized() {
+        let config = EvaluatorConfig::default();
+        let mut image =
+            RgbaImage::new(config.composite_width() as u32 * 2, config.canvas_height as u32 * 2);
+        let ink = Rgba([0, 0, 0, 255]);
+        // The same stroke drawn at devicePixelRatio 2 in both panes.
+        for x in 200..800u32 {
+            for y in 500..502u32 {
+                image.put_pixel(x, y, ink);
+                image.put_pixel(x + 1020, y, ink);
+            }
+        }
+        let result = ImageEvaluator::default().evaluate_image(&image).unwrap();
+        assert_eq!(result.reference_scale, 2.0);
+        assert_eq!(result.observation_scale, 2.0);
+        assert_eq!(result.metrics.top_5_error, 0.0);
+        assert_eq!(result.metrics.coverage, 1.0);
+    }
+
+    #[test]
+    fn oversized_observation_mask_is_rescaled_to_the_reference() {
+        let mut reference = Array2::<u8>::zeros((500, 500));
+        let mut observation = Array2::<u8>::zeros((1000, 1000));
+        for x in 100..400 {
+            reference[(250, x)] = 1;
+        }
+        for x in 200..800 {
+            observation[(500, x)] = 1;
+            observation[(501, x)] = 1;
+        }
+        let result = ImageEvaluator::default()
+            .evaluate_arrays(&reference, &observation)
+            .unwrap();
+        assert_eq!(result.reference_scale, 1.0);
+        assert_eq!(result.observation_scale, 2.0);
+        assert_eq!(result.metrics.top_5_error, 0.0);
+    }
+
+    #[test]
+    fn auto_center_forgives_an_off_center_reference() {
+        let mut reference = Array2::<u8>::zeros((500, 500));
+        let mut observation = Array2::<u8>::zeros((500, 500));
+        // Same line, authored near the top-left of the reference pane
+        // but drawn centred by the user.
+        for x in 0..300 {
+            reference[(50, x)] = 1;
+            observation[(250, x + 100)] = 1;
+        }
+        let skewed = ImageEvaluator::default()
+            .evaluate_arrays(&reference, &observation)
+            .unwrap();
+        assert!(skewed.metrics.top_5_error > 0.0);
+        let centered = ImageEvaluator::new(EvaluatorConfig {
+            auto_center: true,
+            ..EvaluatorConfig::default()
+        })
+        .evaluate_arrays(&reference, &observation)
+        .unwrap();
+        assert_eq!(centered.metrics.top_5_error, 0.0);
+        assert_eq!(centered.metrics.coverage, 1.0);
+    }
+
+    fn expected_reference_line() -> Array2<u8> {
+        let mut expected = Array2::<u8>::zeros((500, 500));
+        for x in 100..400 {
+            expected[(250, x)] = 1;
+        }
+        expected
+    }
+
+    #[test]
+    fn swapped_panes_are_detected_and_evaluated_corrected() {
+        let config = EvaluatorConfig::default();
+        let mut image = RgbaImage::new(config.composite_width() as u32, config.canvas_height as u32);
+        let ink = Rgba([0, 0, 0, 255]);
+        // The user's (vertical) drawing ended up in the left pane and the
+        // exercise reference (horizontal) in the right one.
+        for y in 100..400u32 {
+            image.put_pixel(250, y, ink);
+        }
+        for x in 100..400u32 {
+            image.put_pixel(x + 510, 250, ink);
+        }
+        let result = ImageEvaluator::default()
+            .evaluate_image_checked(&image, &expected_reference_line())
+            .unwrap();
+        assert!(result.panes_swapped);
+        // Evaluated in the corrected order: the horizontal line is the
+        // reference, so the vertical drawing only covers its crossing.
+        assert!(result.metrics.coverage < 0.1, "{}", result.metrics.coverage);
+    }
+
+    #[test]
+    fn correctly_ordered_panes_are_not_flagged_as_swapped() {
+        let result = ImageEvaluator::default()
+            .evaluate_image_checked(&composite_with_strokes(), &expected_reference_line())
+            .unwrap();
+        assert!(!result.panes_swapped);
+        assert_eq!(result.metrics.coverage, 1.0);
+    }
+
+    #[test]
+    fn rgba_buffer_matches_image_evaluation() {
+        let image = composite_with_strokes();
+        let via_image = ImageEvaluator::default().evaluate_image(&image).unwrap();
+        let via_buffer = ImageEvaluator::default()
+            .evaluate_rgba_buffer(
+                image.as_raw(),
+                image.width() as usize,
+                image.height() as usize,
+            )
+            .unwrap();
+        assert_eq!(via_buffer.metrics, via_image.metrics);
+    }
+
+    #[test]
+    fn undersized_rgba_buffer_is_rejected() {
+        let error = ImageEvaluator::default()
+            .evaluate_rgba_buffer(&[0; 16], 500, 500)
+            .unwrap_err();
+        assert!(matches!(error, EvaluationError::InvalidBuffer(_)));
+    }
+
+    #[test]
+    fn data_url_round_trips_through_png() {
+        let image = composite_with_strokes();
+        let mut png = std::io::Cursor::new(Vec::new());
+        image
+            .write_to(&mut png, image::ImageOutputFormat::Png)
+            .unwrap();
+        let encoded =
+            base64::Engine::encode(&base64::engine::general_purpose::STANDARD, png.get_ref());
+        let result = ImageEvaluator::default()
+            .evaluate_data_url(&format!("data:image/png;base64,{encoded}"))
+            .unwrap();
+        assert_eq!(result.metrics.top_5_error, 0.0);
+    }
+
+    #[test]
+    fn custom_decoder_supplies_pre_decoded_pixels() {
+        struct CannedDecoder(RgbaImage);
+        impl crate::decode::Decoder for CannedDecoder {
+            fn decode(&self, _bytes: &[u8]) -> Result<RgbaImage, EvaluationError> {
+                Ok(self.0.clone())
+            }
+        }
+        let decoder = CannedDecoder(composite_with_strokes());
+        let result = ImageEvaluator::default()
+            .evaluate_data_url_with("data:image/png;base64,bm90IGEgcG5n", &decoder)
+            .unwrap();
+        assert_eq!(result.metrics.coverage, 1.0);
+    }
+
+    #[test]
+    fn non_base64_data_url_is_rejected() {
+        let error = ImageEvaluator::default()
+            .evaluate_data_url("data:image/png,rawbytes")
+            .unwrap_err();
+        assert!(matches!(error, EvaluationError::InvalidDataUrl(_)));
+    }
+
+    #[test]
+    fn skeletonization_forgives_a_thick_brush() {
+        let mut reference = Array2::<u8>::zeros((500, 500));
+        let mut observation = Array2::<u8>::zeros((500, 500));
+        for x in 100..400 {
+            reference[(250, x)] = 1;
+            // The same stroke drawn with a wide brush.
+            for y in 245..256 {
+                observation[(y, x)] = 1;
+            }
+        }
+        let plain = ImageEvaluator::default()
+            .evaluate_arrays(&reference, &observation)
+            .unwrap();
+        assert!(plain.metrics.top_5_error > 0.0);
+        assert_eq!(plain.reference_stroke_width, None);
+
+        let skeletonized = ImageEvaluator::new(EvaluatorConfig {
+            skeletonize: true,
+            ..EvaluatorConfig::default()
+        })
+        .evaluate_arrays(&reference, &observation)
+        .unwrap();
+        assert!(skeletonized.metrics.top_5_error < plain.metrics.top_5_error);
+        assert!((skeletonized.reference_stroke_width.unwrap() - 1.0).abs() < 1e-9);
+        assert!(skeletonized.observation_stroke_width.unwrap() > 5.0);
+    }
+
+    #[test]
+    fn max_distance_bounds_the_error_of_a_stray_stroke() {
+        let mut reference = Array2::<u8>::zeros((500, 500));
+        let mut observation = Array2::<u8>::zeros((500, 500));
+        for x in 100..400 {
+            reference[(250, x)] = 1;
+        }
+        observation[(10, 10)] = 1;
+        let unclamped = ImageEvaluator::default()
+            .evaluate_arrays(&reference, &observation)
+            .unwrap();
+        let clamped = ImageEvaluator::new(EvaluatorConfig {
+            max_distance: Some(20),
+            ..EvaluatorConfig::default()
+        })
+        .evaluate_arrays(&reference, &observation)
+        .unwrap();
+        assert!(clamped.metrics.top_5_error < unclamped.metrics.top_5_error);
+        // Five cells at most, each capped at the clamp value.
+        assert!(clamped.metrics.top_5_error <= 5.0 * 20.0 / 25.0);
+    }
+
+    #[test]
+    fn small_components_are_filtered_as_stray_marks() {
+        let mut reference = Array2::<u8>::zeros((500, 500));
+        let mut observation = Array2::<u8>::zeros((500, 500));
+        for x in 100..400 {
+            reference[(250, x)] = 1;
+            observation[(250, x)] = 1;
+        }
+        observation[(10, 10)] = 1;
+        let unfiltered = ImageEvaluator::default()
+            .evaluate_arrays(&reference, &observation)
+            .unwrap();
+        assert!(unfiltered.metrics.top_5_error > 0.0);
+        let filtered = ImageEvaluator::new(EvaluatorConfig {
+            outlier_filter: Some(OutlierFilter::MinComponentSize(5)),
+            ..EvaluatorConfig::default()
+        })
+        .evaluate_arrays(&reference, &observation)
+        .unwrap();
+        assert_eq!(filtered.metrics.top_5_error, 0.0);
+        assert_eq!(filtered.filtered_pixels, 1);
+    }
+
+    #[test]
+    fn trimming_never_touches_pixels_within_tolerance() {
+        let mut reference = Array2::<u8>::zeros((500, 500));
+        let mut observation = Array2::<u8>::zeros((500, 500));
+        for x in 100..400 {
+            reference[(250, x)] = 1;
+            observation[(250, x)] = 1;
+        }
+        observation[(10, 10)] = 1;
+        let result = ImageEvaluator::new(EvaluatorConfig {
+            outlier_filter: Some(OutlierFilter::TrimWorstFraction(0.5)),
+            ..EvaluatorConfig::default()
+        })
+        .evaluate_arrays(&reference, &observation)
+        .unwrap();
+        // The generous budget only spends on the one stray pixel.
+        assert_eq!(result.filtered_pixels, 1);
+        assert_eq!(result.metrics.top_5_error, 0.0);
+        assert_eq!(result.metrics.coverage, 1.0);
+    }
+
+    #[test]
+    fn perf_stats_are_off_by_default() {
+        let result = ImageEvaluator::default()
+            .evaluate_image(&composite_with_strokes())
+            .unwrap();
+        assert!(result.perf.is_none());
+    }
+
+    #[test]
+    fn perf_stats_account_stages_pixels_and_memory() {
+        let result = ImageEvaluator::new(EvaluatorConfig {
+            collect_perf_stats: true,
+            ..EvaluatorConfig::default()
+        })
+        .evaluate_image(&composite_with_strokes())
+        .unwrap();
+        let perf = result.perf.unwrap();
+        // Started from an in-memory image: extraction ran, loading did not.
+        assert!(perf.extract_ms.is_some());
+        assert_eq!(perf.load_ms, None);
+        assert_eq!(perf.reference_pixels, 300);
+        assert_eq!(perf.observation_pixels, 300);
+        assert_eq!(perf.canvas_pixels, 500 * 500);
+        // Masks and heatmaps for both panes, plus the RGBA composite.
+        let composite_bytes = 4 * 1010 * 500;
+        assert_eq!(perf.peak_memory_bytes, 500 * 500 * 10 + composite_bytes);
+    }
+
+    #[test]
+    fn perf_stats_time_the_load_of_a_data_url() {
+        let image = composite_with_strokes();
+        let mut png = std::io::Cursor::new(Vec::new());
+        image
+            .write_to(&mut png, image::ImageOutputFormat::Png)
+            .unwrap();
+        let encoded =
+            base64::Engine::encode(&base64::engine::general_purpose::STANDARD, png.get_ref());
+        let result = ImageEvaluator::new(EvaluatorConfig {
+            collect_perf_stats: true,
+            ..EvaluatorConfig::default()
+        })
+        .evaluate_data_url(&format!("data:image/png;base64,{encoded}"))
+        .unwrap();
+        let perf = result.perf.unwrap();
+        assert!(perf.load_ms.is_some());
+        // The raw PNG bytes join the working-set estimate.
+        let composite_bytes = 4 * 1010 * 500;
+        assert_eq!(
+            perf.peak_memory_bytes,
+            500 * 500 * 10 + composite_bytes + png.get_ref().len() as u64
+        );
+    }
+
+    #[test]
+    fn wrong_dimensions_are_rejected() {
+        let image = RgbaImage::new(300, 300);
+        let error = ImageEvaluator::default().evaluate_image(&image).unwrap_err();
+        assert!(matches!(error, EvaluationError::InvalidDimensions { .. }));
+    }
+
+    #[test]
+    fn empty_reference_is_rejected() {
+        let config = EvaluatorConfig::default();
+        let image = RgbaImage::new(config.composite_width() as u32, config.canvas_height as u32);
+        let error = ImageEvaluator::default().evaluate_image(&image).unwrap_err();
+        assert!(matches!(error, EvaluationError::EmptyReference));
+    }
+}
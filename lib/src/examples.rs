@@ -0,0 +1,213 @@
+//! Generated demo exercises for setup verification.
+//!
+//! A new integrator's first question is "does my toolchain produce
+//! composites this crate accepts?" — and a valid 1010x500 composite is
+//! annoying to come by before the app side works. [`quickstart`] writes
+//! a few generated reference/observation pairs plus their manifests
+//! into a directory and evaluates them end-to-end through the same
+//! code paths real exports take, so a broken setup fails here instead
+//! of in production.
+
+use std::f64::consts::TAU;
+use std::path::{Path, PathBuf};
+
+use image::RgbaImage;
+use ndarray::Array2;
+
+use crate::error::EvaluationError;
+use crate::evaluator::{EvaluationResult, EvaluatorConfig, ImageEvaluator};
+use crate::manifest::ExerciseManifest;
+use crate::render::render_mask;
+
+/// One generated demo exercise, with the files written for it and the
+/// score the end-to-end evaluation produced.
+#[derive(Debug, Clone)]
+pub struct QuickstartDemo {
+    /// Short exercise name ("line", "circle", "wave").
+    pub name: String,
+    /// The 1010x500 composite PNG, ready for `evaluator evaluate`.
+    pub composite: PathBuf,
+    /// The exercise manifest, ready for `--exercise`.
+    pub manifest: PathBuf,
+    /// The score of the generated observation against its reference.
+    pub result: EvaluationResult,
+}
+
+/// Writes three generated exercises — a line, a circle and a wave, each
+/// with a slightly imperfect observation — into `directory` and
+/// evaluates every one end-to-end: the manifest is re-loaded from disk
+/// and the composite scored through [`ImageEvaluator::evaluate_file`],
+/// exactly as the CLI would. Files per exercise: `<name>.png` (the
+/// composite), `<name>.reference.png` and `<name>.manifest.json`.
+pub fn quickstart(directory: impl AsRef<Path>) -> Result<Vec<QuickstartDemo>, EvaluationError> {
+    let directory = directory.as_ref();
+    std::fs::create_dir_all(directory).map_err(|source| EvaluationError::Io {
+        path: directory.to_path_buf(),
+        source,
+    })?;
+    let config = EvaluatorConfig::default();
+    let mut demos = Vec::new();
+    for (name, title) in [
+        ("line", "Demo: horizontal line"),
+        ("circle", "Demo: circle"),
+        ("wave", "Demo: sine wave"),
+    ] {
+        let (reference, observation) = demo_panes(name, &config);
+        let reference_path = directory.join(format!("{name}.reference.png"));
+        render_mask(&reference, config.transparent_background)
+            .save(&reference_path)
+            .map_err(EvaluationError::Decode)?;
+        let composite_path = directory.join(format!("{name}.png"));
+        composite(&reference, &observation, &config)
+            .save(&composite_path)
+            .map_err(EvaluationError::Decode)?;
+        let manifest = ExerciseManifest {
+            id: format!("demo-{name}"),
+            title: title.to_string(),
+            reference: PathBuf::from(format!("{name}.reference.png")),
+            layout: Default::default(),
+            scoring: Default::default(),
+            time_limit_ms: None,
+            overtime: Default::default(),
+        };
+        let manifest_path = directory.join(format!("{name}.manifest.json"));
+        let json = serde_json::to_string_pretty(&manifest)
+            .map_err(|e| EvaluationError::InvalidManifest(e.to_string()))?;
+        std::fs::write(&manifest_path, json).map_err(|source| EvaluationError::Io {
+            path: manifest_path.clone(),
+            source,
+        })?;
+        // Round-trip through the loader and the file evaluator so the
+        // demo exercises the exact paths an integration will.
+        let loaded = ExerciseManifest::load(&manifest_path)?;
+        let result = ImageEvaluator::new(loaded.evaluator_config()).evaluate_file(&composite_path)?;
+        demos.push(QuickstartDemo {
+            name: name.to_string(),
+            composite: composite_path,
+            manifest: manifest_path,
+            result,
+        });
+    }
+    Ok(demos)
+}
+
+/// The generated reference and observation masks for one demo. The
+/// observation is deliberately a little off — shifted, shrunk or
+/// phase-lagged — so the demo scores look like a real attempt instead
+/// of a suspicious zero.
+fn demo_panes(name: &str, config: &EvaluatorConfig) -> (Array2<u8>, Array2<u8>) {
+    let (width, height) = (config.canvas_width, config.canvas_height);
+    let mut reference = Array2::zeros((height, width));
+    let mut observation = Array2::zeros((height, width));
+    let plot = |mask: &mut Array2<u8>, y: f64, x: f64| {
+        let (y, x) = (y.round() as isize, x.round() as isize);
+        if (0..height as isize).contains(&y) && (0..width as isize).contains(&x) {
+            mask[(y as usize, x as usize)] = 1;
+        }
+    };
+    let (center_y, center_x) = (height as f64 / 2.0, width as f64 / 2.0);
+    match name {
+        "line" => {
+            // Slightly low and shifted right: the overlap is covered
+            // within tolerance, the overshot ends are not.
+            for x in width / 5..width * 4 / 5 {
+                plot(&mut reference, center_y, x as f64);
+                plot(&mut observation, center_y + 2.0, x as f64 + 10.0);
+            }
+        }
+        "circle" => {
+            // A wobbly freehand circle around the reference radius.
+            for step in 0..2000 {
+                let angle = TAU * f64::from(step) / 2000.0;
+                let radius = 148.0 + 4.0 * (3.0 * angle).sin();
+                plot(
+                    &mut reference,
+                    center_y + 150.0 * angle.sin(),
+                    center_x + 150.0 * angle.cos(),
+                );
+                plot(
+                    &mut observation,
+                    center_y + radius * angle.sin(),
+                    center_x + radius * angle.cos(),
+                );
+            }
+        }
+        _ => {
+            for x in width / 10..width * 9 / 10 {
+                let phase = x as f64 / 40.0;
+                plot(&mut reference, center_y + 60.0 * phase.sin(), x as f64);
+                plot(
+                    &mut observation,
+                    center_y + 55.0 * (phase - 0.15).sin(),
+                    x as f64,
+                );
+            }
+        }
+    }
+    (reference, observation)
+}
+
+/// Assembles the app's export layout: reference pane, gap, observation
+/// pane, rendered with [`render_mask`]'s background convention.
+fn composite(
+    reference: &Array2<u8>,
+    observation: &Array2<u8>,
+    config: &EvaluatorConfig,
+) -> RgbaImage {
+    let mut image = RgbaImage::new(
+        config.composite_width() as u32,
+        config.canvas_height as u32,
+    );
+    let panes = [
+        (render_mask(reference, config.transparent_background), 0u32),
+        (
+            render_mask(observation, config.transparent_background),
+            (config.canvas_width + config.pane_gap) as u32,
+        ),
+    ];
+    for (pane, offset) in panes {
+        for (x, y, pixel) in pane.enumerate_pixels() {
+            image.put_pixel(x + offset, y, *pixel);
+        }
+    }
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("evaluator-{name}-test"));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn quickstart_writes_and_scores_three_demo_exercises() {
+        let dir = temp_dir("quickstart");
+        let demos = quickstart(&dir).unwrap();
+        assert_eq!(demos.len(), 3);
+        for demo in &demos {
+            assert!(demo.composite.exists());
+            assert!(demo.manifest.exists());
+            assert!(dir.join(format!("{}.reference.png", demo.name)).exists());
+            // Deliberately imperfect observations: scored, not perfect.
+            assert!(demo.result.metrics.top_5_error > 0.0, "{}", demo.name);
+            assert!(demo.result.metrics.coverage > 0.0, "{}", demo.name);
+        }
+    }
+
+    #[test]
+    fn generated_composites_evaluate_through_the_cli_path() {
+        let dir = temp_dir("quickstart-cli");
+        let demos = quickstart(&dir).unwrap();
+        // The manifests round-trip through the loader and reproduce the
+        // quickstart scores.
+        let manifest = ExerciseManifest::load(&demos[0].manifest).unwrap();
+        let evaluator = ImageEvaluator::new(manifest.evaluator_config());
+        let rescored = evaluator.evaluate_file(&demos[0].composite).unwrap();
+        assert_eq!(rescored.metrics, demos[0].result.metrics);
+    }
+}
@@ -0,0 +1,219 @@
+//! Human-readable score explanations.
+//!
+//! Raw metrics tell a grader *how much* was wrong; students want to
+//! know *what*. [`EvaluationResult::explain`] derives a short list of
+//! structured findings from the data the result already carries — the
+//! scoring grid, coverage, problem regions and correction vectors — so
+//! UIs can show "largest deviation in upper-right: 23px" instead of a
+//! bare number. Findings are ordered most important first; a perfect
+//! result explains to an empty list.
+
+use serde::{Deserialize, Serialize};
+
+use crate::evaluator::EvaluationResult;
+
+/// What a finding is about, so UIs can pick icons or phrasing without
+/// parsing the message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    /// The grid cell with the largest error.
+    LargestDeviation,
+    /// Reference pixels no stroke came close to.
+    UncoveredReference,
+    /// A consistent displacement across the misplaced clusters.
+    OverallShift,
+    /// Stray marks removed by the outlier filter.
+    FilteredInk,
+    /// The drawing ran past the exercise time limit.
+    Overtime,
+    /// The panes arrived swapped and were evaluated corrected.
+    PanesSwapped,
+}
+
+/// One thing worth telling the user about their score.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Finding {
+    pub kind: FindingKind,
+    /// Ready-to-display sentence fragment, e.g. "largest deviation in
+    /// upper-right: 23px".
+    pub message: String,
+    /// The magnitude behind the message: pixels for deviations and
+    /// shifts, a `0..=1` fraction for coverage, counts and
+    /// milliseconds for the rest.
+    pub value: f64,
+}
+
+/// Misplacements below this many pixels are noise, not findings.
+const DEVIATION_FLOOR: f64 = 1.0;
+/// Uncovered fractions below this round to "covered" for explanation
+/// purposes; the tolerance already forgives near misses.
+const UNCOVERED_FLOOR: f64 = 0.01;
+
+impl EvaluationResult {
+    /// Derives display-ready findings from this result, most important
+    /// first. A result with nothing notable explains to an empty list.
+    pub fn explain(&self) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        if let Some(region) = self.problem_regions.first() {
+            if region.severity >= DEVIATION_FLOOR {
+                findings.push(Finding {
+                    kind: FindingKind::LargestDeviation,
+                    message: format!(
+                        "largest deviation in {}: {:.0}px",
+                        region.label, region.severity
+                    ),
+                    value: region.severity,
+                });
+            }
+        }
+        let uncovered = 1.0 - self.metrics.coverage;
+        if uncovered >= UNCOVERED_FLOOR {
+            findings.push(Finding {
+                kind: FindingKind::UncoveredReference,
+                message: format!("{:.0}% of the reference untouched", uncovered * 100.0),
+                value: uncovered,
+            });
+        }
+        if let Some((direction, magnitude)) = self.overall_shift() {
+            findings.push(Finding {
+                kind: FindingKind::OverallShift,
+                message: format!("drawing shifted ~{magnitude:.0}px {direction} overall"),
+                value: magnitude,
+            });
+        }
+        if self.filtered_pixels > 0 {
+            findings.push(Finding {
+                kind: FindingKind::FilteredInk,
+                message: format!(
+                    "{} stray pixel(s) ignored by the outlier filter",
+                    self.filtered_pixels
+                ),
+                value: self.filtered_pixels as f64,
+            });
+        }
+        if let Some(overtime_ms) = self.overtime_ms {
+            if overtime_ms > 0 {
+                findings.push(Finding {
+                    kind: FindingKind::Overtime,
+                    message: format!("drawing ran {:.1}s past the time limit", overtime_ms as f64 / 1000.0),
+                    value: overtime_ms as f64,
+                });
+            }
+        }
+        if self.panes_swapped {
+            findings.push(Finding {
+                kind: FindingKind::PanesSwapped,
+                message: "panes looked swapped and were evaluated in the corrected order"
+                    .to_string(),
+                value: 1.0,
+            });
+        }
+        findings
+    }
+
+    /// The mean correction vector, when the misplaced clusters agree on
+    /// a direction — the "everything sits 15px left" case. Returns the
+    /// dominant compass word and the mean displacement length.
+    fn overall_shift(&self) -> Option<(&'static str, f64)> {
+        if self.correction_vectors.is_empty() {
+            return None;
+        }
+        let count = self.correction_vectors.len() as f64;
+        let (dx, dy) = self
+            .correction_vectors
+            .iter()
+            .fold((0.0, 0.0), |(dx, dy), vector| {
+                (dx + vector.to.x - vector.from.x, dy + vector.to.y - vector.from.y)
+            });
+        let (dx, dy) = (dx / count, dy / count);
+        let magnitude = (dx * dx + dy * dy).sqrt();
+        if magnitude < DEVIATION_FLOOR {
+            return None;
+        }
+        // The arrows point from the drawing toward the reference; the
+        // drawing itself is shifted the opposite way.
+        let direction = if dx.abs() >= dy.abs() {
+            if dx > 0.0 {
+                "left"
+            } else {
+                "right"
+            }
+        } else if dy > 0.0 {
+            "up"
+        } else {
+            "down"
+        };
+        Some((direction, magnitude))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::ImageEvaluator;
+    use ndarray::Array2;
+
+    fn line(y: usize, x_range: std::ops::Range<usize>) -> Array2<u8> {
+        let mut mask = Array2::zeros((500, 500));
+        for x in x_range {
+            mask[(y, x)] = 1;
+        }
+        mask
+    }
+
+    #[test]
+    fn a_perfect_result_explains_to_nothing() {
+        let reference = line(250, 100..400);
+        let result = ImageEvaluator::default()
+            .evaluate_arrays(&reference, &reference)
+            .unwrap();
+        assert!(result.explain().is_empty());
+    }
+
+    #[test]
+    fn a_shifted_drawing_yields_deviation_coverage_and_shift_findings() {
+        let reference = line(250, 100..400);
+        // The whole line drawn 40px too low.
+        let observation = line(290, 100..400);
+        let result = ImageEvaluator::default()
+            .evaluate_arrays(&reference, &observation)
+            .unwrap();
+        let findings = result.explain();
+        let kinds: Vec<FindingKind> = findings.iter().map(|f| f.kind).collect();
+        assert!(kinds.contains(&FindingKind::LargestDeviation), "{findings:?}");
+        assert!(kinds.contains(&FindingKind::UncoveredReference), "{findings:?}");
+        let deviation = findings
+            .iter()
+            .find(|f| f.kind == FindingKind::LargestDeviation)
+            .unwrap();
+        assert!(deviation.message.contains("px"), "{}", deviation.message);
+        assert!(deviation.value >= 30.0, "{deviation:?}");
+        let uncovered = findings
+            .iter()
+            .find(|f| f.kind == FindingKind::UncoveredReference)
+            .unwrap();
+        assert!(uncovered.message.contains("% of the reference untouched"));
+        if let Some(shift) = findings.iter().find(|f| f.kind == FindingKind::OverallShift) {
+            // The drawing sits below the reference: shifted down.
+            assert!(shift.message.contains("down"), "{}", shift.message);
+            // Cell-centroid granularity undershoots the true 40px.
+            assert!(shift.value >= 20.0, "{shift:?}");
+        }
+    }
+
+    #[test]
+    fn overtime_and_swapped_panes_are_called_out() {
+        let reference = line(250, 100..400);
+        let mut result = ImageEvaluator::default()
+            .evaluate_arrays(&reference, &reference)
+            .unwrap();
+        result.overtime_ms = Some(2500);
+        result.panes_swapped = true;
+        let findings = result.explain();
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].kind, FindingKind::Overtime);
+        assert_eq!(findings[0].message, "drawing ran 2.5s past the time limit");
+        assert_eq!(findings[1].kind, FindingKind::PanesSwapped);
+    }
+}
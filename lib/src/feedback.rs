@@ -0,0 +1,340 @@
+//! Finding-to-phrase mapping for user-facing feedback.
+//!
+//! [`crate::explain`] turns a result into structured findings; this
+//! module turns findings into sentences. The wording lives in a small
+//! TOML phrase book — several templates per finding kind, with
+//! placeholders for the numbers — so copy changes and translations are
+//! data edits, not frontend releases. A seed picks among the templates,
+//! keeping the feedback varied between attempts but reproducible for
+//! any given one.
+//!
+//! Only the TOML subset the phrase books need is parsed: `[section]`
+//! headers naming a [`FindingKind`] and one `phrases` string array per
+//! section. Comments and blank lines are allowed; anything else is
+//! rejected with its line number.
+
+use std::path::Path;
+
+use crate::error::EvaluationError;
+use crate::explain::{Finding, FindingKind};
+
+/// The built-in phrase book, used by [`PhraseBook::default`] and the
+/// starting point for custom ones.
+pub const DEFAULT_PHRASES: &str = r##"# Default feedback phrases.
+# Placeholders: {value} (the finding's magnitude, rounded),
+# {percent} (the magnitude as a percentage) and {message} (the raw
+# finding text).
+
+[largest_deviation]
+phrases = [
+    "The biggest gap is about {value}px — compare that area closely with the reference.",
+    "One region sits roughly {value}px off; slow down there on the next pass.",
+    "Check your proportions where the drawing drifts {value}px from the reference.",
+]
+
+[uncovered_reference]
+phrases = [
+    "About {percent}% of the reference has no strokes near it yet.",
+    "Some lines are still missing — {percent}% of the reference is untouched.",
+]
+
+[overall_shift]
+phrases = [
+    "The whole drawing is offset by about {value}px; re-check your starting point.",
+    "Everything lands roughly {value}px to one side — anchor a landmark first.",
+]
+
+[filtered_ink]
+phrases = [
+    "A few stray marks ({value}) were ignored; lift the pen between strokes.",
+]
+
+[overtime]
+phrases = [
+    "You ran past the time limit — try committing to longer strokes.",
+]
+
+[panes_swapped]
+phrases = [
+    "The panes looked swapped; the score uses the corrected order.",
+]
+"##;
+
+/// Feedback phrase templates by finding kind, loaded from TOML.
+#[derive(Debug, Clone)]
+pub struct PhraseBook {
+    /// Templates per kind, in phrase-book order.
+    phrases: Vec<(FindingKind, Vec<String>)>,
+}
+
+impl Default for PhraseBook {
+    fn default() -> Self {
+        Self::from_toml(DEFAULT_PHRASES).expect("the built-in phrase book parses")
+    }
+}
+
+impl PhraseBook {
+    /// Reads and parses a phrase book file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, EvaluationError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|source| EvaluationError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::from_toml(&text)
+    }
+
+    /// Parses a phrase book from its TOML text. Every section must name
+    /// a finding kind and hold a non-empty `phrases` array; problems
+    /// are reported with their line number.
+    pub fn from_toml(text: &str) -> Result<Self, EvaluationError> {
+        let invalid = |line: usize, problem: String| {
+            EvaluationError::InvalidPhraseBook(format!("line {}: {problem}", line + 1))
+        };
+        let mut phrases: Vec<(FindingKind, Vec<String>)> = Vec::new();
+        let mut open_array = false;
+        for (number, raw) in text.lines().enumerate() {
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if open_array {
+                let (strings, closed) = parse_string_items(line)
+                    .map_err(|problem| invalid(number, problem))?;
+                let section = &mut phrases.last_mut().expect("an open array has a section").1;
+                section.extend(strings);
+                open_array = !closed;
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+                let kind = kind_by_name(name)
+                    .ok_or_else(|| invalid(number, format!("unknown finding kind [{name}]")))?;
+                if phrases.iter().any(|(existing, _)| *existing == kind) {
+                    return Err(invalid(number, format!("duplicate section [{name}]")));
+                }
+                phrases.push((kind, Vec::new()));
+                continue;
+            }
+            if let Some(rest) = line.strip_prefix("phrases") {
+                let rest = rest.trim_start();
+                let array = rest
+                    .strip_prefix('=')
+                    .map(str::trim_start)
+                    .and_then(|value| value.strip_prefix('['))
+                    .ok_or_else(|| invalid(number, "expected `phrases = [`".to_string()))?;
+                let section = &mut phrases
+                    .last_mut()
+                    .ok_or_else(|| invalid(number, "`phrases` outside a section".to_string()))?
+                    .1;
+                let (strings, closed) =
+                    parse_string_items(array).map_err(|problem| invalid(number, problem))?;
+                section.extend(strings);
+                open_array = !closed;
+                continue;
+            }
+            return Err(invalid(number, format!("unsupported syntax: {line}")));
+        }
+        if open_array {
+            return Err(EvaluationError::InvalidPhraseBook(
+                "unterminated phrases array".to_string(),
+            ));
+        }
+        if let Some((kind, _)) = phrases.iter().find(|(_, templates)| templates.is_empty()) {
+            return Err(EvaluationError::InvalidPhraseBook(format!(
+                "section [{}] has no phrases",
+                kind_name(*kind)
+            )));
+        }
+        Ok(Self { phrases })
+    }
+
+    /// The templates available for one finding kind.
+    pub fn templates(&self, kind: FindingKind) -> &[String] {
+        self.phrases
+            .iter()
+            .find(|(existing, _)| *existing == kind)
+            .map_or(&[], |(_, templates)| templates)
+    }
+
+    /// One sentence for one finding: the seed picks among the kind's
+    /// templates and the placeholders are filled from the finding.
+    /// `None` when the book has no phrases for the kind.
+    pub fn phrase(&self, finding: &Finding, seed: u64) -> Option<String> {
+        let templates = self.templates(finding.kind);
+        let template = templates.get(seed as usize % templates.len().max(1))?;
+        Some(
+            template
+                .replace("{value}", &format!("{:.0}", finding.value))
+                .replace("{percent}", &format!("{:.0}", finding.value * 100.0))
+                .replace("{message}", &finding.message),
+        )
+    }
+
+    /// Sentences for a whole explanation, in finding order. Each
+    /// finding draws from its own templates with a seed offset, so one
+    /// attempt gets varied wording across findings and the next attempt
+    /// (with a different seed) gets different sentences again. Findings
+    /// the book has no phrases for fall back to their raw message, so
+    /// feedback never silently drops one.
+    pub fn feedback(&self, findings: &[Finding], seed: u64) -> Vec<String> {
+        findings
+            .iter()
+            .enumerate()
+            .map(|(index, finding)| {
+                self.phrase(finding, seed.wrapping_add(index as u64))
+                    .unwrap_or_else(|| finding.message.clone())
+            })
+            .collect()
+    }
+}
+
+/// Extracts the double-quoted strings from one line of an array body.
+/// Returns the strings and whether the closing `]` was reached.
+/// Supports `\"` and `\\` escapes; rejects unterminated strings and
+/// anything else that is not a comma, whitespace or a trailing comment.
+fn parse_string_items(line: &str) -> Result<(Vec<String>, bool), String> {
+    let mut strings = Vec::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => match chars.next() {
+                            Some('"') => string.push('"'),
+                            Some('\\') => string.push('\\'),
+                            other => {
+                                return Err(format!("unsupported escape \\{}", other.unwrap_or(' ')))
+                            }
+                        },
+                        Some('"') => break,
+                        Some(c) => string.push(c),
+                        None => return Err("unterminated string".to_string()),
+                    }
+                }
+                strings.push(string);
+            }
+            ']' => return Ok((strings, true)),
+            '#' => return Ok((strings, false)),
+            ',' => {}
+            c if c.is_whitespace() => {}
+            other => return Err(format!("unexpected character `{other}` in phrases array")),
+        }
+    }
+    Ok((strings, false))
+}
+
+/// The snake_case section names, matching [`FindingKind`]'s serde
+/// representation.
+fn kind_by_name(name: &str) -> Option<FindingKind> {
+    Some(match name {
+        "largest_deviation" => FindingKind::LargestDeviation,
+        "uncovered_reference" => FindingKind::UncoveredReference,
+        "overall_shift" => FindingKind::OverallShift,
+        "filtered_ink" => FindingKind::FilteredInk,
+        "overtime" => FindingKind::Overtime,
+        "panes_swapped" => FindingKind::PanesSwapped,
+        _ => return None,
+    })
+}
+
+fn kind_name(kind: FindingKind) -> &'static str {
+    match kind {
+        FindingKind::LargestDeviation => "largest_deviation",
+        FindingKind::UncoveredReference => "uncovered_reference",
+        FindingKind::OverallShift => "overall_shift",
+        FindingKind::FilteredInk => "filtered_ink",
+        FindingKind::Overtime => "overtime",
+        FindingKind::PanesSwapped => "panes_swapped",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(kind: FindingKind, value: f64) -> Finding {
+        Finding {
+            kind,
+            message: "raw finding text".to_string(),
+            value,
+        }
+    }
+
+    #[test]
+    fn the_default_book_covers_every_finding_kind() {
+        let book = PhraseBook::default();
+        for kind in [
+            FindingKind::LargestDeviation,
+            FindingKind::UncoveredReference,
+            FindingKind::OverallShift,
+            FindingKind::FilteredInk,
+            FindingKind::Overtime,
+            FindingKind::PanesSwapped,
+        ] {
+            assert!(!book.templates(kind).is_empty(), "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn placeholders_fill_from_the_finding() {
+        let book = PhraseBook::from_toml(
+            "[largest_deviation]\nphrases = [\"off by {value}px ({percent}%): {message}\"]\n",
+        )
+        .unwrap();
+        let sentence = book
+            .phrase(&finding(FindingKind::LargestDeviation, 23.4), 0)
+            .unwrap();
+        assert_eq!(sentence, "off by 23px (2340%): raw finding text");
+    }
+
+    #[test]
+    fn seeds_vary_the_wording_reproducibly() {
+        let book = PhraseBook::default();
+        let deviation = finding(FindingKind::LargestDeviation, 23.0);
+        let first = book.phrase(&deviation, 0).unwrap();
+        let second = book.phrase(&deviation, 1).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(book.phrase(&deviation, 0).unwrap(), first);
+        // Seeds wrap around the available templates.
+        assert_eq!(book.phrase(&deviation, 3).unwrap(), first);
+    }
+
+    #[test]
+    fn feedback_covers_every_finding_with_a_fallback() {
+        // A book with phrases for deviations only.
+        let book = PhraseBook::from_toml(
+            "[largest_deviation]\nphrases = [\"deviation of {value}px\"]\n",
+        )
+        .unwrap();
+        let findings = [
+            finding(FindingKind::LargestDeviation, 12.0),
+            finding(FindingKind::Overtime, 2500.0),
+        ];
+        let sentences = book.feedback(&findings, 7);
+        assert_eq!(sentences.len(), 2);
+        assert_eq!(sentences[0], "deviation of 12px");
+        assert_eq!(sentences[1], "raw finding text");
+    }
+
+    #[test]
+    fn parse_problems_report_their_line() {
+        let error = PhraseBook::from_toml("[largest_deviation]\nphrases = nope\n").unwrap_err();
+        assert!(error.to_string().contains("line 2"), "{error}");
+        let error = PhraseBook::from_toml("[no_such_kind]\n").unwrap_err();
+        assert!(error.to_string().contains("no_such_kind"), "{error}");
+        let error =
+            PhraseBook::from_toml("[overtime]\nphrases = [\n    \"unclosed\",\n").unwrap_err();
+        assert!(error.to_string().contains("unterminated"), "{error}");
+    }
+
+    #[test]
+    fn multi_line_arrays_and_comments_parse() {
+        let book = PhraseBook::from_toml(
+            "# a comment\n[overtime]\nphrases = [\n    \"one\", # trailing\n    \"two\",\n]\n",
+        )
+        .unwrap();
+        assert_eq!(book.templates(FindingKind::Overtime), ["one", "two"]);
+    }
+}
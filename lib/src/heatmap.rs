@@ -0,0 +1,293 @@
+use std::collections::BTreeMap;
+
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+use crate::error::EvaluationError;
+
+/// Distance metric for [`distance_transform`]. Both are exact under a
+/// breadth-first flood fill.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DistanceMetric {
+    /// Diagonal steps cost 1 (eight-connected). This is what the
+    /// evaluators grade with.
+    #[default]
+    Chessboard,
+    /// Diagonal steps cost 2 (four-connected).
+    Manhattan,
+}
+
+/// Computes, for every canvas position, the distance to the nearest "on"
+/// pixel in a flat row-major `width * height` mask — useful on its own
+/// for things like client-side snapping guides. Every position is `-1`
+/// when the mask contains no pixels; with a `max_distance` clamp,
+/// positions further out read as the clamp value.
+pub fn distance_transform(
+    pixels: &[u8],
+    width: usize,
+    height: usize,
+    metric: DistanceMetric,
+    max_distance: Option<i32>,
+) -> Result<Array2<i32>, EvaluationError> {
+    if pixels.len() != width * height {
+        return Err(EvaluationError::InvalidBuffer(format!(
+            "expected {}x{} = {} mask bytes, got {}",
+            width,
+            height,
+            width * height,
+            pixels.len()
+        )));
+    }
+    let mask = Array2::from_shape_vec((height, width), pixels.to_vec())
+        .map_err(|e| EvaluationError::InvalidBuffer(e.to_string()))?;
+    Ok(flood_fill(&mask, metric, max_distance))
+}
+
+/// The chessboard transform the evaluators build their heatmaps with.
+///
+/// With a `max_distance` clamp, propagation stops there and every
+/// position further out reads as the clamp value. This bounds how much a
+/// single stray stroke can contribute to the metrics and skips most of
+/// the BFS work on sparse masks.
+pub(crate) fn flood_fill_distances(
+    pixels: &Array2<u8>,
+    max_distance: Option<i32>,
+) -> Array2<i32> {
+    flood_fill(pixels, DistanceMetric::Chessboard, max_distance)
+}
+
+/// The [`crate::core`] flood fill, lifted onto `ndarray` buffers.
+fn flood_fill(
+    pixels: &Array2<u8>,
+    metric: DistanceMetric,
+    max_distance: Option<i32>,
+) -> Array2<i32> {
+    let (height, width) = pixels.dim();
+    let flat: Vec<u8> = match pixels.as_slice() {
+        Some(flat) => flat.to_vec(),
+        None => pixels.iter().copied().collect(),
+    };
+    let distances = match metric {
+        DistanceMetric::Chessboard => {
+            crate::core::chessboard_distances(&flat, width, height, max_distance)
+        }
+        DistanceMetric::Manhattan => {
+            crate::core::manhattan_distances(&flat, width, height, max_distance)
+        }
+    };
+    Array2::from_shape_vec((height, width), distances)
+        .expect("the distance buffer matches the mask shape")
+}
+
+/// A distance heatmap stored as 64x64 tiles allocated on demand.
+///
+/// Early in a session the observation heatmap holds one value almost
+/// everywhere — `-1` ("no data") before any stroke, or the
+/// `max_distance` clamp once propagation is bounded — with a small
+/// patch of real distances around the first strokes. Storing only the
+/// tiles that differ from that `fill` value cuts both resident memory
+/// and serialized size, which matters when a server holds many
+/// concurrent sessions. Untouched positions read as `fill`, and
+/// serialization writes only the allocated tiles.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SparseHeatmap {
+    width: usize,
+    height: usize,
+    tiles_across: usize,
+    /// What unallocated positions read as.
+    fill: i32,
+    /// Allocated tiles by row-major tile index, each holding
+    /// `TILE_SIZE * TILE_SIZE` row-major distances.
+    tiles: BTreeMap<usize, Vec<i32>>,
+}
+
+impl SparseHeatmap {
+    /// Edge length of one tile, in heatmap positions.
+    pub const TILE_SIZE: usize = 64;
+
+    /// An empty heatmap: every position reads `fill` (`-1` for an
+    /// unfilled heatmap, the clamp value for a clamped one), nothing
+    /// allocated.
+    pub fn new(width: usize, height: usize, fill: i32) -> Self {
+        Self {
+            width,
+            height,
+            tiles_across: width.div_ceil(Self::TILE_SIZE),
+            fill,
+            tiles: BTreeMap::new(),
+        }
+    }
+
+    /// Dimensions as `(height, width)`, matching `ndarray`.
+    pub fn dim(&self) -> (usize, usize) {
+        (self.height, self.width)
+    }
+
+    /// Number of tiles holding data.
+    pub fn allocated_tiles(&self) -> usize {
+        self.tiles.len()
+    }
+
+    fn tile_and_offset(&self, y: usize, x: usize) -> (usize, usize) {
+        assert!(
+            y < self.height && x < self.width,
+            "position ({y}, {x}) outside the {}x{} heatmap",
+            self.height,
+            self.width
+        );
+        let tile = (y / Self::TILE_SIZE) * self.tiles_across + x / Self::TILE_SIZE;
+        let offset = (y % Self::TILE_SIZE) * Self::TILE_SIZE + x % Self::TILE_SIZE;
+        (tile, offset)
+    }
+
+    pub fn get(&self, y: usize, x: usize) -> i32 {
+        let (tile, offset) = self.tile_and_offset(y, x);
+        self.tiles
+            .get(&tile)
+            .map_or(self.fill, |values| values[offset])
+    }
+
+    /// Writes one distance, allocating its tile on first touch. Writing
+    /// the fill value into an unallocated tile stays free.
+    pub fn set(&mut self, y: usize, x: usize, distance: i32) {
+        let (tile, offset) = self.tile_and_offset(y, x);
+        if distance == self.fill && !self.tiles.contains_key(&tile) {
+            return;
+        }
+        let fill = self.fill;
+        self.tiles
+            .entry(tile)
+            .or_insert_with(|| vec![fill; Self::TILE_SIZE * Self::TILE_SIZE])[offset] = distance;
+    }
+
+    /// Packs a dense heatmap, skipping tiles that hold nothing but
+    /// `fill`.
+    pub fn from_dense(heatmap: &Array2<i32>, fill: i32) -> Self {
+        let (height, width) = heatmap.dim();
+        let mut sparse = Self::new(width, height, fill);
+        for ((y, x), &distance) in heatmap.indexed_iter() {
+            if distance != fill {
+                sparse.set(y, x, distance);
+            }
+        }
+        sparse
+    }
+
+    /// Expands back to the dense form the metrics aggregation reads.
+    pub fn to_dense(&self) -> Array2<i32> {
+        let mut dense = Array2::from_elem((self.height, self.width), self.fill);
+        for (&tile, values) in &self.tiles {
+            let base_y = (tile / self.tiles_across) * Self::TILE_SIZE;
+            let base_x = (tile % self.tiles_across) * Self::TILE_SIZE;
+            for (offset, &distance) in values.iter().enumerate() {
+                let y = base_y + offset / Self::TILE_SIZE;
+                let x = base_x + offset % Self::TILE_SIZE;
+                if y < self.height && x < self.width {
+                    dense[(y, x)] = distance;
+                }
+            }
+        }
+        dense
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distances_radiate_from_single_pixel() {
+        let mut pixels = Array2::zeros((5, 5));
+        pixels[(2, 2)] = 1;
+        let distances = flood_fill_distances(&pixels, None);
+        assert_eq!(distances[(2, 2)], 0);
+        assert_eq!(distances[(2, 3)], 1);
+        assert_eq!(distances[(3, 3)], 1);
+        assert_eq!(distances[(0, 0)], 2);
+        assert_eq!(distances[(4, 0)], 2);
+    }
+
+    #[test]
+    fn empty_mask_yields_all_negative_one() {
+        let pixels = Array2::zeros((3, 3));
+        let distances = flood_fill_distances(&pixels, None);
+        assert!(distances.iter().all(|&d| d == -1));
+    }
+
+    #[test]
+    fn clamp_caps_distances_beyond_the_limit() {
+        let mut pixels = Array2::zeros((9, 9));
+        pixels[(0, 0)] = 1;
+        let distances = flood_fill_distances(&pixels, Some(2));
+        assert_eq!(distances[(0, 0)], 0);
+        assert_eq!(distances[(2, 2)], 2);
+        assert_eq!(distances[(3, 3)], 2);
+        assert_eq!(distances[(8, 8)], 2);
+    }
+
+    #[test]
+    fn clamp_leaves_an_empty_mask_unfilled() {
+        let pixels = Array2::zeros((3, 3));
+        let distances = flood_fill_distances(&pixels, Some(2));
+        assert!(distances.iter().all(|&d| d == -1));
+    }
+
+    #[test]
+    fn manhattan_counts_diagonal_steps_twice() {
+        let mut pixels = vec![0u8; 25];
+        pixels[2 * 5 + 2] = 1;
+        let distances =
+            distance_transform(&pixels, 5, 5, DistanceMetric::Manhattan, None).unwrap();
+        assert_eq!(distances[(2, 3)], 1);
+        assert_eq!(distances[(3, 3)], 2);
+        assert_eq!(distances[(0, 0)], 4);
+    }
+
+    #[test]
+    fn mismatched_buffer_length_is_rejected() {
+        let error =
+            distance_transform(&[0u8; 10], 5, 5, DistanceMetric::Chessboard, None).unwrap_err();
+        assert!(error.to_string().contains("25"));
+    }
+
+    #[test]
+    fn sparse_heatmaps_allocate_only_touched_tiles() {
+        let mut sparse = SparseHeatmap::new(500, 500, -1);
+        assert_eq!(sparse.allocated_tiles(), 0);
+        assert_eq!(sparse.get(250, 250), -1);
+        sparse.set(250, 250, 3);
+        assert_eq!(sparse.allocated_tiles(), 1);
+        assert_eq!(sparse.get(250, 250), 3);
+        // The rest of the touched tile reads as no-data, and writing
+        // the fill value elsewhere allocates nothing.
+        assert_eq!(sparse.get(250, 251), -1);
+        sparse.set(0, 0, -1);
+        assert_eq!(sparse.allocated_tiles(), 1);
+    }
+
+    #[test]
+    fn sparse_heatmaps_round_trip_through_the_dense_form() {
+        let mut pixels = Array2::zeros((500, 500));
+        pixels[(250, 250)] = 1;
+        let dense = flood_fill_distances(&pixels, Some(3));
+        let sparse = SparseHeatmap::from_dense(&dense, 3);
+        assert_eq!(sparse.dim(), (500, 500));
+        assert_eq!(sparse.to_dense(), dense);
+    }
+
+    #[test]
+    fn early_session_heatmaps_serialize_small() {
+        // One clamped stroke on a big canvas has real distances in a
+        // handful of tiles; every all-clamp tile is skipped, so the
+        // serialized form is a fraction of the dense buffer's.
+        let mut pixels = Array2::zeros((500, 500));
+        pixels[(250, 250)] = 1;
+        let dense = flood_fill_distances(&pixels, Some(3));
+        let sparse = SparseHeatmap::from_dense(&dense, 3);
+        assert!(sparse.allocated_tiles() <= 4);
+        let sparse_json = serde_json::to_string(&sparse).unwrap();
+        let dense_json = serde_json::to_string(&dense.into_raw_vec_and_offset().0).unwrap();
+        assert!(sparse_json.len() < dense_json.len() / 10);
+    }
+}
@@ -0,0 +1,255 @@
+//! Integrity hashes over evaluation inputs.
+//!
+//! Stored scores are only auditable when the inputs they were computed
+//! from can be re-verified: a silently re-exported or touched-up image
+//! should be detectable years later. Every [`crate::EvaluationResult`]
+//! embeds SHA-256 digests of the reference pane, the observation pane
+//! and the evaluator configuration. SHA-256 is implemented here
+//! directly — like the bundle hash, it keeps the crate dependency-free
+//! and the digests stable across builds.
+
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+use crate::evaluator::EvaluatorConfig;
+
+/// SHA-256 digests of everything an evaluation depended on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputDigests {
+    /// Hex SHA-256 of the reference pane mask (dimensions + pixels).
+    pub reference_sha256: String,
+    /// Hex SHA-256 of the observation pane mask.
+    pub observation_sha256: String,
+    /// Hex SHA-256 of the evaluator configuration's canonical JSON.
+    pub config_sha256: String,
+}
+
+impl InputDigests {
+    /// Digests the inputs of one evaluation.
+    pub fn of(
+        reference: &Array2<u8>,
+        observation: &Array2<u8>,
+        config: &EvaluatorConfig,
+    ) -> Self {
+        Self {
+            reference_sha256: mask_digest(reference),
+            observation_sha256: mask_digest(observation),
+            config_sha256: config_digest(config),
+        }
+    }
+
+    /// Re-derives the digests from the given inputs and compares; a
+    /// mismatch means the stored result no longer matches the images
+    /// or configuration on disk.
+    pub fn verify(
+        &self,
+        reference: &Array2<u8>,
+        observation: &Array2<u8>,
+        config: &EvaluatorConfig,
+    ) -> bool {
+        *self == Self::of(reference, observation, config)
+    }
+}
+
+/// Hex SHA-256 of a mask's dimensions and raw pixels, so equal content
+/// at different shapes still hashes differently.
+pub fn mask_digest(mask: &Array2<u8>) -> String {
+    let (height, width) = mask.dim();
+    let mut hasher = Sha256::new();
+    hasher.update(&(width as u64).to_le_bytes());
+    hasher.update(&(height as u64).to_le_bytes());
+    match mask.as_slice() {
+        Some(pixels) => hasher.update(pixels),
+        None => {
+            for &pixel in mask.iter() {
+                hasher.update(&[pixel]);
+            }
+        }
+    }
+    hex(&hasher.finalize())
+}
+
+/// Hex SHA-256 of the configuration's serde_json serialization, which
+/// is canonical for a struct: fields serialize in declaration order.
+pub fn config_digest(config: &EvaluatorConfig) -> String {
+    let json = serde_json::to_vec(config).expect("config serializes");
+    sha256_hex(&json)
+}
+
+/// Hex SHA-256 of arbitrary bytes.
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    hex(&hasher.finalize())
+}
+
+fn hex(digest: &[u8; 32]) -> String {
+    digest.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// FIPS 180-4 SHA-256 round constants.
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Streaming SHA-256 (FIPS 180-4), enough for hashing masks and small
+/// JSON documents without pulling in a crypto crate.
+struct Sha256 {
+    state: [u32; 8],
+    buffer: [u8; 64],
+    buffered: usize,
+    length_bits: u64,
+}
+
+impl Sha256 {
+    fn new() -> Self {
+        Self {
+            state: [
+                0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c,
+                0x1f83d9ab, 0x5be0cd19,
+            ],
+            buffer: [0; 64],
+            buffered: 0,
+            length_bits: 0,
+        }
+    }
+
+    fn update(&mut self, mut bytes: &[u8]) {
+        self.length_bits = self.length_bits.wrapping_add(bytes.len() as u64 * 8);
+        while !bytes.is_empty() {
+            let take = (64 - self.buffered).min(bytes.len());
+            self.buffer[self.buffered..self.buffered + take].copy_from_slice(&bytes[..take]);
+            self.buffered += take;
+            bytes = &bytes[take..];
+            if self.buffered == 64 {
+                let block = self.buffer;
+                self.compress(&block);
+                self.buffered = 0;
+            }
+        }
+    }
+
+    fn finalize(mut self) -> [u8; 32] {
+        let length_bits = self.length_bits;
+        self.update(&[0x80]);
+        while self.buffered != 56 {
+            self.update(&[0]);
+        }
+        // update() also advanced the length counter over the padding;
+        // the message length recorded is the one captured above.
+        self.length_bits = length_bits;
+        let block_start = self.buffered;
+        self.buffer[block_start..].copy_from_slice(&length_bits.to_be_bytes());
+        let block = self.buffer;
+        self.compress(&block);
+        let mut digest = [0; 32];
+        for (chunk, word) in digest.chunks_exact_mut(4).zip(self.state) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        digest
+    }
+
+    fn compress(&mut self, block: &[u8; 64]) {
+        let mut w = [0u32; 64];
+        for (index, chunk) in block.chunks_exact(4).enumerate() {
+            w[index] = u32::from_be_bytes(chunk.try_into().expect("4-byte chunk"));
+        }
+        for index in 16..64 {
+            let s0 = w[index - 15].rotate_right(7)
+                ^ w[index - 15].rotate_right(18)
+                ^ (w[index - 15] >> 3);
+            let s1 = w[index - 2].rotate_right(17)
+                ^ w[index - 2].rotate_right(19)
+                ^ (w[index - 2] >> 10);
+            w[index] = w[index - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[index - 7])
+                .wrapping_add(s1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+        for index in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let temp1 = h
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(K[index])
+                .wrapping_add(w[index]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let temp2 = s0.wrapping_add(maj);
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(temp1);
+            d = c;
+            c = b;
+            b = a;
+            a = temp1.wrapping_add(temp2);
+        }
+        for (slot, value) in self.state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *slot = slot.wrapping_add(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sha256_matches_the_fips_test_vectors() {
+        assert_eq!(
+            sha256_hex(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            sha256_hex(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+    }
+
+    #[test]
+    fn long_inputs_cross_block_boundaries_correctly() {
+        let million_a = vec![b'a'; 1_000_000];
+        assert_eq!(
+            sha256_hex(&million_a),
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+        );
+    }
+
+    #[test]
+    fn digests_verify_and_catch_modified_inputs() {
+        let mut reference = Array2::<u8>::zeros((20, 20));
+        reference[(10, 10)] = 1;
+        let observation = Array2::<u8>::zeros((20, 20));
+        let config = EvaluatorConfig::default();
+        let digests = InputDigests::of(&reference, &observation, &config);
+        assert!(digests.verify(&reference, &observation, &config));
+
+        let mut touched = reference.clone();
+        touched[(10, 11)] = 1;
+        assert!(!digests.verify(&touched, &observation, &config));
+        let mut other_config = config.clone();
+        other_config.tolerance += 1;
+        assert!(!digests.verify(&reference, &observation, &other_config));
+    }
+
+    #[test]
+    fn mask_dimensions_change_the_digest() {
+        let flat = Array2::<u8>::zeros((1, 16));
+        let tall = Array2::<u8>::zeros((16, 1));
+        assert_ne!(mask_digest(&flat), mask_digest(&tall));
+    }
+}
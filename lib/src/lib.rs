@@ -0,0 +1,181 @@
+//! Image evaluation engine for VisualArt drawing exercises.
+//!
+//! The drawing app exports a composite PNG: the reference image on the
+//! left pane, the user's observation drawing on the right pane, separated
+//! by a small gap. This crate extracts both panes, computes a
+//! distance-to-reference heatmap with a flood fill, and aggregates the
+//! per-pixel distances into the error metrics the app grades with.
+//!
+//! [`ImageEvaluator`] is the one-shot entry point for finished composites;
+//! [`StreamingEvaluator`] scores pixels incrementally while the user is
+//! still drawing. The pure math behind both lives in [`core`], which is
+//! all that remains when the crate is built without the `std` feature.
+#![cfg_attr(not(feature = "std"), no_std)]
+// The JSON Schema document in [`schema`] is one large `serde_json::json!`
+// literal, which expands past the default macro recursion limit.
+#![recursion_limit = "256"]
+
+extern crate alloc;
+
+pub mod core;
+
+#[cfg(feature = "std")]
+pub mod analysis;
+/// Reproducibility bundles embed the scored panes as PNGs, so they
+/// need the encoder.
+#[cfg(feature = "png")]
+pub mod audit;
+#[cfg(feature = "std")]
+pub mod baseline;
+#[cfg(feature = "std")]
+pub mod batch;
+#[cfg(feature = "std")]
+pub mod builder;
+#[cfg(feature = "std")]
+pub mod bundle;
+#[cfg(feature = "std")]
+pub mod color;
+#[cfg(feature = "std")]
+pub mod colormap;
+#[cfg(feature = "std")]
+pub mod corpus;
+#[cfg(feature = "std")]
+pub mod decode;
+#[cfg(feature = "std")]
+pub mod dedupe;
+#[cfg(feature = "std")]
+pub mod error;
+#[cfg(feature = "std")]
+pub mod evaluator;
+/// Demo exercises are written to disk as PNGs, so they need the
+/// encoder.
+#[cfg(feature = "png")]
+pub mod examples;
+#[cfg(feature = "std")]
+pub mod explain;
+#[cfg(feature = "std")]
+pub mod feedback;
+#[cfg(feature = "std")]
+pub mod heatmap;
+#[cfg(feature = "std")]
+pub mod integrity;
+#[cfg(feature = "std")]
+pub mod lines;
+#[cfg(feature = "std")]
+pub mod manager;
+#[cfg(feature = "std")]
+pub mod manifest;
+#[cfg(feature = "std")]
+pub mod metrics;
+#[cfg(feature = "std")]
+pub mod orientation;
+#[cfg(feature = "std")]
+pub mod quality;
+#[cfg(feature = "std")]
+pub mod regions;
+#[cfg(feature = "std")]
+pub mod registry;
+#[cfg(feature = "std")]
+pub mod render;
+/// HTML reports embed panes as PNG data URLs, so they need the encoder.
+#[cfg(feature = "png")]
+pub mod report;
+#[cfg(feature = "std")]
+pub mod scale;
+#[cfg(feature = "std")]
+pub mod schema;
+#[cfg(feature = "std")]
+pub mod silhouette;
+#[cfg(feature = "std")]
+pub mod streaming;
+#[cfg(feature = "std")]
+pub mod timelapse;
+#[cfg(feature = "std")]
+pub mod weight;
+#[cfg(feature = "std")]
+pub mod worker;
+
+#[cfg(feature = "std")]
+pub use analysis::{validate_reference, Difficulty, ReferenceAnalysis, ReferenceWarning};
+#[cfg(feature = "std")]
+pub use baseline::{normalized_skill, BaselineScores};
+#[cfg(feature = "std")]
+pub use builder::{Background, EvaluatorBuilder};
+#[cfg(feature = "std")]
+pub use bundle::ReferenceBundle;
+#[cfg(feature = "std")]
+pub use color::{color_metrics, combined_badness, ColorMetrics, ColorWeights};
+#[cfg(feature = "std")]
+pub use colormap::Colormap;
+#[cfg(feature = "std")]
+pub use corpus::{parse_corpus, run_corpus, CorpusCase, CorpusReport, MetricRange};
+#[cfg(feature = "std")]
+pub use decode::{channel_view, ink_values, mask_from_view, Decoder, ImageCrateDecoder, InkChannel};
+#[cfg(feature = "std")]
+pub use dedupe::{find_duplicates, observation_hash, DuplicatePair, PerceptualHash};
+#[cfg(feature = "std")]
+pub use error::EvaluationError;
+#[cfg(feature = "std")]
+pub use evaluator::{
+    panes_look_swapped, BestMatch, EvaluationResult, EvaluatorConfig, ImageEvaluator,
+    OutlierFilter, PerfStats,
+};
+#[cfg(feature = "std")]
+pub use explain::{Finding, FindingKind};
+#[cfg(feature = "std")]
+pub use feedback::PhraseBook;
+#[cfg(feature = "std")]
+pub use heatmap::{distance_transform, DistanceMetric, SparseHeatmap};
+#[cfg(feature = "std")]
+pub use integrity::{sha256_hex, InputDigests};
+#[cfg(feature = "std")]
+pub use lines::{compare_lines, detect_segments, LineComparison, LineSegment, SegmentMatch};
+#[cfg(feature = "std")]
+pub use manager::{SessionManager, SessionManagerConfig, SessionManagerMetrics};
+#[cfg(feature = "std")]
+pub use manifest::{ExerciseManifest, OvertimePolicy};
+#[cfg(feature = "std")]
+pub use metrics::{
+    grid_delta, sliding_worst_regions, CellAggregator, ErrorMetrics, Normalization,
+    NormalizedMetrics, SlidingWorstRegions, WorstWindow,
+};
+#[cfg(feature = "std")]
+pub use orientation::{orientation_field, orientation_mismatch, OrientationField, OrientationMismatch};
+#[cfg(feature = "std")]
+pub use quality::{check_quality, GateCheck, QualityGate, QualityGates, QualityReport};
+#[cfg(feature = "std")]
+pub use regions::{correction_vectors, CompassDirection, CorrectionVector, PixelPoint, ProblemRegion};
+#[cfg(feature = "std")]
+pub use registry::{ReferenceRegistry, ReferenceRegistryConfig, ReferenceRegistryMetrics};
+#[cfg(feature = "std")]
+pub use scale::ResampleMode;
+#[cfg(feature = "std")]
+pub use schema::{VersionedResult, SCHEMA_VERSION};
+#[cfg(feature = "std")]
+pub use silhouette::{signed_error_components, silhouette_mask, SignedErrorComponents};
+#[cfg(feature = "std")]
+pub use streaming::{
+    ClipPolicy, CoordinateSpace, HeatTimeline, InputCoalescer, ReferenceModel, ScoreEvent,
+    ScoreProjection, ScoreSample, ScoreTrend, StreamingEvaluator, TileMetrics, UpdatePolicy,
+    UserContribution, UserContributionReport,
+};
+#[cfg(feature = "std")]
+pub use timelapse::{decode_animation, evaluate_composite_frames, evaluate_frames, FrameScore};
+#[cfg(feature = "std")]
+pub use weight::{compare_line_weight, weight_profile, WeightComparison, WeightProfile};
+#[cfg(feature = "std")]
+pub use worker::{run_queue_directory, run_worker, WorkerJob, WorkerOptions, WorkerStats};
+
+/// One-line import of the types most integrations touch: the builder,
+/// both evaluators, their configuration, and the result types.
+#[cfg(feature = "std")]
+pub mod prelude {
+    pub use crate::builder::{Background, EvaluatorBuilder};
+    pub use crate::error::EvaluationError;
+    pub use crate::evaluator::{EvaluationResult, EvaluatorConfig, ImageEvaluator, OutlierFilter};
+    pub use crate::heatmap::DistanceMetric;
+    pub use crate::manifest::ExerciseManifest;
+    pub use crate::metrics::{ErrorMetrics, Normalization};
+    pub use crate::scale::ResampleMode;
+    pub use crate::streaming::{ReferenceModel, StreamingEvaluator};
+}
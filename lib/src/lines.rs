@@ -0,0 +1,332 @@
+//! Line-segment metrics for construction drawing.
+//!
+//! Perspective and construction exercises grade proportions and
+//! intersection points, not pixel overlap: a vanishing line drawn at
+//! the wrong angle is wrong even where it crosses the reference. This
+//! module fits line segments to the stroke pixels of each pane with a
+//! deterministic RANSAC, matches them up, and compares angles, length
+//! ratios and intersection positions.
+
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+/// Segments with fewer supporting pixels than this are noise.
+const MIN_INLIERS: usize = 12;
+/// A pixel supports a candidate line when it lies within this distance.
+const INLIER_DISTANCE: f64 = 2.0;
+/// Candidate pixel pairs tried per fitted segment.
+const CANDIDATES_PER_SEGMENT: usize = 128;
+
+/// A fitted line segment in `(x, y)` canvas coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LineSegment {
+    pub start: (f64, f64),
+    pub end: (f64, f64),
+}
+
+impl LineSegment {
+    pub fn length(&self) -> f64 {
+        let (dx, dy) = (self.end.0 - self.start.0, self.end.1 - self.start.1);
+        (dx * dx + dy * dy).sqrt()
+    }
+
+    /// Orientation in degrees, folded into `0..180` since strokes have
+    /// no direction.
+    pub fn angle_degrees(&self) -> f64 {
+        let degrees = (self.end.1 - self.start.1)
+            .atan2(self.end.0 - self.start.0)
+            .to_degrees();
+        degrees.rem_euclid(180.0)
+    }
+
+    pub fn midpoint(&self) -> (f64, f64) {
+        (
+            (self.start.0 + self.end.0) / 2.0,
+            (self.start.1 + self.end.1) / 2.0,
+        )
+    }
+
+    /// Where the infinite lines through two segments cross, or `None`
+    /// when they are (near-)parallel.
+    pub fn line_intersection(&self, other: &LineSegment) -> Option<(f64, f64)> {
+        let d1 = (self.end.0 - self.start.0, self.end.1 - self.start.1);
+        let d2 = (other.end.0 - other.start.0, other.end.1 - other.start.1);
+        let denominator = d1.0 * d2.1 - d1.1 * d2.0;
+        if denominator.abs() < 1e-9 {
+            return None;
+        }
+        let dx = other.start.0 - self.start.0;
+        let dy = other.start.1 - self.start.1;
+        let t = (dx * d2.1 - dy * d2.0) / denominator;
+        Some((self.start.0 + t * d1.0, self.start.1 + t * d1.1))
+    }
+}
+
+/// One reference segment paired with its observation counterpart.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SegmentMatch {
+    pub reference: LineSegment,
+    pub observation: LineSegment,
+    /// Orientation difference in degrees, folded into `0..=90`.
+    pub angle_error_degrees: f64,
+    /// Observation length over reference length.
+    pub length_ratio: f64,
+}
+
+/// The segment-level comparison of two panes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LineComparison {
+    pub matched: Vec<SegmentMatch>,
+    /// Reference segments with no observation counterpart.
+    pub unmatched_reference: usize,
+    /// Observation segments with no reference counterpart.
+    pub unmatched_observation: usize,
+    pub mean_angle_error_degrees: f64,
+    /// Mean deviation of the matched length ratios from 1.
+    pub mean_length_ratio_error: f64,
+    /// Mean distance between corresponding pairwise intersection
+    /// points of the matched segments.
+    pub mean_intersection_distance: f64,
+}
+
+/// Fits up to `max_segments` line segments to the stroke pixels of a
+/// mask. Detection is deterministic: candidates are drawn from a
+/// generator seeded by the mask content, so repeated runs agree.
+pub fn detect_segments(mask: &Array2<u8>, max_segments: usize) -> Vec<LineSegment> {
+    let mut points: Vec<(f64, f64)> = mask
+        .indexed_iter()
+        .filter(|(_, &on)| on != 0)
+        .map(|((y, x), _)| (x as f64, y as f64))
+        .collect();
+    let mut seed = points
+        .iter()
+        .fold(0x9e37_79b9_u64, |acc, &(x, y)| {
+            acc.wrapping_mul(31).wrapping_add((y as u64) << 16 | x as u64)
+        })
+        .max(1);
+    let mut segments = Vec::new();
+    while segments.len() < max_segments && points.len() >= MIN_INLIERS {
+        let mut best: Option<Vec<usize>> = None;
+        for _ in 0..CANDIDATES_PER_SEGMENT {
+            seed = xorshift(seed);
+            let a = points[seed as usize % points.len()];
+            seed = xorshift(seed);
+            let b = points[seed as usize % points.len()];
+            let length = ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt();
+            if length < 1.0 {
+                continue;
+            }
+            let direction = ((b.0 - a.0) / length, (b.1 - a.1) / length);
+            let inliers: Vec<usize> = points
+                .iter()
+                .enumerate()
+                .filter(|(_, point)| {
+                    let offset = (point.0 - a.0, point.1 - a.1);
+                    (offset.0 * direction.1 - offset.1 * direction.0).abs() <= INLIER_DISTANCE
+                })
+                .map(|(index, _)| index)
+                .collect();
+            if best.as_ref().is_none_or(|b| inliers.len() > b.len()) {
+                best = Some(inliers);
+            }
+        }
+        let Some(inliers) = best.filter(|inliers| inliers.len() >= MIN_INLIERS) else {
+            break;
+        };
+        segments.push(fit_segment(&points, &inliers));
+        let remove: std::collections::HashSet<usize> = inliers.into_iter().collect();
+        points = points
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| !remove.contains(index))
+            .map(|(_, point)| point)
+            .collect();
+    }
+    segments
+}
+
+/// Detects segments in both panes, matches them greedily by midpoint
+/// and angle, and aggregates the proportion metrics. Up to
+/// `max_segments` are fitted per pane.
+pub fn compare_lines(
+    reference: &Array2<u8>,
+    observation: &Array2<u8>,
+    max_segments: usize,
+) -> LineComparison {
+    let reference_segments = detect_segments(reference, max_segments);
+    let observation_segments = detect_segments(observation, max_segments);
+
+    let mut candidates: Vec<(f64, usize, usize)> = Vec::new();
+    for (i, r) in reference_segments.iter().enumerate() {
+        for (j, o) in observation_segments.iter().enumerate() {
+            let (rm, om) = (r.midpoint(), o.midpoint());
+            let distance = ((rm.0 - om.0).powi(2) + (rm.1 - om.1).powi(2)).sqrt();
+            let cost = distance + 2.0 * angle_error(r, o);
+            candidates.push((cost, i, j));
+        }
+    }
+    candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut used_reference = vec![false; reference_segments.len()];
+    let mut used_observation = vec![false; observation_segments.len()];
+    let mut matched = Vec::new();
+    for (_, i, j) in candidates {
+        if used_reference[i] || used_observation[j] {
+            continue;
+        }
+        used_reference[i] = true;
+        used_observation[j] = true;
+        let (reference, observation) = (reference_segments[i], observation_segments[j]);
+        matched.push(SegmentMatch {
+            reference,
+            observation,
+            angle_error_degrees: angle_error(&reference, &observation),
+            length_ratio: observation.length() / reference.length().max(1e-9),
+        });
+    }
+
+    let mean = |values: Vec<f64>| {
+        if values.is_empty() {
+            0.0
+        } else {
+            values.iter().sum::<f64>() / values.len() as f64
+        }
+    };
+    let mut intersection_distances = Vec::new();
+    for i in 0..matched.len() {
+        for j in i + 1..matched.len() {
+            let reference_crossing = matched[i]
+                .reference
+                .line_intersection(&matched[j].reference);
+            let observation_crossing = matched[i]
+                .observation
+                .line_intersection(&matched[j].observation);
+            if let (Some(r), Some(o)) = (reference_crossing, observation_crossing) {
+                intersection_distances.push(((r.0 - o.0).powi(2) + (r.1 - o.1).powi(2)).sqrt());
+            }
+        }
+    }
+    LineComparison {
+        unmatched_reference: used_reference.iter().filter(|&&used| !used).count(),
+        unmatched_observation: used_observation.iter().filter(|&&used| !used).count(),
+        mean_angle_error_degrees: mean(matched.iter().map(|m| m.angle_error_degrees).collect()),
+        mean_length_ratio_error: mean(matched.iter().map(|m| (m.length_ratio - 1.0).abs()).collect()),
+        mean_intersection_distance: mean(intersection_distances),
+        matched,
+    }
+}
+
+/// Orientation difference of two segments, folded into `0..=90` degrees.
+fn angle_error(a: &LineSegment, b: &LineSegment) -> f64 {
+    let difference = (a.angle_degrees() - b.angle_degrees()).abs();
+    difference.min(180.0 - difference)
+}
+
+/// Least-extent fit: the segment spans the extreme projections of the
+/// inliers onto the direction through their centroid.
+fn fit_segment(points: &[(f64, f64)], inliers: &[usize]) -> LineSegment {
+    let count = inliers.len() as f64;
+    let centroid = inliers.iter().fold((0.0, 0.0), |acc, &index| {
+        (acc.0 + points[index].0 / count, acc.1 + points[index].1 / count)
+    });
+    // Principal direction from the 2x2 covariance of the inliers.
+    let (mut xx, mut xy, mut yy) = (0.0, 0.0, 0.0);
+    for &index in inliers {
+        let (dx, dy) = (points[index].0 - centroid.0, points[index].1 - centroid.1);
+        xx += dx * dx;
+        xy += dx * dy;
+        yy += dy * dy;
+    }
+    let angle = 0.5 * (2.0 * xy).atan2(xx - yy);
+    let direction = (angle.cos(), angle.sin());
+    let projections: Vec<f64> = inliers
+        .iter()
+        .map(|&index| {
+            (points[index].0 - centroid.0) * direction.0
+                + (points[index].1 - centroid.1) * direction.1
+        })
+        .collect();
+    let min = projections.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = projections.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    LineSegment {
+        start: (centroid.0 + min * direction.0, centroid.1 + min * direction.1),
+        end: (centroid.0 + max * direction.0, centroid.1 + max * direction.1),
+    }
+}
+
+fn xorshift(mut state: u64) -> u64 {
+    state ^= state << 13;
+    state ^= state >> 7;
+    state ^= state << 17;
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mask_with_segment(
+        (x0, y0): (usize, usize),
+        (x1, y1): (usize, usize),
+        mask: &mut Array2<u8>,
+    ) {
+        let steps = (x1 as i64 - x0 as i64).abs().max((y1 as i64 - y0 as i64).abs()) as usize;
+        for step in 0..=steps {
+            let t = step as f64 / steps.max(1) as f64;
+            let x = (x0 as f64 + (x1 as f64 - x0 as f64) * t).round() as usize;
+            let y = (y0 as f64 + (y1 as f64 - y0 as f64) * t).round() as usize;
+            mask[(y, x)] = 1;
+        }
+    }
+
+    #[test]
+    fn a_straight_stroke_fits_one_segment() {
+        let mut mask = Array2::zeros((100, 100));
+        mask_with_segment((20, 50), (80, 50), &mut mask);
+        let segments = detect_segments(&mask, 4);
+        assert_eq!(segments.len(), 1);
+        assert!(segments[0].angle_degrees() < 1.0);
+        assert!((segments[0].length() - 60.0).abs() < 3.0);
+    }
+
+    #[test]
+    fn identical_panes_compare_with_zero_errors() {
+        let mut mask = Array2::zeros((100, 100));
+        mask_with_segment((20, 20), (80, 80), &mut mask);
+        mask_with_segment((20, 80), (80, 20), &mut mask);
+        let comparison = compare_lines(&mask, &mask, 4);
+        assert_eq!(comparison.matched.len(), 2);
+        assert_eq!(comparison.unmatched_reference, 0);
+        assert!(comparison.mean_angle_error_degrees < 1.0);
+        assert!(comparison.mean_length_ratio_error < 0.05);
+        assert!(comparison.mean_intersection_distance < 2.0);
+    }
+
+    #[test]
+    fn a_tilted_copy_reports_the_angle_error() {
+        let mut reference = Array2::zeros((100, 100));
+        mask_with_segment((20, 50), (80, 50), &mut reference);
+        let mut observation = Array2::zeros((100, 100));
+        mask_with_segment((20, 40), (80, 60), &mut observation);
+        let comparison = compare_lines(&reference, &observation, 4);
+        assert_eq!(comparison.matched.len(), 1);
+        let expected = (20.0f64 / 60.0).atan().to_degrees();
+        assert!(
+            (comparison.mean_angle_error_degrees - expected).abs() < 3.0,
+            "{} vs {expected}",
+            comparison.mean_angle_error_degrees
+        );
+    }
+
+    #[test]
+    fn a_missing_stroke_is_counted_as_unmatched() {
+        let mut reference = Array2::zeros((100, 100));
+        mask_with_segment((20, 20), (80, 80), &mut reference);
+        mask_with_segment((20, 80), (80, 20), &mut reference);
+        let mut observation = Array2::zeros((100, 100));
+        mask_with_segment((20, 20), (80, 80), &mut observation);
+        let comparison = compare_lines(&reference, &observation, 4);
+        assert_eq!(comparison.matched.len(), 1);
+        assert_eq!(comparison.unmatched_reference, 1);
+        assert_eq!(comparison.unmatched_observation, 0);
+    }
+}
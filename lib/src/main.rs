@@ -0,0 +1,393 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use evaluator::batch::{
+    evaluate_archive, evaluate_batch_with_options, ArchiveOptions, BatchOptions, BatchReportWriter,
+    ReportFormat,
+};
+use evaluator::render::render_heatmap;
+#[cfg(feature = "png")]
+use evaluator::report::render_html_report;
+use evaluator::{
+    Colormap, EvaluatorConfig, ExerciseManifest, ImageEvaluator, ReferenceModel, WorkerOptions,
+};
+
+const USAGE: &str = "\
+Usage:
+  evaluator evaluate <composite.png|recording.gif> [--timeline] [--opaque]
+  evaluator batch <directory> [--format json|csv|ndjson] [--opaque]
+                  [--fail-fast] [--max-retries <n>] [--timeout-ms <n>]
+                  [--resume [--threads <n>]]
+  evaluator dedupe <directory> [--max-distance <n>] [--opaque]
+  evaluator heatmap <composite.png> -o <out.png> [--colormap <name>] [--opaque]
+  evaluator report <composite.png> -o <report.html> [--opaque]
+  evaluator validate-reference <image.png> [--opaque]
+  evaluator init-demo <directory>
+  evaluator schema
+  evaluator worker [--threads <n>] [--queue <directory>] [--opaque]
+
+Every command also accepts --exercise <manifest.json> to take the pane
+layout and scoring configuration from an exercise manifest, and
+--verbose to log per-stage timings to stderr (RUST_LOG overrides the
+filter).
+";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{message}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    init_tracing(args.iter().any(|a| a == "--verbose"));
+    let mut config = match flag_value(args, "--exercise") {
+        Some(manifest_path) => ExerciseManifest::load(manifest_path)
+            .map_err(|e| e.to_string())?
+            .evaluator_config(),
+        None => EvaluatorConfig::default(),
+    };
+    if args.iter().any(|a| a == "--opaque") {
+        config.transparent_background = false;
+    }
+    let evaluator = ImageEvaluator::new(config);
+    match args.first().map(String::as_str) {
+        Some("evaluate") => {
+            let path = positional(args, 1)?;
+            let timeline = args.iter().any(|a| a == "--timeline");
+            let bytes = std::fs::read(&path)
+                .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+            // Animated submissions (GIF/WebP/APNG captures of the
+            // drawing process) score their final frame by default, or
+            // every frame with --timeline.
+            let frames = evaluator::timelapse::decode_animation(&bytes)
+                .map_err(|e| format!("{}: {e}", path.display()))?;
+            match frames {
+                Some(frames) if timeline => {
+                    let scores =
+                        evaluator::timelapse::evaluate_composite_frames(&evaluator, &frames)
+                            .map_err(|e| e.to_string())?;
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&scores).map_err(|e| e.to_string())?
+                    );
+                }
+                Some(frames) => {
+                    let last = frames
+                        .last()
+                        .ok_or_else(|| "animation has no frames".to_string())?;
+                    let (reference, observation) =
+                        evaluator.extract_panes(last).map_err(|e| e.to_string())?;
+                    let result = evaluator
+                        .evaluate_arrays(&reference, &observation)
+                        .map_err(|e| e.to_string())?;
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&result).map_err(|e| e.to_string())?
+                    );
+                }
+                None if timeline => {
+                    return Err("--timeline requires an animated input".to_string());
+                }
+                None => {
+                    let result = evaluator.evaluate_file(&path).map_err(|e| e.to_string())?;
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&result).map_err(|e| e.to_string())?
+                    );
+                }
+            }
+            Ok(())
+        }
+        Some("batch") => {
+            let directory = positional(args, 1)?;
+            let paths = png_files_in(&directory)?;
+            // --resume switches to the low-memory archive pipeline:
+            // bounded threads, streaming NDJSON, and a progress
+            // manifest in the batch directory for resumability.
+            if args.iter().any(|a| a == "--resume") {
+                let mut options = ArchiveOptions {
+                    progress_path: Some(directory.join(".batch-progress")),
+                    ..ArchiveOptions::default()
+                };
+                if let Some(threads) = flag_value(args, "--threads") {
+                    options.threads = threads
+                        .parse()
+                        .map_err(|_| format!("invalid --threads value: {threads}"))?;
+                }
+                let stats =
+                    evaluate_archive(&evaluator, &paths, std::io::stdout().lock(), &options)
+                        .map_err(|e| e.to_string())?;
+                eprintln!(
+                    "batch finished: {} succeeded, {} failed, {} already completed",
+                    stats.succeeded, stats.failed, stats.already_completed
+                );
+                return Ok(());
+            }
+            let mut options = BatchOptions {
+                continue_on_error: !args.iter().any(|a| a == "--fail-fast"),
+                ..BatchOptions::default()
+            };
+            if let Some(retries) = flag_value(args, "--max-retries") {
+                options.max_retries = retries
+                    .parse()
+                    .map_err(|_| format!("invalid --max-retries value: {retries}"))?;
+            }
+            if let Some(timeout) = flag_value(args, "--timeout-ms") {
+                let ms: u64 = timeout
+                    .parse()
+                    .map_err(|_| format!("invalid --timeout-ms value: {timeout}"))?;
+                options.per_image_timeout = Some(std::time::Duration::from_millis(ms));
+            }
+            let summary = evaluate_batch_with_options(&evaluator, &paths, &options);
+            let items = summary.items;
+            match flag_value(args, "--format").unwrap_or("json") {
+                "json" => {
+                    let records: Vec<serde_json::Value> = items
+                        .iter()
+                        .map(|item| match &item.result {
+                            Ok(result) => serde_json::json!({
+                                "path": item.path.display().to_string(),
+                                "metrics": result.metrics,
+                                "duration_ms": item.duration_ms,
+                                "error": null,
+                            }),
+                            Err(error) => serde_json::json!({
+                                "path": item.path.display().to_string(),
+                                "metrics": null,
+                                "duration_ms": item.duration_ms,
+                                "error": error.to_string(),
+                            }),
+                        })
+                        .collect();
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&records).map_err(|e| e.to_string())?
+                    );
+                }
+                format @ ("csv" | "ndjson") => {
+                    let format = if format == "csv" {
+                        ReportFormat::Csv
+                    } else {
+                        ReportFormat::Ndjson
+                    };
+                    let mut writer = BatchReportWriter::new(std::io::stdout().lock(), format);
+                    for item in &items {
+                        writer.write_item(item).map_err(|e| e.to_string())?;
+                    }
+                    let _ = writer.finish().map_err(|e| e.to_string())?;
+                }
+                other => return Err(format!("unknown format: {other}\n{USAGE}")),
+            }
+            eprintln!(
+                "batch finished: {} succeeded, {} failed, {} skipped",
+                summary.succeeded,
+                summary.failed,
+                summary.skipped.len()
+            );
+            Ok(())
+        }
+        Some("dedupe") => {
+            let directory = positional(args, 1)?;
+            let paths = png_files_in(&directory)?;
+            let max_distance = match flag_value(args, "--max-distance") {
+                Some(value) => value
+                    .parse()
+                    .map_err(|_| format!("invalid --max-distance value: {value}"))?,
+                None => evaluator::dedupe::DEFAULT_MAX_DISTANCE,
+            };
+            let mut hashes = Vec::with_capacity(paths.len());
+            for path in &paths {
+                let bytes = std::fs::read(path)
+                    .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+                let image = image::load_from_memory(&bytes).map_err(|e| e.to_string())?;
+                let (_, observation) = evaluator
+                    .extract_panes(&image.to_rgba8())
+                    .map_err(|e| format!("{}: {e}", path.display()))?;
+                hashes.push(evaluator::observation_hash(&observation));
+            }
+            let duplicates = evaluator::find_duplicates(&hashes, max_distance);
+            let records: Vec<serde_json::Value> = duplicates
+                .iter()
+                .map(|pair| {
+                    serde_json::json!({
+                        "first": paths[pair.first].display().to_string(),
+                        "second": paths[pair.second].display().to_string(),
+                        "distance": pair.distance,
+                    })
+                })
+                .collect();
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&records).map_err(|e| e.to_string())?
+            );
+            eprintln!(
+                "dedupe finished: {} submission(s), {} near-duplicate pair(s)",
+                paths.len(),
+                duplicates.len()
+            );
+            Ok(())
+        }
+        Some("heatmap") => {
+            let path = positional(args, 1)?;
+            let output = flag_value(args, "-o").ok_or_else(|| USAGE.to_string())?;
+            let colormap = match flag_value(args, "--colormap") {
+                Some(name) => {
+                    Colormap::by_name(name).ok_or_else(|| format!("unknown colormap: {name}"))?
+                }
+                None => Colormap::default(),
+            };
+            let bytes = std::fs::read(&path)
+                .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+            let image = image::load_from_memory(&bytes).map_err(|e| e.to_string())?;
+            let (reference, _) = evaluator
+                .extract_panes(&image.to_rgba8())
+                .map_err(|e| e.to_string())?;
+            let model = ReferenceModel::new(reference, evaluator.config().clone())
+                .map_err(|e| e.to_string())?;
+            render_heatmap(model.heatmap(), &colormap)
+                .save(output)
+                .map_err(|e| e.to_string())?;
+            Ok(())
+        }
+        #[cfg(feature = "png")]
+        Some("report") => {
+            let path = positional(args, 1)?;
+            let output = flag_value(args, "-o").ok_or_else(|| USAGE.to_string())?;
+            let bytes = std::fs::read(&path)
+                .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+            let image = image::load_from_memory(&bytes).map_err(|e| e.to_string())?;
+            let (reference, observation) = evaluator
+                .extract_panes(&image.to_rgba8())
+                .map_err(|e| e.to_string())?;
+            let result = evaluator
+                .evaluate_arrays(&reference, &observation)
+                .map_err(|e| e.to_string())?;
+            let html = render_html_report(&reference, &observation, &result);
+            std::fs::write(output, html)
+                .map_err(|e| format!("failed to write {output}: {e}"))?;
+            Ok(())
+        }
+        Some("worker") => {
+            let mut options = WorkerOptions::default();
+            if let Some(threads) = flag_value(args, "--threads") {
+                options.threads = threads
+                    .parse()
+                    .map_err(|_| format!("invalid --threads value: {threads}"))?;
+            }
+            let config = evaluator.config().clone();
+            let stats = match flag_value(args, "--queue") {
+                Some(directory) => evaluator::run_queue_directory(
+                    directory,
+                    std::io::stdout(),
+                    config,
+                    &options,
+                ),
+                None => evaluator::run_worker(
+                    std::io::stdin().lock(),
+                    std::io::stdout(),
+                    config,
+                    &options,
+                ),
+            }
+            .map_err(|e| e.to_string())?;
+            eprintln!(
+                "worker finished: {} processed, {} succeeded, {} failed",
+                stats.processed, stats.succeeded, stats.failed
+            );
+            Ok(())
+        }
+        Some("validate-reference") => {
+            let path = positional(args, 1)?;
+            let bytes = std::fs::read(&path)
+                .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+            let image = image::load_from_memory(&bytes).map_err(|e| e.to_string())?;
+            let mask = evaluator::timelapse::mask_from_frame(
+                &image.to_rgba8(),
+                evaluator.config().transparent_background,
+            );
+            let warnings = evaluator::validate_reference(&mask);
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&warnings).map_err(|e| e.to_string())?
+            );
+            if warnings.is_empty() {
+                Ok(())
+            } else {
+                Err(format!(
+                    "{} failed validation with {} warning(s)",
+                    path.display(),
+                    warnings.len()
+                ))
+            }
+        }
+        // Generated demo exercises, so integrators can verify their
+        // setup without hunting for valid composites.
+        #[cfg(feature = "png")]
+        Some("init-demo") => {
+            let directory = positional(args, 1)?;
+            let demos = evaluator::examples::quickstart(&directory).map_err(|e| e.to_string())?;
+            for demo in &demos {
+                eprintln!(
+                    "generated {}: top-5 error {:.2}, coverage {:.0}%",
+                    demo.composite.display(),
+                    demo.result.metrics.top_5_error,
+                    demo.result.metrics.coverage * 100.0
+                );
+            }
+            Ok(())
+        }
+        // The JSON Schema for every payload the CLI prints, for
+        // frontend codegen and validation.
+        Some("schema") => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&evaluator::schema::json_schema())
+                    .map_err(|e| e.to_string())?
+            );
+            Ok(())
+        }
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+/// Logs to stderr, honoring `RUST_LOG` when set; `--verbose` lowers the
+/// default level to debug so per-stage timings show up.
+fn init_tracing(verbose: bool) {
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(if verbose { "debug" } else { "warn" }));
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .init();
+}
+
+fn positional(args: &[String], index: usize) -> Result<PathBuf, String> {
+    args.iter()
+        .filter(|a| !a.starts_with("--"))
+        .nth(index)
+        .map(PathBuf::from)
+        .ok_or_else(|| USAGE.to_string())
+}
+
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(String::as_str)
+}
+
+fn png_files_in(directory: &PathBuf) -> Result<Vec<PathBuf>, String> {
+    let entries = std::fs::read_dir(directory)
+        .map_err(|e| format!("failed to read {}: {e}", directory.display()))?;
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "png"))
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
@@ -0,0 +1,217 @@
+//! Shared registry of live streaming sessions.
+//!
+//! Server embedders hold many concurrent [`StreamingEvaluator`]s — one
+//! per drawing user. [`SessionManager`] gives them one safe place to put
+//! them: sessions are keyed by id, locked individually so slow
+//! evaluations don't serialize each other, expired after a TTL of
+//! inactivity, and capped at a configurable count.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::error::EvaluationError;
+use crate::streaming::StreamingEvaluator;
+
+/// Capacity and expiry knobs for a [`SessionManager`].
+#[derive(Debug, Clone)]
+pub struct SessionManagerConfig {
+    /// Most sessions held at once; inserts beyond this are rejected.
+    pub max_sessions: usize,
+    /// A session untouched for this long is evicted.
+    pub ttl: Duration,
+}
+
+impl Default for SessionManagerConfig {
+    fn default() -> Self {
+        Self {
+            max_sessions: 256,
+            ttl: Duration::from_secs(10 * 60),
+        }
+    }
+}
+
+/// Counters exposed for operational dashboards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionManagerMetrics {
+    pub active_sessions: usize,
+    /// Sessions removed because their TTL lapsed.
+    pub evictions: u64,
+    /// Inserts refused because the manager was full.
+    pub rejected: u64,
+}
+
+struct SessionEntry {
+    evaluator: StreamingEvaluator,
+    last_touched: Instant,
+}
+
+/// A concurrency-safe map of active [`StreamingEvaluator`] sessions.
+pub struct SessionManager {
+    config: SessionManagerConfig,
+    sessions: Mutex<HashMap<String, Arc<Mutex<SessionEntry>>>>,
+    evictions: AtomicU64,
+    rejected: AtomicU64,
+}
+
+impl SessionManager {
+    pub fn new(config: SessionManagerConfig) -> Self {
+        Self {
+            config,
+            sessions: Mutex::new(HashMap::new()),
+            evictions: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+        }
+    }
+
+    /// Registers a session under `id`, replacing any existing session
+    /// with that id. Fails when the manager is full even after expired
+    /// sessions are swept.
+    pub fn insert(&self, id: impl Into<String>, evaluator: StreamingEvaluator) -> Result<(), EvaluationError> {
+        let id = id.into();
+        let mut sessions = self.sessions.lock().expect("session map poisoned");
+        self.sweep_expired(&mut sessions);
+        if sessions.len() >= self.config.max_sessions && !sessions.contains_key(&id) {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+            return Err(EvaluationError::InvalidState(format!(
+                "session limit of {} reached",
+                self.config.max_sessions
+            )));
+        }
+        sessions.insert(
+            id,
+            Arc::new(Mutex::new(SessionEntry {
+                evaluator,
+                last_touched: Instant::now(),
+            })),
+        );
+        Ok(())
+    }
+
+    /// Runs `operation` with exclusive access to one session, refreshing
+    /// its TTL. The map lock is released before the session lock is
+    /// taken, so sessions never block each other. Returns `None` for an
+    /// unknown or expired id.
+    pub fn with_session<R>(
+        &self,
+        id: &str,
+        operation: impl FnOnce(&mut StreamingEvaluator) -> R,
+    ) -> Option<R> {
+        let entry = {
+            let mut sessions = self.sessions.lock().expect("session map poisoned");
+            self.sweep_expired(&mut sessions);
+            sessions.get(id).cloned()
+        }?;
+        let mut entry = entry.lock().expect("session poisoned");
+        entry.last_touched = Instant::now();
+        Some(operation(&mut entry.evaluator))
+    }
+
+    /// Removes a session, returning whether it existed.
+    pub fn remove(&self, id: &str) -> bool {
+        let mut sessions = self.sessions.lock().expect("session map poisoned");
+        sessions.remove(id).is_some()
+    }
+
+    pub fn metrics(&self) -> SessionManagerMetrics {
+        let sessions = self.sessions.lock().expect("session map poisoned");
+        SessionManagerMetrics {
+            active_sessions: sessions.len(),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            rejected: self.rejected.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Drops every session whose TTL lapsed. Sessions currently locked
+    /// by [`Self::with_session`] still count as touched at their last
+    /// refresh, so a long-running operation can be evicted only after
+    /// it finishes plus a full TTL.
+    fn sweep_expired(&self, sessions: &mut HashMap<String, Arc<Mutex<SessionEntry>>>) {
+        let ttl = self.config.ttl;
+        let before = sessions.len();
+        sessions.retain(|_, entry| match entry.try_lock() {
+            Ok(entry) => entry.last_touched.elapsed() < ttl,
+            // Locked means in use right now; never evict those.
+            Err(_) => true,
+        });
+        let evicted = (before - sessions.len()) as u64;
+        if evicted > 0 {
+            self.evictions.fetch_add(evicted, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Default for SessionManager {
+    fn default() -> Self {
+        Self::new(SessionManagerConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::EvaluatorConfig;
+    use crate::streaming::ReferenceModel;
+    use ndarray::Array2;
+
+    fn sample_evaluator() -> StreamingEvaluator {
+        let mut pixels = Array2::zeros((100, 100));
+        for x in 20..80 {
+            pixels[(50, x)] = 1;
+        }
+        let config = EvaluatorConfig {
+            canvas_width: 100,
+            canvas_height: 100,
+            ..EvaluatorConfig::default()
+        };
+        StreamingEvaluator::new(ReferenceModel::new(pixels, config).unwrap())
+    }
+
+    #[test]
+    fn sessions_are_retrieved_and_operated_on_by_id() {
+        let manager = SessionManager::default();
+        manager.insert("user-1", sample_evaluator()).unwrap();
+        let count = manager.with_session("user-1", |evaluator| {
+            evaluator.add_observation_pixels(&[(50, 20), (50, 21)]).unwrap();
+            evaluator.observation_count()
+        });
+        assert_eq!(count, Some(2));
+        assert!(manager.with_session("user-2", |_| ()).is_none());
+        assert_eq!(manager.metrics().active_sessions, 1);
+    }
+
+    #[test]
+    fn full_manager_rejects_new_sessions_but_allows_replacement() {
+        let manager = SessionManager::new(SessionManagerConfig {
+            max_sessions: 1,
+            ..SessionManagerConfig::default()
+        });
+        manager.insert("user-1", sample_evaluator()).unwrap();
+        let error = manager.insert("user-2", sample_evaluator()).unwrap_err();
+        assert!(error.to_string().contains("limit of 1"));
+        manager.insert("user-1", sample_evaluator()).unwrap();
+        assert_eq!(manager.metrics().rejected, 1);
+    }
+
+    #[test]
+    fn idle_sessions_expire_and_count_as_evictions() {
+        let manager = SessionManager::new(SessionManagerConfig {
+            ttl: Duration::ZERO,
+            ..SessionManagerConfig::default()
+        });
+        manager.insert("user-1", sample_evaluator()).unwrap();
+        assert!(manager.with_session("user-1", |_| ()).is_none());
+        let metrics = manager.metrics();
+        assert_eq!(metrics.active_sessions, 0);
+        assert_eq!(metrics.evictions, 1);
+    }
+
+    #[test]
+    fn removal_reports_whether_the_session_existed() {
+        let manager = SessionManager::default();
+        manager.insert("user-1", sample_evaluator()).unwrap();
+        assert!(manager.remove("user-1"));
+        assert!(!manager.remove("user-1"));
+    }
+}
@@ -0,0 +1,304 @@
+//! Declarative exercise manifests.
+//!
+//! An exercise is described by a small JSON file — identity, reference
+//! image, pane layout, scoring knobs and an optional time limit —
+//! instead of a growing set of CLI flags. Loading validates the manifest
+//! and reports every problem with the field that caused it.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::decode::InkChannel;
+use crate::error::EvaluationError;
+use crate::evaluator::{EvaluatorConfig, OutlierFilter};
+use crate::metrics::{CellAggregator, Normalization, GRID_SIZE};
+use crate::scale::ResampleMode;
+
+/// One drawing exercise, as loaded from `manifest.json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExerciseManifest {
+    pub id: String,
+    pub title: String,
+    /// Path to the reference image, relative to the manifest file.
+    pub reference: PathBuf,
+    #[serde(default)]
+    pub layout: LayoutSpec,
+    #[serde(default)]
+    pub scoring: ScoringSpec,
+    /// Time the user gets to draw, in milliseconds. `None` is untimed.
+    #[serde(default)]
+    pub time_limit_ms: Option<u64>,
+    /// What happens to strokes drawn after the time limit.
+    #[serde(default)]
+    pub overtime: OvertimePolicy,
+}
+
+/// How scoring treats strokes drawn after the exercise time limit.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OvertimePolicy {
+    /// Overtime strokes are dropped from scoring.
+    #[default]
+    Exclude,
+    /// Overtime strokes count, but the top-5 error is increased by this
+    /// much per overtime second.
+    PenaltyPerSecond(f64),
+    /// The limit is advisory; overtime is recorded but not punished.
+    Ignore,
+}
+
+/// Pane geometry and export format of the exercise canvas.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LayoutSpec {
+    pub canvas_width: usize,
+    pub canvas_height: usize,
+    pub pane_gap: usize,
+    pub transparent_background: bool,
+    /// Which channel(s) mark ink on opaque exports.
+    pub ink_channel: InkChannel,
+}
+
+impl Default for LayoutSpec {
+    fn default() -> Self {
+        let config = EvaluatorConfig::default();
+        Self {
+            canvas_width: config.canvas_width,
+            canvas_height: config.canvas_height,
+            pane_gap: config.pane_gap,
+            transparent_background: config.transparent_background,
+            ink_channel: config.ink_channel,
+        }
+    }
+}
+
+/// Scoring knobs of the exercise.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoringSpec {
+    pub tolerance: i32,
+    pub skeletonize: bool,
+    pub max_distance: Option<i32>,
+    pub resample: ResampleMode,
+    pub normalization: Normalization,
+    pub outlier_filter: Option<OutlierFilter>,
+    pub auto_center: bool,
+    /// Per-grid-cell tolerance multipliers, row-major over the full
+    /// scoring grid.
+    pub cell_tolerance_multipliers: Option<Vec<Vec<f64>>>,
+    /// Fit the scoring grid to the reference's bounding box instead of
+    /// the full canvas.
+    pub fit_grid_to_reference: bool,
+    /// How each grid cell's pixel errors collapse into its score.
+    pub cell_aggregator: CellAggregator,
+}
+
+impl Default for ScoringSpec {
+    fn default() -> Self {
+        let config = EvaluatorConfig::default();
+        Self {
+            tolerance: config.tolerance,
+            skeletonize: config.skeletonize,
+            max_distance: config.max_distance,
+            resample: config.resample,
+            normalization: config.normalization,
+            outlier_filter: config.outlier_filter,
+            auto_center: config.auto_center,
+            cell_tolerance_multipliers: config.cell_tolerance_multipliers,
+            fit_grid_to_reference: config.fit_grid_to_reference,
+            cell_aggregator: config.cell_aggregator,
+        }
+    }
+}
+
+impl ExerciseManifest {
+    /// Loads and validates a manifest file. The reference path is
+    /// resolved relative to the manifest's directory.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, EvaluationError> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|source| EvaluationError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let mut manifest = Self::from_json(&json)?;
+        if let Some(directory) = path.parent() {
+            manifest.reference = directory.join(&manifest.reference);
+        }
+        Ok(manifest)
+    }
+
+    /// Parses and validates a manifest from its JSON text.
+    pub fn from_json(json: &str) -> Result<Self, EvaluationError> {
+        let manifest: Self = serde_json::from_str(json)
+            .map_err(|e| EvaluationError::InvalidManifest(e.to_string()))?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    /// Checks every field, reporting all problems in one message so a
+    /// manifest author does not fix them one at a time.
+    pub fn validate(&self) -> Result<(), EvaluationError> {
+        let mut problems = Vec::new();
+        if self.id.trim().is_empty() {
+            problems.push("id must not be empty".to_string());
+        }
+        if self.title.trim().is_empty() {
+            problems.push("title must not be empty".to_string());
+        }
+        if self.reference.as_os_str().is_empty() {
+            problems.push("reference must point to an image file".to_string());
+        }
+        if self.layout.canvas_width == 0 || self.layout.canvas_height == 0 {
+            problems.push(format!(
+                "layout.canvas_width and layout.canvas_height must be positive, got {}x{}",
+                self.layout.canvas_width, self.layout.canvas_height
+            ));
+        }
+        if self.scoring.tolerance < 0 {
+            problems.push(format!(
+                "scoring.tolerance must not be negative, got {}",
+                self.scoring.tolerance
+            ));
+        }
+        if let Some(max) = self.scoring.max_distance {
+            if max < self.scoring.tolerance {
+                problems.push(format!(
+                    "scoring.max_distance ({max}) must be at least scoring.tolerance ({})",
+                    self.scoring.tolerance
+                ));
+            }
+        }
+        if let Some(multipliers) = &self.scoring.cell_tolerance_multipliers {
+            let rows = multipliers.len();
+            let square = multipliers.iter().all(|row| row.len() == GRID_SIZE);
+            if rows != GRID_SIZE || !square {
+                problems.push(format!(
+                    "scoring.cell_tolerance_multipliers must be a {GRID_SIZE}x{GRID_SIZE} grid"
+                ));
+            }
+            if multipliers
+                .iter()
+                .flatten()
+                .any(|m| *m <= 0.0 || !m.is_finite())
+            {
+                problems.push(
+                    "scoring.cell_tolerance_multipliers must all be positive and finite"
+                        .to_string(),
+                );
+            }
+        }
+        match self.scoring.cell_aggregator {
+            CellAggregator::Percentile(percentile)
+                if !(0.0..=100.0).contains(&percentile) || !percentile.is_finite() =>
+            {
+                problems.push(format!(
+                    "scoring.cell_aggregator percentile ({percentile}) must be between 0 and 100"
+                ));
+            }
+            CellAggregator::MeanOfWorstK(0) => {
+                problems.push(
+                    "scoring.cell_aggregator mean_of_worst_k must be at least 1".to_string(),
+                );
+            }
+            _ => {}
+        }
+        if self.time_limit_ms == Some(0) {
+            problems.push("time_limit_ms must be positive; omit it for untimed".to_string());
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(EvaluationError::InvalidManifest(problems.join("; ")))
+        }
+    }
+
+    /// The evaluator configuration this exercise prescribes.
+    pub fn evaluator_config(&self) -> EvaluatorConfig {
+        EvaluatorConfig {
+            canvas_width: self.layout.canvas_width,
+            canvas_height: self.layout.canvas_height,
+            pane_gap: self.layout.pane_gap,
+            transparent_background: self.layout.transparent_background,
+            ink_channel: self.layout.ink_channel,
+            tolerance: self.scoring.tolerance,
+            skeletonize: self.scoring.skeletonize,
+            max_distance: self.scoring.max_distance,
+            resample: self.scoring.resample,
+            normalization: self.scoring.normalization,
+            outlier_filter: self.scoring.outlier_filter,
+            auto_center: self.scoring.auto_center,
+            cell_tolerance_multipliers: self.scoring.cell_tolerance_multipliers.clone(),
+            fit_grid_to_reference: self.scoring.fit_grid_to_reference,
+            cell_aggregator: self.scoring.cell_aggregator,
+            collect_perf_stats: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minimal_json() -> &'static str {
+        r#"{ "id": "cat-01", "title": "Sitting cat", "reference": "cat.png" }"#
+    }
+
+    #[test]
+    fn minimal_manifest_uses_default_layout_and_scoring() {
+        let manifest = ExerciseManifest::from_json(minimal_json()).unwrap();
+        assert_eq!(manifest.evaluator_config(), EvaluatorConfig::default());
+        assert_eq!(manifest.time_limit_ms, None);
+    }
+
+    #[test]
+    fn validation_reports_every_problem_at_once() {
+        let json = r#"{
+            "id": " ",
+            "title": "Sitting cat",
+            "reference": "cat.png",
+            "scoring": { "tolerance": 5, "max_distance": 2 },
+            "time_limit_ms": 0
+        }"#;
+        let error = ExerciseManifest::from_json(json).unwrap_err();
+        let message = error.to_string();
+        assert!(message.contains("id must not be empty"), "{message}");
+        assert!(message.contains("max_distance (2)"), "{message}");
+        assert!(message.contains("time_limit_ms"), "{message}");
+    }
+
+    #[test]
+    fn cell_tolerance_multipliers_must_form_a_positive_grid() {
+        let json = r#"{
+            "id": "cat-01",
+            "title": "Sitting cat",
+            "reference": "cat.png",
+            "scoring": { "cell_tolerance_multipliers": [[1.0, -2.0]] }
+        }"#;
+        let message = ExerciseManifest::from_json(json).unwrap_err().to_string();
+        assert!(message.contains("10x10 grid"), "{message}");
+        assert!(message.contains("positive and finite"), "{message}");
+    }
+
+    #[test]
+    fn cell_aggregator_parameters_are_validated() {
+        let json = r#"{
+            "id": "cat-01",
+            "title": "Sitting cat",
+            "reference": "cat.png",
+            "scoring": { "cell_aggregator": { "percentile": 120.0 } }
+        }"#;
+        let message = ExerciseManifest::from_json(json).unwrap_err().to_string();
+        assert!(message.contains("between 0 and 100"), "{message}");
+    }
+
+    #[test]
+    fn load_resolves_the_reference_relative_to_the_manifest() {
+        let directory = std::env::temp_dir().join("evaluator-manifest-test");
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("manifest.json");
+        std::fs::write(&path, minimal_json()).unwrap();
+        let manifest = ExerciseManifest::load(&path).unwrap();
+        assert_eq!(manifest.reference, directory.join("cat.png"));
+    }
+}
@@ -0,0 +1,876 @@
+use std::collections::VecDeque;
+
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+/// Number of cells along each axis of the scoring grid.
+pub use crate::core::GRID_SIZE;
+
+/// Side length of the sliding worst-region window, in pixels.
+pub const WINDOW_SIZE: usize = 50;
+/// Offset between neighbouring window positions, in pixels.
+pub const WINDOW_STRIDE: usize = 10;
+
+/// Display-scale constants applied to raw pixel distances. Historically
+/// these were inline magic numbers; naming them makes scores tunable and
+/// reproducible, and the configuration used is echoed in the metrics.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Normalization {
+    /// Divisor applied to the raw mean pixel distance.
+    pub mean_error_divisor: f64,
+    /// Divisor applied to the sum of the five worst grid cells.
+    pub top_5_divisor: f64,
+}
+
+impl Default for Normalization {
+    fn default() -> Self {
+        Self {
+            mean_error_divisor: 5.0,
+            top_5_divisor: 25.0,
+        }
+    }
+}
+
+/// How the pixel errors inside one scoring grid cell collapse into the
+/// cell's score.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CellAggregator {
+    /// Worst pixel error in the cell — the historical behavior, where
+    /// one stray pixel poisons an otherwise-perfect cell.
+    #[default]
+    Max,
+    /// The given percentile (`0..=100`, clamped) of the cell's pixel
+    /// errors, so isolated strays stop dominating.
+    Percentile(f64),
+    /// Max after ignoring the given number of worst pixels.
+    TrimmedMax(usize),
+    /// Mean of the worst k pixels (clamped to at least one).
+    MeanOfWorstK(usize),
+}
+
+impl CellAggregator {
+    /// Collapses one cell's pixel errors; sorts `samples` in place.
+    /// Empty cells score zero under every aggregator.
+    fn aggregate(self, samples: &mut [f64]) -> f64 {
+        if samples.is_empty() {
+            return 0.0;
+        }
+        samples.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+        match self {
+            Self::Max => samples[0],
+            Self::Percentile(percentile) => {
+                let fraction = (percentile / 100.0).clamp(0.0, 1.0);
+                let from_best = ((samples.len() - 1) as f64 * fraction).round() as usize;
+                samples[samples.len() - 1 - from_best]
+            }
+            Self::TrimmedMax(drop) => samples.get(drop).copied().unwrap_or(0.0),
+            Self::MeanOfWorstK(count) => {
+                let count = count.clamp(1, samples.len());
+                samples[..count].iter().sum::<f64>() / count as f64
+            }
+        }
+    }
+}
+
+/// Aggregated error metrics for one observation scored against a reference.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorMetrics {
+    /// Average distance-to-reference over all observation pixels.
+    pub mean_error: f64,
+    /// Mean of the five worst scoring grid cells.
+    pub top_5_error: f64,
+    /// Fraction of reference pixels that have an observation pixel within
+    /// the tolerance radius.
+    pub coverage: f64,
+    /// Worst per-pixel distance in each scoring grid cell, row-major.
+    pub grid: Vec<Vec<f64>>,
+    /// The normalization constants these scores were computed with.
+    #[serde(default)]
+    pub normalization: Normalization,
+    /// Per-cell tolerance multipliers the grid was aggregated with,
+    /// row-major; `None` when every cell used the base tolerance.
+    #[serde(default)]
+    pub cell_tolerance_multipliers: Option<Vec<Vec<f64>>>,
+    /// The same errors expressed relative to the canvas size, for
+    /// comparing attempts across resolutions. `None` on results stored
+    /// before these were recorded.
+    #[serde(default)]
+    pub normalized: Option<NormalizedMetrics>,
+}
+
+/// Error scores expressed as percentages of the canvas diagonal.
+///
+/// Raw pixel distances are not comparable between a 500px and a 1000px
+/// canvas: the same relative miss reads twice as bad on the larger one.
+/// Dividing by the canvas diagonal makes cross-device leaderboards
+/// fair; the raw values stay in [`ErrorMetrics`] for everything that
+/// scores on one fixed canvas.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NormalizedMetrics {
+    /// The canvas diagonal in pixels the percentages are relative to.
+    pub canvas_diagonal: f64,
+    /// Mean pixel distance as a percentage of the canvas diagonal.
+    pub mean_error_pct: f64,
+    /// Mean distance of the five worst grid cells as a percentage of
+    /// the canvas diagonal.
+    pub top_5_error_pct: f64,
+}
+
+/// Aggregates per-pixel distances into [`ErrorMetrics`].
+///
+/// With `observation_weights` (e.g. stylus pressure per pixel, 0..=1),
+/// each observation pixel's error contribution is scaled by its weight
+/// and a reference pixel counts as covered in proportion to the
+/// heaviest observation pixel within the tolerance radius. `None`
+/// weights every pixel fully.
+///
+/// `cell_multipliers` loosens or tightens individual grid cells: a
+/// cell's recorded error is divided by its multiplier and its coverage
+/// tolerance scaled by it, so background-texture cells can score
+/// leniently while focal cells stay strict. `None` keeps every cell at
+/// the base tolerance.
+///
+/// With `fit_grid_to_reference`, the grid spans the reference's
+/// bounding box instead of the whole canvas, so a drawing confined to
+/// one quadrant still exercises every cell; observation pixels outside
+/// the box land in the nearest edge cell.
+///
+/// `cell_aggregator` picks how a cell's pixel errors collapse into its
+/// score; [`CellAggregator::Max`] reproduces the historical scores.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn compute_metrics(
+    reference: &Array2<u8>,
+    reference_heatmap: &Array2<i32>,
+    observation: &Array2<u8>,
+    observation_heatmap: &Array2<i32>,
+    observation_weights: Option<&Array2<f64>>,
+    tolerance: i32,
+    cell_multipliers: Option<&[Vec<f64>]>,
+    fit_grid_to_reference: bool,
+    cell_aggregator: CellAggregator,
+    normalization: Normalization,
+) -> ErrorMetrics {
+    let (height, width) = observation.dim();
+    let bounds = fit_grid_to_reference
+        .then(|| reference_bounds(reference))
+        .flatten();
+    let ((origin_y, origin_x), cell_height, cell_width) = match bounds {
+        Some(((y0, x0), (y1, x1))) => (
+            (y0, x0),
+            (y1 - y0 + 1).div_ceil(GRID_SIZE),
+            (x1 - x0 + 1).div_ceil(GRID_SIZE),
+        ),
+        None => (
+            (0, 0),
+            height.div_ceil(GRID_SIZE),
+            width.div_ceil(GRID_SIZE),
+        ),
+    };
+    let cell_of = |y: usize, x: usize| {
+        let row = y.saturating_sub(origin_y) / cell_height.max(1);
+        let column = x.saturating_sub(origin_x) / cell_width.max(1);
+        (row.min(GRID_SIZE - 1), column.min(GRID_SIZE - 1))
+    };
+    let mut grid = vec![vec![0.0f64; GRID_SIZE]; GRID_SIZE];
+    // Max streams as a running maximum; the partial-credit aggregators
+    // need every sample in the cell before they can collapse it.
+    let mut cell_samples = match cell_aggregator {
+        CellAggregator::Max => Vec::new(),
+        _ => vec![Vec::new(); GRID_SIZE * GRID_SIZE],
+    };
+    let mut error_sum = 0.0f64;
+    let mut weight_sum = 0.0f64;
+    for ((y, x), &on) in observation.indexed_iter() {
+        if on == 0 {
+            continue;
+        }
+        let weight = observation_weights.map_or(1.0, |w| w[(y, x)]);
+        let distance = f64::from(reference_heatmap[(y, x)].max(0));
+        error_sum += weight * distance;
+        weight_sum += weight;
+        let (row, column) = cell_of(y, x);
+        let multiplier = multiplier_at(cell_multipliers, row, column);
+        let value = weight * distance / multiplier;
+        if cell_samples.is_empty() {
+            let cell = &mut grid[row][column];
+            *cell = cell.max(value);
+        } else {
+            cell_samples[row * GRID_SIZE + column].push(value);
+        }
+    }
+    for (index, samples) in cell_samples.iter_mut().enumerate() {
+        grid[index / GRID_SIZE][index % GRID_SIZE] = cell_aggregator.aggregate(samples);
+    }
+    let mean_error = if weight_sum == 0.0 {
+        0.0
+    } else {
+        error_sum / weight_sum / normalization.mean_error_divisor
+    };
+
+    let mut reference_count = 0u64;
+    let mut covered = 0.0f64;
+    for ((y, x), &on) in reference.indexed_iter() {
+        if on == 0 {
+            continue;
+        }
+        reference_count += 1;
+        let (row, column) = cell_of(y, x);
+        let multiplier = multiplier_at(cell_multipliers, row, column);
+        let cell_tolerance = (f64::from(tolerance) * multiplier).round() as i32;
+        if (0..=cell_tolerance).contains(&observation_heatmap[(y, x)]) {
+            covered += match observation_weights {
+                Some(weights) => max_weight_near(weights, observation, (y, x), cell_tolerance),
+                None => 1.0,
+            };
+        }
+    }
+    let coverage = if reference_count == 0 {
+        0.0
+    } else {
+        covered / reference_count as f64
+    };
+
+    let diagonal = ((width * width + height * height) as f64).sqrt();
+    let normalized = (diagonal > 0.0).then(|| NormalizedMetrics {
+        canvas_diagonal: diagonal,
+        mean_error_pct: if weight_sum == 0.0 {
+            0.0
+        } else {
+            100.0 * error_sum / weight_sum / diagonal
+        },
+        top_5_error_pct: 100.0 * top_5_from_grid(&grid, 5.0) / diagonal,
+    });
+
+    ErrorMetrics {
+        mean_error,
+        top_5_error: top_5_from_grid(&grid, normalization.top_5_divisor),
+        coverage,
+        grid,
+        normalization,
+        cell_tolerance_multipliers: cell_multipliers.map(<[Vec<f64>]>::to_vec),
+        normalized,
+    }
+}
+
+/// Bounding box of the reference's set pixels as inclusive
+/// `((top, left), (bottom, right))` corners; `None` for an empty mask.
+fn reference_bounds(reference: &Array2<u8>) -> Option<((usize, usize), (usize, usize))> {
+    let mut bounds: Option<((usize, usize), (usize, usize))> = None;
+    for ((y, x), &on) in reference.indexed_iter() {
+        if on == 0 {
+            continue;
+        }
+        bounds = Some(match bounds {
+            None => ((y, x), (y, x)),
+            Some(((y0, x0), (y1, x1))) => ((y0.min(y), x0.min(x)), (y1.max(y), x1.max(x))),
+        });
+    }
+    bounds
+}
+
+/// The tolerance multiplier of one grid cell; cells outside the
+/// override map (or without one) stay at 1.
+fn multiplier_at(multipliers: Option<&[Vec<f64>]>, row: usize, column: usize) -> f64 {
+    multipliers
+        .and_then(|rows| rows.get(row))
+        .and_then(|cells| cells.get(column))
+        .copied()
+        .filter(|multiplier| *multiplier > 0.0 && multiplier.is_finite())
+        .unwrap_or(1.0)
+}
+
+/// Heaviest observation pixel within the tolerance window around a
+/// covered reference pixel, clamped to a full pixel's worth.
+fn max_weight_near(
+    weights: &Array2<f64>,
+    observation: &Array2<u8>,
+    (y, x): (usize, usize),
+    tolerance: i32,
+) -> f64 {
+    let (height, width) = observation.dim();
+    let mut heaviest = 0.0f64;
+    for dy in -tolerance..=tolerance {
+        for dx in -tolerance..=tolerance {
+            let (ny, nx) = (y as i32 + dy, x as i32 + dx);
+            if ny < 0 || nx < 0 || ny >= height as i32 || nx >= width as i32 {
+                continue;
+            }
+            let pos = (ny as usize, nx as usize);
+            if observation[pos] != 0 {
+                heaviest = heaviest.max(weights[pos]);
+            }
+        }
+    }
+    heaviest.min(1.0)
+}
+
+/// One window of a [`SlidingWorstRegions`] report.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WorstWindow {
+    /// Canvas coordinates of the window's top-left corner.
+    pub x: usize,
+    pub y: usize,
+    /// Worst raw pixel distance inside the window.
+    pub error: f64,
+}
+
+/// Grid-free counterpart of the top-5 score, reported alongside it for
+/// comparison: the scoring grid imposes arbitrary cell boundaries, so a
+/// deviation straddling one splits its error across two cells, while
+/// the sliding windows always have some position that contains it
+/// whole.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlidingWorstRegions {
+    /// Up to five non-overlapping windows with nonzero error, worst
+    /// first.
+    pub windows: Vec<WorstWindow>,
+    /// Sum of those windows' errors over the top-5 divisor — the same
+    /// scale as [`ErrorMetrics::top_5_error`].
+    pub top_5_error: f64,
+}
+
+/// Computes the sliding worst-region report: a [`WINDOW_SIZE`]-square
+/// window slides over the canvas at [`WINDOW_STRIDE`], each position
+/// recording its worst pixel distance via separable 2D max-pooling, and
+/// the five worst non-overlapping positions are kept.
+pub fn sliding_worst_regions(
+    reference_heatmap: &Array2<i32>,
+    observation: &Array2<u8>,
+    normalization: Normalization,
+) -> SlidingWorstRegions {
+    let (height, width) = observation.dim();
+    let window_height = WINDOW_SIZE.min(height);
+    let window_width = WINDOW_SIZE.min(width);
+    if window_height == 0 || window_width == 0 {
+        return SlidingWorstRegions {
+            windows: Vec::new(),
+            top_5_error: 0.0,
+        };
+    }
+    // Row pass: sliding maxima of the per-pixel error surface along x.
+    let row_maxima: Vec<Vec<i32>> = (0..height)
+        .map(|y| {
+            let row: Vec<i32> = (0..width)
+                .map(|x| {
+                    if observation[(y, x)] != 0 {
+                        reference_heatmap[(y, x)].max(0)
+                    } else {
+                        0
+                    }
+                })
+                .collect();
+            sliding_max(&row, window_width)
+        })
+        .collect();
+    // Column pass, sampled at the stride in both axes.
+    let mut candidates = Vec::new();
+    for x in (0..row_maxima[0].len()).step_by(WINDOW_STRIDE) {
+        let column: Vec<i32> = (0..height).map(|y| row_maxima[y][x]).collect();
+        let column_maxima = sliding_max(&column, window_height);
+        for y in (0..column_maxima.len()).step_by(WINDOW_STRIDE) {
+            if column_maxima[y] > 0 {
+                candidates.push((column_maxima[y], y, x));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.cmp(a));
+    let mut windows: Vec<WorstWindow> = Vec::new();
+    for (error, y, x) in candidates {
+        if windows.len() == 5 {
+            break;
+        }
+        let overlaps = windows.iter().any(|window| {
+            window.y.abs_diff(y) < window_height && window.x.abs_diff(x) < window_width
+        });
+        if !overlaps {
+            windows.push(WorstWindow {
+                x,
+                y,
+                error: f64::from(error),
+            });
+        }
+    }
+    let top_5_error =
+        windows.iter().map(|window| window.error).sum::<f64>() / normalization.top_5_divisor;
+    SlidingWorstRegions {
+        windows,
+        top_5_error,
+    }
+}
+
+/// Maximum of every `window`-sample window of `values`, one entry per
+/// starting index, via a monotonic deque in O(n).
+fn sliding_max(values: &[i32], window: usize) -> Vec<i32> {
+    let mut maxima = Vec::with_capacity(values.len().saturating_sub(window) + 1);
+    let mut deque: VecDeque<usize> = VecDeque::new();
+    for (index, &value) in values.iter().enumerate() {
+        while deque.back().is_some_and(|&back| values[back] <= value) {
+            deque.pop_back();
+        }
+        deque.push_back(index);
+        if deque.front().is_some_and(|&front| front + window <= index) {
+            deque.pop_front();
+        }
+        if index + 1 >= window {
+            maxima.push(values[deque[0]]);
+        }
+    }
+    maxima
+}
+
+/// Per-cell score change between two attempts at the same reference:
+/// `after` minus `before`, row-major over the scoring grid. Negative
+/// cells improved, positive cells regressed, zero cells are unchanged.
+/// Grids of mismatched size (e.g. only one attempt was scored with
+/// `fit_grid_to_reference`) compare over the cells both recorded.
+pub fn grid_delta(before: &ErrorMetrics, after: &ErrorMetrics) -> Vec<Vec<f64>> {
+    before
+        .grid
+        .iter()
+        .zip(&after.grid)
+        .map(|(before_row, after_row)| {
+            before_row
+                .iter()
+                .zip(after_row)
+                .map(|(before_cell, after_cell)| after_cell - before_cell)
+                .collect()
+        })
+        .collect()
+}
+
+/// Sum of the five worst grid cells over the configured divisor, on the
+/// scale the app displays.
+pub(crate) fn top_5_from_grid(grid: &[Vec<f64>], divisor: f64) -> f64 {
+    let mut cells: Vec<f64> = grid.iter().flatten().copied().collect();
+    cells.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+    cells.iter().take(5).sum::<f64>() / divisor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heatmap::flood_fill_distances;
+
+    #[test]
+    fn perfect_tracing_scores_zero_error_and_full_coverage() {
+        let mut pixels = Array2::zeros((500, 500));
+        for x in 100..400 {
+            pixels[(250, x)] = 1;
+        }
+        let heatmap = flood_fill_distances(&pixels, None);
+        let metrics = compute_metrics(
+            &pixels,
+            &heatmap,
+            &pixels,
+            &heatmap,
+            None,
+            3,
+            None,
+            false,
+            CellAggregator::Max,
+            Normalization::default(),
+        );
+        assert_eq!(metrics.mean_error, 0.0);
+        assert_eq!(metrics.top_5_error, 0.0);
+        assert_eq!(metrics.coverage, 1.0);
+    }
+
+    #[test]
+    fn offset_stroke_reports_its_distance() {
+        let mut reference = Array2::zeros((500, 500));
+        let mut observation = Array2::zeros((500, 500));
+        for x in 100..400 {
+            reference[(250, x)] = 1;
+            observation[(260, x)] = 1;
+        }
+        let reference_heatmap = flood_fill_distances(&reference, None);
+        let observation_heatmap = flood_fill_distances(&observation, None);
+        let metrics = compute_metrics(
+            &reference,
+            &reference_heatmap,
+            &observation,
+            &observation_heatmap,
+            None,
+            3,
+            None,
+            false,
+            CellAggregator::Max,
+            Normalization::default(),
+        );
+        // Every observation pixel sits 10px below the reference stroke.
+        assert!((metrics.mean_error - 10.0 / 5.0).abs() < 1e-9);
+        assert_eq!(metrics.coverage, 0.0);
+        assert!(metrics.top_5_error > 0.0);
+    }
+
+    #[test]
+    fn normalized_errors_are_comparable_across_canvas_sizes() {
+        // The same relative miss — a stroke offset by 2% of the canvas
+        // — on a 500px and a 1000px canvas. Raw errors differ by 2x;
+        // the diagonal-normalized percentages must agree.
+        let score_at = |size: usize| {
+            let mut reference = Array2::zeros((size, size));
+            let mut observation = Array2::zeros((size, size));
+            for x in size / 5..size * 4 / 5 {
+                reference[(size / 2, x)] = 1;
+                observation[(size / 2 + size / 50, x)] = 1;
+            }
+            compute_metrics(
+                &reference,
+                &flood_fill_distances(&reference, None),
+                &observation,
+                &flood_fill_distances(&observation, None),
+                None,
+                3,
+                None,
+                false,
+                CellAggregator::Max,
+                Normalization::default(),
+            )
+        };
+        let small = score_at(500);
+        let large = score_at(1000);
+        assert!(small.mean_error < large.mean_error);
+        let small_normalized = small.normalized.unwrap();
+        let large_normalized = large.normalized.unwrap();
+        assert_eq!(small_normalized.canvas_diagonal, (500.0f64 * 500.0 * 2.0).sqrt());
+        assert!(
+            (small_normalized.mean_error_pct - large_normalized.mean_error_pct).abs() < 1e-9
+        );
+        assert!(
+            (small_normalized.top_5_error_pct - large_normalized.top_5_error_pct).abs() < 1e-9
+        );
+        assert!(small_normalized.mean_error_pct > 0.0);
+    }
+
+    #[test]
+    fn grid_deltas_report_improvement_as_negative_cells() {
+        let mut reference = Array2::zeros((500, 500));
+        let mut first_attempt = Array2::zeros((500, 500));
+        for x in 100..400 {
+            reference[(250, x)] = 1;
+            first_attempt[(260, x)] = 1;
+        }
+        let reference_heatmap = flood_fill_distances(&reference, None);
+        let score = |observation: &Array2<u8>| {
+            compute_metrics(
+                &reference,
+                &reference_heatmap,
+                observation,
+                &flood_fill_distances(observation, None),
+                None,
+                3,
+                None,
+                false,
+                CellAggregator::Max,
+                Normalization::default(),
+            )
+        };
+        let before = score(&first_attempt);
+        // The second attempt traces the reference exactly.
+        let after = score(&reference);
+        let delta = grid_delta(&before, &after);
+        assert!(delta.iter().flatten().all(|&change| change <= 0.0));
+        assert!(delta[5][2] < 0.0);
+        // Swapping the order flips the sign: a regression reads positive.
+        assert!(grid_delta(&after, &before)[5][2] > 0.0);
+    }
+
+    #[test]
+    fn custom_normalization_rescales_the_scores() {
+        let mut pixels = Array2::zeros((500, 500));
+        let mut observation = Array2::zeros((500, 500));
+        for x in 100..400 {
+            pixels[(250, x)] = 1;
+            observation[(260, x)] = 1;
+        }
+        let heatmap = flood_fill_distances(&pixels, None);
+        let observation_heatmap = flood_fill_distances(&observation, None);
+        let normalization = Normalization {
+            mean_error_divisor: 1.0,
+            top_5_divisor: 5.0,
+        };
+        let metrics = compute_metrics(
+            &pixels,
+            &heatmap,
+            &observation,
+            &observation_heatmap,
+            None,
+            3,
+            None,
+            false,
+            CellAggregator::Max,
+            normalization,
+        );
+        assert!((metrics.mean_error - 10.0).abs() < 1e-9);
+        assert_eq!(metrics.normalization, normalization);
+    }
+
+    #[test]
+    fn light_pressure_discounts_error_and_coverage() {
+        let mut reference = Array2::zeros((500, 500));
+        let mut observation = Array2::zeros((500, 500));
+        let mut weights = Array2::zeros((500, 500));
+        for x in 100..400 {
+            reference[(250, x)] = 1;
+            observation[(260, x)] = 1;
+            weights[(260, x)] = if x < 250 { 1.0 } else { 0.2 };
+        }
+        let reference_heatmap = flood_fill_distances(&reference, None);
+        let observation_heatmap = flood_fill_distances(&observation, None);
+        let weighted = compute_metrics(
+            &reference,
+            &reference_heatmap,
+            &observation,
+            &observation_heatmap,
+            Some(&weights),
+            3,
+            None,
+            false,
+            CellAggregator::Max,
+            Normalization::default(),
+        );
+        // All pixels sit 10px off, so the weighted mean is unchanged,
+        // and nothing is close enough for the tolerance to cover.
+        assert!((weighted.mean_error - 10.0 / 5.0).abs() < 1e-9);
+        assert_eq!(weighted.coverage, 0.0);
+        // Cells drawn with light pressure contribute a discounted error.
+        assert!((weighted.grid[5][2] - 10.0).abs() < 1e-9);
+        assert!((weighted.grid[5][7] - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn covered_reference_counts_the_heaviest_nearby_pixel() {
+        let mut reference = Array2::zeros((500, 500));
+        let mut observation = Array2::zeros((500, 500));
+        let mut weights = Array2::zeros((500, 500));
+        for x in 100..400 {
+            reference[(250, x)] = 1;
+            observation[(250, x)] = 1;
+            weights[(250, x)] = 0.5;
+        }
+        let heatmap = flood_fill_distances(&reference, None);
+        let metrics = compute_metrics(
+            &reference,
+            &heatmap,
+            &observation,
+            &heatmap,
+            Some(&weights),
+            3,
+            None,
+            false,
+            CellAggregator::Max,
+            Normalization::default(),
+        );
+        // A perfect tracing at half pressure covers half as much.
+        assert!((metrics.coverage - 0.5).abs() < 1e-9);
+        assert_eq!(metrics.mean_error, 0.0);
+    }
+
+    #[test]
+    fn cell_multipliers_loosen_and_tighten_individual_cells() {
+        let mut reference = Array2::zeros((500, 500));
+        let mut observation = Array2::zeros((500, 500));
+        for x in 100..400 {
+            reference[(250, x)] = 1;
+            observation[(260, x)] = 1;
+        }
+        let reference_heatmap = flood_fill_distances(&reference, None);
+        let observation_heatmap = flood_fill_distances(&observation, None);
+        let mut multipliers = vec![vec![1.0; GRID_SIZE]; GRID_SIZE];
+        multipliers[5][2] = 2.0;
+        multipliers[5][7] = 0.5;
+        let metrics = compute_metrics(
+            &reference,
+            &reference_heatmap,
+            &observation,
+            &observation_heatmap,
+            None,
+            3,
+            Some(&multipliers),
+            false,
+            CellAggregator::Max,
+            Normalization::default(),
+        );
+        // Every pixel sits 10px off: the loose cell records half the
+        // error, the strict cell double, untouched cells the raw 10.
+        assert!((metrics.grid[5][2] - 5.0).abs() < 1e-9);
+        assert!((metrics.grid[5][7] - 20.0).abs() < 1e-9);
+        assert!((metrics.grid[5][4] - 10.0).abs() < 1e-9);
+        assert_eq!(metrics.cell_tolerance_multipliers, Some(multipliers));
+    }
+
+    #[test]
+    fn a_loose_cell_extends_its_coverage_tolerance() {
+        let mut reference = Array2::zeros((500, 500));
+        let mut observation = Array2::zeros((500, 500));
+        for x in 100..400 {
+            reference[(250, x)] = 1;
+            observation[(255, x)] = 1;
+        }
+        let reference_heatmap = flood_fill_distances(&reference, None);
+        let observation_heatmap = flood_fill_distances(&observation, None);
+        let mut multipliers = vec![vec![1.0; GRID_SIZE]; GRID_SIZE];
+        for cell in &mut multipliers[5] {
+            *cell = 2.0;
+        }
+        let metrics = compute_metrics(
+            &reference,
+            &reference_heatmap,
+            &observation,
+            &observation_heatmap,
+            None,
+            3,
+            Some(&multipliers),
+            false,
+            CellAggregator::Max,
+            Normalization::default(),
+        );
+        // 5px off the stroke: outside the base tolerance of 3, but
+        // inside the doubled tolerance of the loosened row.
+        assert_eq!(metrics.coverage, 1.0);
+    }
+
+    #[test]
+    fn partial_credit_aggregators_forgive_a_stray_pixel() {
+        let mut reference = Array2::zeros((500, 500));
+        let mut observation = Array2::zeros((500, 500));
+        for x in 100..400 {
+            reference[(250, x)] = 1;
+            observation[(250, x)] = 1;
+        }
+        // One stray pixel 30px off, in the same cell as 50 perfect ones.
+        observation[(280, 120)] = 1;
+        let heatmap = flood_fill_distances(&reference, None);
+        let observation_heatmap = flood_fill_distances(&observation, None);
+        let cell = |aggregator: CellAggregator| {
+            compute_metrics(
+                &reference,
+                &heatmap,
+                &observation,
+                &observation_heatmap,
+                None,
+                3,
+                None,
+                false,
+                aggregator,
+                Normalization::default(),
+            )
+            .grid[5][2]
+        };
+        assert!((cell(CellAggregator::Max) - 30.0).abs() < 1e-9);
+        assert_eq!(cell(CellAggregator::Percentile(95.0)), 0.0);
+        assert_eq!(cell(CellAggregator::TrimmedMax(1)), 0.0);
+        assert!((cell(CellAggregator::MeanOfWorstK(2)) - 15.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn a_fitted_grid_spreads_a_quadrant_drawing_over_every_column() {
+        let mut reference = Array2::zeros((500, 500));
+        let mut observation = Array2::zeros((500, 500));
+        // The whole exercise lives in the top-left quadrant.
+        for x in 0..250 {
+            reference[(50, x)] = 1;
+            observation[(60, x)] = 1;
+        }
+        let reference_heatmap = flood_fill_distances(&reference, None);
+        let observation_heatmap = flood_fill_distances(&observation, None);
+        let fitted = compute_metrics(
+            &reference,
+            &reference_heatmap,
+            &observation,
+            &observation_heatmap,
+            None,
+            3,
+            None,
+            true,
+            CellAggregator::Max,
+            Normalization::default(),
+        );
+        // The grid spans the reference's bounding box, so the stroke
+        // crosses all ten columns, and the 10px-low observation falls
+        // past the box into the bottom edge row.
+        for column in 0..GRID_SIZE {
+            assert!((fitted.grid[GRID_SIZE - 1][column] - 10.0).abs() < 1e-9);
+        }
+        let full_canvas = compute_metrics(
+            &reference,
+            &reference_heatmap,
+            &observation,
+            &observation_heatmap,
+            None,
+            3,
+            None,
+            false,
+            CellAggregator::Max,
+            Normalization::default(),
+        );
+        // Unfitted, the same drawing touches only five cells.
+        let touched: usize = full_canvas
+            .grid
+            .iter()
+            .flatten()
+            .filter(|&&cell| cell > 0.0)
+            .count();
+        assert_eq!(touched, 5);
+    }
+
+    #[test]
+    fn perfect_tracing_yields_no_worst_windows() {
+        let mut pixels = Array2::zeros((500, 500));
+        for x in 100..400 {
+            pixels[(250, x)] = 1;
+        }
+        let heatmap = flood_fill_distances(&pixels, None);
+        let report = sliding_worst_regions(&heatmap, &pixels, Normalization::default());
+        assert!(report.windows.is_empty());
+        assert_eq!(report.top_5_error, 0.0);
+    }
+
+    #[test]
+    fn worst_windows_report_each_deviation_unsplit() {
+        let mut reference = Array2::zeros((500, 500));
+        for x in 100..400 {
+            reference[(250, x)] = 1;
+        }
+        let heatmap = flood_fill_distances(&reference, None);
+        let mut observation = Array2::zeros((500, 500));
+        // One cluster straddling the x=250 grid boundary, 60px off, and
+        // a milder one elsewhere, 30px off.
+        for x in 240..260 {
+            observation[(310, x)] = 1;
+        }
+        for x in 120..130 {
+            observation[(280, x)] = 1;
+        }
+        let report = sliding_worst_regions(&heatmap, &observation, Normalization::default());
+        // Whatever window wins, it sees the straddling cluster's full
+        // 60px error — no cell boundary splits it.
+        assert_eq!(report.windows[0].error, 60.0);
+        assert!(report.windows.iter().any(|window| window.error == 30.0));
+        // Reported windows never overlap each other.
+        for (i, a) in report.windows.iter().enumerate() {
+            for b in &report.windows[i + 1..] {
+                assert!(
+                    a.x.abs_diff(b.x) >= WINDOW_SIZE || a.y.abs_diff(b.y) >= WINDOW_SIZE,
+                    "{a:?} overlaps {b:?}"
+                );
+            }
+        }
+        let expected: f64 = report.windows.iter().map(|window| window.error).sum();
+        assert!((report.top_5_error - expected / 25.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn top_5_averages_the_worst_cells() {
+        let mut grid = vec![vec![0.0; GRID_SIZE]; GRID_SIZE];
+        grid[0][0] = 50.0;
+        grid[1][1] = 40.0;
+        grid[2][2] = 30.0;
+        grid[3][3] = 20.0;
+        grid[4][4] = 10.0;
+        grid[5][5] = 5.0;
+        assert!((top_5_from_grid(&grid, 25.0) - 150.0 / 25.0).abs() < 1e-9);
+    }
+}
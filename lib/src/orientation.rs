@@ -0,0 +1,205 @@
+//! Local orientation comparison for hatching and shading.
+//!
+//! Hatching drawn at the wrong angle can overlap the reference region
+//! perfectly and still look wrong. This module estimates a local
+//! orientation field for each pane with the structure tensor — the
+//! dominant stroke direction per block, plus how strongly the block
+//! agrees on it — and aggregates an orientation-mismatch metric over
+//! the blocks where both panes have oriented content.
+
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+/// Blocks whose gradient energy is below this carry no content.
+const ENERGY_FLOOR: f64 = 1e-6;
+/// Blocks less coherent than this have no dominant direction (dots,
+/// crossings, isotropic texture) and are not compared.
+const COHERENCE_FLOOR: f64 = 0.2;
+
+/// The per-block orientation estimate of one pane.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrientationField {
+    /// Side length of the square blocks, in pixels.
+    pub block_size: usize,
+    /// Dominant stroke orientation per block in degrees, folded into
+    /// `0..180` since strokes have no direction.
+    pub angles: Array2<f64>,
+    /// How strongly each block agrees on one direction, in `0..=1`.
+    pub coherence: Array2<f64>,
+    /// Total gradient energy per block; zero means an empty block.
+    pub energy: Array2<f64>,
+}
+
+impl OrientationField {
+    /// Whether this block holds content with a dominant direction.
+    fn is_oriented(&self, block: (usize, usize)) -> bool {
+        self.energy[block] > ENERGY_FLOOR && self.coherence[block] > COHERENCE_FLOOR
+    }
+}
+
+/// The orientation-level comparison of two panes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrientationMismatch {
+    /// Mean angular difference over the compared blocks, in `0..=90`
+    /// degrees; zero when no blocks could be compared.
+    pub mean_mismatch_degrees: f64,
+    pub worst_mismatch_degrees: f64,
+    /// Blocks where both panes had oriented content.
+    pub compared_blocks: usize,
+}
+
+/// Estimates the orientation field of a stroke mask with the structure
+/// tensor: gradients are accumulated per `block_size` square and the
+/// dominant direction read off the tensor's principal axis.
+pub fn orientation_field(mask: &Array2<u8>, block_size: usize) -> OrientationField {
+    let (height, width) = mask.dim();
+    let block_size = block_size.max(1);
+    let blocks = (height.div_ceil(block_size), width.div_ceil(block_size));
+    let mut xx = Array2::<f64>::zeros(blocks);
+    let mut xy = Array2::<f64>::zeros(blocks);
+    let mut yy = Array2::<f64>::zeros(blocks);
+    for y in 0..height {
+        for x in 0..width {
+            let gx = pixel(mask, y, x + 1) - pixel(mask, y, x.wrapping_sub(1));
+            let gy = pixel(mask, y + 1, x) - pixel(mask, y.wrapping_sub(1), x);
+            if gx == 0.0 && gy == 0.0 {
+                continue;
+            }
+            let block = (y / block_size, x / block_size);
+            xx[block] += gx * gx;
+            xy[block] += gx * gy;
+            yy[block] += gy * gy;
+        }
+    }
+    let mut angles = Array2::zeros(blocks);
+    let mut coherence = Array2::zeros(blocks);
+    let mut energy = Array2::zeros(blocks);
+    for block in (0..blocks.0).flat_map(|row| (0..blocks.1).map(move |col| (row, col))) {
+        let (xx, xy, yy) = (xx[block], xy[block], yy[block]);
+        energy[block] = xx + yy;
+        if energy[block] <= ENERGY_FLOOR {
+            continue;
+        }
+        // The tensor's principal axis is the gradient direction; the
+        // stroke runs perpendicular to it.
+        let gradient_degrees = (0.5 * (2.0 * xy).atan2(xx - yy)).to_degrees();
+        angles[block] = (gradient_degrees + 90.0).rem_euclid(180.0);
+        coherence[block] =
+            (((xx - yy).powi(2) + 4.0 * xy * xy).sqrt() / energy[block]).clamp(0.0, 1.0);
+    }
+    OrientationField {
+        block_size,
+        angles,
+        coherence,
+        energy,
+    }
+}
+
+/// Compares the orientation fields of the two panes block by block,
+/// over the blocks where both have a dominant direction.
+pub fn orientation_mismatch(
+    reference: &Array2<u8>,
+    observation: &Array2<u8>,
+    block_size: usize,
+) -> OrientationMismatch {
+    let reference = orientation_field(reference, block_size);
+    let observation = orientation_field(observation, block_size);
+    let blocks = reference.angles.dim();
+    let mut differences = Vec::new();
+    for block in (0..blocks.0).flat_map(|row| (0..blocks.1).map(move |col| (row, col))) {
+        if reference.is_oriented(block) && observation.is_oriented(block) {
+            differences.push(angle_difference(
+                reference.angles[block],
+                observation.angles[block],
+            ));
+        }
+    }
+    OrientationMismatch {
+        mean_mismatch_degrees: if differences.is_empty() {
+            0.0
+        } else {
+            differences.iter().sum::<f64>() / differences.len() as f64
+        },
+        worst_mismatch_degrees: differences.iter().cloned().fold(0.0, f64::max),
+        compared_blocks: differences.len(),
+    }
+}
+
+/// Angular difference of two orientations, folded into `0..=90` degrees.
+fn angle_difference(a: f64, b: f64) -> f64 {
+    let difference = (a - b).abs();
+    difference.min(180.0 - difference)
+}
+
+/// The mask value as a float, treating out-of-bounds as empty so edge
+/// pixels get one-sided gradients.
+fn pixel(mask: &Array2<u8>, y: usize, x: usize) -> f64 {
+    let (height, width) = mask.dim();
+    if y < height && x < width && mask[(y, x)] != 0 {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parallel hatching lines across the whole mask, tilted so each
+    /// line satisfies `x - slope * y = offset (mod spacing)`.
+    fn hatching(slope: i64, spacing: i64) -> Array2<u8> {
+        let mut mask = Array2::zeros((100, 100));
+        for y in 0..100i64 {
+            for x in 0..100i64 {
+                if (x - slope * y).rem_euclid(spacing) == 0 {
+                    mask[(y as usize, x as usize)] = 1;
+                }
+            }
+        }
+        mask
+    }
+
+    #[test]
+    fn a_horizontal_stroke_reports_a_zero_degree_field() {
+        let mut mask = Array2::zeros((100, 100));
+        for x in 10..90 {
+            mask[(50, x)] = 1;
+        }
+        let field = orientation_field(&mask, 16);
+        let block = (50 / 16, 50 / 16);
+        assert!(field.is_oriented(block));
+        let angle = field.angles[block];
+        assert!(angle_difference(angle, 0.0) < 5.0, "angle was {angle}");
+    }
+
+    #[test]
+    fn identical_hatching_has_no_mismatch() {
+        let mask = hatching(1, 8);
+        let mismatch = orientation_mismatch(&mask, &mask, 16);
+        assert!(mismatch.compared_blocks > 0);
+        assert_eq!(mismatch.mean_mismatch_degrees, 0.0);
+    }
+
+    #[test]
+    fn perpendicular_hatching_is_heavily_penalized() {
+        let reference = hatching(1, 8);
+        let observation = hatching(-1, 8);
+        let mismatch = orientation_mismatch(&reference, &observation, 16);
+        assert!(mismatch.compared_blocks > 0);
+        assert!(
+            mismatch.mean_mismatch_degrees > 60.0,
+            "mean was {}",
+            mismatch.mean_mismatch_degrees
+        );
+        assert!(mismatch.worst_mismatch_degrees <= 90.0);
+    }
+
+    #[test]
+    fn empty_panes_compare_without_blocks() {
+        let empty = Array2::zeros((100, 100));
+        let mismatch = orientation_mismatch(&empty, &empty, 16);
+        assert_eq!(mismatch.compared_blocks, 0);
+        assert_eq!(mismatch.mean_mismatch_degrees, 0.0);
+    }
+}
@@ -0,0 +1,201 @@
+//! Submission quality gates.
+//!
+//! A blank canvas, a single accidental dot, or a drawing that barely
+//! touches the reference all score *something*, and the app has had to
+//! guess from the final metrics whether the submission was a real
+//! attempt. Quality gates make that explicit: configurable minimums
+//! checked at finish, each reported pass/fail in a structured
+//! [`QualityReport`] so obviously empty or accidental submissions can
+//! be rejected before scoring.
+
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::{connected_components, SPECK_PIXELS};
+use crate::heatmap::flood_fill_distances;
+
+/// Thresholds a finished observation must clear. Unset gates are not
+/// checked; the default checks nothing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QualityGates {
+    /// Fewest observation pixels a real attempt can have.
+    pub min_observation_pixels: Option<u64>,
+    /// Least fraction (`0..=1`) of reference pixels with an observation
+    /// pixel within `tolerance`.
+    pub min_coverage: Option<f64>,
+    /// Most stray components — eight-connected blobs smaller than
+    /// [`Self::stray_component_pixels`] — allowed before the drawing
+    /// looks like accidental taps.
+    pub max_stray_components: Option<usize>,
+    /// Components smaller than this count as stray.
+    pub stray_component_pixels: u64,
+}
+
+impl Default for QualityGates {
+    fn default() -> Self {
+        Self {
+            min_observation_pixels: None,
+            min_coverage: None,
+            max_stray_components: None,
+            stray_component_pixels: SPECK_PIXELS,
+        }
+    }
+}
+
+/// Which gate a check belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QualityGate {
+    ObservationPixels,
+    Coverage,
+    StrayComponents,
+}
+
+/// One gate's verdict.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GateCheck {
+    pub gate: QualityGate,
+    /// The configured limit, as the gate's own unit.
+    pub threshold: f64,
+    /// What the observation actually measured.
+    pub actual: f64,
+    pub passed: bool,
+}
+
+/// Every configured gate's verdict for one observation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QualityReport {
+    pub checks: Vec<GateCheck>,
+}
+
+impl QualityReport {
+    /// Whether every configured gate passed. An empty report (no gates
+    /// configured) passes.
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.passed)
+    }
+
+    /// The gates that failed, in check order.
+    pub fn failures(&self) -> Vec<&GateCheck> {
+        self.checks.iter().filter(|check| !check.passed).collect()
+    }
+}
+
+/// Checks a finished observation mask against `gates`. Coverage uses
+/// the same tolerance-radius rule as scoring, but only flood-fills when
+/// that gate is actually configured, so cheap gates stay cheap.
+pub fn check_quality(
+    reference: &Array2<u8>,
+    observation: &Array2<u8>,
+    tolerance: i32,
+    gates: &QualityGates,
+) -> QualityReport {
+    let mut checks = Vec::new();
+    if let Some(minimum) = gates.min_observation_pixels {
+        let pixels = observation.iter().filter(|&&p| p != 0).count() as u64;
+        checks.push(GateCheck {
+            gate: QualityGate::ObservationPixels,
+            threshold: minimum as f64,
+            actual: pixels as f64,
+            passed: pixels >= minimum,
+        });
+    }
+    if let Some(minimum) = gates.min_coverage {
+        let heatmap = flood_fill_distances(observation, Some(tolerance));
+        let mut reference_count = 0u64;
+        let mut covered = 0u64;
+        for ((y, x), &on) in reference.indexed_iter() {
+            if on == 0 {
+                continue;
+            }
+            reference_count += 1;
+            if (0..=tolerance).contains(&heatmap[(y, x)]) {
+                covered += 1;
+            }
+        }
+        let coverage = if reference_count == 0 {
+            0.0
+        } else {
+            covered as f64 / reference_count as f64
+        };
+        checks.push(GateCheck {
+            gate: QualityGate::Coverage,
+            threshold: minimum,
+            actual: coverage,
+            passed: coverage >= minimum,
+        });
+    }
+    if let Some(maximum) = gates.max_stray_components {
+        let strays = connected_components(observation)
+            .iter()
+            .filter(|component| component.pixel_count < gates.stray_component_pixels)
+            .count();
+        checks.push(GateCheck {
+            gate: QualityGate::StrayComponents,
+            threshold: maximum as f64,
+            actual: strays as f64,
+            passed: strays <= maximum,
+        });
+    }
+    QualityReport { checks }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(y: usize, range: std::ops::Range<usize>) -> Array2<u8> {
+        let mut mask = Array2::zeros((500, 500));
+        for x in range {
+            mask[(y, x)] = 1;
+        }
+        mask
+    }
+
+    #[test]
+    fn unconfigured_gates_pass_anything() {
+        let blank = Array2::zeros((500, 500));
+        let report = check_quality(&line(250, 100..400), &blank, 3, &QualityGates::default());
+        assert!(report.passed());
+        assert!(report.checks.is_empty());
+    }
+
+    #[test]
+    fn a_blank_submission_fails_the_pixel_and_coverage_gates() {
+        let reference = line(250, 100..400);
+        let blank = Array2::zeros((500, 500));
+        let gates = QualityGates {
+            min_observation_pixels: Some(50),
+            min_coverage: Some(0.5),
+            ..QualityGates::default()
+        };
+        let report = check_quality(&reference, &blank, 3, &gates);
+        assert!(!report.passed());
+        let failures = report.failures();
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0].gate, QualityGate::ObservationPixels);
+        assert_eq!(failures[0].actual, 0.0);
+        assert_eq!(failures[1].gate, QualityGate::Coverage);
+    }
+
+    #[test]
+    fn accidental_taps_trip_the_stray_component_gate() {
+        let reference = line(250, 100..400);
+        let mut observation = line(250, 100..400);
+        observation[(50, 50)] = 1;
+        observation[(400, 450)] = 1;
+        let gates = QualityGates {
+            min_coverage: Some(0.9),
+            max_stray_components: Some(1),
+            ..QualityGates::default()
+        };
+        let report = check_quality(&reference, &observation, 3, &gates);
+        let failures = report.failures();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].gate, QualityGate::StrayComponents);
+        assert_eq!(failures[0].actual, 2.0);
+        // The long stroke itself is not stray and coverage still passes.
+        assert!(report.checks[0].passed);
+    }
+}
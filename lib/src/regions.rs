@@ -0,0 +1,343 @@
+use std::collections::VecDeque;
+
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::BoundingBox;
+use crate::metrics::GRID_SIZE;
+
+/// Eight-way direction from a problem region toward the nearest
+/// reference content — the hint UIs verbalize as "move this up-left".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompassDirection {
+    Up,
+    UpRight,
+    Right,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+    /// The region already sits on reference content.
+    Here,
+}
+
+/// Pixel-space point in canvas coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PixelPoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// A cluster of adjacent high-error grid cells, labeled so feedback can
+/// talk about areas instead of raw cells.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProblemRegion {
+    /// Coarse canvas position, e.g. "upper-left" or "center".
+    pub label: String,
+    /// Grid-cell bounds of the cluster (columns as x, rows as y).
+    pub cell_bounds: BoundingBox,
+    /// Error-weighted centroid of the cluster in pixels.
+    pub centroid: PixelPoint,
+    /// Worst cell error inside the cluster.
+    pub severity: f64,
+    /// Direction from the centroid toward the nearest reference feature.
+    pub direction_hint: CompassDirection,
+}
+
+/// An arrow from a misplaced cluster to where its strokes should move:
+/// the region's centroid and the nearest reference content. UIs render
+/// these directly as corrective arrows over the drawing.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CorrectionVector {
+    /// Error-weighted centroid of the misplaced cluster.
+    pub from: PixelPoint,
+    /// Nearest reference pixel to `from`.
+    pub to: PixelPoint,
+    /// Length of the arrow in pixels.
+    pub magnitude: f64,
+}
+
+/// Cells qualify as problems when their error reaches this fraction of
+/// the worst cell.
+const SEVERITY_FRACTION: f64 = 0.25;
+
+/// Regions closer than this to reference content count as already in
+/// place: no arrow, and [`CompassDirection::Here`] as the hint.
+const SETTLED_DISTANCE: f64 = 10.0;
+
+/// Computes corrective arrows for the given problem regions. Regions
+/// already sitting on reference content produce no arrow, so the list
+/// can be shorter than the region list; order follows the regions
+/// (worst first).
+pub fn correction_vectors(
+    regions: &[ProblemRegion],
+    reference: &Array2<u8>,
+) -> Vec<CorrectionVector> {
+    regions
+        .iter()
+        .filter_map(|region| {
+            let (to, magnitude) = nearest_reference_point(&region.centroid, reference)?;
+            (magnitude >= SETTLED_DISTANCE).then_some(CorrectionVector {
+                from: region.centroid,
+                to,
+                magnitude,
+            })
+        })
+        .collect()
+}
+
+/// Clusters adjacent high-error grid cells into labeled problem regions.
+pub fn compute_problem_regions(
+    grid: &[Vec<f64>],
+    reference: &Array2<u8>,
+) -> Vec<ProblemRegion> {
+    let worst = grid
+        .iter()
+        .flatten()
+        .copied()
+        .fold(0.0f64, f64::max);
+    if worst <= 0.0 {
+        return Vec::new();
+    }
+    let threshold = (worst * SEVERITY_FRACTION).max(1.0);
+    let (height, width) = reference.dim();
+    let cell_height = height.div_ceil(GRID_SIZE);
+    let cell_width = width.div_ceil(GRID_SIZE);
+
+    let mut visited = [[false; GRID_SIZE]; GRID_SIZE];
+    let mut regions = Vec::new();
+    for row in 0..GRID_SIZE {
+        for column in 0..GRID_SIZE {
+            if visited[row][column] || grid[row][column] < threshold {
+                continue;
+            }
+            // Flood over eight-connected qualifying cells.
+            let mut cells = Vec::new();
+            let mut queue = VecDeque::from([(row, column)]);
+            visited[row][column] = true;
+            while let Some((r, c)) = queue.pop_front() {
+                cells.push((r, c));
+                for dr in -1i32..=1 {
+                    for dc in -1i32..=1 {
+                        let nr = r as i32 + dr;
+                        let nc = c as i32 + dc;
+                        if nr < 0 || nc < 0 || nr >= GRID_SIZE as i32 || nc >= GRID_SIZE as i32 {
+                            continue;
+                        }
+                        let (nr, nc) = (nr as usize, nc as usize);
+                        if !visited[nr][nc] && grid[nr][nc] >= threshold {
+                            visited[nr][nc] = true;
+                            queue.push_back((nr, nc));
+                        }
+                    }
+                }
+            }
+
+            let severity = cells
+                .iter()
+                .map(|&(r, c)| grid[r][c])
+                .fold(0.0f64, f64::max);
+            let mut weight_sum = 0.0;
+            let mut cx = 0.0;
+            let mut cy = 0.0;
+            for &(r, c) in &cells {
+                let weight = grid[r][c];
+                cx += (c as f64 + 0.5) * cell_width as f64 * weight;
+                cy += (r as f64 + 0.5) * cell_height as f64 * weight;
+                weight_sum += weight;
+            }
+            let centroid = PixelPoint {
+                x: cx / weight_sum,
+                y: cy / weight_sum,
+            };
+            let cell_pixels: Vec<(usize, usize)> = cells.iter().map(|&(r, c)| (r, c)).collect();
+            regions.push(ProblemRegion {
+                label: position_label(&centroid, width, height),
+                cell_bounds: bounds_of_cells(&cell_pixels),
+                centroid,
+                severity,
+                direction_hint: direction_to_reference(&centroid, reference),
+            });
+        }
+    }
+    regions.sort_by(|a, b| {
+        b.severity
+            .partial_cmp(&a.severity)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    regions
+}
+
+fn bounds_of_cells(cells: &[(usize, usize)]) -> BoundingBox {
+    let mut bounds = BoundingBox {
+        min_x: GRID_SIZE,
+        min_y: GRID_SIZE,
+        max_x: 0,
+        max_y: 0,
+    };
+    for &(row, column) in cells {
+        bounds.min_x = bounds.min_x.min(column);
+        bounds.min_y = bounds.min_y.min(row);
+        bounds.max_x = bounds.max_x.max(column);
+        bounds.max_y = bounds.max_y.max(row);
+    }
+    bounds
+}
+
+/// Coarse thirds-based position name for a canvas point.
+fn position_label(point: &PixelPoint, width: usize, height: usize) -> String {
+    let vertical = match (point.y / height as f64 * 3.0) as usize {
+        0 => "upper",
+        1 => "middle",
+        _ => "lower",
+    };
+    let horizontal = match (point.x / width as f64 * 3.0) as usize {
+        0 => "left",
+        1 => "center",
+        _ => "right",
+    };
+    match (vertical, horizontal) {
+        ("middle", "center") => "center".to_string(),
+        (v, h) => format!("{v}-{h}"),
+    }
+}
+
+/// The reference pixel nearest to `point` and its distance, or `None`
+/// on an empty reference.
+fn nearest_reference_point(
+    point: &PixelPoint,
+    reference: &Array2<u8>,
+) -> Option<(PixelPoint, f64)> {
+    let mut best: Option<(f64, PixelPoint)> = None;
+    for ((y, x), &on) in reference.indexed_iter() {
+        if on == 0 {
+            continue;
+        }
+        let dx = x as f64 - point.x;
+        let dy = y as f64 - point.y;
+        let distance = dx * dx + dy * dy;
+        if best.is_none_or(|(d, _)| distance < d) {
+            best = Some((
+                distance,
+                PixelPoint {
+                    x: x as f64,
+                    y: y as f64,
+                },
+            ));
+        }
+    }
+    best.map(|(distance, nearest)| (nearest, distance.sqrt()))
+}
+
+/// Direction from `point` to the nearest reference pixel.
+fn direction_to_reference(point: &PixelPoint, reference: &Array2<u8>) -> CompassDirection {
+    let Some((nearest, distance)) = nearest_reference_point(point, reference) else {
+        return CompassDirection::Here;
+    };
+    if distance < SETTLED_DISTANCE {
+        return CompassDirection::Here;
+    }
+    let (dx, dy) = (nearest.x - point.x, nearest.y - point.y);
+    // Quantize the angle into eight sectors; y grows downward.
+    let angle = dy.atan2(dx);
+    let sector = ((angle + std::f64::consts::PI) / (std::f64::consts::PI / 4.0)).round() as i32 % 8;
+    match sector {
+        0 => CompassDirection::Left,
+        1 => CompassDirection::UpLeft,
+        2 => CompassDirection::Up,
+        3 => CompassDirection::UpRight,
+        4 => CompassDirection::Right,
+        5 => CompassDirection::DownRight,
+        6 => CompassDirection::Down,
+        _ => CompassDirection::DownLeft,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clean_grid_has_no_problem_regions() {
+        let grid = vec![vec![0.0; GRID_SIZE]; GRID_SIZE];
+        let reference = Array2::zeros((500, 500));
+        assert!(compute_problem_regions(&grid, &reference).is_empty());
+    }
+
+    #[test]
+    fn adjacent_high_error_cells_merge_into_one_region() {
+        let mut grid = vec![vec![0.0; GRID_SIZE]; GRID_SIZE];
+        grid[0][8] = 20.0;
+        grid[0][9] = 24.0;
+        grid[1][9] = 18.0;
+        let mut reference = Array2::zeros((500, 500));
+        for x in 100..400 {
+            reference[(450, x)] = 1;
+        }
+        let regions = compute_problem_regions(&grid, &reference);
+        assert_eq!(regions.len(), 1);
+        let region = &regions[0];
+        assert_eq!(region.severity, 24.0);
+        assert_eq!(region.label, "upper-right");
+        assert_eq!(
+            region.cell_bounds,
+            BoundingBox {
+                min_x: 8,
+                min_y: 0,
+                max_x: 9,
+                max_y: 1,
+            }
+        );
+        // Reference content sits near the bottom of the canvas.
+        assert!(matches!(
+            region.direction_hint,
+            CompassDirection::Down | CompassDirection::DownLeft
+        ));
+    }
+
+    #[test]
+    fn distant_clusters_stay_separate_and_sort_by_severity() {
+        let mut grid = vec![vec![0.0; GRID_SIZE]; GRID_SIZE];
+        grid[0][0] = 10.0;
+        grid[9][9] = 30.0;
+        let mut reference = Array2::zeros((500, 500));
+        reference[(250, 250)] = 1;
+        let regions = compute_problem_regions(&grid, &reference);
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions[0].severity, 30.0);
+        assert_eq!(regions[1].severity, 10.0);
+    }
+
+    #[test]
+    fn correction_vectors_point_misplaced_clusters_at_the_reference() {
+        let mut grid = vec![vec![0.0; GRID_SIZE]; GRID_SIZE];
+        grid[0][0] = 20.0;
+        let mut reference = Array2::zeros((500, 500));
+        reference[(400, 300)] = 1;
+        let regions = compute_problem_regions(&grid, &reference);
+        let vectors = correction_vectors(&regions, &reference);
+        assert_eq!(vectors.len(), 1);
+        let vector = &vectors[0];
+        assert_eq!(vector.from, regions[0].centroid);
+        assert_eq!(vector.to, PixelPoint { x: 300.0, y: 400.0 });
+        let expected =
+            ((300.0 - vector.from.x).powi(2) + (400.0 - vector.from.y).powi(2)).sqrt();
+        assert!((vector.magnitude - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn clusters_already_on_the_reference_get_no_arrow() {
+        let mut grid = vec![vec![0.0; GRID_SIZE]; GRID_SIZE];
+        grid[5][5] = 20.0;
+        let mut reference = Array2::zeros((500, 500));
+        // Reference ink right at the cluster centroid (cell [5][5] spans
+        // 250..300, centroid 275).
+        reference[(275, 275)] = 1;
+        let regions = compute_problem_regions(&grid, &reference);
+        assert_eq!(regions[0].direction_hint, CompassDirection::Here);
+        assert!(correction_vectors(&regions, &reference).is_empty());
+        // An empty reference gives nothing to point at either.
+        assert!(correction_vectors(&regions, &Array2::zeros((500, 500))).is_empty());
+    }
+}
@@ -0,0 +1,245 @@
+//! Shared in-memory registry of reference models.
+//!
+//! Grading servers score many submissions against a comparatively
+//! small set of exercises, and building a [`ReferenceModel`] costs a
+//! disk load plus a flood fill every time. [`ReferenceRegistry`] keeps
+//! the most-used models pinned in memory under a byte budget — a
+//! size-aware LRU — and loads lazily on first use, with hit/miss
+//! counters exposed for operational dashboards.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::error::EvaluationError;
+use crate::streaming::ReferenceModel;
+
+/// Capacity knobs for a [`ReferenceRegistry`].
+#[derive(Debug, Clone)]
+pub struct ReferenceRegistryConfig {
+    /// Most bytes of pinned models held at once. A model larger than
+    /// the whole budget is still served, just never pinned.
+    pub byte_budget: usize,
+}
+
+impl Default for ReferenceRegistryConfig {
+    fn default() -> Self {
+        Self {
+            byte_budget: 256 * 1024 * 1024,
+        }
+    }
+}
+
+/// Counters exposed for operational dashboards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReferenceRegistryMetrics {
+    pub resident_models: usize,
+    pub resident_bytes: usize,
+    pub hits: u64,
+    pub misses: u64,
+    /// Models dropped to stay within the byte budget.
+    pub evictions: u64,
+}
+
+struct Entry {
+    model: Arc<ReferenceModel>,
+    bytes: usize,
+    last_used: u64,
+}
+
+struct Inner {
+    entries: HashMap<String, Entry>,
+    resident_bytes: usize,
+    /// Monotonic use counter; higher means more recently used.
+    tick: u64,
+}
+
+/// A concurrency-safe, size-aware LRU of [`ReferenceModel`]s.
+pub struct ReferenceRegistry {
+    config: ReferenceRegistryConfig,
+    inner: Mutex<Inner>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl ReferenceRegistry {
+    pub fn new(config: ReferenceRegistryConfig) -> Self {
+        Self {
+            config,
+            inner: Mutex::new(Inner {
+                entries: HashMap::new(),
+                resident_bytes: 0,
+                tick: 0,
+            }),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the model registered under `id`, calling `load` on the
+    /// first use (or after an eviction). The lock is held across the
+    /// load on purpose: one flood fill at a time beats every waiting
+    /// request running its own.
+    pub fn get_or_load<F>(&self, id: &str, load: F) -> Result<Arc<ReferenceModel>, EvaluationError>
+    where
+        F: FnOnce() -> Result<ReferenceModel, EvaluationError>,
+    {
+        let mut inner = self.inner.lock().expect("registry poisoned");
+        inner.tick += 1;
+        let tick = inner.tick;
+        if let Some(entry) = inner.entries.get_mut(id) {
+            entry.last_used = tick;
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(Arc::clone(&entry.model));
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let model = Arc::new(load()?);
+        let bytes = model_bytes(&model);
+        if bytes > self.config.byte_budget {
+            // Too big to ever pin; serve it without caching.
+            return Ok(model);
+        }
+        inner.entries.insert(
+            id.to_string(),
+            Entry {
+                model: Arc::clone(&model),
+                bytes,
+                last_used: tick,
+            },
+        );
+        inner.resident_bytes += bytes;
+        while inner.resident_bytes > self.config.byte_budget {
+            let stalest = inner
+                .entries
+                .iter()
+                .filter(|(key, _)| key.as_str() != id)
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+                .expect("over budget implies an older entry to evict");
+            let evicted = inner.entries.remove(&stalest).expect("entry exists");
+            inner.resident_bytes -= evicted.bytes;
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(model)
+    }
+
+    /// Drops the model registered under `id`, so a republished exercise
+    /// reloads on next use. Returns whether anything was resident.
+    pub fn invalidate(&self, id: &str) -> bool {
+        let mut inner = self.inner.lock().expect("registry poisoned");
+        match inner.entries.remove(id) {
+            Some(entry) => {
+                inner.resident_bytes -= entry.bytes;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn metrics(&self) -> ReferenceRegistryMetrics {
+        let inner = self.inner.lock().expect("registry poisoned");
+        ReferenceRegistryMetrics {
+            resident_models: inner.entries.len(),
+            resident_bytes: inner.resident_bytes,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Resident size of a model: one byte per mask pixel plus four per
+/// heatmap cell.
+fn model_bytes(model: &ReferenceModel) -> usize {
+    let (height, width) = model.heatmap().dim();
+    width * height * (1 + std::mem::size_of::<i32>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::EvaluatorConfig;
+    use ndarray::Array2;
+
+    /// A 50x50 model (12.5 KB resident) with one marked row.
+    fn small_model(row: usize) -> ReferenceModel {
+        let mut pixels = Array2::zeros((50, 50));
+        for x in 10..40 {
+            pixels[(row, x)] = 1;
+        }
+        ReferenceModel::new(pixels, EvaluatorConfig::default()).unwrap()
+    }
+
+    #[test]
+    fn repeated_lookups_hit_the_cache_without_reloading() {
+        let registry = ReferenceRegistry::new(ReferenceRegistryConfig::default());
+        let mut loads = 0;
+        for _ in 0..3 {
+            registry
+                .get_or_load("cat-01", || {
+                    loads += 1;
+                    Ok(small_model(25))
+                })
+                .unwrap();
+        }
+        assert_eq!(loads, 1);
+        let metrics = registry.metrics();
+        assert_eq!(metrics.misses, 1);
+        assert_eq!(metrics.hits, 2);
+        assert_eq!(metrics.resident_models, 1);
+        assert_eq!(metrics.resident_bytes, 50 * 50 * 5);
+    }
+
+    #[test]
+    fn the_byte_budget_evicts_the_least_recently_used_model() {
+        // Room for exactly two 12.5 KB models.
+        let registry = ReferenceRegistry::new(ReferenceRegistryConfig {
+            byte_budget: 2 * 50 * 50 * 5,
+        });
+        registry.get_or_load("a", || Ok(small_model(10))).unwrap();
+        registry.get_or_load("b", || Ok(small_model(20))).unwrap();
+        // Touch "a" so "b" is the stalest when "c" arrives.
+        registry.get_or_load("a", || unreachable!()).unwrap();
+        registry.get_or_load("c", || Ok(small_model(30))).unwrap();
+        let metrics = registry.metrics();
+        assert_eq!(metrics.evictions, 1);
+        assert_eq!(metrics.resident_models, 2);
+        // "a" survived the eviction; "b" was dropped and reloads.
+        registry.get_or_load("a", || unreachable!()).unwrap();
+        let mut reloaded = false;
+        registry
+            .get_or_load("b", || {
+                reloaded = true;
+                Ok(small_model(20))
+            })
+            .unwrap();
+        assert!(reloaded);
+    }
+
+    #[test]
+    fn oversized_models_are_served_without_pinning() {
+        let registry = ReferenceRegistry::new(ReferenceRegistryConfig { byte_budget: 1_000 });
+        let model = registry.get_or_load("big", || Ok(small_model(25))).unwrap();
+        assert_eq!(model.pixel_count(), 30);
+        assert_eq!(registry.metrics().resident_models, 0);
+        assert_eq!(registry.metrics().resident_bytes, 0);
+    }
+
+    #[test]
+    fn invalidation_forces_a_reload() {
+        let registry = ReferenceRegistry::new(ReferenceRegistryConfig::default());
+        registry.get_or_load("cat-01", || Ok(small_model(25))).unwrap();
+        assert!(registry.invalidate("cat-01"));
+        assert!(!registry.invalidate("cat-01"));
+        let mut reloaded = false;
+        registry
+            .get_or_load("cat-01", || {
+                reloaded = true;
+                Ok(small_model(25))
+            })
+            .unwrap();
+        assert!(reloaded);
+    }
+}
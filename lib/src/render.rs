@@ -0,0 +1,318 @@
+use image::RgbaImage;
+use ndarray::Array2;
+
+use crate::analysis::BoundingBox;
+use crate::colormap::Colormap;
+use crate::metrics::GRID_SIZE;
+
+/// Renders a distance heatmap as an image, normalizing distances by the
+/// largest one present. Positions without data (`-1`) are transparent.
+pub fn render_heatmap(heatmap: &Array2<i32>, colormap: &Colormap) -> RgbaImage {
+    let (height, width) = heatmap.dim();
+    let max = heatmap.iter().copied().max().unwrap_or(0).max(1);
+    let mut image = RgbaImage::new(width as u32, height as u32);
+    for ((y, x), &distance) in heatmap.indexed_iter() {
+        let pixel = if distance < 0 {
+            [0, 0, 0, 0]
+        } else {
+            colormap.sample(f64::from(distance) / f64::from(max))
+        };
+        image.put_pixel(x as u32, y as u32, image::Rgba(pixel));
+    }
+    image
+}
+
+/// Renders a stroke mask back to RGBA using the app's export
+/// convention: black ink on a transparent canvas, or black on white
+/// when backgrounds are opaque — the inverse of mask extraction.
+pub fn render_mask(mask: &Array2<u8>, transparent_background: bool) -> RgbaImage {
+    let (height, width) = mask.dim();
+    let background = if transparent_background {
+        image::Rgba([0, 0, 0, 0])
+    } else {
+        image::Rgba([255, 255, 255, 255])
+    };
+    let mut image = RgbaImage::from_pixel(width as u32, height as u32, background);
+    for ((y, x), &on) in mask.indexed_iter() {
+        if on != 0 {
+            image.put_pixel(x as u32, y as u32, image::Rgba([0, 0, 0, 255]));
+        }
+    }
+    image
+}
+
+/// Renders a semi-transparent "ghost" of the reference for onion-skin
+/// practice modes, sized to the observation canvas. Reference pixels
+/// render in `color` at `alpha`; the background stays fully
+/// transparent, so the ghost composites over any canvas. A reference at
+/// a different resolution is resampled with the same mode the evaluator
+/// applies to panes, so the ghost sits exactly where the scorer will
+/// grade.
+pub fn render_onion_skin(
+    reference: &Array2<u8>,
+    width: usize,
+    height: usize,
+    color: [u8; 3],
+    alpha: u8,
+    resample: crate::scale::ResampleMode,
+) -> RgbaImage {
+    let resampled;
+    let reference = if reference.dim() == (height, width) {
+        reference
+    } else {
+        resampled = crate::scale::resample_mask(reference, height, width, resample);
+        &resampled
+    };
+    let [r, g, b] = color;
+    let mut image = RgbaImage::new(width as u32, height as u32);
+    for ((y, x), &on) in reference.indexed_iter() {
+        if on != 0 {
+            image.put_pixel(x as u32, y as u32, image::Rgba([r, g, b, alpha]));
+        }
+    }
+    image
+}
+
+/// Renders the scoring grid as a canvas-sized overlay: cells tinted by
+/// their score (normalized to the worst cell, at half opacity so the
+/// drawing stays visible underneath) with 1px boundary lines. The cell
+/// geometry matches [`crate::metrics`] exactly, so the overlay shows
+/// the same boundaries the scorer aggregated with.
+pub fn render_grid_overlay(
+    grid: &[Vec<f64>],
+    width: usize,
+    height: usize,
+    colormap: &Colormap,
+) -> RgbaImage {
+    let cell_width = width.div_ceil(GRID_SIZE);
+    let cell_height = height.div_ceil(GRID_SIZE);
+    let worst = grid
+        .iter()
+        .flatten()
+        .copied()
+        .fold(0.0f64, f64::max)
+        .max(f64::MIN_POSITIVE);
+    let mut image = RgbaImage::new(width as u32, height as u32);
+    for (y, x) in (0..height).flat_map(|y| (0..width).map(move |x| (y, x))) {
+        let (row, column) = ((y / cell_height).min(GRID_SIZE - 1), (x / cell_width).min(GRID_SIZE - 1));
+        let pixel = if y % cell_height == 0 || x % cell_width == 0 {
+            [0, 0, 0, 160]
+        } else {
+            let score = grid
+                .get(row)
+                .and_then(|cells| cells.get(column))
+                .copied()
+                .unwrap_or(0.0);
+            if score <= 0.0 {
+                [0, 0, 0, 0]
+            } else {
+                let [r, g, b, _] = colormap.sample(score / worst);
+                [r, g, b, 128]
+            }
+        };
+        image.put_pixel(x as u32, y as u32, image::Rgba(pixel));
+    }
+    image
+}
+
+/// Renders the per-cell score change between two attempts (see
+/// [`crate::metrics::grid_delta`]) as a canvas-sized overlay for the
+/// "see your improvement" screen: cells that improved tint green, cells
+/// that regressed tint red, opacity scaled to the largest change, with
+/// the same 1px cell boundaries as [`render_grid_overlay`]. Unchanged
+/// cells stay transparent.
+pub fn render_improvement(delta: &[Vec<f64>], width: usize, height: usize) -> RgbaImage {
+    let cell_width = width.div_ceil(GRID_SIZE);
+    let cell_height = height.div_ceil(GRID_SIZE);
+    let largest = delta
+        .iter()
+        .flatten()
+        .map(|change| change.abs())
+        .fold(0.0f64, f64::max)
+        .max(f64::MIN_POSITIVE);
+    let mut image = RgbaImage::new(width as u32, height as u32);
+    for (y, x) in (0..height).flat_map(|y| (0..width).map(move |x| (y, x))) {
+        let (row, column) = ((y / cell_height).min(GRID_SIZE - 1), (x / cell_width).min(GRID_SIZE - 1));
+        let pixel = if y % cell_height == 0 || x % cell_width == 0 {
+            [0, 0, 0, 160]
+        } else {
+            let change = delta
+                .get(row)
+                .and_then(|cells| cells.get(column))
+                .copied()
+                .unwrap_or(0.0);
+            let alpha = (128.0 * change.abs() / largest) as u8;
+            if alpha == 0 {
+                [0, 0, 0, 0]
+            } else if change < 0.0 {
+                [0, 160, 0, alpha]
+            } else {
+                [200, 0, 0, alpha]
+            }
+        };
+        image.put_pixel(x as u32, y as u32, image::Rgba(pixel));
+    }
+    image
+}
+
+/// The scoring grid as an SVG overlay string: one `<rect>` per cell
+/// with its score as a `data-score` attribute and opacity scaled to
+/// the worst cell, the grid lines, and optionally the reference
+/// bounding box — so web UIs draw exactly the cell boundaries the
+/// scorer used.
+pub fn grid_overlay_svg(
+    grid: &[Vec<f64>],
+    width: usize,
+    height: usize,
+    reference_bounds: Option<&BoundingBox>,
+) -> String {
+    use std::fmt::Write;
+
+    let cell_width = width.div_ceil(GRID_SIZE);
+    let cell_height = height.div_ceil(GRID_SIZE);
+    let worst = grid
+        .iter()
+        .flatten()
+        .copied()
+        .fold(0.0f64, f64::max)
+        .max(f64::MIN_POSITIVE);
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {width} {height}\">\n"
+    );
+    for (row, cells) in grid.iter().enumerate().take(GRID_SIZE) {
+        for (column, &score) in cells.iter().enumerate().take(GRID_SIZE) {
+            let x = column * cell_width;
+            let y = row * cell_height;
+            let w = cell_width.min(width - x.min(width));
+            let h = cell_height.min(height - y.min(height));
+            let _ = writeln!(
+                svg,
+                "  <rect x=\"{x}\" y=\"{y}\" width=\"{w}\" height=\"{h}\" \
+                 fill=\"red\" fill-opacity=\"{:.3}\" data-score=\"{score:.3}\"/>",
+                0.5 * score / worst
+            );
+        }
+    }
+    for line in 0..=GRID_SIZE {
+        let x = (line * cell_width).min(width);
+        let y = (line * cell_height).min(height);
+        let _ = writeln!(
+            svg,
+            "  <line x1=\"{x}\" y1=\"0\" x2=\"{x}\" y2=\"{height}\" stroke=\"black\" stroke-opacity=\"0.6\"/>"
+        );
+        let _ = writeln!(
+            svg,
+            "  <line x1=\"0\" y1=\"{y}\" x2=\"{width}\" y2=\"{y}\" stroke=\"black\" stroke-opacity=\"0.6\"/>"
+        );
+    }
+    if let Some(bounds) = reference_bounds {
+        let _ = writeln!(
+            svg,
+            "  <rect class=\"reference-bounds\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" \
+             fill=\"none\" stroke=\"blue\"/>",
+            bounds.min_x,
+            bounds.min_y,
+            bounds.max_x - bounds.min_x + 1,
+            bounds.max_y - bounds.min_y + 1
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_distance_gets_the_first_stop_color() {
+        let mut heatmap = Array2::from_elem((4, 4), -1);
+        heatmap[(0, 0)] = 0;
+        heatmap[(3, 3)] = 8;
+        let image = render_heatmap(&heatmap, &Colormap::grayscale());
+        assert_eq!(image.dimensions(), (4, 4));
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(3, 3).0, [255, 255, 255, 255]);
+        assert_eq!(image.get_pixel(1, 0).0[3], 0);
+    }
+
+    #[test]
+    fn grid_overlays_tint_scored_cells_and_draw_boundaries() {
+        let mut grid = vec![vec![0.0; GRID_SIZE]; GRID_SIZE];
+        grid[5][2] = 10.0;
+        let overlay = render_grid_overlay(&grid, 500, 500, &Colormap::grayscale());
+        assert_eq!(overlay.dimensions(), (500, 500));
+        // An interior pixel of the scored cell is tinted at half alpha;
+        // unscored cells stay transparent; cell boundaries are lines.
+        assert_eq!(overlay.get_pixel(125, 275).0, [255, 255, 255, 128]);
+        assert_eq!(overlay.get_pixel(125, 125).0[3], 0);
+        assert_eq!(overlay.get_pixel(100, 125).0, [0, 0, 0, 160]);
+    }
+
+    #[test]
+    fn improvement_overlays_tint_green_where_error_dropped() {
+        let mut delta = vec![vec![0.0; GRID_SIZE]; GRID_SIZE];
+        delta[5][2] = -10.0;
+        delta[1][1] = 5.0;
+        let overlay = render_improvement(&delta, 500, 500);
+        assert_eq!(overlay.dimensions(), (500, 500));
+        // The improved cell tints green at full weight, the regressed
+        // one red at half, and untouched cells stay transparent.
+        assert_eq!(overlay.get_pixel(125, 275).0, [0, 160, 0, 128]);
+        assert_eq!(overlay.get_pixel(75, 75).0, [200, 0, 0, 64]);
+        assert_eq!(overlay.get_pixel(125, 125).0[3], 0);
+        assert_eq!(overlay.get_pixel(100, 125).0, [0, 0, 0, 160]);
+    }
+
+    #[test]
+    fn onion_skins_tint_reference_pixels_only() {
+        let mut reference = Array2::zeros((500, 500));
+        for x in 100..400 {
+            reference[(250, x)] = 1;
+        }
+        let ghost =
+            render_onion_skin(&reference, 500, 500, [64, 128, 255], 80, Default::default());
+        assert_eq!(ghost.dimensions(), (500, 500));
+        assert_eq!(ghost.get_pixel(200, 250).0, [64, 128, 255, 80]);
+        assert_eq!(ghost.get_pixel(200, 100).0[3], 0);
+    }
+
+    #[test]
+    fn onion_skins_resample_to_the_observation_canvas() {
+        // A devicePixelRatio-2 reference shrinks onto the 500px canvas.
+        let mut reference = Array2::zeros((1000, 1000));
+        for x in 200..800 {
+            reference[(500, x)] = 1;
+            reference[(501, x)] = 1;
+        }
+        let ghost = render_onion_skin(&reference, 500, 500, [0, 0, 0], 96, Default::default());
+        assert_eq!(ghost.dimensions(), (500, 500));
+        assert_eq!(ghost.get_pixel(250, 250).0, [0, 0, 0, 96]);
+        assert_eq!(ghost.get_pixel(50, 250).0[3], 0);
+    }
+
+    #[test]
+    fn svg_overlays_carry_cell_scores_and_the_reference_bounds() {
+        let mut grid = vec![vec![0.0; GRID_SIZE]; GRID_SIZE];
+        grid[5][2] = 10.0;
+        let bounds = BoundingBox {
+            min_x: 100,
+            min_y: 50,
+            max_x: 399,
+            max_y: 249,
+        };
+        let svg = grid_overlay_svg(&grid, 500, 500, Some(&bounds));
+        assert!(svg.starts_with("<svg "), "{svg}");
+        assert!(svg.contains("viewBox=\"0 0 500 500\""), "{svg}");
+        // The worst cell renders fully weighted at the configured 0.5.
+        assert!(
+            svg.contains("x=\"100\" y=\"250\" width=\"50\" height=\"50\" fill=\"red\" fill-opacity=\"0.500\" data-score=\"10.000\""),
+            "{svg}"
+        );
+        assert!(
+            svg.contains("class=\"reference-bounds\" x=\"100\" y=\"50\" width=\"300\" height=\"200\""),
+            "{svg}"
+        );
+        assert!(svg.trim_end().ends_with("</svg>"), "{svg}");
+    }
+}
@@ -0,0 +1,195 @@
+//! Printable per-attempt HTML reports.
+//!
+//! The generated document is self-contained — the reference, observation
+//! and diff overlay are embedded as PNG data URLs — so it can be mailed
+//! as a single file or printed to PDF from a headless browser.
+
+use std::io::Cursor;
+
+use image::{Rgba, RgbaImage};
+use ndarray::Array2;
+
+use crate::baseline::badness;
+use crate::evaluator::EvaluationResult;
+use crate::metrics::ErrorMetrics;
+
+const INK: Rgba<u8> = Rgba([40, 40, 40, 255]);
+const PAPER: Rgba<u8> = Rgba([255, 255, 255, 255]);
+/// Reference strokes the student missed.
+const MISSED: Rgba<u8> = Rgba([43, 108, 196, 255]);
+/// Observation strokes with no reference nearby.
+const EXTRA: Rgba<u8> = Rgba([204, 64, 52, 255]);
+
+/// Letter grade derived from the combined error-and-coverage badness
+/// score; tuned so a careful tracing earns an A and an empty canvas an F.
+pub fn letter_grade(metrics: &ErrorMetrics) -> &'static str {
+    match badness(metrics) {
+        b if b < 1.0 => "A",
+        b if b < 3.0 => "B",
+        b if b < 6.0 => "C",
+        b if b < 10.0 => "D",
+        _ => "F",
+    }
+}
+
+/// Renders a printable HTML report for one evaluated attempt. The masks
+/// are the same (1 = stroke) arrays the evaluator scored.
+pub fn render_html_report(
+    reference: &Array2<u8>,
+    observation: &Array2<u8>,
+    result: &EvaluationResult,
+) -> String {
+    let metrics = &result.metrics;
+    let regions = result
+        .problem_regions
+        .iter()
+        .map(|region| {
+            format!(
+                "<li>{} (severity {:.1})</li>",
+                html_escape(&region.label),
+                region.severity
+            )
+        })
+        .collect::<String>();
+    let regions_block = if regions.is_empty() {
+        "<p>No problem regions — well done.</p>".to_string()
+    } else {
+        format!("<ul>{regions}</ul>")
+    };
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>Drawing evaluation report</title>
+<style>
+  body {{ font-family: system-ui, sans-serif; margin: 2rem; color: #222; }}
+  h1 {{ font-size: 1.4rem; }}
+  .grade {{ font-size: 3rem; font-weight: bold; }}
+  .panes {{ display: flex; gap: 1rem; flex-wrap: wrap; }}
+  .panes figure {{ margin: 0; }}
+  .panes img {{ width: 280px; border: 1px solid #ccc; image-rendering: pixelated; }}
+  table {{ border-collapse: collapse; margin-top: 1rem; }}
+  td, th {{ border: 1px solid #ccc; padding: 0.3rem 0.8rem; text-align: left; }}
+  .legend span {{ padding: 0 0.3rem; }}
+  @media print {{ body {{ margin: 0.5cm; }} }}
+</style>
+</head>
+<body>
+<h1>Drawing evaluation report</h1>
+<p class="grade">{grade}</p>
+<div class="panes">
+<figure><img src="{reference_url}" alt="Reference"><figcaption>Reference</figcaption></figure>
+<figure><img src="{observation_url}" alt="Observation"><figcaption>Observation</figcaption></figure>
+<figure><img src="{diff_url}" alt="Diff overlay"><figcaption>Diff overlay</figcaption></figure>
+</div>
+<p class="legend">
+<span style="color:#2b6cc4">&#9632;</span> missed reference
+<span style="color:#cc4034">&#9632;</span> stray strokes
+</p>
+<table>
+<tr><th>Mean error</th><td>{mean_error:.3}</td></tr>
+<tr><th>Top-5 error</th><td>{top_5_error:.3}</td></tr>
+<tr><th>Coverage</th><td>{coverage:.1}%</td></tr>
+<tr><th>Evaluation time</th><td>{duration_ms} ms</td></tr>
+</table>
+<h2>Problem regions</h2>
+{regions_block}
+</body>
+</html>
+"#,
+        grade = letter_grade(metrics),
+        reference_url = mask_data_url(reference),
+        observation_url = mask_data_url(observation),
+        diff_url = diff_data_url(reference, observation),
+        mean_error = metrics.mean_error,
+        top_5_error = metrics.top_5_error,
+        coverage = metrics.coverage * 100.0,
+        duration_ms = result.duration_ms,
+    )
+}
+
+/// Renders a stroke mask as dark ink on white paper.
+fn mask_data_url(mask: &Array2<u8>) -> String {
+    let (height, width) = mask.dim();
+    let mut image = RgbaImage::from_pixel(width as u32, height as u32, PAPER);
+    for ((y, x), &value) in mask.indexed_iter() {
+        if value != 0 {
+            image.put_pixel(x as u32, y as u32, INK);
+        }
+    }
+    png_data_url(&image)
+}
+
+/// Overlays both masks: shared pixels in ink, reference-only pixels in
+/// blue, observation-only pixels in red.
+fn diff_data_url(reference: &Array2<u8>, observation: &Array2<u8>) -> String {
+    let (height, width) = reference.dim();
+    let mut image = RgbaImage::from_pixel(width as u32, height as u32, PAPER);
+    for ((y, x), &ref_on) in reference.indexed_iter() {
+        let obs_on = observation.get((y, x)).copied().unwrap_or(0) != 0;
+        let color = match (ref_on != 0, obs_on) {
+            (true, true) => INK,
+            (true, false) => MISSED,
+            (false, true) => EXTRA,
+            (false, false) => continue,
+        };
+        image.put_pixel(x as u32, y as u32, color);
+    }
+    png_data_url(&image)
+}
+
+/// Encodes an image as a base64 PNG data URL.
+fn png_data_url(image: &RgbaImage) -> String {
+    let mut png = Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, image::ImageOutputFormat::Png)
+        .expect("encoding to an in-memory PNG cannot fail");
+    let encoded = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, png.get_ref());
+    format!("data:image/png;base64,{encoded}")
+}
+
+/// Minimal escaping for text interpolated into the report body.
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::ImageEvaluator;
+
+    fn line_mask(range: std::ops::Range<usize>) -> Array2<u8> {
+        let mut mask = Array2::zeros((500, 500));
+        for x in range {
+            mask[(250, x)] = 1;
+        }
+        mask
+    }
+
+    #[test]
+    fn report_embeds_images_and_metrics() {
+        let reference = line_mask(100..400);
+        let observation = line_mask(100..400);
+        let result = ImageEvaluator::default()
+            .evaluate_arrays(&reference, &observation)
+            .unwrap();
+        let html = render_html_report(&reference, &observation, &result);
+        assert_eq!(html.matches("data:image/png;base64,").count(), 3);
+        assert!(html.contains("Coverage"));
+        assert!(html.contains(">A<"));
+    }
+
+    #[test]
+    fn grades_degrade_with_badness() {
+        let reference = line_mask(100..400);
+        let mut observation = Array2::zeros((500, 500));
+        for x in 10..30 {
+            observation[(10, x)] = 1;
+        }
+        let result = ImageEvaluator::default()
+            .evaluate_arrays(&reference, &observation)
+            .unwrap();
+        assert_eq!(letter_grade(&result.metrics), "F");
+    }
+}
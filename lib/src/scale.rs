@@ -0,0 +1,107 @@
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+/// How stroke masks are resampled to the evaluation resolution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ResampleMode {
+    /// Pick the nearest source pixel. Fast, can drop thin strokes when
+    /// downscaling.
+    Nearest,
+    /// Average source coverage over the target pixel's footprint and
+    /// re-threshold, preserving thin strokes from high-DPI canvases.
+    #[default]
+    Area,
+}
+
+/// Minimum covered fraction of a target pixel's footprint for it to
+/// count as a stroke pixel under [`ResampleMode::Area`].
+const AREA_THRESHOLD: f64 = 0.25;
+
+/// Resamples a binary stroke mask to `(target_height, target_width)`,
+/// re-thresholding so the output stays binary.
+pub fn resample_mask(
+    mask: &Array2<u8>,
+    target_height: usize,
+    target_width: usize,
+    mode: ResampleMode,
+) -> Array2<u8> {
+    let (source_height, source_width) = mask.dim();
+    let mut resampled = Array2::zeros((target_height, target_width));
+    if source_height == 0 || source_width == 0 || target_height == 0 || target_width == 0 {
+        return resampled;
+    }
+    let y_ratio = source_height as f64 / target_height as f64;
+    let x_ratio = source_width as f64 / target_width as f64;
+    for ((y, x), value) in resampled.indexed_iter_mut() {
+        let on = match mode {
+            ResampleMode::Nearest => {
+                let sy = ((y as f64 * y_ratio) as usize).min(source_height - 1);
+                let sx = ((x as f64 * x_ratio) as usize).min(source_width - 1);
+                mask[(sy, sx)] != 0
+            }
+            ResampleMode::Area => {
+                let y_start = (y as f64 * y_ratio) as usize;
+                let y_end = (((y + 1) as f64 * y_ratio).ceil() as usize)
+                    .clamp(y_start + 1, source_height);
+                let x_start = (x as f64 * x_ratio) as usize;
+                let x_end = (((x + 1) as f64 * x_ratio).ceil() as usize)
+                    .clamp(x_start + 1, source_width);
+                let mut covered = 0usize;
+                let mut total = 0usize;
+                for sy in y_start..y_end {
+                    for sx in x_start..x_end {
+                        total += 1;
+                        if mask[(sy, sx)] != 0 {
+                            covered += 1;
+                        }
+                    }
+                }
+                total > 0 && covered as f64 / total as f64 >= AREA_THRESHOLD
+            }
+        };
+        if on {
+            *value = 1;
+        }
+    }
+    resampled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn downscale_by_two_keeps_a_thick_line() {
+        let mut mask = Array2::zeros((100, 100));
+        for y in 48..52 {
+            for x in 20..80 {
+                mask[(y, x)] = 1;
+            }
+        }
+        for mode in [ResampleMode::Nearest, ResampleMode::Area] {
+            let resampled = resample_mask(&mask, 50, 50, mode);
+            assert_eq!(resampled.dim(), (50, 50));
+            assert!(
+                resampled.row(25).iter().filter(|&&p| p != 0).count() >= 28,
+                "{mode:?} lost the line"
+            );
+        }
+    }
+
+    #[test]
+    fn area_mode_preserves_one_pixel_strokes_on_downscale() {
+        let mut mask = Array2::zeros((100, 100));
+        for x in 20..80 {
+            mask[(50, x)] = 1;
+        }
+        let resampled = resample_mask(&mask, 50, 50, ResampleMode::Area);
+        assert!(resampled.row(25).iter().filter(|&&p| p != 0).count() >= 28);
+    }
+
+    #[test]
+    fn identity_resample_is_a_copy() {
+        let mut mask = Array2::zeros((10, 10));
+        mask[(3, 4)] = 1;
+        assert_eq!(resample_mask(&mask, 10, 10, ResampleMode::Area), mask);
+    }
+}
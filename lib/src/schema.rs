@@ -0,0 +1,539 @@
+//! Versioning for stored evaluation results.
+//!
+//! Results are persisted as JSON and outlive the code that wrote them.
+//! [`EvaluationResult`] carries a `schema_version`; [`VersionedResult`]
+//! deserializes any known layout — including results stored before
+//! versioning existed — and [`VersionedResult::migrate`] lifts them to
+//! the current one so old and new results stay comparable.
+//!
+//! [`json_schema`] exports the current layouts as a JSON Schema
+//! document (also available as `evaluator schema` on the CLI) so
+//! frontends can codegen matching interfaces and validate payloads.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::EvaluationError;
+use crate::evaluator::EvaluationResult;
+use crate::metrics::{ErrorMetrics, Normalization};
+
+/// The layout version written by this build.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// A stored result in whichever layout it was written with.
+// Results are short-lived parse targets; boxing the current layout to
+// shrink the enum would only add noise at every use site.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum VersionedResult {
+    /// The original layout: metrics and evaluation duration only.
+    V1(ResultV1),
+    /// The current [`EvaluationResult`] layout.
+    Current(EvaluationResult),
+}
+
+/// The version-1 result layout, before normalization constants, pane
+/// scales, stroke widths, overtime and problem regions were recorded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResultV1 {
+    pub metrics: MetricsV1,
+    pub duration_ms: u64,
+}
+
+/// The version-1 [`ErrorMetrics`] layout.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsV1 {
+    pub mean_error: f64,
+    pub top_5_error: f64,
+    pub coverage: f64,
+    pub grid: Vec<Vec<f64>>,
+}
+
+impl VersionedResult {
+    /// Deserializes a stored result of any known schema version. A
+    /// missing `schema_version` means the result predates versioning
+    /// and is read as version 1.
+    pub fn from_json(json: &str) -> Result<Self, EvaluationError> {
+        let value: serde_json::Value = serde_json::from_str(json)
+            .map_err(|e| EvaluationError::InvalidBuffer(e.to_string()))?;
+        let version = value
+            .get("schema_version")
+            .and_then(serde_json::Value::as_u64)
+            .unwrap_or(1);
+        match version {
+            1 => serde_json::from_value(value)
+                .map(Self::V1)
+                .map_err(|e| EvaluationError::InvalidBuffer(e.to_string())),
+            2 => serde_json::from_value(value)
+                .map(Self::Current)
+                .map_err(|e| EvaluationError::InvalidBuffer(e.to_string())),
+            other => Err(EvaluationError::InvalidBuffer(format!(
+                "unsupported result schema version {other}; this build reads up to {SCHEMA_VERSION}"
+            ))),
+        }
+    }
+
+    pub fn schema_version(&self) -> u32 {
+        match self {
+            Self::V1(_) => 1,
+            Self::Current(result) => result.schema_version,
+        }
+    }
+
+    /// Lifts the result to the current layout. Fields a prior version
+    /// did not record take the defaults that were in effect then: unit
+    /// pane scales and the default normalization constants.
+    pub fn migrate(self) -> EvaluationResult {
+        match self {
+            Self::Current(mut result) => {
+                result.schema_version = SCHEMA_VERSION;
+                result
+            }
+            Self::V1(result) => EvaluationResult {
+                schema_version: SCHEMA_VERSION,
+                metrics: ErrorMetrics {
+                    mean_error: result.metrics.mean_error,
+                    top_5_error: result.metrics.top_5_error,
+                    coverage: result.metrics.coverage,
+                    grid: result.metrics.grid,
+                    normalization: Normalization::default(),
+                    cell_tolerance_multipliers: None,
+                    normalized: None,
+                },
+                duration_ms: result.duration_ms,
+                reference_scale: 1.0,
+                observation_scale: 1.0,
+                reference_stroke_width: None,
+                observation_stroke_width: None,
+                overtime_ms: None,
+                filtered_pixels: 0,
+                panes_swapped: false,
+                problem_regions: Vec::new(),
+                correction_vectors: Vec::new(),
+                integrity: None,
+                perf: None,
+            },
+        }
+    }
+}
+
+/// A JSON Schema (draft 2020-12) document describing every payload this
+/// crate writes: [`EvaluationResult`], the serialized
+/// [`crate::streaming::StreamingEvaluator`] session state, and errors
+/// (which cross the wire as their display strings). Every nested type
+/// sits under `$defs`, so codegen tools emit one interface per Rust
+/// type. The document is maintained alongside the types and
+/// cross-checked by tests that serialize real values against it.
+pub fn json_schema() -> serde_json::Value {
+    serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "VisualArt evaluator payloads",
+        "$defs": {
+            "EvaluationResult": object(
+                serde_json::json!({
+                    "schema_version": integer(),
+                    "metrics": defs_ref("ErrorMetrics"),
+                    "duration_ms": integer(),
+                    "reference_scale": number(),
+                    "observation_scale": number(),
+                    "reference_stroke_width": nullable(number()),
+                    "observation_stroke_width": nullable(number()),
+                    "overtime_ms": nullable(integer()),
+                    "filtered_pixels": integer(),
+                    "panes_swapped": boolean(),
+                    "problem_regions": array(defs_ref("ProblemRegion")),
+                    "correction_vectors": array(defs_ref("CorrectionVector")),
+                    "integrity": nullable(defs_ref("InputDigests")),
+                    "perf": nullable(defs_ref("PerfStats")),
+                }),
+                &["metrics", "duration_ms"],
+            ),
+            "ErrorMetrics": object(
+                serde_json::json!({
+                    "mean_error": number(),
+                    "top_5_error": number(),
+                    "coverage": number(),
+                    "grid": grid(),
+                    "normalization": defs_ref("Normalization"),
+                    "cell_tolerance_multipliers": nullable(grid()),
+                    "normalized": nullable(defs_ref("NormalizedMetrics")),
+                }),
+                &["mean_error", "top_5_error", "coverage", "grid"],
+            ),
+            "NormalizedMetrics": object(
+                serde_json::json!({
+                    "canvas_diagonal": number(),
+                    "mean_error_pct": number(),
+                    "top_5_error_pct": number(),
+                }),
+                &["canvas_diagonal", "mean_error_pct", "top_5_error_pct"],
+            ),
+            "Normalization": object(
+                serde_json::json!({
+                    "mean_error_divisor": number(),
+                    "top_5_divisor": number(),
+                }),
+                &["mean_error_divisor", "top_5_divisor"],
+            ),
+            "ProblemRegion": object(
+                serde_json::json!({
+                    "label": string(),
+                    "cell_bounds": defs_ref("BoundingBox"),
+                    "centroid": defs_ref("PixelPoint"),
+                    "severity": number(),
+                    "direction_hint": defs_ref("CompassDirection"),
+                }),
+                &["label", "cell_bounds", "centroid", "severity", "direction_hint"],
+            ),
+            "BoundingBox": object(
+                serde_json::json!({
+                    "min_x": integer(),
+                    "min_y": integer(),
+                    "max_x": integer(),
+                    "max_y": integer(),
+                }),
+                &["min_x", "min_y", "max_x", "max_y"],
+            ),
+            "PixelPoint": object(
+                serde_json::json!({ "x": number(), "y": number() }),
+                &["x", "y"],
+            ),
+            "CompassDirection": string_enum(&[
+                "Up", "UpRight", "Right", "DownRight", "Down", "DownLeft", "Left",
+                "UpLeft", "Here",
+            ]),
+            "CorrectionVector": object(
+                serde_json::json!({
+                    "from": defs_ref("PixelPoint"),
+                    "to": defs_ref("PixelPoint"),
+                    "magnitude": number(),
+                }),
+                &["from", "to", "magnitude"],
+            ),
+            "InputDigests": object(
+                serde_json::json!({
+                    "reference_sha256": string(),
+                    "observation_sha256": string(),
+                    "config_sha256": string(),
+                }),
+                &["reference_sha256", "observation_sha256", "config_sha256"],
+            ),
+            "PerfStats": object(
+                serde_json::json!({
+                    "load_ms": nullable(integer()),
+                    "extract_ms": nullable(integer()),
+                    "heatmap_ms": integer(),
+                    "metrics_ms": integer(),
+                    "reference_pixels": integer(),
+                    "observation_pixels": integer(),
+                    "canvas_pixels": integer(),
+                    "peak_memory_bytes": integer(),
+                }),
+                &[
+                    "heatmap_ms", "metrics_ms", "reference_pixels",
+                    "observation_pixels", "canvas_pixels", "peak_memory_bytes",
+                ],
+            ),
+            "EvaluatorConfig": object(
+                serde_json::json!({
+                    "canvas_width": integer(),
+                    "canvas_height": integer(),
+                    "pane_gap": integer(),
+                    "transparent_background": boolean(),
+                    "ink_channel": defs_ref("InkChannel"),
+                    "tolerance": integer(),
+                    "skeletonize": boolean(),
+                    "max_distance": nullable(integer()),
+                    "resample": defs_ref("ResampleMode"),
+                    "normalization": defs_ref("Normalization"),
+                    "outlier_filter": nullable(defs_ref("OutlierFilter")),
+                    "auto_center": boolean(),
+                    "cell_tolerance_multipliers": nullable(grid()),
+                    "fit_grid_to_reference": boolean(),
+                    "cell_aggregator": defs_ref("CellAggregator"),
+                    "collect_perf_stats": boolean(),
+                }),
+                &[
+                    "canvas_width", "canvas_height", "pane_gap",
+                    "transparent_background", "tolerance",
+                ],
+            ),
+            "InkChannel": string_enum(&["red", "green", "blue", "min_rgb", "luminance"]),
+            "ResampleMode": string_enum(&["Nearest", "Area"]),
+            "OutlierFilter": serde_json::json!({ "oneOf": [
+                variant("min_component_size", integer()),
+                variant("trim_worst_fraction", number()),
+            ]}),
+            "CellAggregator": serde_json::json!({ "oneOf": [
+                string_enum(&["max"]),
+                variant("percentile", number()),
+                variant("trimmed_max", integer()),
+                variant("mean_of_worst_k", integer()),
+            ]}),
+            "UpdatePolicy": serde_json::json!({ "oneOf": [
+                string_enum(&["Immediate", "Manual"]),
+                variant("EveryNPixels", integer()),
+                variant("EveryMs", integer()),
+                variant("AdaptiveMs", integer()),
+            ]}),
+            "ClipPolicy": string_enum(&["ignore", "clamp", "error"]),
+            "CoordinateSpace": string_enum(&["row_major", "xy", "xy_up"]),
+            "SerializableHeatmap": object(
+                serde_json::json!({
+                    "width": integer(),
+                    "height": integer(),
+                    "data": array(integer()),
+                    "compressed": boolean(),
+                }),
+                &["width", "height", "data"],
+            ),
+            "UserContribution": object(
+                serde_json::json!({
+                    "user_id": string(),
+                    "pixels_added": integer(),
+                    "error_sum": integer(),
+                    "covered_reference": integer(),
+                }),
+                &["user_id", "pixels_added", "error_sum", "covered_reference"],
+            ),
+            "ScoreSample": object(
+                serde_json::json!({
+                    "elapsed_ms": integer(),
+                    "top_5_error": number(),
+                    "mean_error": number(),
+                }),
+                &["elapsed_ms", "top_5_error", "mean_error"],
+            ),
+            "HeatTimeline": object(
+                serde_json::json!({
+                    "sample_every_flushes": integer(),
+                    "grid_size": integer(),
+                    "data": array(number()),
+                }),
+                &["sample_every_flushes", "grid_size", "data"],
+            ),
+            "StreamingEvaluatorState": object(
+                serde_json::json!({
+                    "config": defs_ref("EvaluatorConfig"),
+                    "reference_pixels": array(integer()),
+                    "reference_heatmap": defs_ref("SerializableHeatmap"),
+                    "observation_pixels": array(integer()),
+                    "observation_heatmap": defs_ref("SerializableHeatmap"),
+                    "cell_errors": grid(),
+                    "error_sum": integer(),
+                    "observation_count": integer(),
+                    "covered_reference": integer(),
+                    "policy": defs_ref("UpdatePolicy"),
+                    "clip_policy": defs_ref("ClipPolicy"),
+                    "coordinate_space": defs_ref("CoordinateSpace"),
+                    "out_of_bounds_count": integer(),
+                    "pending_pixels": array(array(integer())),
+                    "pending_owners": array(nullable(integer())),
+                    "contributors": array(defs_ref("UserContribution")),
+                    "smoothing_alpha": number(),
+                    "smoothed_score": nullable(number()),
+                    "recent_scores": array(number()),
+                    "score_history": array(defs_ref("ScoreSample")),
+                    "heat_timeline": nullable(defs_ref("HeatTimeline")),
+                    "flushes_since_heat_sample": integer(),
+                    "adaptive_batch": integer(),
+                }),
+                &[
+                    "config", "reference_pixels", "reference_heatmap",
+                    "observation_pixels", "observation_heatmap", "cell_errors",
+                    "error_sum", "observation_count", "covered_reference",
+                ],
+            ),
+            "EvaluationError": serde_json::json!({
+                "type": "string",
+                "description": "Errors cross the wire as their display strings.",
+            }),
+        },
+    })
+}
+
+fn number() -> serde_json::Value {
+    serde_json::json!({ "type": "number" })
+}
+
+fn integer() -> serde_json::Value {
+    serde_json::json!({ "type": "integer" })
+}
+
+fn string() -> serde_json::Value {
+    serde_json::json!({ "type": "string" })
+}
+
+fn boolean() -> serde_json::Value {
+    serde_json::json!({ "type": "boolean" })
+}
+
+fn array(items: serde_json::Value) -> serde_json::Value {
+    serde_json::json!({ "type": "array", "items": items })
+}
+
+/// A row-major grid of numbers, as the scoring grid and the
+/// per-cell tolerance maps serialize.
+fn grid() -> serde_json::Value {
+    array(array(number()))
+}
+
+fn nullable(schema: serde_json::Value) -> serde_json::Value {
+    serde_json::json!({ "anyOf": [schema, { "type": "null" }] })
+}
+
+fn string_enum(values: &[&str]) -> serde_json::Value {
+    serde_json::json!({ "type": "string", "enum": values })
+}
+
+fn defs_ref(name: &str) -> serde_json::Value {
+    serde_json::json!({ "$ref": format!("#/$defs/{name}") })
+}
+
+fn object(properties: serde_json::Value, required: &[&str]) -> serde_json::Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    })
+}
+
+/// An externally tagged enum variant carrying a payload, as serde
+/// serializes them: a single-key object.
+fn variant(tag: &str, payload: serde_json::Value) -> serde_json::Value {
+    object(serde_json::json!({ tag: payload }), &[tag])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::GRID_SIZE;
+
+    fn v1_json() -> String {
+        serde_json::json!({
+            "metrics": {
+                "mean_error": 1.5,
+                "top_5_error": 2.0,
+                "coverage": 0.75,
+                "grid": vec![vec![0.0; GRID_SIZE]; GRID_SIZE],
+            },
+            "duration_ms": 12,
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn unversioned_json_is_read_as_version_1_and_migrates() {
+        let stored = VersionedResult::from_json(&v1_json()).unwrap();
+        assert_eq!(stored.schema_version(), 1);
+        let migrated = stored.migrate();
+        assert_eq!(migrated.schema_version, SCHEMA_VERSION);
+        assert_eq!(migrated.metrics.coverage, 0.75);
+        assert_eq!(migrated.reference_scale, 1.0);
+        assert_eq!(migrated.metrics.normalization, Normalization::default());
+    }
+
+    #[test]
+    fn current_results_round_trip_through_their_own_json() {
+        let migrated = VersionedResult::from_json(&v1_json()).unwrap().migrate();
+        let json = serde_json::to_string(&migrated).unwrap();
+        let reread = VersionedResult::from_json(&json).unwrap();
+        assert_eq!(reread.schema_version(), SCHEMA_VERSION);
+        assert_eq!(reread.migrate().metrics.mean_error, 1.5);
+    }
+
+    #[test]
+    fn future_schema_versions_are_rejected_with_the_supported_range() {
+        let json = r#"{ "schema_version": 99, "duration_ms": 1 }"#;
+        let error = VersionedResult::from_json(json).unwrap_err();
+        assert!(error.to_string().contains("99"));
+        assert!(error.to_string().contains("up to 2"));
+    }
+
+    /// Both directions: every serialized field must be described, and
+    /// every described field must still serialize — so the hand-written
+    /// schema cannot drift from the Rust layouts unnoticed.
+    fn assert_matches_def(schema: &serde_json::Value, name: &str, value: &serde_json::Value) {
+        let def = &schema["$defs"][name];
+        let properties = def["properties"].as_object().unwrap();
+        let fields = value.as_object().unwrap();
+        for key in fields.keys() {
+            assert!(
+                properties.contains_key(key),
+                "the {name} schema is missing serialized field {key}"
+            );
+        }
+        for key in properties.keys() {
+            assert!(
+                fields.contains_key(key),
+                "the {name} schema lists {key}, which no longer serializes"
+            );
+        }
+        for required in def["required"].as_array().unwrap() {
+            assert!(
+                properties.contains_key(required.as_str().unwrap()),
+                "the {name} schema requires a field it does not describe"
+            );
+        }
+    }
+
+    #[test]
+    fn the_schema_tracks_the_evaluation_result_layout() {
+        let result = VersionedResult::from_json(&v1_json()).unwrap().migrate();
+        let value = serde_json::to_value(&result).unwrap();
+        let schema = json_schema();
+        assert_matches_def(&schema, "EvaluationResult", &value);
+        assert_matches_def(&schema, "ErrorMetrics", &value["metrics"]);
+        assert_matches_def(&schema, "Normalization", &value["metrics"]["normalization"]);
+    }
+
+    #[test]
+    fn the_schema_tracks_the_streaming_state_and_config_layouts() {
+        let mut reference = ndarray::Array2::zeros((500, 500));
+        for x in 100..400 {
+            reference[(250, x)] = 1;
+        }
+        let model = crate::streaming::ReferenceModel::new(
+            reference,
+            crate::evaluator::EvaluatorConfig::default(),
+        )
+        .unwrap();
+        let mut streaming = crate::streaming::StreamingEvaluator::new(model);
+        streaming
+            .add_observation_pixels(&[(255, 150), (255, 151)])
+            .unwrap();
+        let value = serde_json::to_value(streaming.to_serialized_state()).unwrap();
+        let schema = json_schema();
+        assert_matches_def(&schema, "StreamingEvaluatorState", &value);
+        assert_matches_def(&schema, "EvaluatorConfig", &value["config"]);
+        assert_matches_def(&schema, "SerializableHeatmap", &value["reference_heatmap"]);
+    }
+
+    #[test]
+    fn every_reference_in_the_schema_resolves() {
+        fn collect<'a>(value: &'a serde_json::Value, refs: &mut Vec<&'a str>) {
+            match value {
+                serde_json::Value::Object(map) => {
+                    if let Some(target) = map.get("$ref").and_then(serde_json::Value::as_str) {
+                        refs.push(target);
+                    }
+                    map.values().for_each(|v| collect(v, refs));
+                }
+                serde_json::Value::Array(items) => items.iter().for_each(|v| collect(v, refs)),
+                _ => {}
+            }
+        }
+        let schema = json_schema();
+        let mut refs = Vec::new();
+        collect(&schema, &mut refs);
+        assert!(!refs.is_empty());
+        for target in refs {
+            let name = target
+                .strip_prefix("#/$defs/")
+                .unwrap_or_else(|| panic!("non-local reference {target}"));
+            assert!(
+                schema["$defs"].get(name).is_some(),
+                "dangling schema reference {target}"
+            );
+        }
+    }
+}
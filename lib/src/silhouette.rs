@@ -0,0 +1,156 @@
+//! Inside/outside error decomposition for closed shapes.
+//!
+//! Contour exercises care about *which side* of the reference outline a
+//! stray pixel landed on: marks inside the silhouette are fill, marks
+//! outside are overshoot, and the fixes differ. This module derives the
+//! reference silhouette by flood-filling the background from the canvas
+//! border, then splits the observation's error into the two components.
+
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+use crate::heatmap::flood_fill_distances;
+
+/// The observation's error split by side of the reference silhouette.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedErrorComponents {
+    /// Mean distance-to-reference of the observation pixels inside the
+    /// silhouette (fill); zero when there are none.
+    pub inside_mean_error: f64,
+    /// Mean distance of the pixels outside the silhouette (overshoot).
+    pub outside_mean_error: f64,
+    pub inside_pixels: u64,
+    pub outside_pixels: u64,
+    /// Fraction of the silhouette's interior area the reference stroke
+    /// itself occupies; near 1 means the shape is not really closed
+    /// and the split is meaningless.
+    pub stroke_fraction: f64,
+}
+
+/// Marks the reference silhouette: every pixel on the reference stroke
+/// or enclosed by it. Background is whatever a 4-connected flood fill
+/// reaches from the canvas border; everything else is inside.
+pub fn silhouette_mask(reference: &Array2<u8>) -> Array2<u8> {
+    let (height, width) = reference.dim();
+    let mut outside = Array2::<u8>::zeros((height, width));
+    let mut queue = std::collections::VecDeque::new();
+    let visit = |mask: &mut Array2<u8>, queue: &mut std::collections::VecDeque<_>, y, x| {
+        if reference[(y, x)] == 0 && mask[(y, x)] == 0 {
+            mask[(y, x)] = 1;
+            queue.push_back((y, x));
+        }
+    };
+    for x in 0..width {
+        visit(&mut outside, &mut queue, 0, x);
+        visit(&mut outside, &mut queue, height - 1, x);
+    }
+    for y in 0..height {
+        visit(&mut outside, &mut queue, y, 0);
+        visit(&mut outside, &mut queue, y, width - 1);
+    }
+    while let Some((y, x)) = queue.pop_front() {
+        for (dy, dx) in [(-1i64, 0i64), (1, 0), (0, -1), (0, 1)] {
+            let (ny, nx) = (y as i64 + dy, x as i64 + dx);
+            if ny < 0 || nx < 0 || ny >= height as i64 || nx >= width as i64 {
+                continue;
+            }
+            visit(&mut outside, &mut queue, ny as usize, nx as usize);
+        }
+    }
+    outside.mapv(|reached| u8::from(reached == 0))
+}
+
+/// Splits the observation's distance error by side of the reference
+/// silhouette. Distances come from the reference's flood-fill field,
+/// the same one the overall metrics use.
+pub fn signed_error_components(
+    reference: &Array2<u8>,
+    observation: &Array2<u8>,
+) -> SignedErrorComponents {
+    let heatmap = flood_fill_distances(reference, None);
+    let silhouette = silhouette_mask(reference);
+    let mut inside = (0u64, 0.0f64);
+    let mut outside = (0u64, 0.0f64);
+    for ((y, x), &on) in observation.indexed_iter() {
+        if on == 0 {
+            continue;
+        }
+        let distance = f64::from(heatmap[(y, x)].max(0));
+        let side = if silhouette[(y, x)] != 0 {
+            &mut inside
+        } else {
+            &mut outside
+        };
+        side.0 += 1;
+        side.1 += distance;
+    }
+    let mean = |(count, sum): (u64, f64)| if count == 0 { 0.0 } else { sum / count as f64 };
+    let silhouette_area = silhouette.iter().filter(|&&p| p != 0).count();
+    let stroke_area = reference.iter().filter(|&&p| p != 0).count();
+    SignedErrorComponents {
+        inside_mean_error: mean(inside),
+        outside_mean_error: mean(outside),
+        inside_pixels: inside.0,
+        outside_pixels: outside.0,
+        stroke_fraction: if silhouette_area == 0 {
+            0.0
+        } else {
+            stroke_area as f64 / silhouette_area as f64
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 60x60 square outline centred on a 100x100 canvas.
+    fn square_outline() -> Array2<u8> {
+        let mut mask = Array2::zeros((100, 100));
+        for i in 20..80 {
+            mask[(20, i)] = 1;
+            mask[(79, i)] = 1;
+            mask[(i, 20)] = 1;
+            mask[(i, 79)] = 1;
+        }
+        mask
+    }
+
+    #[test]
+    fn the_silhouette_covers_the_outline_and_its_interior() {
+        let silhouette = silhouette_mask(&square_outline());
+        assert_eq!(silhouette[(50, 50)], 1);
+        assert_eq!(silhouette[(20, 50)], 1);
+        assert_eq!(silhouette[(10, 50)], 0);
+        assert_eq!(silhouette.iter().filter(|&&p| p != 0).count(), 60 * 60);
+    }
+
+    #[test]
+    fn an_open_stroke_encloses_nothing() {
+        let mut line = Array2::zeros((100, 100));
+        for x in 20..80 {
+            line[(50, x)] = 1;
+        }
+        let silhouette = silhouette_mask(&line);
+        assert_eq!(silhouette.iter().filter(|&&p| p != 0).count(), 60);
+        let components = signed_error_components(&line, &line);
+        assert_eq!(components.stroke_fraction, 1.0);
+    }
+
+    #[test]
+    fn errors_split_into_fill_and_overshoot() {
+        let reference = square_outline();
+        let mut observation = Array2::zeros((100, 100));
+        // Fill: 10px inside the left edge. Overshoot: 5px outside it.
+        for y in 30..70 {
+            observation[(y, 30)] = 1;
+            observation[(y, 15)] = 1;
+        }
+        let components = signed_error_components(&reference, &observation);
+        assert_eq!(components.inside_pixels, 40);
+        assert_eq!(components.outside_pixels, 40);
+        assert!((components.inside_mean_error - 10.0).abs() < 1e-9);
+        assert!((components.outside_mean_error - 5.0).abs() < 1e-9);
+        assert!(components.stroke_fraction < 0.1);
+    }
+}
@@ -0,0 +1,2213 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, OnceLock};
+
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+use crate::error::EvaluationError;
+use crate::evaluator::EvaluatorConfig;
+use crate::heatmap::flood_fill_distances;
+use crate::metrics::{top_5_from_grid, ErrorMetrics, GRID_SIZE};
+
+/// A reference image with its precomputed distance heatmap, reusable
+/// across many observations.
+#[derive(Debug, Clone)]
+pub struct ReferenceModel {
+    pub(crate) pixels: Array2<u8>,
+    pub(crate) heatmap: Array2<i32>,
+    pub(crate) config: EvaluatorConfig,
+    /// Lazily computed no-skill baselines; see [`crate::baseline`].
+    pub(crate) baselines: std::sync::OnceLock<crate::baseline::BaselineScores>,
+}
+
+impl ReferenceModel {
+    /// Builds a model from an extracted reference mask, computing the
+    /// distance heatmap up front.
+    pub fn new(pixels: Array2<u8>, config: EvaluatorConfig) -> Result<Self, EvaluationError> {
+        if pixels.iter().all(|&p| p == 0) {
+            return Err(EvaluationError::EmptyReference);
+        }
+        let heatmap = flood_fill_distances(&pixels, config.max_distance);
+        Ok(Self {
+            pixels,
+            heatmap,
+            config,
+            baselines: std::sync::OnceLock::new(),
+        })
+    }
+
+    /// Number of stroke pixels in the reference.
+    pub fn pixel_count(&self) -> u64 {
+        self.pixels.iter().filter(|&&p| p != 0).count() as u64
+    }
+
+    /// The precomputed distance-to-reference heatmap.
+    pub fn heatmap(&self) -> &Array2<i32> {
+        &self.heatmap
+    }
+}
+
+/// Controls when pixel batches fed to [`StreamingEvaluator`] are folded
+/// into the score, decoupling recomputation from pointer-event frequency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum UpdatePolicy {
+    /// Integrate pixels into the score as soon as they arrive.
+    #[default]
+    Immediate,
+    /// Integrate once at least this many pixels are pending.
+    EveryNPixels(usize),
+    /// Integrate at most once per this many milliseconds.
+    EveryMs(u64),
+    /// Self-tuning: measure each update's own latency and batch enough
+    /// pixels to keep updates under this millisecond budget, so
+    /// per-stroke evaluation stays smooth on low-end devices.
+    AdaptiveMs(u64),
+    /// Integrate only on an explicit [`StreamingEvaluator::flush`].
+    Manual,
+}
+
+/// What [`StreamingEvaluator`] does with stroke pixels outside the
+/// canvas. Mis-scaled clients routinely send them, and silently
+/// dropping them makes the resulting scores quietly wrong.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClipPolicy {
+    /// Drop out-of-bounds pixels, counting them.
+    #[default]
+    Ignore,
+    /// Clamp out-of-bounds pixels to the nearest canvas edge.
+    Clamp,
+    /// Reject the whole batch with an error at the first such pixel.
+    Error,
+}
+
+/// How incoming pixel pairs are interpreted by the ingestion APIs.
+/// The native convention — `(y, x)`, origin top-left — keeps biting
+/// canvas integrators who think in `(x, y)`; declaring the space once
+/// converts every batch instead of every call site.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoordinateSpace {
+    /// `(y, x)` pairs, origin top-left, y growing downward — the
+    /// ndarray convention this crate uses internally.
+    #[default]
+    RowMajor,
+    /// `(x, y)` pairs, origin top-left, y growing downward — the
+    /// canvas/`getImageData` convention.
+    Xy,
+    /// `(x, y)` pairs, origin bottom-left, y growing upward — the
+    /// plotting convention.
+    XyUp,
+}
+
+impl CoordinateSpace {
+    /// Converts a pair in this space to native `(y, x)`. A y-up pair
+    /// above the canvas converts to an out-of-range row, so the clip
+    /// policy sees it like any other out-of-bounds pixel; debug builds
+    /// assert first, because such a pair usually means the declared
+    /// space is wrong rather than the stroke.
+    fn to_row_major(self, pair: (usize, usize), height: usize) -> (usize, usize) {
+        match self {
+            Self::RowMajor => pair,
+            Self::Xy => (pair.1, pair.0),
+            Self::XyUp => {
+                let (x, y) = pair;
+                debug_assert!(
+                    y < height,
+                    "y-up coordinate y={y} outside canvas height {height}; \
+                     is the coordinate space declared correctly?"
+                );
+                (height.wrapping_sub(1).wrapping_sub(y), x)
+            }
+        }
+    }
+}
+
+/// Coalesces a high-rate pointer stream into batches worth a heatmap
+/// update.
+///
+/// Browsers fire `pointermove` at up to 240Hz, which reaches
+/// [`StreamingEvaluator::add_observation_pixels`] as a flood of one- and
+/// two-pixel batches. The coalescer drops samples that barely moved
+/// since the last kept one and buffers the rest until a time window
+/// elapses, so the evaluator sees one batch per window instead of one
+/// per event. [`InputCoalescer::end_stroke`] flushes whatever is
+/// buffered, so lifting the pen never leaves pixels unscored.
+///
+/// Samples carry their own event timestamps, so replayed sessions
+/// coalesce exactly like live ones.
+#[derive(Debug, Clone)]
+pub struct InputCoalescer {
+    /// Buffered samples are released as one batch once this much event
+    /// time has passed since the first of them.
+    window_ms: u64,
+    /// Samples closer than this (Euclidean, in pixels) to the last kept
+    /// sample of the stroke are dropped.
+    min_distance_px: f64,
+    buffer: Vec<(usize, usize)>,
+    last_kept: Option<(usize, usize)>,
+    window_started_ms: Option<u64>,
+}
+
+impl InputCoalescer {
+    /// A coalescer releasing at most one batch per `window_ms` of event
+    /// time and dropping samples that moved less than `min_distance_px`
+    /// since the last kept one. A 16ms window batches a 240Hz stream
+    /// down to display rate.
+    pub fn new(window_ms: u64, min_distance_px: f64) -> Self {
+        Self {
+            window_ms,
+            min_distance_px,
+            buffer: Vec::new(),
+            last_kept: None,
+            window_started_ms: None,
+        }
+    }
+
+    /// Offers one pointer sample in native `(y, x)` coordinates with
+    /// its event timestamp. Returns a batch for
+    /// [`StreamingEvaluator::add_observation_pixels`] when the time
+    /// window has elapsed, `None` while the sample was buffered or
+    /// dropped.
+    pub fn push(&mut self, y: usize, x: usize, t_ms: u64) -> Option<Vec<(usize, usize)>> {
+        let moved_enough = self.last_kept.is_none_or(|(last_y, last_x)| {
+            let dy = y as f64 - last_y as f64;
+            let dx = x as f64 - last_x as f64;
+            (dy * dy + dx * dx).sqrt() >= self.min_distance_px
+        });
+        if moved_enough {
+            self.last_kept = Some((y, x));
+            self.buffer.push((y, x));
+            self.window_started_ms.get_or_insert(t_ms);
+        }
+        let started = self.window_started_ms?;
+        if t_ms.saturating_sub(started) >= self.window_ms {
+            self.window_started_ms = None;
+            Some(std::mem::take(&mut self.buffer))
+        } else {
+            None
+        }
+    }
+
+    /// The pen lifted: releases whatever is buffered (possibly nothing)
+    /// and resets the distance filter, so the next stroke's first
+    /// sample is always kept wherever it lands.
+    pub fn end_stroke(&mut self) -> Vec<(usize, usize)> {
+        self.last_kept = None;
+        self.window_started_ms = None;
+        std::mem::take(&mut self.buffer)
+    }
+
+    /// Samples buffered but not yet released.
+    pub fn pending(&self) -> usize {
+        self.buffer.len()
+    }
+}
+
+/// Upper bound on the self-tuned batch size under
+/// [`UpdatePolicy::AdaptiveMs`], so the score never lags by more than
+/// one stroke's worth of pixels.
+const MAX_ADAPTIVE_BATCH: usize = 4096;
+
+/// An event fired by the callback hooks registered with
+/// [`StreamingEvaluator::on_score_change`] and
+/// [`StreamingEvaluator::on_cell_error`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScoreEvent {
+    /// The top-5 error crossed the registered threshold, in either
+    /// direction.
+    ScoreCrossed {
+        threshold: f64,
+        previous: f64,
+        current: f64,
+    },
+    /// A grid cell's error exceeded the registered limit for the first
+    /// time.
+    CellExceeded {
+        row: usize,
+        col: usize,
+        limit: f64,
+        error: f64,
+    },
+}
+
+/// Shared callback invoked with score events on flush.
+type ScoreCallback = std::sync::Arc<dyn Fn(ScoreEvent) + Send + Sync>;
+
+/// A threshold-crossing hook on the top-5 error.
+#[derive(Clone)]
+struct ScoreHook {
+    threshold: f64,
+    callback: ScoreCallback,
+}
+
+/// A once-per-cell hook on grid cell errors.
+#[derive(Clone)]
+struct CellHook {
+    limit: f64,
+    callback: ScoreCallback,
+    /// Cells that already fired, so haptics trigger once per cell.
+    fired: Vec<Vec<bool>>,
+}
+
+/// Registered callback hooks; transient, so they are neither serialized
+/// nor shown in debug output beyond their counts.
+#[derive(Clone, Default)]
+struct Hooks {
+    score: Vec<ScoreHook>,
+    cell: Vec<CellHook>,
+}
+
+impl Hooks {
+    fn is_empty(&self) -> bool {
+        self.score.is_empty() && self.cell.is_empty()
+    }
+}
+
+impl std::fmt::Debug for Hooks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Hooks")
+            .field("score", &self.score.len())
+            .field("cell", &self.cell.len())
+            .finish()
+    }
+}
+
+/// Direction of the live score over recent updates. Lower scores are
+/// better, so a falling score is improving.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScoreTrend {
+    Improving,
+    Worsening,
+    Stable,
+}
+
+/// Bound on the retained recent raw scores used for trend detection.
+const SCORE_SAMPLE_LIMIT: usize = 64;
+
+/// Bound on the score-history ring kept for sparklines.
+const SCORE_HISTORY_LIMIT: usize = 512;
+
+/// One score-history sample, recorded per flush; see
+/// [`StreamingEvaluator::score_history`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScoreSample {
+    /// Milliseconds since the session started.
+    pub elapsed_ms: u64,
+    pub top_5_error: f64,
+    pub mean_error: f64,
+}
+
+/// Compact `(time, 10, 10)` tensor of cell errors sampled while a
+/// streaming session runs, for analytics on the order and quality in
+/// which users address different regions of the reference.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HeatTimeline {
+    /// Flushes between samples; 1 records a frame on every flush.
+    pub sample_every_flushes: usize,
+    /// Cells along each axis of a frame (the scoring grid size).
+    pub grid_size: usize,
+    /// Frames concatenated row-major: `(frame, row, col)`.
+    pub data: Vec<f64>,
+}
+
+impl HeatTimeline {
+    fn new(sample_every_flushes: usize) -> Self {
+        Self {
+            sample_every_flushes: sample_every_flushes.max(1),
+            grid_size: GRID_SIZE,
+            data: Vec::new(),
+        }
+    }
+
+    /// Number of recorded frames.
+    pub fn frames(&self) -> usize {
+        self.data.len() / (self.grid_size * self.grid_size)
+    }
+
+    /// Cell error at `(frame, row, col)`.
+    pub fn cell(&self, frame: usize, row: usize, col: usize) -> f64 {
+        self.data[(frame * self.grid_size + row) * self.grid_size + col]
+    }
+
+    fn push_frame(&mut self, cell_errors: &[Vec<f64>]) {
+        self.data.extend(cell_errors.iter().flatten());
+    }
+}
+
+/// The observation-side heatmap and the covered-reference count derived
+/// from it. Mean and top-5 error are maintained by cheap counters and
+/// never need this; sessions that only poll the live score skip it
+/// entirely, so it is built on the first coverage request and kept
+/// incremental from then on.
+#[derive(Debug, Clone)]
+struct CoverageState {
+    /// Distance from each canvas position to the nearest observation
+    /// pixel. Copy-on-write: clones share the buffer until one writes.
+    heatmap: Arc<Array2<i32>>,
+    /// Reference pixels with an observation pixel within the tolerance.
+    covered_reference: u64,
+}
+
+impl CoverageState {
+    /// Flood-fills the heatmap from every drawn pixel and counts the
+    /// covered reference pixels — the one full-canvas pass a session
+    /// pays when coverage is first requested.
+    fn compute(reference: &ReferenceModel, observation: &Array2<u8>) -> Self {
+        let heatmap = flood_fill_distances(observation, reference.config.max_distance);
+        let tolerance = reference.config.tolerance;
+        let covered_reference = reference
+            .pixels
+            .indexed_iter()
+            .filter(|&(pos, &on)| on != 0 && (0..=tolerance).contains(&heatmap[pos]))
+            .count() as u64;
+        Self {
+            heatmap: Arc::new(heatmap),
+            covered_reference,
+        }
+    }
+
+    /// Lowers the heatmap at `pos`, maintaining the covered reference
+    /// pixel counts — globally and for the contributing user. Returns
+    /// whether the distance changed.
+    fn lower_distance(
+        &mut self,
+        reference: &ReferenceModel,
+        contributors: &mut [UserContribution],
+        pos: (usize, usize),
+        new_distance: i32,
+        owner: Option<usize>,
+    ) -> bool {
+        let current = self.heatmap[pos];
+        if current >= 0 && current <= new_distance {
+            return false;
+        }
+        if reference.pixels[pos] != 0 {
+            let tolerance = reference.config.tolerance;
+            let was_covered = (0..=tolerance).contains(&current);
+            if !was_covered && new_distance <= tolerance {
+                self.covered_reference += 1;
+                if let Some(owner) = owner {
+                    contributors[owner].covered_reference += 1;
+                }
+            }
+        }
+        Arc::make_mut(&mut self.heatmap)[pos] = new_distance;
+        true
+    }
+}
+
+/// Incremental evaluator fed by live stroke pixels from the drawing app.
+///
+/// Scores stay consistent with [`crate::ImageEvaluator`]: feeding every
+/// observation pixel through `add_observation_pixels` yields the same
+/// metrics as a one-shot evaluation of the finished masks.
+#[derive(Debug, Clone)]
+pub struct StreamingEvaluator {
+    /// Shared between clones — the precomputed heatmap is the expensive
+    /// part and never changes after construction.
+    reference: Arc<ReferenceModel>,
+    /// Copy-on-write: clones share this buffer until one side writes.
+    observation: Arc<Array2<u8>>,
+    /// Lazily materialized on the first coverage request; until then
+    /// the live-score-only path pays neither the buffer nor the BFS.
+    coverage: OnceLock<CoverageState>,
+    cell_errors: Vec<Vec<f64>>,
+    error_sum: i64,
+    observation_count: u64,
+    policy: UpdatePolicy,
+    clip_policy: ClipPolicy,
+    coordinate_space: CoordinateSpace,
+    /// Pixels received outside the canvas, under any clip policy.
+    out_of_bounds_count: u64,
+    pending: Vec<(usize, usize)>,
+    /// Contributor index per pending pixel, aligned with `pending`.
+    pending_owners: Vec<Option<usize>>,
+    contributors: Vec<UserContribution>,
+    last_flush: std::time::Instant,
+    smoothing_alpha: f64,
+    smoothed_score: Option<f64>,
+    recent_scores: VecDeque<f64>,
+    session_started: std::time::Instant,
+    score_history: VecDeque<ScoreSample>,
+    heat_timeline: Option<HeatTimeline>,
+    flushes_since_heat_sample: usize,
+    adaptive_batch: usize,
+    hooks: Hooks,
+}
+
+impl StreamingEvaluator {
+    pub fn new(reference: ReferenceModel) -> Self {
+        Self::with_shared_reference(Arc::new(reference))
+    }
+
+    /// Like [`Self::new`], but sharing an already-`Arc`'d model, so a
+    /// server holding many sessions against one exercise keeps a single
+    /// copy of the reference heatmap.
+    pub fn with_shared_reference(reference: Arc<ReferenceModel>) -> Self {
+        let (height, width) = reference.pixels.dim();
+        Self {
+            reference,
+            observation: Arc::new(Array2::zeros((height, width))),
+            coverage: OnceLock::new(),
+            cell_errors: vec![vec![0.0; GRID_SIZE]; GRID_SIZE],
+            error_sum: 0,
+            observation_count: 0,
+            policy: UpdatePolicy::Immediate,
+            clip_policy: ClipPolicy::default(),
+            coordinate_space: CoordinateSpace::default(),
+            out_of_bounds_count: 0,
+            pending: Vec::new(),
+            pending_owners: Vec::new(),
+            contributors: Vec::new(),
+            last_flush: std::time::Instant::now(),
+            smoothing_alpha: 0.3,
+            smoothed_score: None,
+            recent_scores: VecDeque::new(),
+            session_started: std::time::Instant::now(),
+            score_history: VecDeque::new(),
+            heat_timeline: None,
+            flushes_since_heat_sample: 0,
+            adaptive_batch: 1,
+            hooks: Hooks::default(),
+        }
+    }
+
+    /// Registers a callback fired whenever the live top-5 error crosses
+    /// `threshold` in either direction, so embedders can trigger
+    /// haptics or sounds without polling. Hooks run on the flush that
+    /// moved the score and are not carried through serialization.
+    pub fn on_score_change(
+        &mut self,
+        threshold: f64,
+        callback: impl Fn(ScoreEvent) + Send + Sync + 'static,
+    ) {
+        self.hooks.score.push(ScoreHook {
+            threshold,
+            callback: std::sync::Arc::new(callback),
+        });
+    }
+
+    /// Registers a callback fired the first time each grid cell's error
+    /// exceeds `limit`.
+    pub fn on_cell_error(
+        &mut self,
+        limit: f64,
+        callback: impl Fn(ScoreEvent) + Send + Sync + 'static,
+    ) {
+        self.hooks.cell.push(CellHook {
+            limit,
+            callback: std::sync::Arc::new(callback),
+            fired: vec![vec![false; GRID_SIZE]; GRID_SIZE],
+        });
+    }
+
+    /// Starts recording a [`HeatTimeline`] frame every
+    /// `sample_every_flushes` flushes. Calling again restarts recording.
+    pub fn enable_heat_timeline(&mut self, sample_every_flushes: usize) {
+        self.heat_timeline = Some(HeatTimeline::new(sample_every_flushes));
+        self.flushes_since_heat_sample = 0;
+    }
+
+    /// The recorded heat-over-time tensor, if recording was enabled.
+    pub fn heat_timeline(&self) -> Option<&HeatTimeline> {
+        self.heat_timeline.as_ref()
+    }
+
+    /// Sets the exponential-moving-average factor for the smoothed score.
+    /// Higher values follow the raw score more closely.
+    pub fn set_smoothing_alpha(&mut self, alpha: f64) {
+        self.smoothing_alpha = alpha.clamp(0.0, 1.0);
+    }
+
+    /// The smoothed top-5 error, updated on every flush. Falls back to
+    /// the raw score before the first update.
+    pub fn smoothed_score(&self) -> f64 {
+        self.smoothed_score.unwrap_or_else(|| self.current_score())
+    }
+
+    /// Compares the oldest and newest of the last `updates` raw score
+    /// samples to indicate which way the score is moving.
+    pub fn score_trend(&self, updates: usize) -> ScoreTrend {
+        let samples: Vec<f64> = self
+            .recent_scores
+            .iter()
+            .rev()
+            .take(updates.max(2))
+            .copied()
+            .collect();
+        let (Some(&newest), Some(&oldest)) = (samples.first(), samples.last()) else {
+            return ScoreTrend::Stable;
+        };
+        const EPSILON: f64 = 1e-9;
+        if newest < oldest - EPSILON {
+            ScoreTrend::Improving
+        } else if newest > oldest + EPSILON {
+            ScoreTrend::Worsening
+        } else {
+            ScoreTrend::Stable
+        }
+    }
+
+    fn record_score_sample(&mut self) {
+        let raw = self.current_score();
+        self.smoothed_score = Some(match self.smoothed_score {
+            Some(previous) => self.smoothing_alpha * raw + (1.0 - self.smoothing_alpha) * previous,
+            None => raw,
+        });
+        self.recent_scores.push_back(raw);
+        if self.recent_scores.len() > SCORE_SAMPLE_LIMIT {
+            self.recent_scores.pop_front();
+        }
+        let mean_error = if self.observation_count == 0 {
+            0.0
+        } else {
+            self.error_sum as f64
+                / self.observation_count as f64
+                / self.reference.config.normalization.mean_error_divisor
+        };
+        self.score_history.push_back(ScoreSample {
+            elapsed_ms: self.session_started.elapsed().as_millis() as u64,
+            top_5_error: raw,
+            mean_error,
+        });
+        if self.score_history.len() > SCORE_HISTORY_LIMIT {
+            self.score_history.pop_front();
+        }
+    }
+
+    /// The retained score history downsampled to at most `n` evenly
+    /// spaced samples, oldest first and always ending on the newest —
+    /// sized for a sparkline widget, so the app does not have to track
+    /// this itself. One sample is recorded per flush; the underlying
+    /// ring holds the most recent [`SCORE_HISTORY_LIMIT`] of them.
+    pub fn score_history(&self, n: usize) -> Vec<ScoreSample> {
+        let len = self.score_history.len();
+        if n == 0 || len == 0 {
+            return Vec::new();
+        }
+        if len <= n {
+            return self.score_history.iter().copied().collect();
+        }
+        if n == 1 {
+            return vec![self.score_history[len - 1]];
+        }
+        (0..n)
+            .map(|i| self.score_history[i * (len - 1) / (n - 1)])
+            .collect()
+    }
+
+    /// Chooses when ingested pixels are folded into the score. Pixels
+    /// already pending stay buffered until the next flush trigger.
+    pub fn set_update_policy(&mut self, policy: UpdatePolicy) {
+        self.policy = policy;
+    }
+
+    /// Chooses what happens to stroke pixels outside the canvas.
+    pub fn set_clip_policy(&mut self, policy: ClipPolicy) {
+        self.clip_policy = policy;
+    }
+
+    /// Declares the coordinate convention of subsequent pixel batches;
+    /// see [`CoordinateSpace`]. Pixels already ingested or pending are
+    /// not reinterpreted.
+    pub fn set_coordinate_space(&mut self, space: CoordinateSpace) {
+        self.coordinate_space = space;
+    }
+
+    /// Pixels received outside the canvas so far. A nonzero count under
+    /// [`ClipPolicy::Ignore`] is the telltale of mis-scaled input.
+    pub fn out_of_bounds_count(&self) -> u64 {
+        self.out_of_bounds_count
+    }
+
+    /// Whether pixels are buffered but not yet reflected in the score.
+    pub fn is_dirty(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Folds all pending pixels into the score immediately.
+    pub fn flush(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let previous_score = self.current_score();
+        let pending = std::mem::take(&mut self.pending);
+        let owners = std::mem::take(&mut self.pending_owners);
+        let started = std::time::Instant::now();
+        self.ingest(&pending, &owners);
+        if let UpdatePolicy::AdaptiveMs(budget_ms) = self.policy {
+            self.tune_adaptive_batch(started.elapsed().as_secs_f64() * 1000.0, budget_ms as f64);
+        }
+        self.last_flush = std::time::Instant::now();
+        self.record_score_sample();
+        if let Some(timeline) = &mut self.heat_timeline {
+            self.flushes_since_heat_sample += 1;
+            if self.flushes_since_heat_sample >= timeline.sample_every_flushes {
+                timeline.push_frame(&self.cell_errors);
+                self.flushes_since_heat_sample = 0;
+            }
+        }
+        self.fire_hooks(previous_score);
+    }
+
+    /// Runs the registered hooks against the score movement of one
+    /// flush. Hooks are moved out for the duration of the calls so the
+    /// callbacks cannot alias the evaluator.
+    fn fire_hooks(&mut self, previous: f64) {
+        if self.hooks.is_empty() {
+            return;
+        }
+        let current = self.current_score();
+        let mut hooks = std::mem::take(&mut self.hooks);
+        for hook in &hooks.score {
+            let crossed_up = previous < hook.threshold && current >= hook.threshold;
+            let crossed_down = previous >= hook.threshold && current < hook.threshold;
+            if crossed_up || crossed_down {
+                (hook.callback)(ScoreEvent::ScoreCrossed {
+                    threshold: hook.threshold,
+                    previous,
+                    current,
+                });
+            }
+        }
+        for hook in &mut hooks.cell {
+            for (row, errors) in self.cell_errors.iter().enumerate() {
+                for (col, &error) in errors.iter().enumerate() {
+                    if error > hook.limit && !hook.fired[row][col] {
+                        hook.fired[row][col] = true;
+                        (hook.callback)(ScoreEvent::CellExceeded {
+                            row,
+                            col,
+                            limit: hook.limit,
+                            error,
+                        });
+                    }
+                }
+            }
+        }
+        self.hooks = hooks;
+    }
+
+    pub fn reference(&self) -> &ReferenceModel {
+        &self.reference
+    }
+
+    /// A cheap copy-on-write snapshot of the session, suitable for
+    /// handing to a background persistence thread: the reference and
+    /// the canvas buffers are shared with the live session, and either
+    /// side copies a buffer only when it next writes to it. Hooks stay
+    /// with the live session, like with serialization.
+    pub fn snapshot(&self) -> Self {
+        Self {
+            hooks: Hooks::default(),
+            ..self.clone()
+        }
+    }
+
+    /// Number of distinct observation pixels ingested so far.
+    pub fn observation_count(&self) -> u64 {
+        self.observation_count
+    }
+
+    /// Ingests newly drawn pixels as `(y, x)` canvas coordinates.
+    /// Already-drawn pixels are ignored; out-of-bounds pixels are
+    /// counted and handled per the clip policy, and only
+    /// [`ClipPolicy::Error`] makes this fail. Depending on the update
+    /// policy, pixels may stay buffered until the next flush.
+    pub fn add_observation_pixels(
+        &mut self,
+        pixels: &[(usize, usize)],
+    ) -> Result<(), EvaluationError> {
+        self.queue_pixels(None, pixels)
+    }
+
+    /// [`Self::add_observation_pixels`] tagged with the drawing user, so
+    /// collaborative sessions can report per-user contributions.
+    pub fn add_observation_pixels_for(
+        &mut self,
+        user_id: &str,
+        pixels: &[(usize, usize)],
+    ) -> Result<(), EvaluationError> {
+        // Per-user coverage is attributed stroke by stroke, which needs
+        // the incremental path: collaborative sessions keep the
+        // coverage state live rather than lazy.
+        self.coverage_state();
+        let owner = self.contributor_index(user_id);
+        self.queue_pixels(Some(owner), pixels)
+    }
+
+    /// Bulk-initializes the observation from a saved canvas mask, so a
+    /// resumed session does not replay every stroke: the mask replaces
+    /// anything ingested so far and its heatmap is rebuilt in full,
+    /// bypassing the update policy. Per-user attribution is cleared —
+    /// a saved canvas carries no ownership. The mask must match the
+    /// reference dimensions.
+    pub fn set_observation_from_array(
+        &mut self,
+        observation: &Array2<u8>,
+    ) -> Result<(), EvaluationError> {
+        let (height, width) = self.observation.dim();
+        let (observation_height, observation_width) = observation.dim();
+        if (observation_height, observation_width) != (height, width) {
+            return Err(EvaluationError::InvalidDimensions {
+                expected_width: width,
+                expected_height: height,
+                width: observation_width,
+                height: observation_height,
+            });
+        }
+        Arc::make_mut(&mut self.observation).fill(0);
+        self.coverage = OnceLock::new();
+        self.cell_errors = vec![vec![0.0; GRID_SIZE]; GRID_SIZE];
+        self.error_sum = 0;
+        self.observation_count = 0;
+        self.out_of_bounds_count = 0;
+        self.pending.clear();
+        self.pending_owners.clear();
+        self.contributors.clear();
+        let pixels: Vec<(usize, usize)> = observation
+            .indexed_iter()
+            .filter(|&(_, &on)| on != 0)
+            .map(|(pos, _)| pos)
+            .collect();
+        self.ingest(&pixels, &[]);
+        Ok(())
+    }
+
+    /// Swaps in a new reference mid-session, for multi-step tutorials
+    /// where the reference changes at each step but the user keeps
+    /// drawing on the same canvas. Drawn pixels are retained and
+    /// rescored: the error counters and the scoring grid are rebuilt
+    /// against the new heatmap, the coverage state is rebuilt on the
+    /// next request, score smoothing and trend restart, and cell hooks
+    /// may fire again. Per-user contribution sums keep the values
+    /// recorded as the pixels were drawn, so they accumulate across
+    /// steps. The new reference must match the canvas dimensions.
+    pub fn swap_reference(&mut self, reference: ReferenceModel) -> Result<(), EvaluationError> {
+        self.swap_shared_reference(Arc::new(reference))
+    }
+
+    /// Like [`Self::swap_reference`], but sharing an already-`Arc`'d
+    /// model across the sessions of one exercise step.
+    pub fn swap_shared_reference(
+        &mut self,
+        reference: Arc<ReferenceModel>,
+    ) -> Result<(), EvaluationError> {
+        let (height, width) = self.observation.dim();
+        let (reference_height, reference_width) = reference.pixels.dim();
+        if (reference_height, reference_width) != (height, width) {
+            return Err(EvaluationError::InvalidDimensions {
+                expected_width: width,
+                expected_height: height,
+                width: reference_width,
+                height: reference_height,
+            });
+        }
+        // Pixels still buffered were drawn against the outgoing
+        // reference; score them under it before the new one takes over.
+        self.flush();
+        self.reference = reference;
+        self.error_sum = 0;
+        self.cell_errors = vec![vec![0.0; GRID_SIZE]; GRID_SIZE];
+        let cell_height = height.div_ceil(GRID_SIZE);
+        let cell_width = width.div_ceil(GRID_SIZE);
+        for ((y, x), &on) in self.observation.indexed_iter() {
+            if on == 0 {
+                continue;
+            }
+            let distance = self.reference.heatmap[(y, x)].max(0);
+            self.error_sum += i64::from(distance);
+            let cell = &mut self.cell_errors[y / cell_height][x / cell_width];
+            *cell = cell.max(f64::from(distance));
+        }
+        // The observation-side heatmap depends on the new max_distance
+        // and the covered count on the new reference pixels; both are
+        // rebuilt lazily, like on a fresh session.
+        self.coverage = OnceLock::new();
+        self.smoothed_score = None;
+        self.recent_scores.clear();
+        for hook in &mut self.hooks.cell {
+            hook.fired = vec![vec![false; GRID_SIZE]; GRID_SIZE];
+        }
+        Ok(())
+    }
+
+    /// Per-user contribution metrics, in order of first appearance. A
+    /// reference pixel counts for whichever user's stroke first brought
+    /// it within the tolerance radius.
+    pub fn user_contributions(&self) -> Vec<UserContributionReport> {
+        let normalization = self.reference.config.normalization;
+        let reference_count = self.reference.pixel_count();
+        self.contributors
+            .iter()
+            .map(|contribution| UserContributionReport {
+                user_id: contribution.user_id.clone(),
+                pixels_added: contribution.pixels_added,
+                mean_error: if contribution.pixels_added == 0 {
+                    0.0
+                } else {
+                    contribution.error_sum as f64
+                        / contribution.pixels_added as f64
+                        / normalization.mean_error_divisor
+                },
+                coverage: if reference_count == 0 {
+                    0.0
+                } else {
+                    contribution.covered_reference as f64 / reference_count as f64
+                },
+            })
+            .collect()
+    }
+
+    fn contributor_index(&mut self, user_id: &str) -> usize {
+        if let Some(index) = self
+            .contributors
+            .iter()
+            .position(|contribution| contribution.user_id == user_id)
+        {
+            return index;
+        }
+        self.contributors.push(UserContribution {
+            user_id: user_id.to_string(),
+            pixels_added: 0,
+            error_sum: 0,
+            covered_reference: 0,
+        });
+        self.contributors.len() - 1
+    }
+
+    fn queue_pixels(
+        &mut self,
+        owner: Option<usize>,
+        pixels: &[(usize, usize)],
+    ) -> Result<(), EvaluationError> {
+        let (height, width) = self.observation.dim();
+        for &pair in pixels {
+            let (y, x) = self.coordinate_space.to_row_major(pair, height);
+            if y < height && x < width {
+                self.pending.push((y, x));
+                self.pending_owners.push(owner);
+                continue;
+            }
+            self.out_of_bounds_count += 1;
+            match self.clip_policy {
+                ClipPolicy::Ignore => {}
+                ClipPolicy::Clamp => {
+                    self.pending.push((y.min(height - 1), x.min(width - 1)));
+                    self.pending_owners.push(owner);
+                }
+                ClipPolicy::Error => {
+                    return Err(EvaluationError::OutOfBounds {
+                        x,
+                        y,
+                        width,
+                        height,
+                    })
+                }
+            }
+        }
+        let should_flush = match self.policy {
+            UpdatePolicy::Immediate => true,
+            UpdatePolicy::EveryNPixels(n) => self.pending.len() >= n,
+            UpdatePolicy::EveryMs(ms) => self.last_flush.elapsed().as_millis() as u64 >= ms,
+            UpdatePolicy::AdaptiveMs(_) => self.pending.len() >= self.adaptive_batch,
+            UpdatePolicy::Manual => false,
+        };
+        if should_flush {
+            self.flush();
+        }
+        Ok(())
+    }
+
+    /// Grows the batch when an update blew its budget and shrinks it
+    /// back once updates run comfortably under it.
+    fn tune_adaptive_batch(&mut self, elapsed_ms: f64, budget_ms: f64) {
+        if elapsed_ms > budget_ms {
+            self.adaptive_batch = (self.adaptive_batch * 2).min(MAX_ADAPTIVE_BATCH);
+        } else if elapsed_ms < budget_ms / 2.0 {
+            self.adaptive_batch = (self.adaptive_batch / 2).max(1);
+        }
+    }
+
+    fn ingest(&mut self, pixels: &[(usize, usize)], owners: &[Option<usize>]) {
+        let (height, width) = self.observation.dim();
+        let cell_height = height.div_ceil(GRID_SIZE);
+        let cell_width = width.div_ceil(GRID_SIZE);
+        let mut added = Vec::new();
+        for (index, &(y, x)) in pixels.iter().enumerate() {
+            // Pending pixels passed the clip policy, but states restored
+            // from before it may still carry out-of-bounds entries.
+            if y >= height || x >= width || self.observation[(y, x)] != 0 {
+                continue;
+            }
+            let owner = owners.get(index).copied().flatten();
+            Arc::make_mut(&mut self.observation)[(y, x)] = 1;
+            let distance = self.reference.heatmap[(y, x)].max(0);
+            self.error_sum += i64::from(distance);
+            self.observation_count += 1;
+            if let Some(owner) = owner {
+                let contribution = &mut self.contributors[owner];
+                contribution.pixels_added += 1;
+                contribution.error_sum += i64::from(distance);
+            }
+            let cell = &mut self.cell_errors[y / cell_height][x / cell_width];
+            *cell = cell.max(f64::from(distance));
+            added.push(((y, x), owner));
+        }
+        // The coverage state is only maintained once someone has asked
+        // for coverage; until then the new pixels are folded in by the
+        // flood fill that materializes it.
+        let Some(coverage) = self.coverage.get_mut() else {
+            return;
+        };
+        let mut queue = VecDeque::new();
+        for (pos, owner) in added {
+            if coverage.lower_distance(&self.reference, &mut self.contributors, pos, 0, owner) {
+                queue.push_back((pos, owner));
+            }
+        }
+        // Relax the observation heatmap outward from the new pixels;
+        // distances only ever decrease, so this terminates quickly.
+        let max_distance = self.reference.config.max_distance.unwrap_or(i32::MAX);
+        while let Some(((y, x), owner)) = queue.pop_front() {
+            let next = coverage.heatmap[(y, x)] + 1;
+            if next > max_distance {
+                continue;
+            }
+            for (dy, dx) in [
+                (-1i32, -1i32),
+                (-1, 0),
+                (-1, 1),
+                (0, -1),
+                (0, 1),
+                (1, -1),
+                (1, 0),
+                (1, 1),
+            ] {
+                let ny = y as i32 + dy;
+                let nx = x as i32 + dx;
+                if ny < 0 || nx < 0 || ny >= height as i32 || nx >= width as i32 {
+                    continue;
+                }
+                let pos = (ny as usize, nx as usize);
+                if coverage.lower_distance(&self.reference, &mut self.contributors, pos, next, owner)
+                {
+                    queue.push_back((pos, owner));
+                }
+            }
+        }
+    }
+
+    /// The live top-5 error, cheap enough to poll on every repaint.
+    pub fn current_score(&self) -> f64 {
+        top_5_from_grid(
+            &self.cell_errors,
+            self.reference.config.normalization.top_5_divisor,
+        )
+    }
+
+    /// Fraction of reference pixels that already have an observation
+    /// pixel within the tolerance radius — a progress estimate that is
+    /// independent of the error score, suitable for a live progress
+    /// bar. The first call pays the flood fill that materializes the
+    /// coverage state; later calls read an incrementally maintained
+    /// count.
+    pub fn completion_estimate(&self) -> f64 {
+        let reference_count = self.reference.pixel_count();
+        if reference_count == 0 {
+            0.0
+        } else {
+            self.coverage_state().covered_reference as f64 / reference_count as f64
+        }
+    }
+
+    /// The lazily built coverage state; see [`CoverageState`].
+    fn coverage_state(&self) -> &CoverageState {
+        self.coverage
+            .get_or_init(|| CoverageState::compute(&self.reference, &self.observation))
+    }
+
+    /// Full metrics for the observation as drawn so far.
+    pub fn get_full_evaluation(&self) -> ErrorMetrics {
+        let normalization = self.reference.config.normalization;
+        let mean_error = if self.observation_count == 0 {
+            0.0
+        } else {
+            self.error_sum as f64
+                / self.observation_count as f64
+                / normalization.mean_error_divisor
+        };
+        let (height, width) = self.observation.dim();
+        let diagonal = ((width * width + height * height) as f64).sqrt();
+        let normalized = (diagonal > 0.0).then(|| crate::metrics::NormalizedMetrics {
+            canvas_diagonal: diagonal,
+            mean_error_pct: if self.observation_count == 0 {
+                0.0
+            } else {
+                100.0 * self.error_sum as f64 / self.observation_count as f64 / diagonal
+            },
+            top_5_error_pct: 100.0 * top_5_from_grid(&self.cell_errors, 5.0) / diagonal,
+        });
+        ErrorMetrics {
+            mean_error,
+            top_5_error: top_5_from_grid(&self.cell_errors, normalization.top_5_divisor),
+            coverage: self.completion_estimate(),
+            grid: self.cell_errors.clone(),
+            normalization,
+            cell_tolerance_multipliers: None,
+            normalized,
+        }
+    }
+
+    /// Predicts the final normalized-skill range for this session, for
+    /// UI copy like "on track for 0.85+".
+    ///
+    /// `low` assumes the user stops now, `high` that the remaining
+    /// reference gets traced perfectly, and `expected` interpolates by
+    /// the accuracy shown so far. All three are on the
+    /// [`crate::baseline::normalized_skill`] scale.
+    pub fn projected_score(&self) -> ScoreProjection {
+        let metrics = self.get_full_evaluation();
+        let baselines = *self.reference.baselines();
+        let low = crate::baseline::normalized_skill(&metrics, &baselines);
+        let mut optimistic = metrics.clone();
+        optimistic.coverage = 1.0;
+        let high = crate::baseline::normalized_skill(&optimistic, &baselines);
+        let accuracy = 1.0 / (1.0 + metrics.mean_error);
+        ScoreProjection {
+            low,
+            expected: low + (high - low) * accuracy,
+            high,
+            completion: metrics.coverage,
+        }
+    }
+
+    /// The grid-free sliding-window worst-region report for the
+    /// observation as drawn so far, reported alongside the grid top-5
+    /// for comparison; see [`crate::metrics::sliding_worst_regions`].
+    pub fn sliding_worst_regions(&self) -> crate::metrics::SlidingWorstRegions {
+        crate::metrics::sliding_worst_regions(
+            &self.reference.heatmap,
+            &self.observation,
+            self.reference.config.normalization,
+        )
+    }
+
+    /// Metrics restricted to a rectangle of the canvas, for a zoomed-in
+    /// UI showing accuracy of the visible viewport only. The rectangle
+    /// is clamped to the canvas; pixels still buffered by the update
+    /// policy are not included until the next flush.
+    pub fn evaluate_tile(&self, x: usize, y: usize, width: usize, height: usize) -> TileMetrics {
+        let (canvas_height, canvas_width) = self.observation.dim();
+        let x_end = (x + width).min(canvas_width);
+        let y_end = (y + height).min(canvas_height);
+        let normalization = self.reference.config.normalization;
+        let tolerance = self.reference.config.tolerance;
+        let coverage = self.coverage_state();
+        let mut error_sum = 0i64;
+        let mut worst_error = 0i32;
+        let mut observation_pixels = 0u64;
+        let mut reference_pixels = 0u64;
+        let mut covered = 0u64;
+        for row in y.min(y_end)..y_end {
+            for column in x.min(x_end)..x_end {
+                let pos = (row, column);
+                if self.observation[pos] != 0 {
+                    let distance = self.reference.heatmap[pos].max(0);
+                    error_sum += i64::from(distance);
+                    worst_error = worst_error.max(distance);
+                    observation_pixels += 1;
+                }
+                if self.reference.pixels[pos] != 0 {
+                    reference_pixels += 1;
+                    if (0..=tolerance).contains(&coverage.heatmap[pos]) {
+                        covered += 1;
+                    }
+                }
+            }
+        }
+        let mean_error = if observation_pixels == 0 {
+            0.0
+        } else {
+            error_sum as f64 / observation_pixels as f64 / normalization.mean_error_divisor
+        };
+        let coverage = if reference_pixels == 0 {
+            0.0
+        } else {
+            covered as f64 / reference_pixels as f64
+        };
+        TileMetrics {
+            mean_error,
+            worst_error: f64::from(worst_error),
+            coverage,
+            observation_pixels,
+            reference_pixels,
+        }
+    }
+
+    /// Reconstructs the current observation raster as an RGBA image in
+    /// the app's export convention, so a server can archive exactly
+    /// what was scored. Pixels still buffered by the update policy are
+    /// not included until the next flush.
+    pub fn render_observation(&self) -> image::RgbaImage {
+        crate::render::render_mask(&self.observation, self.reference.config.transparent_background)
+    }
+
+    /// [`Self::render_observation`] laid out as a full composite:
+    /// reference pane, pane gap, observation pane — the same geometry
+    /// [`crate::ImageEvaluator`] extracts panes from.
+    pub fn render_composite(&self) -> image::RgbaImage {
+        let config = &self.reference.config;
+        let transparent = config.transparent_background;
+        let reference = crate::render::render_mask(&self.reference.pixels, transparent);
+        let observation = self.render_observation();
+        let (height, width) = self.observation.dim();
+        let background = if transparent {
+            image::Rgba([0, 0, 0, 0])
+        } else {
+            image::Rgba([255, 255, 255, 255])
+        };
+        let mut composite = image::RgbaImage::from_pixel(
+            (width * 2 + config.pane_gap) as u32,
+            height as u32,
+            background,
+        );
+        for (x, y, &pixel) in reference.enumerate_pixels() {
+            composite.put_pixel(x, y, pixel);
+        }
+        let offset = (width + config.pane_gap) as u32;
+        for (x, y, &pixel) in observation.enumerate_pixels() {
+            composite.put_pixel(x + offset, y, pixel);
+        }
+        composite
+    }
+
+    /// Distance from canvas position `(x, y)` to the nearest reference
+    /// stroke, read from the cached heatmap. `None` outside the canvas.
+    /// Lets a UI answer "this stroke is 12px off" on hover without
+    /// exporting the whole heatmap.
+    pub fn error_at(&self, x: usize, y: usize) -> Option<i32> {
+        let (canvas_height, canvas_width) = self.observation.dim();
+        if x >= canvas_width || y >= canvas_height {
+            return None;
+        }
+        Some(self.reference.heatmap[(y, x)].max(0))
+    }
+
+    /// [`Self::error_at`] sampled along a polyline of `(x, y)` points,
+    /// e.g. the recorded positions of one stroke.
+    pub fn errors_along_path(&self, path: &[(usize, usize)]) -> Vec<Option<i32>> {
+        path.iter().map(|&(x, y)| self.error_at(x, y)).collect()
+    }
+
+    /// Snapshots the full session state for persistence.
+    pub fn to_serialized_state(&self) -> StreamingEvaluatorState {
+        StreamingEvaluatorState {
+            config: self.reference.config.clone(),
+            reference_pixels: self.reference.pixels.iter().copied().collect(),
+            reference_heatmap: SerializableHeatmap::from_array(&self.reference.heatmap),
+            observation_pixels: self.observation.iter().copied().collect(),
+            observation_heatmap: SerializableHeatmap::from_array(&self.coverage_state().heatmap),
+            cell_errors: self.cell_errors.clone(),
+            error_sum: self.error_sum,
+            observation_count: self.observation_count,
+            covered_reference: self.coverage_state().covered_reference,
+            policy: self.policy,
+            clip_policy: self.clip_policy,
+            coordinate_space: self.coordinate_space,
+            out_of_bounds_count: self.out_of_bounds_count,
+            pending_pixels: self.pending.clone(),
+            pending_owners: self.pending_owners.clone(),
+            contributors: self.contributors.clone(),
+            smoothing_alpha: self.smoothing_alpha,
+            smoothed_score: self.smoothed_score,
+            recent_scores: self.recent_scores.iter().copied().collect(),
+            score_history: self.score_history.iter().copied().collect(),
+            heat_timeline: self.heat_timeline.clone(),
+            flushes_since_heat_sample: self.flushes_since_heat_sample,
+            adaptive_batch: self.adaptive_batch,
+        }
+    }
+
+    /// Restores a session persisted with [`Self::to_serialized_state`].
+    pub fn from_serialized_state(state: StreamingEvaluatorState) -> Result<Self, EvaluationError> {
+        let (height, width) = (state.config.canvas_height, state.config.canvas_width);
+        let expected = height * width;
+        if state.reference_pixels.len() != expected || state.observation_pixels.len() != expected {
+            return Err(EvaluationError::InvalidState(format!(
+                "pixel masks do not match a {width}x{height} canvas"
+            )));
+        }
+        let reference_pixels = Array2::from_shape_vec((height, width), state.reference_pixels)
+            .map_err(|e| EvaluationError::InvalidState(e.to_string()))?;
+        let observation = Array2::from_shape_vec((height, width), state.observation_pixels)
+            .map_err(|e| EvaluationError::InvalidState(e.to_string()))?;
+        Ok(Self {
+            reference: Arc::new(ReferenceModel {
+                pixels: reference_pixels,
+                heatmap: state.reference_heatmap.to_array()?,
+                config: state.config,
+                baselines: std::sync::OnceLock::new(),
+            }),
+            observation: Arc::new(observation),
+            coverage: OnceLock::from(CoverageState {
+                heatmap: Arc::new(state.observation_heatmap.to_array()?),
+                covered_reference: state.covered_reference,
+            }),
+            cell_errors: state.cell_errors,
+            error_sum: state.error_sum,
+            observation_count: state.observation_count,
+            policy: state.policy,
+            clip_policy: state.clip_policy,
+            coordinate_space: state.coordinate_space,
+            out_of_bounds_count: state.out_of_bounds_count,
+            pending_owners: {
+                // States written before attribution have no owners.
+                let mut owners = state.pending_owners;
+                owners.resize(state.pending_pixels.len(), None);
+                owners
+            },
+            pending: state.pending_pixels,
+            contributors: state.contributors,
+            last_flush: std::time::Instant::now(),
+            smoothing_alpha: state.smoothing_alpha,
+            smoothed_score: state.smoothed_score,
+            recent_scores: state.recent_scores.into(),
+            session_started: {
+                // Restart the clock where the saved history left off, so
+                // elapsed times stay monotonic across restores.
+                let elapsed = state.score_history.last().map_or(0, |s| s.elapsed_ms);
+                std::time::Instant::now() - std::time::Duration::from_millis(elapsed)
+            },
+            score_history: state.score_history.into(),
+            heat_timeline: state.heat_timeline,
+            flushes_since_heat_sample: state.flushes_since_heat_sample,
+            adaptive_batch: state.adaptive_batch,
+            hooks: Hooks::default(),
+        })
+    }
+}
+
+/// Predicted final score range from
+/// [`StreamingEvaluator::projected_score`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScoreProjection {
+    /// Final normalized skill if the user stops now.
+    pub low: f64,
+    /// Likely final skill at the accuracy shown so far.
+    pub expected: f64,
+    /// Final skill if the rest is traced perfectly.
+    pub high: f64,
+    /// Fraction of the reference already covered.
+    pub completion: f64,
+}
+
+/// Raw per-user tallies kept while a collaborative session runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserContribution {
+    pub user_id: String,
+    pub pixels_added: u64,
+    /// Sum of raw pixel distances this user's pixels contributed.
+    pub error_sum: i64,
+    /// Reference pixels first covered by this user's strokes.
+    pub covered_reference: u64,
+}
+
+/// Normalized per-user metrics from
+/// [`StreamingEvaluator::user_contributions`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserContributionReport {
+    pub user_id: String,
+    pub pixels_added: u64,
+    /// Mean error of this user's pixels, on the same scale as
+    /// [`ErrorMetrics::mean_error`].
+    pub mean_error: f64,
+    /// Fraction of all reference pixels this user covered first.
+    pub coverage: f64,
+}
+
+/// Viewport-restricted metrics from [`StreamingEvaluator::evaluate_tile`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TileMetrics {
+    /// Mean distance-to-reference of the observation pixels inside the
+    /// tile, on the same scale as [`ErrorMetrics::mean_error`].
+    pub mean_error: f64,
+    /// Worst raw pixel distance inside the tile.
+    pub worst_error: f64,
+    /// Fraction of the tile's reference pixels already covered.
+    pub coverage: f64,
+    pub observation_pixels: u64,
+    pub reference_pixels: u64,
+}
+
+/// Flattened row-major heatmap layout used in serialized session state.
+///
+/// Distances are spatially smooth, so the compressed form stores the
+/// row-major delta stream run-length encoded as `(delta, run)` pairs —
+/// an order of magnitude smaller than one JSON number per pixel. States
+/// written before compression carry `compressed: false` and decode as
+/// the raw layout.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SerializableHeatmap {
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<i32>,
+    #[serde(default)]
+    pub compressed: bool,
+}
+
+impl SerializableHeatmap {
+    pub(crate) fn from_array(array: &Array2<i32>) -> Self {
+        let (height, width) = array.dim();
+        let mut data = Vec::new();
+        let mut previous = 0i32;
+        let mut run: Option<(i32, i32)> = None;
+        for &value in array {
+            let delta = value - previous;
+            previous = value;
+            run = Some(match run {
+                Some((d, count)) if d == delta && count < i32::MAX => (d, count + 1),
+                Some((d, count)) => {
+                    data.push(d);
+                    data.push(count);
+                    (delta, 1)
+                }
+                None => (delta, 1),
+            });
+        }
+        if let Some((d, count)) = run {
+            data.push(d);
+            data.push(count);
+        }
+        Self {
+            width,
+            height,
+            data,
+            compressed: true,
+        }
+    }
+
+    pub(crate) fn to_array(&self) -> Result<Array2<i32>, EvaluationError> {
+        let data = if self.compressed {
+            self.decode_runs()?
+        } else {
+            self.data.clone()
+        };
+        Array2::from_shape_vec((self.height, self.width), data)
+            .map_err(|e| EvaluationError::InvalidState(e.to_string()))
+    }
+
+    /// Expands the `(delta, run)` pairs back into per-pixel distances.
+    fn decode_runs(&self) -> Result<Vec<i32>, EvaluationError> {
+        if !self.data.len().is_multiple_of(2) {
+            return Err(EvaluationError::InvalidState(
+                "compressed heatmap has a dangling run pair".to_string(),
+            ));
+        }
+        let expected = self.width * self.height;
+        let mut values = Vec::with_capacity(expected);
+        let mut previous = 0i32;
+        for pair in self.data.chunks(2) {
+            let (delta, run) = (pair[0], pair[1]);
+            if run <= 0 {
+                return Err(EvaluationError::InvalidState(format!(
+                    "compressed heatmap has a non-positive run of {run}"
+                )));
+            }
+            for _ in 0..run {
+                previous += delta;
+                values.push(previous);
+            }
+            if values.len() > expected {
+                break;
+            }
+        }
+        if values.len() != expected {
+            return Err(EvaluationError::InvalidState(format!(
+                "compressed heatmap decodes to {} values, expected {expected}",
+                values.len()
+            )));
+        }
+        Ok(values)
+    }
+}
+
+/// Serialized snapshot of a streaming session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamingEvaluatorState {
+    pub config: EvaluatorConfig,
+    pub reference_pixels: Vec<u8>,
+    pub reference_heatmap: SerializableHeatmap,
+    pub observation_pixels: Vec<u8>,
+    pub observation_heatmap: SerializableHeatmap,
+    pub cell_errors: Vec<Vec<f64>>,
+    pub error_sum: i64,
+    pub observation_count: u64,
+    pub covered_reference: u64,
+    #[serde(default)]
+    pub policy: UpdatePolicy,
+    #[serde(default)]
+    pub clip_policy: ClipPolicy,
+    #[serde(default)]
+    pub coordinate_space: CoordinateSpace,
+    #[serde(default)]
+    pub out_of_bounds_count: u64,
+    #[serde(default)]
+    pub pending_pixels: Vec<(usize, usize)>,
+    #[serde(default)]
+    pub pending_owners: Vec<Option<usize>>,
+    #[serde(default)]
+    pub contributors: Vec<UserContribution>,
+    #[serde(default = "default_smoothing_alpha")]
+    pub smoothing_alpha: f64,
+    #[serde(default)]
+    pub smoothed_score: Option<f64>,
+    #[serde(default)]
+    pub recent_scores: Vec<f64>,
+    #[serde(default)]
+    pub score_history: Vec<ScoreSample>,
+    #[serde(default)]
+    pub heat_timeline: Option<HeatTimeline>,
+    #[serde(default)]
+    pub flushes_since_heat_sample: usize,
+    #[serde(default = "default_adaptive_batch")]
+    pub adaptive_batch: usize,
+}
+
+fn default_adaptive_batch() -> usize {
+    1
+}
+
+fn default_smoothing_alpha() -> f64 {
+    0.3
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::ImageEvaluator;
+
+    fn line_mask(y: usize, x_range: std::ops::Range<usize>) -> Array2<u8> {
+        let mut pixels = Array2::zeros((500, 500));
+        for x in x_range {
+            pixels[(y, x)] = 1;
+        }
+        pixels
+    }
+
+    #[test]
+    fn rendered_observations_reproduce_the_ingested_pixels() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        streaming
+            .add_observation_pixels(&[(250, 100), (250, 101)])
+            .unwrap();
+        streaming.flush();
+        let image = streaming.render_observation();
+        assert_eq!(image.dimensions(), (500, 500));
+        assert_eq!(image.get_pixel(100, 250).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(102, 250).0[3], 0);
+    }
+
+    #[test]
+    fn rendered_composites_use_the_configured_pane_layout() {
+        let config = EvaluatorConfig::default();
+        let model = ReferenceModel::new(line_mask(250, 100..400), config.clone()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        streaming.add_observation_pixels(&[(250, 100)]).unwrap();
+        streaming.flush();
+        let composite = streaming.render_composite();
+        assert_eq!(
+            composite.dimensions(),
+            (config.composite_width() as u32, 500)
+        );
+        // Reference stroke in the left pane, the drawn pixel on the right.
+        assert_eq!(composite.get_pixel(200, 250).0, [0, 0, 0, 255]);
+        let offset = (config.canvas_width + config.pane_gap) as u32;
+        assert_eq!(composite.get_pixel(offset + 100, 250).0, [0, 0, 0, 255]);
+        assert_eq!(composite.get_pixel(offset + 101, 250).0[3], 0);
+    }
+
+    #[test]
+    fn warm_starting_from_a_saved_canvas_matches_replaying_the_strokes() {
+        let reference = line_mask(250, 100..400);
+        let saved_canvas = line_mask(260, 120..380);
+
+        let model = ReferenceModel::new(reference.clone(), EvaluatorConfig::default()).unwrap();
+        let mut replayed = StreamingEvaluator::new(model.clone());
+        let pixels: Vec<(usize, usize)> = (120..380).map(|x| (260, x)).collect();
+        replayed.add_observation_pixels(&pixels).unwrap();
+        replayed.flush();
+
+        let mut resumed = StreamingEvaluator::new(model);
+        // Stale pixels from before the save are replaced wholesale.
+        resumed.add_observation_pixels(&[(10, 10)]).unwrap();
+        resumed.flush();
+        resumed.set_observation_from_array(&saved_canvas).unwrap();
+
+        assert_eq!(resumed.observation_count(), replayed.observation_count());
+        assert_eq!(resumed.current_score(), replayed.current_score());
+        assert_eq!(resumed.get_full_evaluation(), replayed.get_full_evaluation());
+    }
+
+    #[test]
+    fn warm_start_masks_must_match_the_reference_dimensions() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        let error = streaming
+            .set_observation_from_array(&Array2::zeros((100, 100)))
+            .unwrap_err();
+        assert!(matches!(error, EvaluationError::InvalidDimensions { .. }));
+    }
+
+    #[test]
+    fn swapped_references_rescore_the_retained_canvas() {
+        let first = line_mask(250, 100..400);
+        let second = line_mask(300, 100..400);
+        let one_shot = ImageEvaluator::default()
+            .evaluate_arrays(&second, &first)
+            .unwrap();
+
+        let model = ReferenceModel::new(first, EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        let pixels: Vec<(usize, usize)> = (100..400).map(|x| (250, x)).collect();
+        streaming.add_observation_pixels(&pixels).unwrap();
+        assert_eq!(streaming.current_score(), 0.0);
+        assert_eq!(streaming.completion_estimate(), 1.0);
+
+        // The next tutorial step: same canvas, a new line to trace.
+        let next = ReferenceModel::new(second, EvaluatorConfig::default()).unwrap();
+        streaming.swap_reference(next).unwrap();
+        assert_eq!(streaming.observation_count(), 300);
+        let metrics = streaming.get_full_evaluation();
+        assert!((metrics.mean_error - one_shot.metrics.mean_error).abs() < 1e-9);
+        assert!((metrics.top_5_error - one_shot.metrics.top_5_error).abs() < 1e-9);
+        assert!((metrics.coverage - one_shot.metrics.coverage).abs() < 1e-9);
+    }
+
+    #[test]
+    fn drawing_continues_against_the_swapped_reference() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        streaming.set_update_policy(UpdatePolicy::Manual);
+        streaming.add_observation_pixels(&[(250, 100)]).unwrap();
+        assert!(streaming.is_dirty());
+
+        let next =
+            ReferenceModel::new(line_mask(300, 100..400), EvaluatorConfig::default()).unwrap();
+        streaming.swap_reference(next).unwrap();
+        // The buffered pixel was folded in before the swap and now
+        // scores against the new line; tracing that line stays clean.
+        assert!(!streaming.is_dirty());
+        assert_eq!(streaming.error_at(100, 250), Some(50));
+        streaming.add_observation_pixels(&[(300, 200)]).unwrap();
+        streaming.flush();
+        assert_eq!(streaming.observation_count(), 2);
+        assert_eq!(streaming.error_at(200, 300), Some(0));
+    }
+
+    #[test]
+    fn swapped_references_must_match_the_canvas_dimensions() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        let mut small_pixels = Array2::zeros((100, 100));
+        small_pixels[(50, 50)] = 1;
+        let small = ReferenceModel::new(small_pixels, EvaluatorConfig::default()).unwrap();
+        let error = streaming.swap_reference(small).unwrap_err();
+        assert!(matches!(error, EvaluationError::InvalidDimensions { .. }));
+    }
+
+    #[test]
+    fn score_history_downsamples_to_a_sparkline_series() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        assert!(streaming.score_history(8).is_empty());
+        // Ten flushes, drifting further from the line each time.
+        for step in 0..10usize {
+            streaming
+                .add_observation_pixels(&[(260 + step * 10, 250)])
+                .unwrap();
+        }
+        assert_eq!(streaming.score_history(20).len(), 10);
+        let sparkline = streaming.score_history(4);
+        assert_eq!(sparkline.len(), 4);
+        assert_eq!(sparkline[3].top_5_error, streaming.current_score());
+        assert!(sparkline[0].top_5_error < sparkline[3].top_5_error);
+        assert!(sparkline.windows(2).all(|w| w[0].elapsed_ms <= w[1].elapsed_ms));
+        assert!(sparkline[3].mean_error > 0.0);
+    }
+
+    #[test]
+    fn score_history_survives_serialization() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        streaming.add_observation_pixels(&[(10, 10)]).unwrap();
+        streaming.add_observation_pixels(&[(250, 100)]).unwrap();
+        let restored =
+            StreamingEvaluator::from_serialized_state(streaming.to_serialized_state()).unwrap();
+        assert_eq!(restored.score_history(8), streaming.score_history(8));
+    }
+
+    #[test]
+    fn late_coverage_requests_match_polled_coverage() {
+        let reference = line_mask(250, 100..400);
+        let model = ReferenceModel::new(reference.clone(), EvaluatorConfig::default()).unwrap();
+        let pixels: Vec<(usize, usize)> = (100..400).map(|x| (252, x)).collect();
+        // One session polls coverage throughout, keeping the coverage
+        // state incremental; the other asks only at the end, paying a
+        // single materializing flood fill. The answers must agree.
+        let mut polled = StreamingEvaluator::new(model.clone());
+        let mut lazy = StreamingEvaluator::new(model);
+        for chunk in pixels.chunks(32) {
+            polled.add_observation_pixels(chunk).unwrap();
+            polled.completion_estimate();
+            lazy.add_observation_pixels(chunk).unwrap();
+        }
+        assert_eq!(lazy.completion_estimate(), polled.completion_estimate());
+        assert_eq!(lazy.get_full_evaluation(), polled.get_full_evaluation());
+    }
+
+    #[test]
+    fn coverage_stays_incremental_after_materialization() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        streaming.add_observation_pixels(&[(250, 100)]).unwrap();
+        let early = streaming.completion_estimate();
+        streaming
+            .add_observation_pixels(&(101..400).map(|x| (250, x)).collect::<Vec<_>>())
+            .unwrap();
+        assert!(early > 0.0);
+        assert_eq!(streaming.completion_estimate(), 1.0);
+    }
+
+    #[test]
+    fn evaluators_move_between_threads_and_clone() {
+        fn assert_clone_send_sync<T: Clone + Send + Sync>() {}
+        assert_clone_send_sync::<StreamingEvaluator>();
+    }
+
+    #[test]
+    fn snapshots_do_not_see_later_strokes() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        streaming.add_observation_pixels(&[(250, 100), (250, 101)]).unwrap();
+        let snapshot = streaming.snapshot();
+        streaming.add_observation_pixels(&[(10, 10)]).unwrap();
+        assert_eq!(snapshot.observation_count(), 2);
+        assert_eq!(snapshot.current_score(), 0.0);
+        assert!(streaming.current_score() > 0.0);
+        // The snapshot is independently usable on another thread.
+        let persisted = std::thread::spawn(move || snapshot.to_serialized_state())
+            .join()
+            .unwrap();
+        assert_eq!(persisted.observation_count, 2);
+    }
+
+    #[test]
+    fn sessions_share_one_reference_model() {
+        let model = Arc::new(
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap(),
+        );
+        let mut first = StreamingEvaluator::with_shared_reference(model.clone());
+        let second = StreamingEvaluator::with_shared_reference(model);
+        first.add_observation_pixels(&[(250, 100)]).unwrap();
+        assert_eq!(first.observation_count(), 1);
+        assert_eq!(second.observation_count(), 0);
+        assert_eq!(
+            first.reference().pixel_count(),
+            second.reference().pixel_count()
+        );
+    }
+
+    #[test]
+    fn streaming_matches_one_shot_evaluation() {
+        let reference = line_mask(250, 100..400);
+        let observation = line_mask(260, 120..380);
+        let one_shot = ImageEvaluator::default()
+            .evaluate_arrays(&reference, &observation)
+            .unwrap();
+
+        let model = ReferenceModel::new(reference, EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        let pixels: Vec<(usize, usize)> = (120..380).map(|x| (260, x)).collect();
+        for chunk in pixels.chunks(7) {
+            streaming.add_observation_pixels(chunk).unwrap();
+        }
+
+        let metrics = streaming.get_full_evaluation();
+        assert!((metrics.mean_error - one_shot.metrics.mean_error).abs() < 1e-9);
+        assert!((metrics.top_5_error - one_shot.metrics.top_5_error).abs() < 1e-9);
+        assert!((metrics.coverage - one_shot.metrics.coverage).abs() < 1e-9);
+    }
+
+    #[test]
+    fn duplicate_and_out_of_bounds_pixels_are_ignored() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        streaming.add_observation_pixels(&[(250, 100), (250, 100), (900, 900)]).unwrap();
+        assert_eq!(streaming.observation_count(), 1);
+    }
+
+    #[test]
+    fn score_hooks_fire_when_the_threshold_is_crossed() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = events.clone();
+        streaming.on_score_change(1.0, move |event| sink.lock().unwrap().push(event));
+        // On the line: score stays at zero, below the threshold.
+        streaming.add_observation_pixels(&[(250, 200)]).unwrap();
+        assert!(events.lock().unwrap().is_empty());
+        // Far off the line: the score jumps across the threshold once.
+        streaming.add_observation_pixels(&[(10, 10)]).unwrap();
+        let fired = events.lock().unwrap().clone();
+        assert_eq!(fired.len(), 1);
+        assert!(matches!(
+            fired[0],
+            ScoreEvent::ScoreCrossed { previous, current, .. }
+                if previous < 1.0 && current >= 1.0
+        ));
+    }
+
+    #[test]
+    fn cell_hooks_fire_once_per_offending_cell() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = events.clone();
+        streaming.on_cell_error(5.0, move |event| sink.lock().unwrap().push(event));
+        streaming.add_observation_pixels(&[(10, 10)]).unwrap();
+        streaming.add_observation_pixels(&[(10, 12)]).unwrap();
+        let fired = events.lock().unwrap().clone();
+        assert_eq!(fired.len(), 1);
+        assert!(matches!(
+            fired[0],
+            ScoreEvent::CellExceeded { row: 0, col: 0, .. }
+        ));
+    }
+
+    #[test]
+    fn out_of_bounds_pixels_are_counted_under_the_default_policy() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        streaming
+            .add_observation_pixels(&[(250, 100), (900, 900), (250, 600)])
+            .unwrap();
+        assert_eq!(streaming.observation_count(), 1);
+        assert_eq!(streaming.out_of_bounds_count(), 2);
+    }
+
+    #[test]
+    fn clamp_policy_snaps_pixels_to_the_canvas_edge() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        streaming.set_clip_policy(ClipPolicy::Clamp);
+        streaming.add_observation_pixels(&[(250, 700)]).unwrap();
+        assert_eq!(streaming.observation_count(), 1);
+        assert_eq!(streaming.out_of_bounds_count(), 1);
+        // The pixel landed on the right edge, 100px past the line's end.
+        let tile = streaming.evaluate_tile(499, 250, 1, 1);
+        assert_eq!(tile.observation_pixels, 1);
+    }
+
+    #[test]
+    fn error_policy_rejects_the_first_out_of_bounds_pixel() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        streaming.set_clip_policy(ClipPolicy::Error);
+        let error = streaming
+            .add_observation_pixels(&[(250, 100), (900, 900)])
+            .unwrap_err();
+        assert!(matches!(error, EvaluationError::OutOfBounds { x: 900, .. }));
+        assert_eq!(streaming.out_of_bounds_count(), 1);
+    }
+
+    #[test]
+    fn xy_space_transposes_incoming_pairs() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut native = StreamingEvaluator::new(model.clone());
+        native.add_observation_pixels(&[(250, 100), (240, 200)]).unwrap();
+        let mut canvas = StreamingEvaluator::new(model);
+        canvas.set_coordinate_space(CoordinateSpace::Xy);
+        canvas.add_observation_pixels(&[(100, 250), (200, 240)]).unwrap();
+        assert_eq!(canvas.observation_count(), native.observation_count());
+        assert_eq!(canvas.current_score(), native.current_score());
+    }
+
+    #[test]
+    fn xy_up_space_flips_the_vertical_axis() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        streaming.set_coordinate_space(CoordinateSpace::XyUp);
+        // (x=200, y=0) in a y-up space is the bottom row of the canvas.
+        streaming.add_observation_pixels(&[(200, 0)]).unwrap();
+        let tile = streaming.evaluate_tile(200, 499, 1, 1);
+        assert_eq!(tile.observation_pixels, 1);
+    }
+
+    #[test]
+    fn error_at_reads_the_distance_to_the_reference() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let streaming = StreamingEvaluator::new(model);
+        assert_eq!(streaming.error_at(200, 250), Some(0));
+        assert_eq!(streaming.error_at(200, 262), Some(12));
+        assert_eq!(streaming.error_at(900, 250), None);
+    }
+
+    #[test]
+    fn errors_along_path_sample_each_point() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let streaming = StreamingEvaluator::new(model);
+        let errors = streaming.errors_along_path(&[(200, 250), (200, 255), (900, 900)]);
+        assert_eq!(errors, vec![Some(0), Some(5), None]);
+    }
+
+    #[test]
+    fn completion_estimate_tracks_covered_reference_pixels() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..200), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        assert_eq!(streaming.completion_estimate(), 0.0);
+        // Trace the first half of the reference line exactly.
+        let pixels: Vec<(usize, usize)> = (100..150).map(|x| (250, x)).collect();
+        streaming.add_observation_pixels(&pixels).unwrap();
+        let estimate = streaming.completion_estimate();
+        // Tolerance also covers a few pixels past the traced half.
+        assert!((0.5..0.6).contains(&estimate), "estimate {estimate}");
+    }
+
+    #[test]
+    fn smoothed_score_lags_behind_a_score_jump() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        streaming.set_smoothing_alpha(0.5);
+        streaming.add_observation_pixels(&[(250, 100)]).unwrap();
+        assert_eq!(streaming.smoothed_score(), 0.0);
+        // A far-off pixel spikes the raw score; the EMA follows halfway.
+        streaming.add_observation_pixels(&[(10, 10)]).unwrap();
+        let raw = streaming.current_score();
+        assert!(raw > 0.0);
+        assert!((streaming.smoothed_score() - raw / 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn trend_reports_worsening_after_a_stray_pixel() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        assert_eq!(streaming.score_trend(5), ScoreTrend::Stable);
+        streaming.add_observation_pixels(&[(250, 100)]).unwrap();
+        streaming.add_observation_pixels(&[(10, 10)]).unwrap();
+        assert_eq!(streaming.score_trend(5), ScoreTrend::Worsening);
+    }
+
+    #[test]
+    fn manual_policy_defers_score_updates_until_flush() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        streaming.set_update_policy(UpdatePolicy::Manual);
+        streaming.add_observation_pixels(&[(100, 100)]).unwrap();
+        assert!(streaming.is_dirty());
+        assert_eq!(streaming.current_score(), 0.0);
+        streaming.flush();
+        assert!(!streaming.is_dirty());
+        assert!(streaming.current_score() > 0.0);
+    }
+
+    #[test]
+    fn pixel_count_policy_batches_ingestion() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        streaming.set_update_policy(UpdatePolicy::EveryNPixels(3));
+        streaming.add_observation_pixels(&[(250, 100)]).unwrap();
+        streaming.add_observation_pixels(&[(250, 101)]).unwrap();
+        assert!(streaming.is_dirty());
+        assert_eq!(streaming.observation_count(), 0);
+        streaming.add_observation_pixels(&[(250, 102)]).unwrap();
+        assert!(!streaming.is_dirty());
+        assert_eq!(streaming.observation_count(), 3);
+    }
+
+    #[test]
+    fn blown_budget_grows_the_adaptive_batch() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        // A zero budget means every update is over it.
+        streaming.set_update_policy(UpdatePolicy::AdaptiveMs(0));
+        streaming.add_observation_pixels(&[(250, 100)]).unwrap();
+        assert_eq!(streaming.observation_count(), 1);
+        // The batch doubled, so a single pixel now stays buffered.
+        streaming.add_observation_pixels(&[(250, 101)]).unwrap();
+        assert!(streaming.is_dirty());
+        assert_eq!(streaming.observation_count(), 1);
+        streaming.add_observation_pixels(&[(250, 102)]).unwrap();
+        assert_eq!(streaming.observation_count(), 3);
+    }
+
+    #[test]
+    fn generous_budget_keeps_updates_immediate() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        streaming.set_update_policy(UpdatePolicy::AdaptiveMs(10_000));
+        for x in 100..110 {
+            streaming.add_observation_pixels(&[(250, x)]).unwrap();
+            assert!(!streaming.is_dirty());
+        }
+        assert_eq!(streaming.observation_count(), 10);
+    }
+
+    #[test]
+    fn heat_timeline_samples_cell_errors_over_time() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        streaming.enable_heat_timeline(2);
+        // Four flushes at every-other sampling record two frames.
+        streaming.add_observation_pixels(&[(250, 100)]).unwrap();
+        streaming.add_observation_pixels(&[(250, 101)]).unwrap();
+        streaming.add_observation_pixels(&[(10, 10)]).unwrap();
+        streaming.add_observation_pixels(&[(250, 102)]).unwrap();
+        let timeline = streaming.heat_timeline().unwrap();
+        assert_eq!(timeline.frames(), 2);
+        // The stray pixel only shows up in the second frame.
+        assert_eq!(timeline.cell(0, 0, 0), 0.0);
+        assert!(timeline.cell(1, 0, 0) > 0.0);
+    }
+
+    #[test]
+    fn serialization_round_trips() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        streaming.add_observation_pixels(&[(255, 150), (255, 151), (255, 152)]).unwrap();
+        let state = streaming.to_serialized_state();
+        let json = serde_json::to_string(&state).unwrap();
+        let restored: StreamingEvaluatorState = serde_json::from_str(&json).unwrap();
+        let restored = StreamingEvaluator::from_serialized_state(restored).unwrap();
+        assert_eq!(restored.get_full_evaluation(), streaming.get_full_evaluation());
+    }
+
+    #[test]
+    fn accurate_half_done_tracing_projects_a_high_ceiling() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        let pixels: Vec<(usize, usize)> = (100..250).map(|x| (250, x)).collect();
+        streaming.add_observation_pixels(&pixels).unwrap();
+        let projection = streaming.projected_score();
+        assert!(projection.low < projection.high);
+        assert!(projection.expected >= projection.low);
+        assert!(projection.expected <= projection.high);
+        assert!((projection.high - 1.0).abs() < 1e-9);
+        // Tolerance covers a few extra reference pixels past x=250.
+        assert!((projection.completion - 0.5).abs() < 0.05);
+    }
+
+    #[test]
+    fn a_finished_perfect_tracing_projects_its_own_score() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        let pixels: Vec<(usize, usize)> = (100..400).map(|x| (250, x)).collect();
+        streaming.add_observation_pixels(&pixels).unwrap();
+        let projection = streaming.projected_score();
+        assert!((projection.low - 1.0).abs() < 1e-9);
+        assert_eq!(projection.low, projection.expected);
+        assert_eq!(projection.expected, projection.high);
+    }
+
+    #[test]
+    fn contributions_are_attributed_per_user() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        let left: Vec<(usize, usize)> = (100..250).map(|x| (250, x)).collect();
+        let right: Vec<(usize, usize)> = (250..400).map(|x| (250, x)).collect();
+        streaming.add_observation_pixels_for("ada", &left).unwrap();
+        streaming.add_observation_pixels_for("grace", &right).unwrap();
+        streaming.add_observation_pixels_for("grace", &[(20, 450)]).unwrap();
+        let contributions = streaming.user_contributions();
+        assert_eq!(contributions.len(), 2);
+        let ada = &contributions[0];
+        let grace = &contributions[1];
+        assert_eq!(ada.user_id, "ada");
+        assert_eq!(ada.pixels_added, 150);
+        assert_eq!(ada.mean_error, 0.0);
+        assert_eq!(grace.pixels_added, 151);
+        assert!(grace.mean_error > 0.0);
+        assert!((ada.coverage + grace.coverage - 1.0).abs() < 1e-9);
+        assert_eq!(streaming.completion_estimate(), 1.0);
+    }
+
+    #[test]
+    fn untagged_pixels_count_only_in_the_combined_score() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        streaming.add_observation_pixels(&[(250, 100), (250, 101)]).unwrap();
+        assert_eq!(streaming.observation_count(), 2);
+        assert!(streaming.user_contributions().is_empty());
+    }
+
+    #[test]
+    fn contributions_survive_serialization() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        streaming.add_observation_pixels_for("ada", &[(250, 100), (250, 101)]).unwrap();
+        let state = streaming.to_serialized_state();
+        let restored = StreamingEvaluator::from_serialized_state(state).unwrap();
+        assert_eq!(restored.user_contributions(), streaming.user_contributions());
+    }
+
+    #[test]
+    fn tile_metrics_only_see_the_viewport() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        // An accurate left half and one stray pixel far away.
+        let pixels: Vec<(usize, usize)> = (100..250).map(|x| (250, x)).collect();
+        streaming.add_observation_pixels(&pixels).unwrap();
+        streaming.add_observation_pixels(&[(20, 450)]).unwrap();
+        let left = streaming.evaluate_tile(0, 0, 250, 500);
+        assert_eq!(left.mean_error, 0.0);
+        assert_eq!(left.observation_pixels, 150);
+        assert_eq!(left.reference_pixels, 150);
+        assert_eq!(left.coverage, 1.0);
+        let top_right = streaming.evaluate_tile(400, 0, 100, 100);
+        assert_eq!(top_right.observation_pixels, 1);
+        assert!(top_right.worst_error > 0.0);
+        assert_eq!(top_right.reference_pixels, 0);
+    }
+
+    #[test]
+    fn tiles_are_clamped_to_the_canvas() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let streaming = StreamingEvaluator::new(model);
+        let tile = streaming.evaluate_tile(450, 450, 500, 500);
+        assert_eq!(tile.observation_pixels, 0);
+        assert_eq!(tile.reference_pixels, 0);
+        assert_eq!(tile.coverage, 0.0);
+    }
+
+    #[test]
+    fn compressed_heatmap_is_an_order_of_magnitude_smaller() {
+        let heatmap = crate::heatmap::flood_fill_distances(&line_mask(250, 100..400), None);
+        let compressed = SerializableHeatmap::from_array(&heatmap);
+        assert!(compressed.compressed);
+        assert!(
+            compressed.data.len() * 10 < heatmap.len(),
+            "{} pairs for {} pixels",
+            compressed.data.len() / 2,
+            heatmap.len()
+        );
+        assert_eq!(compressed.to_array().unwrap(), heatmap);
+    }
+
+    #[test]
+    fn uncompressed_legacy_heatmaps_still_decode() {
+        let legacy = SerializableHeatmap {
+            width: 2,
+            height: 2,
+            data: vec![0, 1, 1, 0],
+            compressed: false,
+        };
+        assert_eq!(
+            legacy.to_array().unwrap(),
+            Array2::from_shape_vec((2, 2), vec![0, 1, 1, 0]).unwrap()
+        );
+    }
+
+    #[test]
+    fn truncated_compressed_heatmaps_are_rejected() {
+        let truncated = SerializableHeatmap {
+            width: 2,
+            height: 2,
+            data: vec![1, 3],
+            compressed: true,
+        };
+        let error = truncated.to_array().unwrap_err();
+        assert!(error.to_string().contains("expected 4"));
+    }
+
+    #[test]
+    fn high_rate_pointer_streams_coalesce_to_one_batch_per_window() {
+        // A 240Hz stroke across 100px: one sample every ~4ms. A 16ms
+        // window should release roughly display-rate batches instead of
+        // one-pixel floods.
+        let mut coalescer = InputCoalescer::new(16, 1.0);
+        let mut batches = Vec::new();
+        for step in 0..100u64 {
+            if let Some(batch) = coalescer.push(250, 100 + step as usize, step * 4) {
+                batches.push(batch);
+            }
+        }
+        batches.push(coalescer.end_stroke());
+        assert!(batches.len() <= 26, "{} batches", batches.len());
+        let total: usize = batches.iter().map(Vec::len).sum();
+        assert_eq!(total, 100);
+    }
+
+    #[test]
+    fn jitter_below_the_distance_window_is_dropped() {
+        let mut coalescer = InputCoalescer::new(16, 2.0);
+        assert!(coalescer.push(250, 100, 0).is_none());
+        // Sub-window wiggles around the same spot buffer nothing new.
+        assert!(coalescer.push(250, 101, 4).is_none());
+        assert!(coalescer.push(251, 100, 8).is_none());
+        assert_eq!(coalescer.pending(), 1);
+        assert_eq!(coalescer.end_stroke(), vec![(250, 100)]);
+    }
+
+    #[test]
+    fn stroke_ends_flush_the_remainder_and_reset_the_filter() {
+        let mut coalescer = InputCoalescer::new(1_000, 2.0);
+        assert!(coalescer.push(250, 100, 0).is_none());
+        assert_eq!(coalescer.end_stroke(), vec![(250, 100)]);
+        assert_eq!(coalescer.pending(), 0);
+        // A new stroke starting on the same spot is kept: the distance
+        // filter only spans one stroke.
+        assert!(coalescer.push(250, 100, 50).is_none());
+        assert_eq!(coalescer.pending(), 1);
+    }
+
+    #[test]
+    fn coalesced_batches_feed_the_streaming_evaluator() {
+        let model =
+            ReferenceModel::new(line_mask(250, 100..400), EvaluatorConfig::default()).unwrap();
+        let mut streaming = StreamingEvaluator::new(model);
+        let mut coalescer = InputCoalescer::new(16, 1.0);
+        for step in 0..50u64 {
+            if let Some(batch) = coalescer.push(250, 100 + step as usize, step * 4) {
+                streaming.add_observation_pixels(&batch).unwrap();
+            }
+        }
+        streaming.add_observation_pixels(&coalescer.end_stroke()).unwrap();
+        assert_eq!(streaming.observation_count(), 50);
+    }
+}
@@ -0,0 +1,298 @@
+//! Scoring recorded drawings frame by frame.
+//!
+//! Timelapse and screen recordings arrive as a sequence of observation
+//! snapshots. Diffing consecutive frames recovers the pixels drawn
+//! between them, which replay through a [`StreamingEvaluator`] exactly
+//! like live input — yielding a score timeline for the whole recording.
+
+#[cfg(any(feature = "png", feature = "gif", feature = "webp"))]
+use std::io::Cursor;
+
+use image::RgbaImage;
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+use crate::decode::{channel_view, mask_channel, mask_from_view};
+use crate::error::EvaluationError;
+use crate::evaluator::{EvaluatorConfig, ImageEvaluator};
+use crate::streaming::{ReferenceModel, StreamingEvaluator};
+
+/// The running score after one frame of a recording was ingested.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FrameScore {
+    /// Zero-based index of the frame in the recording.
+    pub frame: usize,
+    /// Stroke pixels that first appeared in this frame.
+    pub new_pixels: usize,
+    pub score: f64,
+    pub completion: f64,
+}
+
+/// Replays a recording through a [`StreamingEvaluator`] and returns one
+/// [`FrameScore`] per frame.
+///
+/// Each frame is the full observation stroke mask as of that moment;
+/// only pixels not seen in any earlier frame are ingested, so erased
+/// pixels keep counting as drawn — the same rule live streaming applies.
+/// Every frame must match the reference's dimensions.
+pub fn evaluate_frames(
+    reference: Array2<u8>,
+    frames: &[Array2<u8>],
+    config: EvaluatorConfig,
+) -> Result<Vec<FrameScore>, EvaluationError> {
+    let (height, width) = reference.dim();
+    let model = ReferenceModel::new(reference, config)?;
+    let mut evaluator = StreamingEvaluator::new(model);
+    let mut seen = Array2::<u8>::zeros((height, width));
+    let mut timeline = Vec::with_capacity(frames.len());
+    for (index, frame) in frames.iter().enumerate() {
+        if frame.dim() != (height, width) {
+            return Err(EvaluationError::InvalidBuffer(format!(
+                "frame {index} is {:?}, expected {:?}",
+                frame.dim(),
+                (height, width)
+            )));
+        }
+        let new_pixels: Vec<(usize, usize)> = frame
+            .indexed_iter()
+            .filter(|&(pos, &on)| on != 0 && seen[pos] == 0)
+            .map(|(pos, _)| pos)
+            .collect();
+        for &pos in &new_pixels {
+            seen[pos] = 1;
+        }
+        evaluator.add_observation_pixels(&new_pixels)?;
+        evaluator.flush();
+        timeline.push(FrameScore {
+            frame: index,
+            new_pixels: new_pixels.len(),
+            score: evaluator.current_score(),
+            completion: evaluator.completion_estimate(),
+        });
+    }
+    Ok(timeline)
+}
+
+/// Decodes an animated submission — a GIF, an animated WebP or an APNG
+/// — into its composited full-canvas RGBA frames. Still images
+/// (including single-image WebP and plain PNG) return `None` so callers
+/// can fall back to the one-shot path. An animated format whose decoder
+/// feature is not compiled in also falls through to `None`; the
+/// still-image decoder then reports the unsupported format by name.
+pub fn decode_animation(bytes: &[u8]) -> Result<Option<Vec<RgbaImage>>, EvaluationError> {
+    let Ok(format) = image::guess_format(bytes) else {
+        return Ok(None);
+    };
+    match format {
+        #[cfg(feature = "gif")]
+        image::ImageFormat::Gif => {
+            collect_frames(image::codecs::gif::GifDecoder::new(Cursor::new(bytes))?)
+        }
+        #[cfg(feature = "png")]
+        image::ImageFormat::Png => {
+            let decoder = image::codecs::png::PngDecoder::new(Cursor::new(bytes))?;
+            if !decoder.is_apng() {
+                return Ok(None);
+            }
+            collect_frames(decoder.apng())
+        }
+        #[cfg(feature = "webp")]
+        image::ImageFormat::WebP => {
+            let decoder = image::codecs::webp::WebPDecoder::new(Cursor::new(bytes))?;
+            if !decoder.has_animation() {
+                return Ok(None);
+            }
+            collect_frames(decoder)
+        }
+        _ => Ok(None),
+    }
+}
+
+#[cfg(any(feature = "png", feature = "gif", feature = "webp"))]
+fn collect_frames<'a>(
+    decoder: impl image::AnimationDecoder<'a>,
+) -> Result<Option<Vec<RgbaImage>>, EvaluationError> {
+    let frames = decoder.into_frames().collect_frames()?;
+    Ok(Some(
+        frames.into_iter().map(image::Frame::into_buffer).collect(),
+    ))
+}
+
+/// Scores an animated composite recording — a capture of the drawing
+/// app, so every frame has the reference pane on the left and the
+/// in-progress observation on the right. The reference is taken from
+/// the final frame (the finished export) and each frame's observation
+/// pane replays through [`evaluate_frames`], one [`FrameScore`] per
+/// frame.
+pub fn evaluate_composite_frames(
+    evaluator: &ImageEvaluator,
+    frames: &[RgbaImage],
+) -> Result<Vec<FrameScore>, EvaluationError> {
+    let last = frames
+        .last()
+        .ok_or_else(|| EvaluationError::InvalidBuffer("animation has no frames".to_string()))?;
+    let (reference, _) = evaluator.extract_panes(last)?;
+    let mut masks = Vec::with_capacity(frames.len());
+    for frame in frames {
+        let (_, observation) = evaluator.extract_panes(frame)?;
+        masks.push(observation);
+    }
+    evaluate_frames(reference, &masks, evaluator.config().clone())
+}
+
+/// Converts one decoded recording frame into a stroke mask, using the
+/// same ink rule the composite evaluator applies to panes. Animated
+/// formats decode to exactly these RGBA frames.
+pub fn mask_from_frame(frame: &RgbaImage, transparent_background: bool) -> Array2<u8> {
+    let channel = mask_channel(transparent_background);
+    mask_from_view(&channel_view(frame, channel), transparent_background)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line_mask(width: usize, height: usize, y: usize, xs: std::ops::Range<usize>) -> Array2<u8> {
+        let mut mask = Array2::zeros((height, width));
+        for x in xs {
+            mask[(y, x)] = 1;
+        }
+        mask
+    }
+
+    fn small_config() -> EvaluatorConfig {
+        EvaluatorConfig {
+            canvas_width: 100,
+            canvas_height: 100,
+            ..EvaluatorConfig::default()
+        }
+    }
+
+    #[test]
+    fn timeline_improves_as_frames_trace_the_reference() {
+        let reference = line_mask(100, 100, 50, 20..80);
+        let frames = vec![
+            line_mask(100, 100, 50, 20..40),
+            line_mask(100, 100, 50, 20..60),
+            line_mask(100, 100, 50, 20..80),
+        ];
+        let timeline = evaluate_frames(reference, &frames, small_config()).unwrap();
+        assert_eq!(timeline.len(), 3);
+        assert_eq!(timeline[0].new_pixels, 20);
+        assert_eq!(timeline[2].new_pixels, 20);
+        assert!(timeline[0].completion < timeline[1].completion);
+        assert_eq!(timeline[2].completion, 1.0);
+    }
+
+    #[test]
+    fn erased_pixels_are_not_ingested_twice() {
+        let reference = line_mask(100, 100, 50, 20..80);
+        let frames = vec![
+            line_mask(100, 100, 50, 20..60),
+            // The user "undid" part of the stroke; the mask shrinks.
+            line_mask(100, 100, 50, 20..40),
+            line_mask(100, 100, 50, 20..60),
+        ];
+        let timeline = evaluate_frames(reference, &frames, small_config()).unwrap();
+        assert_eq!(timeline[0].new_pixels, 40);
+        assert_eq!(timeline[1].new_pixels, 0);
+        assert_eq!(timeline[2].new_pixels, 0);
+    }
+
+    #[test]
+    fn mismatched_frame_dimensions_are_rejected() {
+        let reference = line_mask(100, 100, 50, 20..80);
+        let frames = vec![Array2::zeros((50, 50))];
+        let error = evaluate_frames(reference, &frames, small_config()).unwrap_err();
+        assert!(error.to_string().contains("frame 0"));
+    }
+
+    #[test]
+    #[cfg(feature = "png")]
+    fn still_images_and_unknown_bytes_are_not_animations() {
+        let mut png = Vec::new();
+        RgbaImage::from_pixel(4, 4, image::Rgba([255, 255, 255, 255]))
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        assert!(decode_animation(&png).unwrap().is_none());
+        assert!(decode_animation(b"not an image").unwrap().is_none());
+    }
+
+    /// An opaque-background layout small enough to animate in tests:
+    /// two 100x100 panes with the default gap.
+    #[cfg(feature = "gif")]
+    fn composite_config() -> EvaluatorConfig {
+        EvaluatorConfig {
+            transparent_background: false,
+            ..small_config()
+        }
+    }
+
+    /// One white composite frame: the reference line fully drawn on the
+    /// left pane, the observation traced up to `xs` on the right.
+    #[cfg(feature = "gif")]
+    fn composite_frame(xs: std::ops::Range<usize>) -> RgbaImage {
+        let config = composite_config();
+        let mut frame = RgbaImage::from_pixel(
+            config.composite_width() as u32,
+            config.canvas_height as u32,
+            image::Rgba([255, 255, 255, 255]),
+        );
+        let ink = image::Rgba([0, 0, 0, 255]);
+        let observation_left = (config.canvas_width + config.pane_gap) as u32;
+        for x in 20..80u32 {
+            frame.put_pixel(x, 50, ink);
+        }
+        for x in xs {
+            frame.put_pixel(observation_left + x as u32, 50, ink);
+        }
+        frame
+    }
+
+    #[cfg(feature = "gif")]
+    fn encode_gif(frames: &[RgbaImage]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut encoder = image::codecs::gif::GifEncoder::new(&mut bytes);
+        for frame in frames {
+            encoder
+                .encode_frame(image::Frame::new(frame.clone()))
+                .unwrap();
+        }
+        drop(encoder);
+        bytes
+    }
+
+    #[test]
+    #[cfg(feature = "gif")]
+    fn animated_gifs_score_frame_by_frame() {
+        let frames = [
+            composite_frame(20..40),
+            composite_frame(20..60),
+            composite_frame(20..80),
+        ];
+        let decoded = decode_animation(&encode_gif(&frames))
+            .unwrap()
+            .expect("a GIF is an animation");
+        assert_eq!(decoded.len(), 3);
+        let evaluator = ImageEvaluator::new(composite_config());
+        let timeline = evaluate_composite_frames(&evaluator, &decoded).unwrap();
+        assert_eq!(timeline.len(), 3);
+        assert!(timeline[0].completion < timeline[1].completion);
+        assert_eq!(timeline[2].completion, 1.0);
+    }
+
+    #[test]
+    #[cfg(feature = "gif")]
+    fn the_final_gif_frame_scores_like_a_still_composite() {
+        let frames = [composite_frame(20..60), composite_frame(20..80)];
+        let decoded = decode_animation(&encode_gif(&frames)).unwrap().unwrap();
+        let evaluator = ImageEvaluator::new(composite_config());
+        let (reference, observation) =
+            evaluator.extract_panes(decoded.last().unwrap()).unwrap();
+        let result = evaluator.evaluate_arrays(&reference, &observation).unwrap();
+        // The final frame traces the reference exactly, so the GIF
+        // round-trip must not disturb either pane.
+        assert_eq!(result.metrics.top_5_error, 0.0);
+        assert_eq!(result.metrics.coverage, 1.0);
+    }
+}
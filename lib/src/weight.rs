@@ -0,0 +1,196 @@
+//! Line-weight fidelity metrics.
+//!
+//! Inking and brush-control exercises train a consistent stroke weight:
+//! a line traced in exactly the right place but twice as heavy as the
+//! reference is still a miss. Placement scoring cannot see that, so this
+//! module estimates local stroke thickness — the clearance to the
+//! nearest background pixel, read along the thinned skeleton — and
+//! compares observation widths against the reference widths at the
+//! nearest reference skeleton points.
+
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::skeletonize;
+use crate::heatmap::flood_fill_distances;
+
+/// Observation skeleton points further than this from every reference
+/// skeleton point are misplacement, not line weight, and are left to
+/// the placement metrics.
+const MAX_PAIRING_DISTANCE: f64 = 20.0;
+
+/// Thickness statistics of one pane's strokes, sampled at every
+/// skeleton point.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WeightProfile {
+    /// Estimated local width in pixels, averaged over the skeleton.
+    pub mean_width: f64,
+    pub min_width: f64,
+    pub max_width: f64,
+    /// Skeleton points sampled; 0 for a blank mask.
+    pub samples: usize,
+}
+
+/// The thickness comparison of two panes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeightComparison {
+    pub reference: WeightProfile,
+    pub observation: WeightProfile,
+    /// Mean absolute width difference, in pixels, across the paired
+    /// skeleton points.
+    pub mean_deviation: f64,
+    pub max_deviation: f64,
+    /// Observation skeleton points with no reference skeleton point
+    /// within [`MAX_PAIRING_DISTANCE`]; excluded from the deviations.
+    pub unpaired_samples: usize,
+}
+
+/// Measures the thickness profile of a stroke mask.
+pub fn weight_profile(mask: &Array2<u8>) -> WeightProfile {
+    profile_of(&width_samples(mask))
+}
+
+/// Compares local stroke thickness between the panes. Each observation
+/// skeleton point is paired with the nearest reference skeleton point
+/// and the width difference there contributes to the deviations.
+pub fn compare_line_weight(
+    reference: &Array2<u8>,
+    observation: &Array2<u8>,
+) -> WeightComparison {
+    let reference_samples = width_samples(reference);
+    let observation_samples = width_samples(observation);
+
+    let mut deviations = Vec::new();
+    let mut unpaired_samples = 0;
+    for &((oy, ox), width) in &observation_samples {
+        let nearest = reference_samples
+            .iter()
+            .map(|&((ry, rx), reference_width)| {
+                let dy = ry as f64 - oy as f64;
+                let dx = rx as f64 - ox as f64;
+                ((dy * dy + dx * dx).sqrt(), reference_width)
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        match nearest {
+            Some((distance, reference_width)) if distance <= MAX_PAIRING_DISTANCE => {
+                deviations.push((width - reference_width).abs());
+            }
+            _ => unpaired_samples += 1,
+        }
+    }
+
+    let max_deviation = deviations.iter().cloned().fold(0.0, f64::max);
+    let mean_deviation = if deviations.is_empty() {
+        0.0
+    } else {
+        deviations.iter().sum::<f64>() / deviations.len() as f64
+    };
+    WeightComparison {
+        reference: profile_of(&reference_samples),
+        observation: profile_of(&observation_samples),
+        mean_deviation,
+        max_deviation,
+        unpaired_samples,
+    }
+}
+
+/// Local width at every skeleton point of `mask`. The width estimate is
+/// the distance-transform clearance to the nearest background pixel,
+/// mirrored to a full stroke width (`2d - 1`): the centre of a
+/// five-pixel line sits three chessboard steps from background.
+fn width_samples(mask: &Array2<u8>) -> Vec<((usize, usize), f64)> {
+    let background = mask.mapv(|p| u8::from(p == 0));
+    let clearance = flood_fill_distances(&background, None);
+    skeletonize(mask)
+        .indexed_iter()
+        .filter(|(_, &on)| on != 0)
+        .map(|(pos, _)| (pos, (2 * clearance[pos] - 1).max(1) as f64))
+        .collect()
+}
+
+fn profile_of(samples: &[((usize, usize), f64)]) -> WeightProfile {
+    if samples.is_empty() {
+        return WeightProfile {
+            mean_width: 0.0,
+            min_width: 0.0,
+            max_width: 0.0,
+            samples: 0,
+        };
+    }
+    let widths = samples.iter().map(|&(_, width)| width);
+    WeightProfile {
+        mean_width: widths.clone().sum::<f64>() / samples.len() as f64,
+        min_width: widths.clone().fold(f64::INFINITY, f64::min),
+        max_width: widths.fold(0.0, f64::max),
+        samples: samples.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn thick_line(y: usize, range: std::ops::Range<usize>, thickness: usize) -> Array2<u8> {
+        let mut mask = Array2::zeros((500, 500));
+        for row in y..y + thickness {
+            for x in range.clone() {
+                mask[(row, x)] = 1;
+            }
+        }
+        mask
+    }
+
+    #[test]
+    fn the_profile_measures_the_stroke_width() {
+        let profile = weight_profile(&thick_line(248, 100..400, 5));
+        assert!(profile.samples > 100);
+        assert!(
+            (profile.mean_width - 5.0).abs() < 1.0,
+            "mean width {}",
+            profile.mean_width
+        );
+        assert!(profile.max_width <= 5.0);
+    }
+
+    #[test]
+    fn identical_panes_deviate_by_nothing() {
+        let mask = thick_line(248, 100..400, 5);
+        let comparison = compare_line_weight(&mask, &mask);
+        assert_eq!(comparison.mean_deviation, 0.0);
+        assert_eq!(comparison.max_deviation, 0.0);
+        assert_eq!(comparison.unpaired_samples, 0);
+    }
+
+    #[test]
+    fn a_heavier_copy_reports_the_thickness_deviation() {
+        let reference = thick_line(249, 100..400, 3);
+        let observation = thick_line(247, 100..400, 7);
+        let comparison = compare_line_weight(&reference, &observation);
+        assert!(
+            comparison.mean_deviation > 2.5,
+            "mean deviation {}",
+            comparison.mean_deviation
+        );
+        assert!(comparison.max_deviation >= comparison.mean_deviation);
+        assert_eq!(comparison.unpaired_samples, 0);
+    }
+
+    #[test]
+    fn far_away_strokes_are_unpaired_not_deviant() {
+        let reference = thick_line(100, 100..400, 3);
+        let observation = thick_line(400, 100..400, 7);
+        let comparison = compare_line_weight(&reference, &observation);
+        assert_eq!(comparison.mean_deviation, 0.0);
+        assert!(comparison.unpaired_samples > 100);
+    }
+
+    #[test]
+    fn blank_masks_profile_to_zeroes() {
+        let blank = Array2::zeros((500, 500));
+        let profile = weight_profile(&blank);
+        assert_eq!(profile.samples, 0);
+        assert_eq!(profile.mean_width, 0.0);
+        let comparison = compare_line_weight(&blank, &blank);
+        assert_eq!(comparison.mean_deviation, 0.0);
+    }
+}
@@ -0,0 +1,375 @@
+//! Long-running evaluation worker.
+//!
+//! Jobs arrive as JSON lines — an image path plus optional id and
+//! per-job configuration — either from a reader (stdin in the CLI) or
+//! as files dropped into a queue directory. A bounded thread pool
+//! evaluates them and results stream out as NDJSON, one record per
+//! job, so existing pipelines can pipe through the worker without
+//! running an HTTP server. Shutdown is graceful: end of input (or a
+//! `stop` file in the queue directory) lets in-flight jobs finish
+//! before the worker exits.
+
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::EvaluationError;
+use crate::evaluator::{EvaluatorConfig, ImageEvaluator};
+use crate::metrics::ErrorMetrics;
+
+/// One evaluation job, as parsed from a queue line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerJob {
+    /// Caller-chosen identifier, echoed back in the result record.
+    #[serde(default)]
+    pub id: Option<String>,
+    /// Path to the composite image to evaluate.
+    pub image: PathBuf,
+    /// Per-job evaluator configuration; the worker default when absent.
+    #[serde(default)]
+    pub config: Option<EvaluatorConfig>,
+}
+
+/// One NDJSON result record. Records are emitted in completion order,
+/// which under a thread pool is not necessarily submission order; use
+/// `id` to correlate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerOutcome {
+    pub id: Option<String>,
+    /// The job's image path; `None` when the job line failed to parse.
+    pub image: Option<PathBuf>,
+    pub metrics: Option<ErrorMetrics>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// Knobs of the worker loop.
+#[derive(Debug, Clone)]
+pub struct WorkerOptions {
+    /// Jobs evaluated concurrently; clamped to at least one thread.
+    pub threads: usize,
+    /// How often the queue directory is re-scanned when it is empty.
+    pub poll_interval: Duration,
+}
+
+impl Default for WorkerOptions {
+    fn default() -> Self {
+        Self {
+            threads: 4,
+            poll_interval: Duration::from_millis(250),
+        }
+    }
+}
+
+/// Totals reported once the worker has drained and shut down.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WorkerStats {
+    pub processed: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+}
+
+/// Reads jobs from `reader` until end of input, evaluating them on a
+/// bounded thread pool and streaming NDJSON records to `writer`. Lines
+/// that do not parse as jobs become error records rather than killing
+/// the worker.
+pub fn run_worker(
+    reader: impl BufRead,
+    writer: impl Write + Send + 'static,
+    config: EvaluatorConfig,
+    options: &WorkerOptions,
+) -> Result<WorkerStats, EvaluationError> {
+    let mut pool = WorkerPool::start(writer, config, options);
+    for line in reader.lines() {
+        let line = line.map_err(|source| EvaluationError::Io {
+            path: PathBuf::from("<worker input>"),
+            source,
+        })?;
+        pool.submit_line(&line);
+    }
+    Ok(pool.finish())
+}
+
+/// Runs the worker against a directory queue: every `*.json` file is a
+/// batch of job lines, processed in name order and renamed to
+/// `*.json.done` afterwards so a crash never loses or repeats work
+/// silently. The loop polls for new files until a file named `stop`
+/// appears in the directory; the stop file is removed and the worker
+/// drains before returning.
+pub fn run_queue_directory(
+    directory: impl AsRef<Path>,
+    writer: impl Write + Send + 'static,
+    config: EvaluatorConfig,
+    options: &WorkerOptions,
+) -> Result<WorkerStats, EvaluationError> {
+    let directory = directory.as_ref();
+    let mut pool = WorkerPool::start(writer, config, options);
+    loop {
+        for path in pending_job_files(directory)? {
+            let text = std::fs::read_to_string(&path).map_err(|source| EvaluationError::Io {
+                path: path.clone(),
+                source,
+            })?;
+            for line in text.lines() {
+                pool.submit_line(line);
+            }
+            let done = PathBuf::from(format!("{}.done", path.display()));
+            std::fs::rename(&path, done).map_err(|source| EvaluationError::Io {
+                path: path.clone(),
+                source,
+            })?;
+        }
+        let stop = directory.join("stop");
+        if stop.exists() {
+            let _ = std::fs::remove_file(stop);
+            break;
+        }
+        std::thread::sleep(options.poll_interval);
+    }
+    Ok(pool.finish())
+}
+
+/// The bounded pool shared by both entry points: evaluator threads pull
+/// jobs from one channel, and a dedicated writer thread serializes
+/// outcomes so records never interleave.
+struct WorkerPool {
+    jobs: Option<mpsc::Sender<WorkerJob>>,
+    outcomes: Option<mpsc::Sender<WorkerOutcome>>,
+    evaluators: Vec<JoinHandle<()>>,
+    writer: JoinHandle<WorkerStats>,
+}
+
+impl WorkerPool {
+    fn start(
+        mut writer: impl Write + Send + 'static,
+        config: EvaluatorConfig,
+        options: &WorkerOptions,
+    ) -> Self {
+        let (jobs, job_receiver) = mpsc::channel::<WorkerJob>();
+        let (outcomes, outcome_receiver) = mpsc::channel::<WorkerOutcome>();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+        let default_evaluator = ImageEvaluator::new(config);
+        let evaluators = (0..options.threads.max(1))
+            .map(|_| {
+                let jobs = Arc::clone(&job_receiver);
+                let outcomes = outcomes.clone();
+                let default_evaluator = default_evaluator.clone();
+                std::thread::spawn(move || loop {
+                    let job = match jobs.lock().expect("worker queue lock").recv() {
+                        Ok(job) => job,
+                        Err(_) => break,
+                    };
+                    let _ = outcomes.send(evaluate_job(&default_evaluator, job));
+                })
+            })
+            .collect();
+        let writer = std::thread::spawn(move || {
+            let mut stats = WorkerStats::default();
+            for outcome in outcome_receiver {
+                stats.processed += 1;
+                if outcome.error.is_none() {
+                    stats.succeeded += 1;
+                } else {
+                    stats.failed += 1;
+                }
+                let record = serde_json::to_string(&outcome).expect("outcome serializes");
+                if writeln!(writer, "{record}").and_then(|()| writer.flush()).is_err() {
+                    break;
+                }
+            }
+            stats
+        });
+        Self {
+            jobs: Some(jobs),
+            outcomes: Some(outcomes),
+            evaluators,
+            writer,
+        }
+    }
+
+    /// Parses one queue line and hands it to the pool; malformed lines
+    /// turn into error records immediately.
+    fn submit_line(&mut self, line: &str) {
+        if line.trim().is_empty() {
+            return;
+        }
+        match serde_json::from_str::<WorkerJob>(line) {
+            Ok(job) => {
+                let _ = self.jobs.as_ref().expect("pool not finished").send(job);
+            }
+            Err(error) => {
+                let _ = self
+                    .outcomes
+                    .as_ref()
+                    .expect("pool not finished")
+                    .send(WorkerOutcome {
+                        id: None,
+                        image: None,
+                        metrics: None,
+                        error: Some(format!("invalid job line: {error}")),
+                        duration_ms: 0,
+                    });
+            }
+        }
+    }
+
+    /// Drains in-flight jobs, shuts the threads down and returns the
+    /// totals.
+    fn finish(mut self) -> WorkerStats {
+        drop(self.jobs.take());
+        for evaluator in self.evaluators.drain(..) {
+            let _ = evaluator.join();
+        }
+        drop(self.outcomes.take());
+        self.writer.join().unwrap_or_default()
+    }
+}
+
+/// Evaluates one job, building a dedicated evaluator when the job
+/// overrides the configuration.
+fn evaluate_job(default_evaluator: &ImageEvaluator, job: WorkerJob) -> WorkerOutcome {
+    let started = Instant::now();
+    let result = match job.config {
+        Some(config) => ImageEvaluator::new(config).evaluate_file(&job.image),
+        None => default_evaluator.evaluate_file(&job.image),
+    };
+    let duration_ms = started.elapsed().as_millis() as u64;
+    match result {
+        Ok(result) => WorkerOutcome {
+            id: job.id,
+            image: Some(job.image),
+            metrics: Some(result.metrics),
+            error: None,
+            duration_ms,
+        },
+        Err(error) => WorkerOutcome {
+            id: job.id,
+            image: Some(job.image),
+            metrics: None,
+            error: Some(error.to_string()),
+            duration_ms,
+        },
+    }
+}
+
+/// The queue files still waiting in `directory`, in name order.
+fn pending_job_files(directory: &Path) -> Result<Vec<PathBuf>, EvaluationError> {
+    let entries = std::fs::read_dir(directory).map_err(|source| EvaluationError::Io {
+        path: directory.to_path_buf(),
+        source,
+    })?;
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(output: &[u8]) -> Vec<WorkerOutcome> {
+        String::from_utf8(output.to_vec())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn malformed_and_failing_jobs_become_error_records() {
+        let input = "not a job\n{\"id\": \"a\", \"image\": \"does-not-exist.png\"}\n";
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let stats = run_worker(
+            input.as_bytes(),
+            SharedWriter(Arc::clone(&output)),
+            EvaluatorConfig::default(),
+            &WorkerOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(stats.processed, 2);
+        assert_eq!(stats.failed, 2);
+        let records = records(&output.lock().unwrap());
+        assert!(records.iter().all(|r| r.error.is_some()));
+        assert!(records.iter().any(|r| r.id.as_deref() == Some("a")));
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn jobs_are_evaluated_and_streamed_as_ndjson() {
+        use image::{Rgba, RgbaImage};
+
+        let directory = std::env::temp_dir().join("evaluator-worker-test");
+        std::fs::create_dir_all(&directory).unwrap();
+        let config = EvaluatorConfig::default();
+        let mut image =
+            RgbaImage::new(config.composite_width() as u32, config.canvas_height as u32);
+        for x in 100..400u32 {
+            image.put_pixel(x, 250, Rgba([0, 0, 0, 255]));
+            image.put_pixel(x + 510, 250, Rgba([0, 0, 0, 255]));
+        }
+        let path = directory.join("perfect.png");
+        image.save(&path).unwrap();
+
+        let input = format!("{{\"id\": \"perfect\", \"image\": {:?}}}\n", path);
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let stats = run_worker(
+            input.as_bytes(),
+            SharedWriter(Arc::clone(&output)),
+            config,
+            &WorkerOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(stats, WorkerStats { processed: 1, succeeded: 1, failed: 0 });
+        let records = records(&output.lock().unwrap());
+        assert_eq!(records[0].id.as_deref(), Some("perfect"));
+        assert_eq!(records[0].metrics.as_ref().unwrap().top_5_error, 0.0);
+    }
+
+    #[test]
+    fn a_stop_file_drains_the_queue_directory_and_shuts_down() {
+        let directory = std::env::temp_dir().join("evaluator-worker-queue-test");
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(
+            directory.join("jobs.json"),
+            "{\"id\": \"q\", \"image\": \"missing.png\"}\n",
+        )
+        .unwrap();
+        std::fs::write(directory.join("stop"), "").unwrap();
+
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let stats = run_queue_directory(
+            &directory,
+            SharedWriter(Arc::clone(&output)),
+            EvaluatorConfig::default(),
+            &WorkerOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(stats.processed, 1);
+        assert!(directory.join("jobs.json.done").exists());
+        assert!(!directory.join("stop").exists());
+        let records = records(&output.lock().unwrap());
+        assert_eq!(records[0].id.as_deref(), Some("q"));
+    }
+
+    /// Lets a test keep reading what the worker's writer thread wrote.
+    struct SharedWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,154 @@
+//! wasm-bindgen surface for the TypeScript app.
+//!
+//! Wraps [`Observation`] in a JS-friendly handle so the frontend can use
+//! the same timing, speed and evaluation logic it would otherwise
+//! reimplement. Compiled only for wasm32 with the `wasm-bindings`
+//! feature enabled.
+
+use wasm_bindgen::prelude::*;
+
+use crate::time::{Clock, PerformanceClock};
+use crate::fast_utils::compute_drawing_speed;
+use crate::image::Image;
+use crate::observation::Observation;
+use std::sync::Arc;
+
+/// An in-progress drawing attempt, driven from JS pointer events.
+#[wasm_bindgen]
+pub struct ObservationHandle {
+    inner: Observation,
+}
+
+#[wasm_bindgen]
+impl ObservationHandle {
+    /// Starts a new observation timed from `performance.now()`.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self {
+            inner: Observation::start_with_clock(Arc::new(PerformanceClock)),
+        }
+    }
+
+    /// Opens a new stroke; call on pointerdown.
+    #[wasm_bindgen(js_name = beginStroke)]
+    pub fn begin_stroke(&mut self) {
+        self.inner.begin_stroke();
+    }
+
+    /// Records a pointer sample; call on pointermove.
+    #[wasm_bindgen(js_name = addPoint)]
+    pub fn add_point(&mut self, x: f64, y: f64) {
+        self.inner.add_point(x, y);
+    }
+
+    /// Marks the attempt as finished; call when the user submits.
+    pub fn finish(&mut self) {
+        self.inner.finish();
+    }
+
+    #[wasm_bindgen(js_name = isFinished)]
+    pub fn is_finished(&self) -> bool {
+        self.inner.is_finished()
+    }
+
+    #[wasm_bindgen(js_name = startedAtMs)]
+    pub fn started_at_ms(&self) -> u64 {
+        self.inner.started_at_ms()
+    }
+
+    #[wasm_bindgen(js_name = durationMs)]
+    pub fn duration_ms(&self) -> Option<u64> {
+        self.inner.duration_ms()
+    }
+
+    #[wasm_bindgen(js_name = totalPoints)]
+    pub fn total_points(&self) -> usize {
+        self.inner.total_points()
+    }
+
+    /// Current average drawing speed in points per second.
+    #[wasm_bindgen(js_name = drawingSpeed)]
+    pub fn drawing_speed(&self) -> f64 {
+        compute_drawing_speed(
+            self.inner.total_points(),
+            self.inner.started_at_ms(),
+            PerformanceClock.now_ms(),
+        )
+    }
+
+    /// Scores the strokes against a reference canvas, given as the flat
+    /// RGBA buffer from `getImageData`. Returns the evaluation result as
+    /// a JSON string, or throws on malformed input.
+    pub fn evaluate(
+        &self,
+        reference_rgba: &[u8],
+        width: usize,
+        height: usize,
+    ) -> Result<String, JsError> {
+        let reference = Image::from_rgba_buffer(reference_rgba, width, height)
+            .map_err(|message| JsError::new(&message))?;
+        let result = self
+            .inner
+            .evaluate_against(&reference)
+            .map_err(|error| JsError::new(&error.to_string()))?;
+        serde_json::to_string(&result).map_err(|error| JsError::new(&error.to_string()))
+    }
+}
+
+impl Default for ObservationHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A reference downsampled for coarse alignment previews. Build once
+/// per exercise; each preview is sub-millisecond, so the app can run
+/// one on every pointer-up without waiting for the full evaluator.
+#[wasm_bindgen]
+pub struct CoarseReferenceHandle {
+    inner: crate::preview::CoarseReference,
+}
+
+#[wasm_bindgen]
+impl CoarseReferenceHandle {
+    /// Downsamples a reference canvas, given as the flat RGBA buffer
+    /// from `getImageData`, to a 64x64 occupancy grid.
+    #[wasm_bindgen(constructor)]
+    pub fn new(reference_rgba: &[u8], width: usize, height: usize) -> Result<CoarseReferenceHandle, JsError> {
+        let reference = Image::from_rgba_buffer(reference_rgba, width, height)
+            .map_err(|message| JsError::new(&message))?;
+        Ok(Self {
+            inner: crate::preview::CoarseReference::from_mask(&reference.to_mask(true)),
+        })
+    }
+
+    /// A rough placement estimate for the strokes drawn so far, as a
+    /// JSON string: `coverage`, `stray`, `offset_x`, `offset_y`.
+    pub fn preview(&self, observation: &ObservationHandle) -> Result<String, JsError> {
+        let preview = self.inner.preview(observation.inner.strokes());
+        serde_json::to_string(&preview).map_err(|error| JsError::new(&error.to_string()))
+    }
+}
+
+/// Distance to the nearest "on" pixel for every position of a flat
+/// row-major mask, as an `Int32Array` — the client uses this for
+/// snapping guides without a round trip. `metric` is `"chessboard"` or
+/// `"manhattan"`; pass a negative `max_distance` for unclamped.
+#[wasm_bindgen(js_name = distanceTransform)]
+pub fn distance_transform(
+    mask: &[u8],
+    width: usize,
+    height: usize,
+    metric: &str,
+    max_distance: i32,
+) -> Result<Vec<i32>, JsError> {
+    let metric = match metric {
+        "chessboard" => evaluator::DistanceMetric::Chessboard,
+        "manhattan" => evaluator::DistanceMetric::Manhattan,
+        other => return Err(JsError::new(&format!("unknown metric: {other}"))),
+    };
+    let clamp = (max_distance >= 0).then_some(max_distance);
+    let distances = evaluator::distance_transform(mask, width, height, metric, clamp)
+        .map_err(|error| JsError::new(&error.to_string()))?;
+    Ok(distances.into_raw_vec_and_offset().0)
+}
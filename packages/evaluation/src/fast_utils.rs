@@ -0,0 +1,27 @@
+//! Small hot-path helpers called at pointer-event frequency.
+
+/// Average drawing speed in points per second between the observation
+/// start and `now_ms`. Returns 0 when no time has elapsed.
+pub fn compute_drawing_speed(total_points: usize, started_at_ms: u64, now_ms: u64) -> f64 {
+    let elapsed_ms = now_ms.saturating_sub(started_at_ms);
+    if elapsed_ms == 0 {
+        return 0.0;
+    }
+    total_points as f64 * 1000.0 / elapsed_ms as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn speed_is_points_per_second() {
+        assert_eq!(compute_drawing_speed(100, 0, 2000), 50.0);
+    }
+
+    #[test]
+    fn zero_elapsed_time_yields_zero_speed() {
+        assert_eq!(compute_drawing_speed(100, 5000, 5000), 0.0);
+        assert_eq!(compute_drawing_speed(100, 5000, 4000), 0.0);
+    }
+}
@@ -0,0 +1,235 @@
+//! Curvature matching for gesture-drawing mode.
+//!
+//! Gesture exercises grade *flow* — did the hand bend where the
+//! reference bends — more than pixel placement, so raster metrics
+//! punish exactly the wrong things. This module works on the
+//! stroke-level API instead: each observation stroke is paired with the
+//! nearest reference stroke, both are resampled to a common arc-length
+//! grid, and their turning-angle (curvature) profiles are compared into
+//! a 0..1 flow score.
+
+use serde::{Deserialize, Serialize};
+
+use crate::input::resample_uniform;
+use crate::observation::{Point, Stroke};
+
+/// Samples each stroke is resampled to before profiles are compared.
+const PROFILE_SAMPLES: usize = 32;
+/// A mean turning-angle difference of this many radians per sample (or
+/// more) scores zero flow. A right-angle-per-sample disagreement is
+/// already a completely different gesture.
+const ZERO_FLOW_DIFFERENCE: f64 = std::f64::consts::FRAC_PI_2;
+
+/// One observation stroke paired with its nearest reference stroke.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StrokeFlowMatch {
+    pub observation_stroke: usize,
+    pub reference_stroke: usize,
+    /// Mean absolute turning-angle difference along the matched
+    /// profiles, radians per sample; 0 for identical flow.
+    pub curvature_difference: f64,
+    /// `1 - difference / ZERO_FLOW_DIFFERENCE`, clamped to 0..1.
+    pub flow_score: f64,
+}
+
+/// Flow comparison of a whole observation against reference strokes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlowReport {
+    pub matches: Vec<StrokeFlowMatch>,
+    /// Mean flow score over the matched pairs, dragged down by a zero
+    /// for every unmatched stroke on either side; 0..1.
+    pub rhythm_score: f64,
+    /// Observation strokes too short to profile, or left over after
+    /// every reference stroke was taken.
+    pub unmatched_observation_strokes: usize,
+    /// Reference strokes no observation stroke paired with.
+    pub unmatched_reference_strokes: usize,
+}
+
+/// Compares curvature profiles of observation strokes against the
+/// reference's strokes. Pairing is greedy by centroid distance —
+/// closest pairs first, each stroke used once — which matches how
+/// gesture references are drawn: one reference path per intended
+/// stroke.
+pub fn flow_report(observation: &[Stroke], reference: &[Stroke]) -> FlowReport {
+    let observation_profiles: Vec<_> = observation.iter().map(profile).collect();
+    let reference_profiles: Vec<_> = reference.iter().map(profile).collect();
+
+    let mut candidates: Vec<(f64, usize, usize)> = Vec::new();
+    for (o, observed) in observation_profiles.iter().enumerate() {
+        for (r, expected) in reference_profiles.iter().enumerate() {
+            if let (Some(observed), Some(expected)) = (observed, expected) {
+                let distance = centroid_distance(observed.centroid, expected.centroid);
+                candidates.push((distance, o, r));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut observation_taken = vec![false; observation.len()];
+    let mut reference_taken = vec![false; reference.len()];
+    let mut matches = Vec::new();
+    for (_, o, r) in candidates {
+        if observation_taken[o] || reference_taken[r] {
+            continue;
+        }
+        observation_taken[o] = true;
+        reference_taken[r] = true;
+        let observed = observation_profiles[o].as_ref().expect("profiled above");
+        let expected = reference_profiles[r].as_ref().expect("profiled above");
+        let curvature_difference = observed
+            .turns
+            .iter()
+            .zip(&expected.turns)
+            .map(|(a, b)| (a - b).abs())
+            .sum::<f64>()
+            / observed.turns.len().max(1) as f64;
+        matches.push(StrokeFlowMatch {
+            observation_stroke: o,
+            reference_stroke: r,
+            curvature_difference,
+            flow_score: (1.0 - curvature_difference / ZERO_FLOW_DIFFERENCE).clamp(0.0, 1.0),
+        });
+    }
+
+    let unmatched_observation_strokes = observation_taken.iter().filter(|&&t| !t).count();
+    let unmatched_reference_strokes = reference_taken.iter().filter(|&&t| !t).count();
+    let graded = matches.len() + unmatched_observation_strokes + unmatched_reference_strokes;
+    let rhythm_score = if graded == 0 {
+        0.0
+    } else {
+        matches.iter().map(|m| m.flow_score).sum::<f64>() / graded as f64
+    };
+    FlowReport {
+        matches,
+        rhythm_score,
+        unmatched_observation_strokes,
+        unmatched_reference_strokes,
+    }
+}
+
+/// A stroke's comparable shape: its centroid plus turning angles at
+/// each of the evenly-spaced profile samples.
+struct Profile {
+    centroid: (f64, f64),
+    turns: Vec<f64>,
+}
+
+/// Profiles a stroke, `None` when it is too short to have a direction.
+fn profile(stroke: &Stroke) -> Option<Profile> {
+    let points = &stroke.points;
+    if points.len() < 2 {
+        return None;
+    }
+    let length: f64 = points
+        .windows(2)
+        .map(|pair| ((pair[1].x - pair[0].x).powi(2) + (pair[1].y - pair[0].y).powi(2)).sqrt())
+        .sum();
+    if length == 0.0 {
+        return None;
+    }
+    let spacing = length / (PROFILE_SAMPLES - 1) as f64;
+    let resampled = resample_uniform(points, spacing);
+    let turns = resampled
+        .windows(3)
+        .map(|window| turning_angle(window[0], window[1], window[2]))
+        .collect();
+    let count = points.len() as f64;
+    let centroid = (
+        points.iter().map(|p| p.x).sum::<f64>() / count,
+        points.iter().map(|p| p.y).sum::<f64>() / count,
+    );
+    Some(Profile { centroid, turns })
+}
+
+/// Signed turning angle at `b` between segments `a→b` and `b→c`,
+/// radians; positive bends keep their sign so mirrored gestures differ.
+fn turning_angle(a: Point, b: Point, c: Point) -> f64 {
+    let (ux, uy) = (b.x - a.x, b.y - a.y);
+    let (vx, vy) = (c.x - b.x, c.y - b.y);
+    (ux * vy - uy * vx).atan2(ux * vx + uy * vy)
+}
+
+fn centroid_distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stroke(points: impl IntoIterator<Item = (f64, f64)>) -> Stroke {
+        Stroke {
+            points: points
+                .into_iter()
+                .enumerate()
+                .map(|(i, (x, y))| Point {
+                    x,
+                    y,
+                    t_ms: i as u64 * 10,
+                    pressure: 1.0,
+                })
+                .collect(),
+        }
+    }
+
+    fn arc(center: (f64, f64), radius: f64) -> Stroke {
+        stroke((0..=20).map(|i| {
+            let angle = std::f64::consts::PI * i as f64 / 20.0;
+            (
+                center.0 + radius * angle.cos(),
+                center.1 - radius * angle.sin(),
+            )
+        }))
+    }
+
+    #[test]
+    fn identical_gestures_score_full_flow() {
+        let reference = [arc((100.0, 100.0), 50.0)];
+        let report = flow_report(&reference, &reference);
+        assert_eq!(report.matches.len(), 1);
+        assert!(report.matches[0].curvature_difference < 1e-9);
+        assert!((report.rhythm_score - 1.0).abs() < 1e-9);
+        assert_eq!(report.unmatched_observation_strokes, 0);
+        assert_eq!(report.unmatched_reference_strokes, 0);
+    }
+
+    #[test]
+    fn a_straight_slash_through_a_curve_loses_flow() {
+        let reference = [arc((100.0, 100.0), 50.0)];
+        let slash = [stroke([(50.0, 100.0), (150.0, 100.0)])];
+        let report = flow_report(&slash, &reference);
+        let flat = &report.matches[0];
+        // The arc turns ~6 degrees per sample; the slash never turns.
+        assert!(flat.curvature_difference > 0.05, "{flat:?}");
+        assert!(flat.flow_score < 0.95, "{flat:?}");
+        assert!(report.rhythm_score < 0.95);
+    }
+
+    #[test]
+    fn strokes_pair_with_their_nearest_reference_path() {
+        let reference = [arc((100.0, 100.0), 40.0), stroke([(300.0, 50.0), (300.0, 150.0)])];
+        let observation = [stroke([(310.0, 60.0), (310.0, 160.0)]), arc((110.0, 110.0), 40.0)];
+        let report = flow_report(&observation, &reference);
+        let pairing: Vec<(usize, usize)> = report
+            .matches
+            .iter()
+            .map(|m| (m.observation_stroke, m.reference_stroke))
+            .collect();
+        assert!(pairing.contains(&(0, 1)), "{pairing:?}");
+        assert!(pairing.contains(&(1, 0)), "{pairing:?}");
+        assert!(report.rhythm_score > 0.9);
+    }
+
+    #[test]
+    fn unmatched_strokes_drag_the_rhythm_score_down() {
+        let reference = [arc((100.0, 100.0), 50.0), arc((300.0, 100.0), 50.0)];
+        let observation = [arc((100.0, 100.0), 50.0)];
+        let report = flow_report(&observation, &reference);
+        assert_eq!(report.matches.len(), 1);
+        assert_eq!(report.unmatched_reference_strokes, 1);
+        assert!((report.rhythm_score - 0.5).abs() < 0.01);
+        // Nothing drawn at all scores zero.
+        assert_eq!(flow_report(&[], &reference).rhythm_score, 0.0);
+    }
+}
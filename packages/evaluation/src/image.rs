@@ -0,0 +1,211 @@
+use ndarray::{Array2, Array3};
+
+/// An RGBA raster held as rows of pixels, mirroring the flat buffer the
+/// drawing app uploads from a canvas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Vec<[u8; 4]>>,
+}
+
+impl Image {
+    /// Creates a fully transparent image.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![vec![[0; 4]; width]; height],
+        }
+    }
+
+    /// Builds an image from a flat RGBA buffer (`width * height * 4` bytes).
+    pub fn from_rgba_buffer(buffer: &[u8], width: usize, height: usize) -> Result<Self, String> {
+        if buffer.len() != width * height * 4 {
+            return Err(format!(
+                "buffer has {} bytes, expected {} for {width}x{height} RGBA",
+                buffer.len(),
+                width * height * 4
+            ));
+        }
+        let pixels = buffer
+            .chunks_exact(width * 4)
+            .map(|row| {
+                row.chunks_exact(4)
+                    .map(|p| [p[0], p[1], p[2], p[3]])
+                    .collect()
+            })
+            .collect();
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The RGBA value at `(x, y)`, or `None` outside the image.
+    pub fn pixel(&self, x: usize, y: usize) -> Option<[u8; 4]> {
+        self.pixels.get(y).and_then(|row| row.get(x)).copied()
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, rgba: [u8; 4]) {
+        if let Some(pixel) = self.pixels.get_mut(y).and_then(|row| row.get_mut(x)) {
+            *pixel = rgba;
+        }
+    }
+
+    /// Extracts the binary stroke mask the lib evaluator consumes,
+    /// honouring the background mode: the alpha channel for transparent
+    /// canvas exports, the red channel (dark ink on white) otherwise.
+    pub fn to_mask(&self, transparent_background: bool) -> Array2<u8> {
+        let mut mask = Array2::zeros((self.height, self.width));
+        for ((y, x), value) in mask.indexed_iter_mut() {
+            let pixel = self.pixels[y][x];
+            let on = if transparent_background {
+                pixel[3] >= 128
+            } else {
+                pixel[0] < 128
+            };
+            if on {
+                *value = 1;
+            }
+        }
+        mask
+    }
+
+    /// The image as a `(height, width, 4)` RGBA tensor.
+    pub fn to_ndarray(&self) -> Array3<u8> {
+        let mut tensor = Array3::zeros((self.height, self.width, 4));
+        for ((y, x, channel), value) in tensor.indexed_iter_mut() {
+            *value = self.pixels[y][x][channel];
+        }
+        tensor
+    }
+
+    /// Builds an image from a `(height, width, 4)` RGBA tensor.
+    pub fn from_ndarray(tensor: &Array3<u8>) -> Result<Self, String> {
+        let (height, width, channels) = tensor.dim();
+        if channels != 4 {
+            return Err(format!("tensor has {channels} channels, expected 4 (RGBA)"));
+        }
+        let mut image = Self::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                image.pixels[y][x] =
+                    [0, 1, 2, 3].map(|channel| tensor[(y, x, channel)]);
+            }
+        }
+        Ok(image)
+    }
+
+    /// The red channel as a `(height, width)` array, for opaque exports
+    /// where ink is dark on white.
+    pub fn red_channel(&self) -> Array2<u8> {
+        self.channel(0)
+    }
+
+    /// The alpha channel as a `(height, width)` array, for transparent
+    /// exports where ink is any opaque pixel.
+    pub fn alpha_channel(&self) -> Array2<u8> {
+        self.channel(3)
+    }
+
+    fn channel(&self, channel: usize) -> Array2<u8> {
+        let mut values = Array2::zeros((self.height, self.width));
+        for ((y, x), value) in values.indexed_iter_mut() {
+            *value = self.pixels[y][x][channel];
+        }
+        values
+    }
+
+    /// Flattens the image back into the canvas buffer layout.
+    pub fn to_rgba_buffer(&self) -> Vec<u8> {
+        let mut buffer = Vec::with_capacity(self.width * self.height * 4);
+        for row in &self.pixels {
+            for pixel in row {
+                buffer.extend_from_slice(pixel);
+            }
+        }
+        buffer
+    }
+}
+
+/// Default conversion for transparent canvas exports; use
+/// [`Image::to_mask`] to select the background mode explicitly.
+impl From<&Image> for Array2<u8> {
+    fn from(image: &Image) -> Self {
+        image.to_mask(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mask_honours_the_background_mode() {
+        let mut image = Image::new(2, 2);
+        image.set_pixel(0, 0, [0, 0, 0, 255]); // opaque ink
+        image.set_pixel(1, 1, [255, 255, 255, 0]); // transparent white
+        let transparent = image.to_mask(true);
+        assert_eq!(transparent[(0, 0)], 1);
+        assert_eq!(transparent[(1, 1)], 0);
+        let opaque = image.to_mask(false);
+        assert_eq!(opaque[(0, 0)], 1);
+        assert_eq!(opaque[(1, 1)], 0);
+        assert_eq!(Array2::from(&image), transparent);
+    }
+
+    #[test]
+    fn buffer_round_trip_preserves_pixels() {
+        let buffer: Vec<u8> = (0..2 * 2 * 4).map(|i| i as u8).collect();
+        let image = Image::from_rgba_buffer(&buffer, 2, 2).unwrap();
+        assert_eq!(image.pixel(1, 0), Some([4, 5, 6, 7]));
+        assert_eq!(image.to_rgba_buffer(), buffer);
+    }
+
+    #[test]
+    fn ndarray_round_trip_preserves_pixels() {
+        let buffer: Vec<u8> = (0..2 * 2 * 4).map(|i| i as u8).collect();
+        let image = Image::from_rgba_buffer(&buffer, 2, 2).unwrap();
+        let tensor = image.to_ndarray();
+        assert_eq!(tensor.dim(), (2, 2, 4));
+        assert_eq!(tensor[(0, 1, 2)], 6);
+        assert_eq!(Image::from_ndarray(&tensor).unwrap(), image);
+    }
+
+    #[test]
+    fn tensors_without_four_channels_are_rejected() {
+        let tensor = Array3::zeros((2, 2, 3));
+        assert!(Image::from_ndarray(&tensor).is_err());
+    }
+
+    #[test]
+    fn channel_helpers_extract_single_planes() {
+        let mut image = Image::new(2, 1);
+        image.set_pixel(0, 0, [10, 20, 30, 40]);
+        image.set_pixel(1, 0, [50, 60, 70, 80]);
+        assert_eq!(image.red_channel()[(0, 1)], 50);
+        assert_eq!(image.alpha_channel()[(0, 0)], 40);
+    }
+
+    #[test]
+    fn mismatched_buffer_length_is_rejected() {
+        assert!(Image::from_rgba_buffer(&[0; 10], 2, 2).is_err());
+    }
+
+    #[test]
+    fn out_of_bounds_access_is_safe() {
+        let mut image = Image::new(2, 2);
+        image.set_pixel(5, 5, [1, 2, 3, 4]);
+        assert_eq!(image.pixel(5, 5), None);
+    }
+}
@@ -0,0 +1,188 @@
+//! Pointer-input cleanup applied to strokes before rasterization.
+//!
+//! Raw pointer events arrive at display frequency and produce thousands
+//! of nearly-duplicate samples per stroke. These helpers reduce them to
+//! a clean polyline: simplify to drop redundant points, smooth to round
+//! off sampling jitter, and resample to an even spacing.
+
+use crate::observation::Point;
+
+/// Ramer–Douglas–Peucker simplification: drops every point closer than
+/// `epsilon` pixels to the line between its retained neighbours.
+/// Endpoints and their timestamps are always kept.
+pub fn simplify_rdp(points: &[Point], epsilon: f64) -> Vec<Point> {
+    if points.len() <= 2 {
+        return points.to_vec();
+    }
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+    rdp_mark(points, 0, points.len() - 1, epsilon, &mut keep);
+    points
+        .iter()
+        .zip(&keep)
+        .filter_map(|(&point, &kept)| kept.then_some(point))
+        .collect()
+}
+
+fn rdp_mark(points: &[Point], first: usize, last: usize, epsilon: f64, keep: &mut [bool]) {
+    if last <= first + 1 {
+        return;
+    }
+    let (mut furthest, mut max_distance) = (first, 0.0);
+    for index in first + 1..last {
+        let distance = perpendicular_distance(points[index], points[first], points[last]);
+        if distance > max_distance {
+            furthest = index;
+            max_distance = distance;
+        }
+    }
+    if max_distance > epsilon {
+        keep[furthest] = true;
+        rdp_mark(points, first, furthest, epsilon, keep);
+        rdp_mark(points, furthest, last, epsilon, keep);
+    }
+}
+
+/// Distance from `point` to the segment `start`..`end`, falling back to
+/// point distance when the segment is degenerate.
+fn perpendicular_distance(point: Point, start: Point, end: Point) -> f64 {
+    let (dx, dy) = (end.x - start.x, end.y - start.y);
+    let length = (dx * dx + dy * dy).sqrt();
+    if length == 0.0 {
+        return ((point.x - start.x).powi(2) + (point.y - start.y).powi(2)).sqrt();
+    }
+    ((point.x - start.x) * dy - (point.y - start.y) * dx).abs() / length
+}
+
+/// Resamples a polyline to points an even `spacing` pixels apart along
+/// its arc length. The first and last input points are always included;
+/// timestamps are interpolated linearly along each segment.
+pub fn resample_uniform(points: &[Point], spacing: f64) -> Vec<Point> {
+    if points.len() <= 1 || spacing <= 0.0 {
+        return points.to_vec();
+    }
+    let mut resampled = vec![points[0]];
+    let mut carried = 0.0;
+    for pair in points.windows(2) {
+        let (from, to) = (pair[0], pair[1]);
+        let length = ((to.x - from.x).powi(2) + (to.y - from.y).powi(2)).sqrt();
+        if length == 0.0 {
+            continue;
+        }
+        let mut travelled = spacing - carried;
+        while travelled <= length {
+            let t = travelled / length;
+            resampled.push(lerp(from, to, t));
+            travelled += spacing;
+        }
+        carried = (carried + length) % spacing;
+    }
+    let last = *points.last().expect("checked non-empty above");
+    if resampled.last() != Some(&last) {
+        resampled.push(last);
+    }
+    resampled
+}
+
+/// Smooths a polyline with a uniform Catmull-Rom spline, emitting
+/// `samples_per_segment` points between each pair of input points. The
+/// curve passes through every input point, so the stroke shape is kept
+/// while sampling jitter is rounded off.
+pub fn smooth_catmull_rom(points: &[Point], samples_per_segment: usize) -> Vec<Point> {
+    if points.len() < 3 || samples_per_segment == 0 {
+        return points.to_vec();
+    }
+    let mut smoothed = vec![points[0]];
+    for segment in 0..points.len() - 1 {
+        // Clamp the outer control points at the stroke ends.
+        let p0 = points[segment.saturating_sub(1)];
+        let p1 = points[segment];
+        let p2 = points[segment + 1];
+        let p3 = points[(segment + 2).min(points.len() - 1)];
+        for sample in 1..=samples_per_segment {
+            let t = sample as f64 / samples_per_segment as f64;
+            smoothed.push(catmull_rom_point(p0, p1, p2, p3, t));
+        }
+    }
+    smoothed
+}
+
+fn catmull_rom_point(p0: Point, p1: Point, p2: Point, p3: Point, t: f64) -> Point {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    let basis = |a: f64, b: f64, c: f64, d: f64| {
+        0.5 * ((2.0 * b) + (-a + c) * t + (2.0 * a - 5.0 * b + 4.0 * c - d) * t2
+            + (-a + 3.0 * b - 3.0 * c + d) * t3)
+    };
+    Point {
+        x: basis(p0.x, p1.x, p2.x, p3.x),
+        y: basis(p0.y, p1.y, p2.y, p3.y),
+        t_ms: lerp_t(p1.t_ms, p2.t_ms, t),
+        pressure: p1.pressure + (p2.pressure - p1.pressure) * t,
+    }
+}
+
+fn lerp(from: Point, to: Point, t: f64) -> Point {
+    Point {
+        x: from.x + (to.x - from.x) * t,
+        y: from.y + (to.y - from.y) * t,
+        t_ms: lerp_t(from.t_ms, to.t_ms, t),
+        pressure: from.pressure + (to.pressure - from.pressure) * t,
+    }
+}
+
+fn lerp_t(from: u64, to: u64, t: f64) -> u64 {
+    (from as f64 + (to as f64 - from as f64) * t).round() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: f64, y: f64, t_ms: u64) -> Point {
+        Point {
+            x,
+            y,
+            t_ms,
+            pressure: 1.0,
+        }
+    }
+
+    #[test]
+    fn rdp_collapses_collinear_points() {
+        let points: Vec<Point> = (0..50).map(|i| point(i as f64, 0.0, i)).collect();
+        let simplified = simplify_rdp(&points, 0.5);
+        assert_eq!(simplified.len(), 2);
+        assert_eq!(simplified[0], points[0]);
+        assert_eq!(simplified[1], points[49]);
+    }
+
+    #[test]
+    fn rdp_keeps_a_significant_corner() {
+        let mut points: Vec<Point> = (0..10).map(|i| point(i as f64, 0.0, i)).collect();
+        points.extend((1..10).map(|i| point(9.0, i as f64, 9 + i)));
+        let simplified = simplify_rdp(&points, 0.5);
+        assert_eq!(simplified.len(), 3);
+        assert_eq!((simplified[1].x, simplified[1].y), (9.0, 0.0));
+    }
+
+    #[test]
+    fn resampling_spaces_points_evenly() {
+        let points = [point(0.0, 0.0, 0), point(10.0, 0.0, 100)];
+        let resampled = resample_uniform(&points, 2.5);
+        let xs: Vec<f64> = resampled.iter().map(|p| p.x).collect();
+        assert_eq!(xs, vec![0.0, 2.5, 5.0, 7.5, 10.0]);
+        assert_eq!(resampled[1].t_ms, 25);
+    }
+
+    #[test]
+    fn smoothing_passes_through_input_points() {
+        let points = [point(0.0, 0.0, 0), point(5.0, 5.0, 50), point(10.0, 0.0, 100)];
+        let smoothed = smooth_catmull_rom(&points, 4);
+        assert_eq!((smoothed[0].x, smoothed[0].y), (0.0, 0.0));
+        assert_eq!((smoothed[4].x, smoothed[4].y), (5.0, 5.0));
+        assert_eq!((smoothed[8].x, smoothed[8].y), (10.0, 0.0));
+        assert_eq!(smoothed.len(), 9);
+    }
+}
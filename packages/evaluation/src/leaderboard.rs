@@ -0,0 +1,334 @@
+//! Ranked standings computed from many users' scored attempts.
+//!
+//! The app reports one [`DrawingReport`] per evaluated attempt; this
+//! module aggregates them into per-exercise standings for the social
+//! features. Everything is serializable so standings can be cached and
+//! shipped to clients as-is.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::observation::SpeedAnalytics;
+
+/// Milliseconds in the weekly-improvement window.
+const WEEK_MS: u64 = 7 * 24 * 60 * 60 * 1000;
+
+/// One scored attempt, as reported by the app after evaluation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DrawingReport {
+    pub exercise_id: String,
+    pub user_id: String,
+    /// Badness-style score; lower is better.
+    pub score: f64,
+    pub duration_ms: u64,
+    /// When the attempt finished, in milliseconds since the Unix epoch.
+    pub completed_at_ms: u64,
+    /// Detail metrics for rubric grading; older reports omit them.
+    #[serde(default)]
+    pub top_5_error: Option<f64>,
+    #[serde(default)]
+    pub coverage: Option<f64>,
+    /// Line-quality score in `0..=1`, from the segment comparison.
+    #[serde(default)]
+    pub line_quality: Option<f64>,
+    /// Speed analytics of the attempt; older reports omit them.
+    #[serde(default)]
+    pub speed: Option<SpeedAnalytics>,
+}
+
+/// One user's row in an exercise leaderboard.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Standing {
+    /// 1-based position; equal best scores still get distinct ranks via
+    /// the tie-breaking rules.
+    pub rank: usize,
+    pub user_id: String,
+    pub best_score: f64,
+    /// Duration of the attempt that produced the best score.
+    pub best_duration_ms: u64,
+    pub attempts: usize,
+    /// Fraction of ranked users this user scores at least as well as,
+    /// in percent. The leader is at 100.
+    pub percentile: f64,
+    /// Best score before the last seven days minus the best within
+    /// them; positive means the user improved. `None` until the user
+    /// has attempts on both sides of the window.
+    pub weekly_improvement: Option<f64>,
+}
+
+/// Computes ranked standings from every report for a single exercise.
+/// Users are ordered by best score, ties broken by shorter duration,
+/// then earlier completion, then user id for determinism. `now_ms`
+/// anchors the weekly-improvement window.
+pub fn compute_standings(reports: &[DrawingReport], now_ms: u64) -> Vec<Standing> {
+    let mut by_user: BTreeMap<&str, Vec<&DrawingReport>> = BTreeMap::new();
+    for report in reports {
+        by_user.entry(&report.user_id).or_default().push(report);
+    }
+    let mut standings: Vec<Standing> = by_user
+        .into_iter()
+        .map(|(user_id, attempts)| {
+            let best = attempts
+                .iter()
+                .copied()
+                .min_by(compare_attempts)
+                .expect("every grouped user has at least one attempt");
+            Standing {
+                rank: 0,
+                user_id: user_id.to_string(),
+                best_score: best.score,
+                best_duration_ms: best.duration_ms,
+                attempts: attempts.len(),
+                percentile: 0.0,
+                weekly_improvement: weekly_improvement(&attempts, now_ms),
+            }
+        })
+        .collect();
+    standings.sort_by(|a, b| {
+        a.best_score
+            .total_cmp(&b.best_score)
+            .then(a.best_duration_ms.cmp(&b.best_duration_ms))
+            .then(a.user_id.cmp(&b.user_id))
+    });
+    let total = standings.len();
+    for (index, standing) in standings.iter_mut().enumerate() {
+        standing.rank = index + 1;
+        standing.percentile = (total - index) as f64 / total as f64 * 100.0;
+    }
+    standings
+}
+
+/// Computes standings for every exercise present in the reports.
+pub fn standings_by_exercise(
+    reports: &[DrawingReport],
+    now_ms: u64,
+) -> BTreeMap<String, Vec<Standing>> {
+    let mut by_exercise: BTreeMap<&str, Vec<DrawingReport>> = BTreeMap::new();
+    for report in reports {
+        by_exercise
+            .entry(&report.exercise_id)
+            .or_default()
+            .push(report.clone());
+    }
+    by_exercise
+        .into_iter()
+        .map(|(exercise_id, reports)| {
+            (exercise_id.to_string(), compute_standings(&reports, now_ms))
+        })
+        .collect()
+}
+
+/// Orders two attempts best-first: lower score, then shorter duration,
+/// then earlier completion.
+fn compare_attempts(a: &&DrawingReport, b: &&DrawingReport) -> std::cmp::Ordering {
+    a.score
+        .total_cmp(&b.score)
+        .then(a.duration_ms.cmp(&b.duration_ms))
+        .then(a.completed_at_ms.cmp(&b.completed_at_ms))
+}
+
+/// Best score before the weekly window minus the best score within it.
+fn weekly_improvement(attempts: &[&DrawingReport], now_ms: u64) -> Option<f64> {
+    let window_start = now_ms.saturating_sub(WEEK_MS);
+    let best_within = attempts
+        .iter()
+        .filter(|r| r.completed_at_ms >= window_start)
+        .map(|r| r.score)
+        .min_by(f64::total_cmp)?;
+    let best_before = attempts
+        .iter()
+        .filter(|r| r.completed_at_ms < window_start)
+        .map(|r| r.score)
+        .min_by(f64::total_cmp)?;
+    Some(best_before - best_within)
+}
+
+/// The evaluator's JSON Schema document extended with the payloads this
+/// crate adds on top of it: [`DrawingReport`] and its nested speed
+/// analytics. One document covers everything the app persists, so the
+/// TS frontend codegens all of its interfaces from a single source.
+pub fn json_schema() -> serde_json::Value {
+    let mut schema = evaluator::schema::json_schema();
+    let defs = schema["$defs"]
+        .as_object_mut()
+        .expect("the evaluator schema keeps its definitions under $defs");
+    defs.insert(
+        "DrawingReport".into(),
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "exercise_id": { "type": "string" },
+                "user_id": { "type": "string" },
+                "score": { "type": "number" },
+                "duration_ms": { "type": "integer" },
+                "completed_at_ms": { "type": "integer" },
+                "top_5_error": { "anyOf": [{ "type": "number" }, { "type": "null" }] },
+                "coverage": { "anyOf": [{ "type": "number" }, { "type": "null" }] },
+                "line_quality": { "anyOf": [{ "type": "number" }, { "type": "null" }] },
+                "speed": { "anyOf": [{ "$ref": "#/$defs/SpeedAnalytics" }, { "type": "null" }] },
+            },
+            "required": ["exercise_id", "user_id", "score", "duration_ms", "completed_at_ms"],
+        }),
+    );
+    defs.insert(
+        "SpeedAnalytics".into(),
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "bucket_width": { "type": "number" },
+                "histogram": { "type": "array", "items": { "type": "integer" } },
+                "median_speed": { "type": "number" },
+                "rushed_threshold": { "type": "number" },
+                "dwell_ms": { "type": "integer" },
+                "rushed": { "type": "array", "items": { "$ref": "#/$defs/RushedSegment" } },
+            },
+            "required": [
+                "bucket_width", "histogram", "median_speed", "rushed_threshold",
+                "dwell_ms", "rushed",
+            ],
+        }),
+    );
+    defs.insert(
+        "RushedSegment".into(),
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "stroke": { "type": "integer" },
+                "from": { "$ref": "#/$defs/Point" },
+                "to": { "$ref": "#/$defs/Point" },
+                "speed": { "type": "number" },
+                "in_high_error_region": { "type": "boolean" },
+            },
+            "required": ["stroke", "from", "to", "speed", "in_high_error_region"],
+        }),
+    );
+    defs.insert(
+        "Point".into(),
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "x": { "type": "number" },
+                "y": { "type": "number" },
+                "t_ms": { "type": "integer" },
+                "pressure": { "type": "number" },
+            },
+            "required": ["x", "y", "t_ms"],
+        }),
+    );
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(user_id: &str, score: f64, duration_ms: u64, completed_at_ms: u64) -> DrawingReport {
+        DrawingReport {
+            exercise_id: "cat-01".into(),
+            user_id: user_id.into(),
+            score,
+            duration_ms,
+            completed_at_ms,
+            top_5_error: None,
+            coverage: None,
+            line_quality: None,
+            speed: None,
+        }
+    }
+
+    #[test]
+    fn standings_rank_by_best_score_then_duration() {
+        let reports = [
+            report("ana", 2.0, 30_000, 1_000),
+            report("ana", 1.0, 40_000, 2_000),
+            report("bo", 1.0, 20_000, 3_000),
+            report("cy", 3.0, 10_000, 4_000),
+        ];
+        let standings = compute_standings(&reports, 5_000);
+        let order: Vec<&str> = standings.iter().map(|s| s.user_id.as_str()).collect();
+        // bo wins the score tie with ana on duration.
+        assert_eq!(order, vec!["bo", "ana", "cy"]);
+        assert_eq!(standings[0].rank, 1);
+        assert_eq!(standings[1].attempts, 2);
+        assert_eq!(standings[0].percentile, 100.0);
+        assert!((standings[2].percentile - 100.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn weekly_improvement_needs_attempts_on_both_sides_of_the_window() {
+        let now = WEEK_MS * 4;
+        let reports = [
+            report("ana", 5.0, 30_000, now - 2 * WEEK_MS),
+            report("ana", 2.0, 30_000, now - 1_000),
+            report("bo", 1.0, 20_000, now - 1_000),
+        ];
+        let standings = compute_standings(&reports, now);
+        let ana = standings.iter().find(|s| s.user_id == "ana").unwrap();
+        assert_eq!(ana.weekly_improvement, Some(3.0));
+        let bo = standings.iter().find(|s| s.user_id == "bo").unwrap();
+        assert_eq!(bo.weekly_improvement, None);
+    }
+
+    #[test]
+    fn exercises_are_ranked_independently() {
+        let mut reports = vec![report("ana", 1.0, 30_000, 1_000)];
+        reports.push(DrawingReport {
+            exercise_id: "tree-02".into(),
+            ..report("bo", 2.0, 30_000, 1_000)
+        });
+        let by_exercise = standings_by_exercise(&reports, 5_000);
+        assert_eq!(by_exercise.len(), 2);
+        assert_eq!(by_exercise["cat-01"][0].user_id, "ana");
+        assert_eq!(by_exercise["tree-02"][0].percentile, 100.0);
+    }
+
+    /// Every serialized field must be described and vice versa, so the
+    /// hand-written schema cannot drift from the report layout.
+    fn assert_matches_def(schema: &serde_json::Value, name: &str, value: &serde_json::Value) {
+        let properties = schema["$defs"][name]["properties"].as_object().unwrap();
+        let fields = value.as_object().unwrap();
+        for key in fields.keys() {
+            assert!(
+                properties.contains_key(key),
+                "the {name} schema is missing serialized field {key}"
+            );
+        }
+        for key in properties.keys() {
+            assert!(
+                fields.contains_key(key),
+                "the {name} schema lists {key}, which no longer serializes"
+            );
+        }
+    }
+
+    #[test]
+    fn the_schema_tracks_the_report_layout_on_top_of_the_evaluators() {
+        use crate::observation::{Point, RushedSegment};
+
+        let point = |x: f64, t_ms: u64| Point { x, y: 0.0, t_ms, pressure: 1.0 };
+        let mut full = report("ana", 1.0, 30_000, 1_000);
+        full.speed = Some(SpeedAnalytics {
+            bucket_width: 100.0,
+            histogram: vec![1],
+            median_speed: 400.0,
+            rushed_threshold: 800.0,
+            dwell_ms: 50,
+            rushed: vec![RushedSegment {
+                stroke: 0,
+                from: point(0.0, 0),
+                to: point(90.0, 100),
+                speed: 900.0,
+                in_high_error_region: false,
+            }],
+        });
+        let value = serde_json::to_value(&full).unwrap();
+        let schema = json_schema();
+        assert_matches_def(&schema, "DrawingReport", &value);
+        assert_matches_def(&schema, "SpeedAnalytics", &value["speed"]);
+        assert_matches_def(&schema, "RushedSegment", &value["speed"]["rushed"][0]);
+        // The evaluator's own definitions come along in the one document.
+        assert!(schema["$defs"]["EvaluationResult"].is_object());
+        assert!(schema["$defs"]["StreamingEvaluatorState"].is_object());
+    }
+}
@@ -0,0 +1,45 @@
+//! Session, timing and image helpers shared with the VisualArt drawing app.
+//!
+//! This package mirrors the concepts the TypeScript frontend works with —
+//! raw RGBA canvases, strokes with timestamps, and observation sessions —
+//! and keeps the timing/speed logic in one place so the app and the
+//! evaluator agree on them.
+
+#[cfg(all(target_arch = "wasm32", feature = "wasm-bindings"))]
+pub mod bindings;
+pub mod fast_utils;
+pub mod gesture;
+pub mod image;
+pub mod input;
+pub mod leaderboard;
+pub mod lifecycle;
+pub mod observation;
+pub mod phase;
+pub mod preview;
+pub mod replay;
+pub mod rubric;
+pub mod session;
+pub mod time;
+/// Webhook delivery opens TCP sockets; in the browser the network
+/// belongs to JS.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod webhook;
+
+pub use gesture::{flow_report, FlowReport, StrokeFlowMatch};
+pub use image::Image;
+pub use leaderboard::{json_schema, DrawingReport, Standing};
+pub use lifecycle::{
+    IllegalTransition, LoggedTransition, SessionEvent, SessionLifecycle, SessionState,
+};
+pub use observation::{
+    write_provenance_csv, Brush, Observation, PixelProvenance, Point, RushedSegment,
+    SpeedAccuracyCurve, SpeedAccuracySample, SpeedAnalytics, Stroke,
+};
+pub use phase::{segment_phases, DrawingPhase, PhaseReport, PhaseSegment};
+pub use preview::{AlignmentPreview, CoarseReference, PREVIEW_SIZE};
+pub use replay::{ReplayDriver, ReplaySample};
+pub use rubric::{CriterionGrade, Rubric, RubricCriterion, RubricGrade, RubricMetric};
+pub use session::Session;
+pub use time::{now_ms, Clock, MockClock, SystemClock};
+#[cfg(not(target_arch = "wasm32"))]
+pub use webhook::{WebhookConfig, WebhookDelivery, WebhookEmitter, WebhookTrigger};
@@ -0,0 +1,177 @@
+//! Explicit session lifecycle state machine.
+//!
+//! The lifecycle of an exercise run — created, observing the reference,
+//! drawing, finished, evaluated — has so far been implicit: `Session`
+//! only knows whether an observation exists, and servers reconstruct
+//! the rest from timestamps. This module makes the states and events
+//! explicit, rejects illegal transitions instead of silently accepting
+//! them, and keeps a serializable transition log for audit trails.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Where a session currently is in its lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionState {
+    #[default]
+    Created,
+    /// The user is studying the reference before drawing.
+    Observing,
+    Drawing,
+    Paused,
+    Finished,
+    Evaluated,
+}
+
+/// Something that happened to a session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionEvent {
+    ObservationStarted,
+    DrawingStarted,
+    StrokeAdded,
+    Paused,
+    Resumed,
+    Finished,
+    Evaluated,
+}
+
+/// An event arrived in a state that does not accept it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IllegalTransition {
+    pub state: SessionState,
+    pub event: SessionEvent,
+}
+
+impl fmt::Display for IllegalTransition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "event {:?} is illegal in state {:?}", self.event, self.state)
+    }
+}
+
+impl std::error::Error for IllegalTransition {}
+
+/// One accepted transition, as recorded in the audit log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoggedTransition {
+    pub event: SessionEvent,
+    pub from: SessionState,
+    pub to: SessionState,
+    pub at_ms: u64,
+}
+
+/// The session state machine plus its transition log.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionLifecycle {
+    state: SessionState,
+    log: Vec<LoggedTransition>,
+}
+
+impl SessionLifecycle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn state(&self) -> SessionState {
+        self.state
+    }
+
+    /// Every transition accepted so far, oldest first.
+    pub fn log(&self) -> &[LoggedTransition] {
+        &self.log
+    }
+
+    /// Applies `event` at timestamp `at_ms`, returning the new state.
+    /// Illegal transitions are rejected and leave the machine (and the
+    /// log) untouched.
+    pub fn apply(
+        &mut self,
+        event: SessionEvent,
+        at_ms: u64,
+    ) -> Result<SessionState, IllegalTransition> {
+        let to = transition(self.state, event).ok_or(IllegalTransition {
+            state: self.state,
+            event,
+        })?;
+        self.log.push(LoggedTransition {
+            event,
+            from: self.state,
+            to,
+            at_ms,
+        });
+        self.state = to;
+        Ok(to)
+    }
+}
+
+/// The transition table; `None` marks an illegal pairing. Observing is
+/// optional (the app lets users draw immediately), pausing only makes
+/// sense mid-drawing, and nothing leaves `Evaluated`.
+fn transition(state: SessionState, event: SessionEvent) -> Option<SessionState> {
+    match (state, event) {
+        (SessionState::Created, SessionEvent::ObservationStarted) => Some(SessionState::Observing),
+        (SessionState::Created | SessionState::Observing, SessionEvent::DrawingStarted) => {
+            Some(SessionState::Drawing)
+        }
+        (SessionState::Drawing, SessionEvent::StrokeAdded) => Some(SessionState::Drawing),
+        (SessionState::Drawing, SessionEvent::Paused) => Some(SessionState::Paused),
+        (SessionState::Paused, SessionEvent::Resumed) => Some(SessionState::Drawing),
+        (SessionState::Drawing | SessionState::Paused, SessionEvent::Finished) => {
+            Some(SessionState::Finished)
+        }
+        (SessionState::Finished, SessionEvent::Evaluated) => Some(SessionState::Evaluated),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_full_run_walks_the_lifecycle_and_logs_every_step() {
+        let mut lifecycle = SessionLifecycle::new();
+        assert_eq!(lifecycle.state(), SessionState::Created);
+        lifecycle.apply(SessionEvent::ObservationStarted, 0).unwrap();
+        lifecycle.apply(SessionEvent::DrawingStarted, 5_000).unwrap();
+        lifecycle.apply(SessionEvent::StrokeAdded, 5_100).unwrap();
+        lifecycle.apply(SessionEvent::Paused, 6_000).unwrap();
+        lifecycle.apply(SessionEvent::Resumed, 8_000).unwrap();
+        lifecycle.apply(SessionEvent::Finished, 9_000).unwrap();
+        let state = lifecycle.apply(SessionEvent::Evaluated, 9_500).unwrap();
+        assert_eq!(state, SessionState::Evaluated);
+        assert_eq!(lifecycle.log().len(), 7);
+        assert_eq!(lifecycle.log()[3].from, SessionState::Drawing);
+        assert_eq!(lifecycle.log()[3].to, SessionState::Paused);
+        assert_eq!(lifecycle.log()[3].at_ms, 6_000);
+    }
+
+    #[test]
+    fn illegal_transitions_are_rejected_without_side_effects() {
+        let mut lifecycle = SessionLifecycle::new();
+        let error = lifecycle.apply(SessionEvent::StrokeAdded, 0).unwrap_err();
+        assert_eq!(error.state, SessionState::Created);
+        assert_eq!(error.event, SessionEvent::StrokeAdded);
+        assert_eq!(error.to_string(), "event StrokeAdded is illegal in state Created");
+        assert_eq!(lifecycle.state(), SessionState::Created);
+        assert!(lifecycle.log().is_empty());
+
+        lifecycle.apply(SessionEvent::DrawingStarted, 0).unwrap();
+        lifecycle.apply(SessionEvent::Finished, 100).unwrap();
+        lifecycle.apply(SessionEvent::Evaluated, 200).unwrap();
+        // Nothing leaves the terminal state.
+        assert!(lifecycle.apply(SessionEvent::DrawingStarted, 300).is_err());
+    }
+
+    #[test]
+    fn lifecycles_round_trip_through_json_for_audit_logs() {
+        let mut lifecycle = SessionLifecycle::new();
+        lifecycle.apply(SessionEvent::DrawingStarted, 42).unwrap();
+        let json = serde_json::to_string(&lifecycle).unwrap();
+        assert!(json.contains("\"drawing_started\""), "{json}");
+        let restored: SessionLifecycle = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, lifecycle);
+    }
+}
@@ -0,0 +1,1120 @@
+use std::sync::Arc;
+
+use evaluator::{
+    distance_transform, DistanceMetric, ErrorMetrics, EvaluationError, EvaluationResult,
+    EvaluatorConfig, ImageEvaluator, OvertimePolicy,
+};
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+use crate::time::{default_clock, Clock};
+use crate::image::Image;
+
+/// A single pointer sample inside a stroke.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+    /// Wall-clock timestamp of the pointer event, in milliseconds.
+    pub t_ms: u64,
+    /// Stylus pressure in 0..=1; mouse input reports the full 1.0.
+    #[serde(default = "full_pressure")]
+    pub pressure: f64,
+}
+
+fn full_pressure() -> f64 {
+    1.0
+}
+
+/// A contiguous pen-down…pen-up segment.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Stroke {
+    pub points: Vec<Point>,
+}
+
+impl Stroke {
+    /// Returns a cleaned copy for rasterization: RDP-simplified with
+    /// `epsilon`, Catmull-Rom smoothed, then uniformly resampled to
+    /// `spacing` pixels between points. See [`crate::input`].
+    pub fn cleaned(&self, epsilon: f64, spacing: f64) -> Stroke {
+        let simplified = crate::input::simplify_rdp(&self.points, epsilon);
+        let smoothed = crate::input::smooth_catmull_rom(&simplified, 4);
+        Stroke {
+            points: crate::input::resample_uniform(&smoothed, spacing),
+        }
+    }
+}
+
+/// One drawing attempt: the recorded strokes plus wall-clock timing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Observation {
+    started_at_ms: u64,
+    finished_at_ms: Option<u64>,
+    strokes: Vec<Stroke>,
+    /// Exercise time limit, counted from `started_at_ms`. `None` is
+    /// untimed; points are still recorded past the limit.
+    #[serde(default)]
+    time_limit_ms: Option<u64>,
+    /// Time source; not serialized, deserializes to the system clock.
+    #[serde(skip, default = "default_clock")]
+    clock: Arc<dyn Clock>,
+}
+
+impl Observation {
+    /// Starts a new observation timed from the system clock.
+    pub fn start() -> Self {
+        Self::start_with_clock(default_clock())
+    }
+
+    /// Starts a new observation timed from an injected clock — a mock in
+    /// tests, or `performance.now()` on wasm targets.
+    pub fn start_with_clock(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            started_at_ms: clock.now_ms(),
+            finished_at_ms: None,
+            strokes: Vec::new(),
+            time_limit_ms: None,
+            clock,
+        }
+    }
+
+    /// Imposes an exercise time limit. Points keep being recorded after
+    /// the deadline; scoring decides what to do with them via an
+    /// [`OvertimePolicy`].
+    pub fn set_time_limit(&mut self, limit_ms: u64) {
+        self.time_limit_ms = Some(limit_ms);
+    }
+
+    pub fn time_limit_ms(&self) -> Option<u64> {
+        self.time_limit_ms
+    }
+
+    /// Absolute deadline timestamp, when a time limit is set.
+    fn deadline_ms(&self) -> Option<u64> {
+        self.time_limit_ms.map(|limit| self.started_at_ms + limit)
+    }
+
+    /// Whether this sample arrived after the time limit.
+    pub fn is_overtime(&self, point: &Point) -> bool {
+        self.deadline_ms().is_some_and(|deadline| point.t_ms > deadline)
+    }
+
+    /// How far drawing ran past the deadline: the last recorded event
+    /// (finish, or the last sample) minus the deadline. Zero when
+    /// untimed or finished in time.
+    pub fn overtime_ms(&self) -> u64 {
+        let Some(deadline) = self.deadline_ms() else {
+            return 0;
+        };
+        let last_event = self
+            .finished_at_ms
+            .or_else(|| {
+                self.strokes
+                    .iter()
+                    .flat_map(|stroke| stroke.points.iter().map(|point| point.t_ms))
+                    .max()
+            })
+            .unwrap_or(self.started_at_ms);
+        last_event.saturating_sub(deadline)
+    }
+
+    /// The strokes trimmed to the samples that arrived within the time
+    /// limit. Strokes left empty by the trim are dropped.
+    pub fn strokes_within_limit(&self) -> Vec<Stroke> {
+        let Some(deadline) = self.deadline_ms() else {
+            return self.strokes.clone();
+        };
+        self.strokes
+            .iter()
+            .map(|stroke| Stroke {
+                points: stroke
+                    .points
+                    .iter()
+                    .copied()
+                    .filter(|point| point.t_ms <= deadline)
+                    .collect(),
+            })
+            .filter(|stroke| !stroke.points.is_empty())
+            .collect()
+    }
+
+    /// Opens a new stroke; subsequent points are appended to it.
+    pub fn begin_stroke(&mut self) {
+        self.strokes.push(Stroke::default());
+    }
+
+    /// Records a pointer sample at the current time. Opens a stroke if
+    /// none is active yet.
+    pub fn add_point(&mut self, x: f64, y: f64) {
+        self.add_point_with_pressure(x, y, 1.0);
+    }
+
+    /// [`Self::add_point`] with the stylus pressure of the sample.
+    pub fn add_point_with_pressure(&mut self, x: f64, y: f64, pressure: f64) {
+        if self.strokes.is_empty() {
+            self.begin_stroke();
+        }
+        let t_ms = self.clock.now_ms();
+        if let Some(stroke) = self.strokes.last_mut() {
+            stroke.points.push(Point {
+                x,
+                y,
+                t_ms,
+                pressure: pressure.clamp(0.0, 1.0),
+            });
+        }
+    }
+
+    /// Marks the observation as finished. Finishing twice keeps the first
+    /// timestamp.
+    pub fn finish(&mut self) {
+        if self.finished_at_ms.is_none() {
+            self.finished_at_ms = Some(self.clock.now_ms());
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished_at_ms.is_some()
+    }
+
+    pub fn started_at_ms(&self) -> u64 {
+        self.started_at_ms
+    }
+
+    /// Total drawing duration, once finished.
+    pub fn duration_ms(&self) -> Option<u64> {
+        self.finished_at_ms
+            .map(|end| end.saturating_sub(self.started_at_ms))
+    }
+
+    pub fn strokes(&self) -> &[Stroke] {
+        &self.strokes
+    }
+
+    /// Gesture-mode grading: compares this observation's stroke
+    /// curvature profiles against the reference's stroke paths. See
+    /// [`crate::gesture`].
+    pub fn flow_against(&self, reference: &[Stroke]) -> crate::gesture::FlowReport {
+        crate::gesture::flow_report(&self.strokes, reference)
+    }
+
+    /// Process-phase breakdown of this observation's strokes scored
+    /// against the reference. See [`crate::phase`].
+    pub fn phases_against(
+        &self,
+        reference: &Image,
+    ) -> Result<crate::phase::PhaseReport, EvaluationError> {
+        crate::phase::segment_phases(self, reference)
+    }
+
+    pub fn total_points(&self) -> usize {
+        self.strokes.iter().map(|s| s.points.len()).sum()
+    }
+
+    /// Rasterizes the recorded strokes into a stroke mask, connecting
+    /// consecutive points with straight segments. Pixels outside the
+    /// canvas are dropped.
+    pub fn rasterize(&self, width: usize, height: usize) -> Array2<u8> {
+        rasterize_strokes(&self.strokes, width, height)
+    }
+
+    /// [`Self::rasterize`] stamping every sample with a [`Brush`]
+    /// footprint instead of a single pixel, so the mask matches what
+    /// the drawing app's brush rendered on canvas.
+    pub fn rasterize_with_brush(&self, width: usize, height: usize, brush: &Brush) -> Array2<u8> {
+        rasterize_strokes_with_brush(&self.strokes, width, height, brush)
+    }
+
+    /// Rasterizes stroke pressure into per-pixel weights aligned with
+    /// [`Self::rasterize`]: every touched pixel keeps the heaviest
+    /// pressure that crossed it, interpolated along segments.
+    pub fn rasterize_weights(&self, width: usize, height: usize) -> Array2<f64> {
+        let mut weights = Array2::zeros((height, width));
+        for stroke in &self.strokes {
+            let points = &stroke.points;
+            if let [only] = points[..] {
+                plot_weight(&mut weights, only.x, only.y, only.pressure);
+            }
+            for pair in points.windows(2) {
+                draw_segment_weights(&mut weights, pair[0], pair[1]);
+            }
+        }
+        weights
+    }
+
+    /// Emits one provenance record per rasterized pixel — where and
+    /// when it was inked, by which stroke, and how far it landed from
+    /// the reference mask — for building research datasets of drawing
+    /// behavior. Pixels crossed several times are attributed to the
+    /// first sample that inked them; records come out in row-major
+    /// order. See [`write_provenance_csv`] for the columnar export.
+    pub fn pixel_provenance(
+        &self,
+        reference: &Array2<u8>,
+    ) -> Result<Vec<PixelProvenance>, EvaluationError> {
+        let (height, width) = reference.dim();
+        let flat: Vec<u8> = reference.iter().copied().collect();
+        let heatmap = distance_transform(&flat, width, height, DistanceMetric::Chessboard, None)?;
+        let mut first: Vec<Option<(usize, u64)>> = vec![None; width * height];
+        let mut stamp = |x: f64, y: f64, stroke_id: usize, t_ms: u64| {
+            let (x, y) = (x.round() as isize, y.round() as isize);
+            if x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height {
+                let slot = &mut first[y as usize * width + x as usize];
+                if slot.is_none() {
+                    *slot = Some((stroke_id, t_ms));
+                }
+            }
+        };
+        for (stroke_id, stroke) in self.strokes.iter().enumerate() {
+            let points = &stroke.points;
+            if let [only] = points[..] {
+                stamp(only.x, only.y, stroke_id, only.t_ms);
+            }
+            for pair in points.windows(2) {
+                let (from, to) = (pair[0], pair[1]);
+                let steps = (to.x - from.x).abs().max((to.y - from.y).abs()).ceil() as usize;
+                for step in 0..=steps {
+                    let t = if steps == 0 { 0.0 } else { step as f64 / steps as f64 };
+                    // A pixel between two samples carries the earlier
+                    // sample's timestamp: that is when the pen reached it.
+                    stamp(
+                        from.x + (to.x - from.x) * t,
+                        from.y + (to.y - from.y) * t,
+                        stroke_id,
+                        from.t_ms,
+                    );
+                }
+            }
+        }
+        Ok(first
+            .iter()
+            .enumerate()
+            .filter_map(|(index, slot)| {
+                slot.map(|(stroke_id, timestamp_ms)| PixelProvenance {
+                    x: index % width,
+                    y: index / width,
+                    stroke_id,
+                    timestamp_ms,
+                    distance: heatmap[(index / width, index % width)],
+                })
+            })
+            .collect())
+    }
+
+    /// Scores this observation's strokes against a reference image by
+    /// calling into the lib evaluator. The reference's dimensions define
+    /// the evaluation canvas.
+    pub fn evaluate_against(&self, reference: &Image) -> Result<EvaluationResult, EvaluationError> {
+        self.evaluate_against_weighted(reference, false)
+    }
+
+    /// [`Self::evaluate_against`] with pressure weighting: light
+    /// exploratory lines contribute proportionally less error and
+    /// coverage than committed strokes. `use_pressure: false` keeps the
+    /// historical unweighted scoring.
+    pub fn evaluate_against_weighted(
+        &self,
+        reference: &Image,
+        use_pressure: bool,
+    ) -> Result<EvaluationResult, EvaluationError> {
+        let config = EvaluatorConfig {
+            canvas_width: reference.width(),
+            canvas_height: reference.height(),
+            ..EvaluatorConfig::default()
+        };
+        let reference_mask = reference.to_mask(config.transparent_background);
+        let observation_mask = self.rasterize(reference.width(), reference.height());
+        let weights = use_pressure
+            .then(|| self.rasterize_weights(reference.width(), reference.height()));
+        ImageEvaluator::new(config).evaluate_arrays_weighted(
+            &reference_mask,
+            &observation_mask,
+            weights.as_ref(),
+        )
+    }
+
+    /// [`Self::evaluate_against`] with the time limit enforced per the
+    /// exercise's [`OvertimePolicy`]: overtime strokes are either
+    /// excluded from the mask, punished with a top-5 error penalty, or
+    /// merely recorded. The result carries the overtime duration.
+    pub fn evaluate_with_policy(
+        &self,
+        reference: &Image,
+        policy: OvertimePolicy,
+    ) -> Result<EvaluationResult, EvaluationError> {
+        let config = EvaluatorConfig {
+            canvas_width: reference.width(),
+            canvas_height: reference.height(),
+            ..EvaluatorConfig::default()
+        };
+        let scored = match policy {
+            OvertimePolicy::Exclude => self.strokes_within_limit(),
+            OvertimePolicy::PenaltyPerSecond(_) | OvertimePolicy::Ignore => self.strokes.clone(),
+        };
+        let reference_mask = reference.to_mask(config.transparent_background);
+        let observation_mask = rasterize_strokes(&scored, reference.width(), reference.height());
+        let mut result =
+            ImageEvaluator::new(config).evaluate_arrays(&reference_mask, &observation_mask)?;
+        let overtime_ms = self.overtime_ms();
+        if let OvertimePolicy::PenaltyPerSecond(per_second) = policy {
+            result.metrics.top_5_error += per_second * overtime_ms as f64 / 1_000.0;
+        }
+        result.overtime_ms = Some(overtime_ms);
+        Ok(result)
+    }
+
+    /// Replays the recorded strokes against a reference in wall-clock
+    /// slices of `interval_ms`, producing the speed-vs-accuracy curve:
+    /// how much score each minute of active (pen-down) drawing bought.
+    /// Coaches read a high [`SpeedAccuracyCurve::auc`] as "got accurate
+    /// early" and a low one as accuracy arriving only at the end.
+    pub fn speed_accuracy_curve(
+        &self,
+        reference: &Image,
+        interval_ms: u64,
+    ) -> Result<SpeedAccuracyCurve, EvaluationError> {
+        let config = EvaluatorConfig {
+            canvas_width: reference.width(),
+            canvas_height: reference.height(),
+            ..EvaluatorConfig::default()
+        };
+        let interval_ms = interval_ms.max(1);
+        let end = self
+            .finished_at_ms
+            .or_else(|| self.last_sample_ms())
+            .unwrap_or(self.started_at_ms);
+        let duration = end.saturating_sub(self.started_at_ms);
+        let slices = duration.div_ceil(interval_ms).max(1);
+
+        let mut cutoffs = Vec::with_capacity(slices as usize);
+        let mut frames = Vec::with_capacity(slices as usize);
+        for slice in 1..=slices {
+            let cutoff = self.started_at_ms + (slice * interval_ms).min(duration);
+            let truncated: Vec<Stroke> = self
+                .strokes
+                .iter()
+                .map(|stroke| Stroke {
+                    points: stroke
+                        .points
+                        .iter()
+                        .copied()
+                        .filter(|point| point.t_ms <= cutoff)
+                        .collect(),
+                })
+                .filter(|stroke| !stroke.points.is_empty())
+                .collect();
+            frames.push(rasterize_strokes(
+                &truncated,
+                reference.width(),
+                reference.height(),
+            ));
+            cutoffs.push(cutoff);
+        }
+
+        let reference_mask = reference.to_mask(config.transparent_background);
+        let scores = evaluator::evaluate_frames(reference_mask, &frames, config)?;
+        let samples: Vec<SpeedAccuracySample> = scores
+            .iter()
+            .zip(&cutoffs)
+            .map(|(frame, &cutoff)| SpeedAccuracySample {
+                elapsed_ms: cutoff - self.started_at_ms,
+                active_ms: self.active_ms_until(cutoff),
+                score: frame.score,
+                completion: frame.completion,
+            })
+            .collect();
+
+        // Trapezoidal area under completion over normalized elapsed
+        // time, from an implicit (0, 0); 1.0 would be instant accuracy.
+        let mut auc = 0.0;
+        let mut previous = (0.0, 0.0);
+        for sample in &samples {
+            let at = sample.elapsed_ms as f64 / duration.max(1) as f64;
+            auc += (at - previous.0) * (sample.completion + previous.1) / 2.0;
+            previous = (at, sample.completion);
+        }
+        Ok(SpeedAccuracyCurve { samples, auc })
+    }
+
+    /// Computes drawing-speed analytics from the stroke log: a
+    /// histogram of instantaneous speeds in `bucket_width` px/s
+    /// buckets, the pen-down dwell time, and the segments faster than
+    /// the given speed percentile (`0..=100`) flagged as rushed.
+    pub fn speed_analytics(&self, bucket_width: f64, rushed_percentile: f64) -> SpeedAnalytics {
+        let mut segments = Vec::new();
+        let mut dwell_ms = 0;
+        for (stroke, points) in self.strokes.iter().map(|s| &s.points).enumerate() {
+            for pair in points.windows(2) {
+                let dt_ms = pair[1].t_ms.saturating_sub(pair[0].t_ms);
+                if dt_ms == 0 {
+                    continue;
+                }
+                let distance = (pair[1].x - pair[0].x).hypot(pair[1].y - pair[0].y);
+                let speed = distance / dt_ms as f64 * 1000.0;
+                if speed < DWELL_SPEED {
+                    dwell_ms += dt_ms;
+                }
+                segments.push((stroke, pair[0], pair[1], speed));
+            }
+        }
+        let mut sorted: Vec<f64> = segments.iter().map(|&(_, _, _, speed)| speed).collect();
+        sorted.sort_by(f64::total_cmp);
+        let percentile = |fraction: f64| match sorted.len() {
+            0 => 0.0,
+            len => sorted[((len - 1) as f64 * fraction / 100.0).round() as usize],
+        };
+        let rushed_threshold = percentile(rushed_percentile.clamp(0.0, 100.0));
+        let bucket_width = bucket_width.max(1e-9);
+        let mut histogram = Vec::new();
+        for &speed in &sorted {
+            let bucket = (speed / bucket_width) as usize;
+            if histogram.len() <= bucket {
+                histogram.resize(bucket + 1, 0);
+            }
+            histogram[bucket] += 1;
+        }
+        SpeedAnalytics {
+            bucket_width,
+            histogram,
+            median_speed: percentile(50.0),
+            rushed_threshold,
+            dwell_ms,
+            rushed: segments
+                .into_iter()
+                .filter(|&(_, _, _, speed)| speed > rushed_threshold)
+                .map(|(stroke, from, to, speed)| RushedSegment {
+                    stroke,
+                    from,
+                    to,
+                    speed,
+                    in_high_error_region: false,
+                })
+                .collect(),
+        }
+    }
+
+    /// Timestamp of the last recorded pointer sample.
+    fn last_sample_ms(&self) -> Option<u64> {
+        self.strokes
+            .iter()
+            .flat_map(|stroke| stroke.points.iter().map(|point| point.t_ms))
+            .max()
+    }
+
+    /// Pen-down time spent before `cutoff`: the within-cutoff span of
+    /// each stroke, summed.
+    fn active_ms_until(&self, cutoff: u64) -> u64 {
+        self.strokes
+            .iter()
+            .filter_map(|stroke| {
+                let first = stroke.points.first()?.t_ms;
+                let last = stroke.points.last()?.t_ms;
+                (first <= cutoff).then(|| last.min(cutoff) - first)
+            })
+            .sum()
+    }
+}
+
+/// One wall-clock slice of a [`SpeedAccuracyCurve`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SpeedAccuracySample {
+    /// Wall-clock time since the observation started.
+    pub elapsed_ms: u64,
+    /// Pen-down drawing time spent so far.
+    pub active_ms: u64,
+    /// Live top-5 error at this point of the replay.
+    pub score: f64,
+    /// Fraction of the reference covered at this point.
+    pub completion: f64,
+}
+
+/// Speed-vs-accuracy curve from [`Observation::speed_accuracy_curve`],
+/// serialized into session reports for coaching feedback.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeedAccuracyCurve {
+    pub samples: Vec<SpeedAccuracySample>,
+    /// Area under completion over normalized time, in `0..=1`; higher
+    /// means accuracy arrived earlier in the attempt.
+    pub auc: f64,
+}
+
+/// Speeds below this count as dwell rather than movement, in px/s.
+const DWELL_SPEED: f64 = 15.0;
+
+/// Drawing-speed analytics of one attempt, from
+/// [`Observation::speed_analytics`]. Shipped in [`crate::DrawingReport`]
+/// so coaching can point at rushing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeedAnalytics {
+    /// Width of one histogram bucket, in px/s.
+    pub bucket_width: f64,
+    /// Segment counts per speed bucket, starting at 0 px/s.
+    pub histogram: Vec<usize>,
+    /// Median instantaneous speed in px/s; zero with no segments.
+    pub median_speed: f64,
+    /// Speed above which a segment counted as rushed, in px/s.
+    pub rushed_threshold: f64,
+    /// Pen-down time spent (nearly) stationary, in milliseconds.
+    pub dwell_ms: u64,
+    pub rushed: Vec<RushedSegment>,
+}
+
+/// One stroke segment drawn faster than the rushed threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RushedSegment {
+    /// Index of the stroke the segment belongs to.
+    pub stroke: usize,
+    pub from: Point,
+    pub to: Point,
+    /// Instantaneous speed over the segment, in px/s.
+    pub speed: f64,
+    /// Set by [`SpeedAnalytics::correlate`]: the segment runs through
+    /// a grid cell with above-average error.
+    pub in_high_error_region: bool,
+}
+
+impl SpeedAnalytics {
+    /// Marks every rushed segment that touches an evaluation grid cell
+    /// with above-average error, tying rushing to the damage it did.
+    /// `width` and `height` are the canvas dimensions the grid spans.
+    pub fn correlate(&mut self, metrics: &ErrorMetrics, width: usize, height: usize) {
+        let rows = metrics.grid.len();
+        let columns = metrics.grid.first().map_or(0, Vec::len);
+        if rows == 0 || columns == 0 || width == 0 || height == 0 {
+            return;
+        }
+        let mean: f64 =
+            metrics.grid.iter().flatten().sum::<f64>() / (rows * columns) as f64;
+        for segment in &mut self.rushed {
+            segment.in_high_error_region = [segment.from, segment.to].iter().any(|point| {
+                let row = ((point.y / height as f64 * rows as f64) as isize)
+                    .clamp(0, rows as isize - 1) as usize;
+                let column = ((point.x / width as f64 * columns as f64) as isize)
+                    .clamp(0, columns as isize - 1) as usize;
+                metrics.grid[row][column] > mean
+            });
+        }
+    }
+}
+
+/// The pixel footprint stamped at every rasterized sample, so the mask
+/// the evaluator scores matches what the drawing app's brush actually
+/// rendered on canvas.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Brush {
+    /// One-pixel pen, the historical default.
+    #[default]
+    Pen,
+    /// Filled disc of this radius in pixels.
+    Round { radius: f64 },
+    /// Axis-aligned filled square with this side length in pixels.
+    Square { size: usize },
+    /// Calligraphic nib: a straight `width`-pixel edge tilted by
+    /// `angle` radians from the x axis.
+    Calligraphic { width: usize, angle: f64 },
+    /// Texture stamp: the nonzero cells of a small mask, centred on the
+    /// sample.
+    Stamp { mask: Vec<Vec<u8>> },
+}
+
+impl Brush {
+    /// The footprint as pixel offsets relative to the sample.
+    fn offsets(&self) -> Vec<(isize, isize)> {
+        match self {
+            Self::Pen => vec![(0, 0)],
+            Self::Round { radius } => {
+                let reach = radius.max(0.0).floor() as isize;
+                let mut offsets = Vec::new();
+                for dy in -reach..=reach {
+                    for dx in -reach..=reach {
+                        if ((dy * dy + dx * dx) as f64) <= radius * radius {
+                            offsets.push((dy, dx));
+                        }
+                    }
+                }
+                offsets
+            }
+            Self::Square { size } => {
+                let size = (*size).max(1) as isize;
+                let start = -(size / 2);
+                (start..start + size)
+                    .flat_map(|dy| (start..start + size).map(move |dx| (dy, dx)))
+                    .collect()
+            }
+            Self::Calligraphic { width, angle } => {
+                let width = (*width).max(1);
+                let mid = (width - 1) as f64 / 2.0;
+                (0..width)
+                    .map(|i| {
+                        let t = i as f64 - mid;
+                        ((t * angle.sin()).round() as isize, (t * angle.cos()).round() as isize)
+                    })
+                    .collect()
+            }
+            Self::Stamp { mask } => {
+                let rows = mask.len() as isize;
+                let cols = mask.first().map_or(0, Vec::len) as isize;
+                let mut offsets = Vec::new();
+                for (row, cells) in mask.iter().enumerate() {
+                    for (col, &on) in cells.iter().enumerate() {
+                        if on != 0 {
+                            offsets.push((row as isize - rows / 2, col as isize - cols / 2));
+                        }
+                    }
+                }
+                offsets
+            }
+        }
+    }
+}
+
+/// The shared rasterizer behind [`Observation::rasterize`] and the
+/// time-limited scoring paths.
+pub(crate) fn rasterize_strokes(strokes: &[Stroke], width: usize, height: usize) -> Array2<u8> {
+    rasterize_strokes_with_brush(strokes, width, height, &Brush::Pen)
+}
+
+/// Rasterizes strokes stamping each sample with the brush footprint.
+fn rasterize_strokes_with_brush(
+    strokes: &[Stroke],
+    width: usize,
+    height: usize,
+    brush: &Brush,
+) -> Array2<u8> {
+    let offsets = brush.offsets();
+    let mut mask = Array2::zeros((height, width));
+    for stroke in strokes {
+        let points = &stroke.points;
+        if let [only] = points[..] {
+            plot(&mut mask, only.x, only.y, &offsets);
+        }
+        for pair in points.windows(2) {
+            draw_segment(&mut mask, pair[0], pair[1], &offsets);
+        }
+    }
+    mask
+}
+
+/// Stamps the brush footprint under a pointer sample; pixels off the
+/// canvas are dropped.
+fn plot(mask: &mut Array2<u8>, x: f64, y: f64, offsets: &[(isize, isize)]) {
+    let (height, width) = mask.dim();
+    let (x, y) = (x.round() as isize, y.round() as isize);
+    for &(dy, dx) in offsets {
+        let (px, py) = (x + dx, y + dy);
+        if px >= 0 && py >= 0 && (px as usize) < width && (py as usize) < height {
+            mask[(py as usize, px as usize)] = 1;
+        }
+    }
+}
+
+/// Draws a straight segment between two samples by stepping one pixel at
+/// a time along the longer axis.
+fn draw_segment(mask: &mut Array2<u8>, from: Point, to: Point, offsets: &[(isize, isize)]) {
+    let steps = (to.x - from.x).abs().max((to.y - from.y).abs()).ceil() as usize;
+    for step in 0..=steps {
+        let t = if steps == 0 { 0.0 } else { step as f64 / steps as f64 };
+        plot(
+            mask,
+            from.x + (to.x - from.x) * t,
+            from.y + (to.y - from.y) * t,
+            offsets,
+        );
+    }
+}
+
+/// Keeps the heaviest pressure seen at a pixel, if it lies on the canvas.
+fn plot_weight(weights: &mut Array2<f64>, x: f64, y: f64, pressure: f64) {
+    let (height, width) = weights.dim();
+    let (x, y) = (x.round(), y.round());
+    if x >= 0.0 && y >= 0.0 && (x as usize) < width && (y as usize) < height {
+        let pos = (y as usize, x as usize);
+        weights[pos] = weights[pos].max(pressure);
+    }
+}
+
+/// The pressure counterpart of [`draw_segment`], interpolating pressure
+/// between the two samples.
+fn draw_segment_weights(weights: &mut Array2<f64>, from: Point, to: Point) {
+    let steps = (to.x - from.x).abs().max((to.y - from.y).abs()).ceil() as usize;
+    for step in 0..=steps {
+        let t = if steps == 0 { 0.0 } else { step as f64 / steps as f64 };
+        plot_weight(
+            weights,
+            from.x + (to.x - from.x) * t,
+            from.y + (to.y - from.y) * t,
+            from.pressure + (to.pressure - from.pressure) * t,
+        );
+    }
+}
+
+/// One observation pixel with where and when it was drawn and how far
+/// it landed from the reference — one row of the provenance export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PixelProvenance {
+    pub x: usize,
+    pub y: usize,
+    /// Index of the stroke that first inked this pixel.
+    pub stroke_id: usize,
+    /// Pointer timestamp (ms) of the sample that first inked it.
+    pub timestamp_ms: u64,
+    /// Chessboard distance to the nearest reference pixel; `-1` when
+    /// the reference mask is blank.
+    pub distance: i32,
+}
+
+/// Writes provenance records as CSV with a header row, the columnar
+/// format every dataset tool ingests. (A Parquet writer would pull in
+/// the arrow stack, so anything heavier converts from this CSV.)
+pub fn write_provenance_csv(
+    records: &[PixelProvenance],
+    mut writer: impl std::io::Write,
+) -> std::io::Result<()> {
+    writeln!(writer, "x,y,stroke_id,timestamp_ms,distance")?;
+    for record in records {
+        writeln!(
+            writer,
+            "{},{},{},{},{}",
+            record.x, record.y, record.stroke_id, record.timestamp_ms, record.distance
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::MockClock;
+
+    #[test]
+    fn points_accumulate_into_strokes() {
+        let mut observation = Observation::start();
+        observation.add_point(1.0, 2.0);
+        observation.add_point(3.0, 4.0);
+        observation.begin_stroke();
+        observation.add_point(5.0, 6.0);
+        assert_eq!(observation.strokes().len(), 2);
+        assert_eq!(observation.total_points(), 3);
+    }
+
+    #[test]
+    fn rasterize_connects_consecutive_points() {
+        let mut observation = Observation::start();
+        observation.begin_stroke();
+        observation.add_point(10.0, 20.0);
+        observation.add_point(14.0, 20.0);
+        let mask = observation.rasterize(50, 50);
+        for x in 10..=14 {
+            assert_eq!(mask[(20, x)], 1, "missing pixel at x={x}");
+        }
+        assert_eq!(mask.iter().filter(|&&p| p != 0).count(), 5);
+    }
+
+    #[test]
+    fn a_round_brush_thickens_the_stroke() {
+        let mut observation = Observation::start();
+        observation.begin_stroke();
+        observation.add_point(10.0, 20.0);
+        observation.add_point(14.0, 20.0);
+        let mask = observation.rasterize_with_brush(50, 50, &Brush::Round { radius: 2.0 });
+        for x in 10..=14 {
+            for y in 18..=22 {
+                assert_eq!(mask[(y, x)], 1, "missing pixel at ({x}, {y})");
+            }
+        }
+        assert_eq!(mask[(17, 12)], 0);
+    }
+
+    #[test]
+    fn a_stamp_brush_repeats_its_mask_at_every_sample() {
+        let mut observation = Observation::start();
+        observation.begin_stroke();
+        observation.add_point(25.0, 25.0);
+        let stamp = Brush::Stamp {
+            mask: vec![vec![1, 0, 1], vec![0, 0, 0], vec![1, 0, 1]],
+        };
+        let mask = observation.rasterize_with_brush(50, 50, &stamp);
+        assert_eq!(mask[(24, 24)], 1);
+        assert_eq!(mask[(24, 26)], 1);
+        assert_eq!(mask[(26, 24)], 1);
+        assert_eq!(mask[(26, 26)], 1);
+        assert_eq!(mask[(25, 25)], 0);
+    }
+
+    #[test]
+    fn the_pen_brush_matches_plain_rasterization() {
+        let mut observation = Observation::start();
+        observation.begin_stroke();
+        observation.add_point(5.0, 5.0);
+        observation.add_point(30.0, 12.0);
+        assert_eq!(
+            observation.rasterize_with_brush(50, 50, &Brush::Pen),
+            observation.rasterize(50, 50)
+        );
+    }
+
+    #[test]
+    fn pixel_provenance_attributes_pixels_to_their_first_stroke() {
+        let clock = MockClock::new(1_000);
+        let mut observation = Observation::start_with_clock(Arc::new(clock.clone()));
+        observation.begin_stroke();
+        observation.add_point(10.0, 20.0);
+        observation.add_point(14.0, 20.0);
+        clock.advance(100);
+        observation.begin_stroke();
+        // Retraces a pixel of the first stroke, then strays off.
+        observation.add_point(10.0, 20.0);
+        observation.add_point(30.0, 40.0);
+
+        let mut reference = Array2::zeros((50, 50));
+        for x in 10..=14 {
+            reference[(20, x)] = 1;
+        }
+        let records = observation.pixel_provenance(&reference).unwrap();
+        let on_line: Vec<_> = records.iter().filter(|r| r.y == 20).collect();
+        assert_eq!(on_line.len(), 5);
+        // The retraced pixel keeps its first stroke and timestamp.
+        assert_eq!(on_line[0].stroke_id, 0);
+        assert_eq!(on_line[0].timestamp_ms, 1_000);
+        assert_eq!(on_line[0].distance, 0);
+        let stray = records.iter().find(|r| (r.x, r.y) == (30, 40)).unwrap();
+        assert_eq!(stray.stroke_id, 1);
+        assert_eq!(stray.timestamp_ms, 1_100);
+        assert_eq!(stray.distance, 20);
+    }
+
+    #[test]
+    fn provenance_exports_as_csv_with_a_header() {
+        let records = [PixelProvenance {
+            x: 3,
+            y: 7,
+            stroke_id: 1,
+            timestamp_ms: 42,
+            distance: 5,
+        }];
+        let mut csv = Vec::new();
+        write_provenance_csv(&records, &mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "x,y,stroke_id,timestamp_ms,distance\n3,7,1,42,5\n"
+        );
+    }
+
+    #[test]
+    fn speed_analytics_bucket_speeds_and_flag_rushed_segments() {
+        let clock = MockClock::new(0);
+        let mut observation = Observation::start_with_clock(Arc::new(clock.clone()));
+        observation.begin_stroke();
+        // Three steady segments at 100 px/s, then a 1000 px/s dash.
+        for step in 0..4 {
+            observation.add_point(step as f64 * 10.0, 50.0);
+            clock.advance(100);
+        }
+        observation.add_point(130.0, 50.0);
+        let analytics = observation.speed_analytics(50.0, 75.0);
+        assert_eq!(analytics.histogram[2], 3);
+        assert_eq!(analytics.histogram[20], 1);
+        assert_eq!(analytics.median_speed, 100.0);
+        assert_eq!(analytics.rushed.len(), 1);
+        assert_eq!(analytics.rushed[0].speed, 1_000.0);
+        assert!(!analytics.rushed[0].in_high_error_region);
+    }
+
+    #[test]
+    fn a_stationary_pen_accumulates_dwell_time() {
+        let clock = MockClock::new(0);
+        let mut observation = Observation::start_with_clock(Arc::new(clock.clone()));
+        observation.begin_stroke();
+        for _ in 0..3 {
+            observation.add_point(20.0, 20.0);
+            clock.advance(200);
+        }
+        observation.add_point(20.5, 20.0);
+        let analytics = observation.speed_analytics(50.0, 90.0);
+        assert_eq!(analytics.dwell_ms, 600);
+        assert!(analytics.rushed.is_empty());
+    }
+
+    #[test]
+    fn rushed_segments_correlate_with_high_error_cells() {
+        let clock = MockClock::new(0);
+        let mut observation = Observation::start_with_clock(Arc::new(clock.clone()));
+        observation.begin_stroke();
+        observation.add_point(5.0, 5.0);
+        clock.advance(100);
+        observation.add_point(15.0, 5.0);
+        clock.advance(10);
+        // The dash ends in the top-right of a 100x100 canvas.
+        observation.add_point(95.0, 5.0);
+        let mut analytics = observation.speed_analytics(50.0, 25.0);
+        assert_eq!(analytics.rushed.len(), 1);
+
+        let mut grid = vec![vec![0.0; 10]; 10];
+        grid[0][9] = 20.0;
+        let metrics = ErrorMetrics {
+            mean_error: 0.2,
+            top_5_error: 20.0,
+            coverage: 1.0,
+            grid,
+            normalization: Default::default(),
+            cell_tolerance_multipliers: None,
+            normalized: None,
+        };
+        analytics.correlate(&metrics, 100, 100);
+        assert!(analytics.rushed[0].in_high_error_region);
+    }
+
+    #[test]
+    fn tracing_the_reference_evaluates_perfectly() {
+        let mut reference = Image::new(100, 100);
+        for x in 20..80 {
+            reference.set_pixel(x, 50, [0, 0, 0, 255]);
+        }
+        let mut observation = Observation::start();
+        observation.begin_stroke();
+        observation.add_point(20.0, 50.0);
+        observation.add_point(79.0, 50.0);
+        let result = observation.evaluate_against(&reference).unwrap();
+        assert_eq!(result.metrics.top_5_error, 0.0);
+        assert_eq!(result.metrics.coverage, 1.0);
+    }
+
+    #[test]
+    fn light_pressure_tracing_scores_partial_coverage() {
+        let mut reference = Image::new(100, 100);
+        for x in 20..80 {
+            reference.set_pixel(x, 50, [0, 0, 0, 255]);
+        }
+        let mut observation = Observation::start();
+        observation.begin_stroke();
+        observation.add_point_with_pressure(20.0, 50.0, 0.5);
+        observation.add_point_with_pressure(79.0, 50.0, 0.5);
+        let unweighted = observation.evaluate_against(&reference).unwrap();
+        assert_eq!(unweighted.metrics.coverage, 1.0);
+        let weighted = observation
+            .evaluate_against_weighted(&reference, true)
+            .unwrap();
+        assert!((weighted.metrics.coverage - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn duration_follows_the_injected_clock() {
+        let clock = MockClock::new(1_000);
+        let mut observation = Observation::start_with_clock(Arc::new(clock.clone()));
+        assert_eq!(observation.started_at_ms(), 1_000);
+        clock.advance(250);
+        observation.add_point(1.0, 1.0);
+        assert_eq!(observation.strokes()[0].points[0].t_ms, 1_250);
+        clock.advance(750);
+        observation.finish();
+        assert_eq!(observation.duration_ms(), Some(1_000));
+    }
+
+    #[test]
+    fn overtime_strokes_are_excluded_from_scoring() {
+        let mut reference = Image::new(100, 100);
+        for x in 20..80 {
+            reference.set_pixel(x, 50, [0, 0, 0, 255]);
+        }
+        let clock = MockClock::new(0);
+        let mut observation = Observation::start_with_clock(Arc::new(clock.clone()));
+        observation.set_time_limit(1_000);
+        observation.begin_stroke();
+        observation.add_point(20.0, 50.0);
+        observation.add_point(79.0, 50.0);
+        clock.advance(2_000);
+        observation.begin_stroke();
+        observation.add_point(10.0, 10.0);
+        observation.add_point(10.0, 30.0);
+        let excluded = observation
+            .evaluate_with_policy(&reference, OvertimePolicy::Exclude)
+            .unwrap();
+        assert_eq!(excluded.metrics.top_5_error, 0.0);
+        assert_eq!(excluded.overtime_ms, Some(1_000));
+        let ignored = observation
+            .evaluate_with_policy(&reference, OvertimePolicy::Ignore)
+            .unwrap();
+        assert!(ignored.metrics.top_5_error > 0.0);
+        assert_eq!(ignored.overtime_ms, Some(1_000));
+    }
+
+    #[test]
+    fn overtime_penalty_raises_the_top_5_error() {
+        let mut reference = Image::new(100, 100);
+        for x in 20..80 {
+            reference.set_pixel(x, 50, [0, 0, 0, 255]);
+        }
+        let clock = MockClock::new(0);
+        let mut observation = Observation::start_with_clock(Arc::new(clock.clone()));
+        observation.set_time_limit(1_000);
+        observation.begin_stroke();
+        observation.add_point(20.0, 50.0);
+        observation.add_point(79.0, 50.0);
+        clock.advance(2_500);
+        observation.finish();
+        let result = observation
+            .evaluate_with_policy(&reference, OvertimePolicy::PenaltyPerSecond(2.0))
+            .unwrap();
+        assert!((result.metrics.top_5_error - 3.0).abs() < 1e-9);
+        assert_eq!(result.overtime_ms, Some(1_500));
+    }
+
+    #[test]
+    fn speed_accuracy_curve_tracks_progress_per_slice() {
+        let mut reference = Image::new(100, 100);
+        for x in 20..80 {
+            reference.set_pixel(x, 50, [0, 0, 0, 255]);
+        }
+        let clock = MockClock::new(0);
+        let mut observation = Observation::start_with_clock(Arc::new(clock.clone()));
+        // First second: trace the left half; second second: the rest.
+        observation.begin_stroke();
+        observation.add_point(20.0, 50.0);
+        clock.advance(900);
+        observation.add_point(49.0, 50.0);
+        clock.advance(100);
+        observation.begin_stroke();
+        observation.add_point(50.0, 50.0);
+        clock.advance(900);
+        observation.add_point(79.0, 50.0);
+        clock.advance(100);
+        observation.finish();
+
+        let curve = observation.speed_accuracy_curve(&reference, 1_000).unwrap();
+        assert_eq!(curve.samples.len(), 2);
+        assert!(curve.samples[0].completion > 0.4);
+        assert!(curve.samples[0].completion < curve.samples[1].completion);
+        assert_eq!(curve.samples[1].completion, 1.0);
+        assert_eq!(curve.samples[0].active_ms, 900);
+        assert_eq!(curve.samples[1].active_ms, 1_800);
+        // Roughly half the reference covered for half the time, all of
+        // it for the rest.
+        assert!(curve.auc > 0.5 && curve.auc < 0.9, "auc {}", curve.auc);
+    }
+
+    #[test]
+    fn an_empty_observation_yields_a_flat_curve() {
+        let reference = {
+            let mut image = Image::new(100, 100);
+            image.set_pixel(50, 50, [0, 0, 0, 255]);
+            image
+        };
+        let mut observation = Observation::start_with_clock(Arc::new(MockClock::new(0)));
+        observation.finish();
+        let curve = observation.speed_accuracy_curve(&reference, 1_000).unwrap();
+        assert_eq!(curve.samples.len(), 1);
+        assert_eq!(curve.samples[0].completion, 0.0);
+        assert_eq!(curve.auc, 0.0);
+    }
+
+    #[test]
+    fn finish_is_idempotent() {
+        let mut observation = Observation::start();
+        observation.finish();
+        let first = observation.duration_ms();
+        observation.finish();
+        assert_eq!(observation.duration_ms(), first);
+        assert!(observation.is_finished());
+    }
+}
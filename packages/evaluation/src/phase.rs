@@ -0,0 +1,377 @@
+//! Process phase segmentation.
+//!
+//! Instructors care how a drawing was made, not only how it scored: a
+//! sound process blocks in long construction strokes first, refines
+//! them, and saves small detailing marks for last. This module clusters
+//! a session's strokes by length and speed into those three phases and
+//! reports, for every contiguous run of same-phase strokes, how long it
+//! took and how much error it removed.
+
+use evaluator::{EvaluationError, EvaluatorConfig};
+use serde::{Deserialize, Serialize};
+
+use crate::image::Image;
+use crate::observation::{rasterize_strokes, Observation, Stroke};
+
+/// Iteration cap for the stroke-feature clustering; the three clusters
+/// settle long before this on real sessions.
+const CLUSTER_ITERATIONS: usize = 32;
+
+/// The process phase a stroke belongs to, inferred from its length and
+/// speed relative to the rest of the session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DrawingPhase {
+    /// Long, fast construction strokes laying out the big shapes.
+    BlockingIn,
+    /// Medium strokes correcting and firming up the construction.
+    Refinement,
+    /// Short, careful marks finishing small features.
+    Detailing,
+}
+
+/// One contiguous run of same-phase strokes.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PhaseSegment {
+    pub phase: DrawingPhase,
+    /// Index of the first stroke in the run.
+    pub first_stroke: usize,
+    /// Number of strokes in the run.
+    pub strokes: usize,
+    /// When the run started, in milliseconds since the session start.
+    pub started_ms: u64,
+    /// Pointer time from the run's first sample to its last.
+    pub duration_ms: u64,
+    pub mean_stroke_length: f64,
+    /// Mean stroke speed over the run, in px/s.
+    pub mean_speed: f64,
+    /// Top-5 error of everything drawn before the run.
+    pub error_before: f64,
+    /// Top-5 error once the run's strokes are on the canvas.
+    pub error_after: f64,
+    /// Fraction of the reference covered before the run.
+    pub completion_before: f64,
+    /// Fraction of the reference covered once the run is on the canvas.
+    pub completion_after: f64,
+}
+
+impl PhaseSegment {
+    /// How much top-5 error the run removed; negative when its strokes
+    /// landed off the reference and made the drawing worse. Strokes
+    /// never raise a score they merely fail to improve, so read this
+    /// together with [`Self::completion_gained`].
+    pub fn error_reduction(&self) -> f64 {
+        self.error_before - self.error_after
+    }
+
+    /// How much reference coverage the run added.
+    pub fn completion_gained(&self) -> f64 {
+        self.completion_after - self.completion_before
+    }
+}
+
+/// Phase breakdown of one session, from [`segment_phases`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PhaseReport {
+    /// The runs in drawing order.
+    pub segments: Vec<PhaseSegment>,
+    /// Whether the phases appeared in the canonical blocking-in →
+    /// refinement → detailing order, never stepping back.
+    pub canonical_order: bool,
+}
+
+/// Segments an observation into process phases and scores each run
+/// against the reference. Strokes are clustered on (length, speed) with
+/// a three-centroid k-means; the cluster with the longest strokes is
+/// blocking-in and the shortest is detailing. The reference's
+/// dimensions define the evaluation canvas.
+pub fn segment_phases(
+    observation: &Observation,
+    reference: &Image,
+) -> Result<PhaseReport, EvaluationError> {
+    let strokes = observation.strokes();
+    let features: Vec<(f64, f64)> = strokes.iter().map(stroke_features).collect();
+    let phases = cluster_phases(&features);
+
+    let mut runs: Vec<(DrawingPhase, usize, usize)> = Vec::new();
+    for (index, &phase) in phases.iter().enumerate() {
+        match runs.last_mut() {
+            Some((last, _, count)) if *last == phase => *count += 1,
+            _ => runs.push((phase, index, 1)),
+        }
+    }
+
+    // Blank canvas first, then the drawing as of the end of each run,
+    // scored in one replay pass.
+    let config = EvaluatorConfig {
+        canvas_width: reference.width(),
+        canvas_height: reference.height(),
+        ..EvaluatorConfig::default()
+    };
+    let mut frames = vec![rasterize_strokes(&[], reference.width(), reference.height())];
+    for &(_, first, count) in &runs {
+        frames.push(rasterize_strokes(
+            &strokes[..first + count],
+            reference.width(),
+            reference.height(),
+        ));
+    }
+    let reference_mask = reference.to_mask(config.transparent_background);
+    let scores = evaluator::evaluate_frames(reference_mask, &frames, config)?;
+
+    let started_at = observation.started_at_ms();
+    let segments = runs
+        .iter()
+        .enumerate()
+        .map(|(run, &(phase, first, count))| {
+            let members = &strokes[first..first + count];
+            let first_ms = members
+                .iter()
+                .filter_map(|stroke| stroke.points.first())
+                .map(|point| point.t_ms)
+                .min()
+                .unwrap_or(started_at);
+            let last_ms = members
+                .iter()
+                .filter_map(|stroke| stroke.points.last())
+                .map(|point| point.t_ms)
+                .max()
+                .unwrap_or(first_ms);
+            let run_features = &features[first..first + count];
+            PhaseSegment {
+                phase,
+                first_stroke: first,
+                strokes: count,
+                started_ms: first_ms.saturating_sub(started_at),
+                duration_ms: last_ms.saturating_sub(first_ms),
+                mean_stroke_length: run_features.iter().map(|&(length, _)| length).sum::<f64>()
+                    / count as f64,
+                mean_speed: run_features.iter().map(|&(_, speed)| speed).sum::<f64>()
+                    / count as f64,
+                error_before: scores[run].score,
+                error_after: scores[run + 1].score,
+                completion_before: scores[run].completion,
+                completion_after: scores[run + 1].completion,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let canonical_order = segments
+        .windows(2)
+        .all(|pair| pair[0].phase <= pair[1].phase);
+    Ok(PhaseReport {
+        segments,
+        canonical_order,
+    })
+}
+
+/// A stroke's clustering features: path length in pixels and mean speed
+/// in px/s. Single dots have both at zero.
+fn stroke_features(stroke: &Stroke) -> (f64, f64) {
+    let points = &stroke.points;
+    let length: f64 = points
+        .windows(2)
+        .map(|pair| (pair[1].x - pair[0].x).hypot(pair[1].y - pair[0].y))
+        .sum();
+    let duration_ms = match (points.first(), points.last()) {
+        (Some(first), Some(last)) => last.t_ms.saturating_sub(first.t_ms),
+        _ => 0,
+    };
+    let speed = if duration_ms == 0 {
+        0.0
+    } else {
+        length / duration_ms as f64 * 1_000.0
+    };
+    (length, speed)
+}
+
+/// Assigns a phase to every stroke: k-means with three centroids over
+/// the max-normalized features, seeded deterministically from the
+/// shortest, median and longest strokes, with clusters ranked into
+/// phases by their centroid's stroke length.
+fn cluster_phases(features: &[(f64, f64)]) -> Vec<DrawingPhase> {
+    if features.is_empty() {
+        return Vec::new();
+    }
+    let max_length = features.iter().map(|&(l, _)| l).fold(0.0, f64::max).max(1e-9);
+    let max_speed = features.iter().map(|&(_, s)| s).fold(0.0, f64::max).max(1e-9);
+    let normalized: Vec<(f64, f64)> = features
+        .iter()
+        .map(|&(length, speed)| (length / max_length, speed / max_speed))
+        .collect();
+
+    let mut by_size: Vec<usize> = (0..normalized.len()).collect();
+    by_size.sort_by(|&a, &b| {
+        (normalized[a].0 + normalized[a].1).total_cmp(&(normalized[b].0 + normalized[b].1))
+    });
+    let mut centroids = [
+        normalized[by_size[0]],
+        normalized[by_size[by_size.len() / 2]],
+        normalized[by_size[by_size.len() - 1]],
+    ];
+
+    let mut assignments = vec![0usize; normalized.len()];
+    for _ in 0..CLUSTER_ITERATIONS {
+        let mut changed = false;
+        for (index, &point) in normalized.iter().enumerate() {
+            let nearest = centroids
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| distance(point, **a).total_cmp(&distance(point, **b)))
+                .map(|(cluster, _)| cluster)
+                .unwrap_or(0);
+            if assignments[index] != nearest {
+                assignments[index] = nearest;
+                changed = true;
+            }
+        }
+        for (cluster, centroid) in centroids.iter_mut().enumerate() {
+            let members: Vec<(f64, f64)> = normalized
+                .iter()
+                .zip(&assignments)
+                .filter(|&(_, &assigned)| assigned == cluster)
+                .map(|(&point, _)| point)
+                .collect();
+            // An emptied cluster keeps its seed rather than collapsing.
+            if !members.is_empty() {
+                let count = members.len() as f64;
+                *centroid = (
+                    members.iter().map(|&(l, _)| l).sum::<f64>() / count,
+                    members.iter().map(|&(_, s)| s).sum::<f64>() / count,
+                );
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    // Longest-stroke cluster is blocking-in, shortest is detailing.
+    let mut ranked = [0, 1, 2];
+    ranked.sort_by(|&a, &b| centroids[b].0.total_cmp(&centroids[a].0));
+    let mut phase_of = [DrawingPhase::BlockingIn; 3];
+    for (rank, &cluster) in ranked.iter().enumerate() {
+        phase_of[cluster] = match rank {
+            0 => DrawingPhase::BlockingIn,
+            1 => DrawingPhase::Refinement,
+            _ => DrawingPhase::Detailing,
+        };
+    }
+    assignments
+        .into_iter()
+        .map(|cluster| phase_of[cluster])
+        .collect()
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::time::MockClock;
+
+    /// Draws a horizontal stroke of `length` px over `duration_ms`.
+    fn draw(
+        observation: &mut Observation,
+        clock: &MockClock,
+        at: (f64, f64),
+        length: f64,
+        duration_ms: u64,
+    ) {
+        observation.begin_stroke();
+        observation.add_point(at.0, at.1);
+        clock.advance(duration_ms);
+        observation.add_point(at.0 + length, at.1);
+        clock.advance(50);
+    }
+
+    fn line_reference() -> Image {
+        let mut image = Image::new(400, 400);
+        for x in 50..350 {
+            image.set_pixel(x, 200, [0, 0, 0, 255]);
+        }
+        image
+    }
+
+    #[test]
+    fn a_textbook_session_splits_into_the_three_phases() {
+        let clock = MockClock::new(0);
+        let mut observation = Observation::start_with_clock(Arc::new(clock.clone()));
+        // Two sweeping construction strokes, two medium corrections,
+        // then two short detail marks.
+        draw(&mut observation, &clock, (50.0, 190.0), 300.0, 300);
+        draw(&mut observation, &clock, (50.0, 210.0), 300.0, 300);
+        draw(&mut observation, &clock, (100.0, 200.0), 80.0, 400);
+        draw(&mut observation, &clock, (220.0, 200.0), 80.0, 400);
+        draw(&mut observation, &clock, (50.0, 200.0), 6.0, 200);
+        draw(&mut observation, &clock, (340.0, 200.0), 6.0, 200);
+
+        let report = segment_phases(&observation, &line_reference()).unwrap();
+        let phases: Vec<DrawingPhase> =
+            report.segments.iter().map(|segment| segment.phase).collect();
+        assert_eq!(
+            phases,
+            [
+                DrawingPhase::BlockingIn,
+                DrawingPhase::Refinement,
+                DrawingPhase::Detailing
+            ],
+            "{report:?}"
+        );
+        assert!(report.canonical_order);
+        assert_eq!(report.segments[0].first_stroke, 0);
+        assert_eq!(report.segments[0].strokes, 2);
+        assert_eq!(report.segments[2].first_stroke, 4);
+        assert!(report.segments[0].mean_stroke_length > report.segments[2].mean_stroke_length);
+    }
+
+    #[test]
+    fn segments_report_their_duration_and_progress() {
+        let clock = MockClock::new(0);
+        let mut observation = Observation::start_with_clock(Arc::new(clock.clone()));
+        // The construction stroke traces the reference exactly; two
+        // later marks land 10px off it.
+        draw(&mut observation, &clock, (50.0, 200.0), 299.0, 300);
+        draw(&mut observation, &clock, (100.0, 190.0), 5.0, 200);
+        draw(&mut observation, &clock, (250.0, 190.0), 5.0, 200);
+
+        let report = segment_phases(&observation, &line_reference()).unwrap();
+        let first = &report.segments[0];
+        assert_eq!(first.started_ms, 0);
+        assert_eq!(first.duration_ms, 300);
+        // Tracing the whole line covers the reference without error.
+        assert_eq!(first.error_after, 0.0);
+        assert_eq!(first.completion_before, 0.0);
+        assert!(first.completion_gained() > 0.99, "{first:?}");
+        // The stray marks add error rather than removing any.
+        let last = report.segments.last().unwrap();
+        assert!(last.error_reduction() < 0.0, "{last:?}");
+        assert_eq!(last.completion_gained(), 0.0);
+    }
+
+    #[test]
+    fn detailing_first_breaks_the_canonical_order() {
+        let clock = MockClock::new(0);
+        let mut observation = Observation::start_with_clock(Arc::new(clock.clone()));
+        draw(&mut observation, &clock, (50.0, 200.0), 6.0, 200);
+        draw(&mut observation, &clock, (70.0, 200.0), 6.0, 200);
+        draw(&mut observation, &clock, (50.0, 190.0), 300.0, 300);
+        draw(&mut observation, &clock, (100.0, 200.0), 80.0, 400);
+
+        let report = segment_phases(&observation, &line_reference()).unwrap();
+        assert!(!report.canonical_order, "{report:?}");
+        assert_eq!(report.segments[0].phase, DrawingPhase::Detailing);
+    }
+
+    #[test]
+    fn an_empty_session_reports_no_phases() {
+        let observation = Observation::start_with_clock(Arc::new(MockClock::new(0)));
+        let report = segment_phases(&observation, &line_reference()).unwrap();
+        assert!(report.segments.is_empty());
+        assert!(report.canonical_order);
+    }
+}
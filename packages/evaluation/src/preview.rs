@@ -0,0 +1,188 @@
+//! Coarse alignment preview for in-progress drawings.
+//!
+//! While the user is still drawing, the app wants a cheap "am I roughly
+//! in the right place?" signal without paying for the full evaluator.
+//! [`CoarseReference`] downsamples the reference mask once into a
+//! [`PREVIEW_SIZE`]² occupancy grid; each preview then only bins the
+//! observation's stroke points into the same grid — no rasterization,
+//! no flood fill — so a check costs well under a millisecond and can
+//! run on every pointer-up.
+
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+use crate::observation::Stroke;
+
+/// Side length of the downsampled occupancy grid.
+pub const PREVIEW_SIZE: usize = 64;
+
+/// The cheap placement estimate: all values are approximate by design
+/// and disagree with the full evaluator near cell boundaries.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AlignmentPreview {
+    /// Fraction of reference-occupied coarse cells the strokes touch.
+    pub coverage: f64,
+    /// Fraction of stroke-touched coarse cells with no reference ink.
+    pub stray: f64,
+    /// Stroke centroid minus reference centroid, in canvas pixels;
+    /// zero when either side is empty.
+    pub offset_x: f64,
+    pub offset_y: f64,
+}
+
+/// A reference downsampled for coarse previews. Build once per
+/// exercise and reuse across pointer events.
+pub struct CoarseReference {
+    cells: Array2<u8>,
+    occupied: usize,
+    centroid: Option<(f64, f64)>,
+    width: usize,
+    height: usize,
+}
+
+impl CoarseReference {
+    /// Downsamples a binary reference mask: a coarse cell is occupied
+    /// when any of its pixels are inked.
+    pub fn from_mask(mask: &Array2<u8>) -> Self {
+        let (height, width) = mask.dim();
+        let mut cells = Array2::zeros((PREVIEW_SIZE, PREVIEW_SIZE));
+        let mut sum = (0.0, 0.0);
+        let mut inked = 0usize;
+        for ((y, x), &on) in mask.indexed_iter() {
+            if on != 0 {
+                cells[cell_of(x, y, width, height)] = 1;
+                sum.0 += x as f64;
+                sum.1 += y as f64;
+                inked += 1;
+            }
+        }
+        let occupied = cells.iter().filter(|&&cell| cell != 0).count();
+        let centroid =
+            (inked > 0).then(|| (sum.0 / inked as f64, sum.1 / inked as f64));
+        Self {
+            cells,
+            occupied,
+            centroid,
+            width,
+            height,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Estimates placement of the strokes drawn so far. Points outside
+    /// the canvas clamp to the border cells.
+    pub fn preview(&self, strokes: &[Stroke]) -> AlignmentPreview {
+        let mut touched = Array2::<u8>::zeros((PREVIEW_SIZE, PREVIEW_SIZE));
+        let mut sum = (0.0, 0.0);
+        let mut points = 0usize;
+        for point in strokes.iter().flat_map(|stroke| &stroke.points) {
+            let x = point.x.clamp(0.0, (self.width.max(1) - 1) as f64);
+            let y = point.y.clamp(0.0, (self.height.max(1) - 1) as f64);
+            touched[cell_of(x as usize, y as usize, self.width, self.height)] = 1;
+            sum.0 += point.x;
+            sum.1 += point.y;
+            points += 1;
+        }
+        let covered = touched
+            .iter()
+            .zip(self.cells.iter())
+            .filter(|&(&drawn, &expected)| drawn != 0 && expected != 0)
+            .count();
+        let drawn = touched.iter().filter(|&&cell| cell != 0).count();
+        let coverage = if self.occupied == 0 {
+            0.0
+        } else {
+            covered as f64 / self.occupied as f64
+        };
+        let stray = if drawn == 0 {
+            0.0
+        } else {
+            (drawn - covered) as f64 / drawn as f64
+        };
+        let (offset_x, offset_y) = match (self.centroid, points) {
+            (Some((cx, cy)), 1..) => {
+                (sum.0 / points as f64 - cx, sum.1 / points as f64 - cy)
+            }
+            _ => (0.0, 0.0),
+        };
+        AlignmentPreview {
+            coverage,
+            stray,
+            offset_x,
+            offset_y,
+        }
+    }
+}
+
+/// The coarse cell containing pixel `(x, y)` of a `width`x`height`
+/// canvas, clamped to the grid.
+fn cell_of(x: usize, y: usize, width: usize, height: usize) -> (usize, usize) {
+    let column = (x * PREVIEW_SIZE / width.max(1)).min(PREVIEW_SIZE - 1);
+    let row = (y * PREVIEW_SIZE / height.max(1)).min(PREVIEW_SIZE - 1);
+    (row, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observation::Point;
+
+    fn horizontal_line_mask(y: usize, x0: usize, x1: usize) -> Array2<u8> {
+        let mut mask = Array2::zeros((512, 512));
+        for x in x0..x1 {
+            mask[(y, x)] = 1;
+        }
+        mask
+    }
+
+    fn stroke_along(y: f64, x0: f64, x1: f64) -> Stroke {
+        Stroke {
+            points: (0..50)
+                .map(|i| Point {
+                    x: x0 + (x1 - x0) * i as f64 / 49.0,
+                    y,
+                    t_ms: i * 10,
+                    pressure: 1.0,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn tracing_the_reference_previews_as_covered_and_centered() {
+        let reference = CoarseReference::from_mask(&horizontal_line_mask(250, 100, 400));
+        let preview = reference.preview(&[stroke_along(250.0, 100.0, 399.0)]);
+        assert!(preview.coverage > 0.9, "{preview:?}");
+        assert_eq!(preview.stray, 0.0);
+        assert!(preview.offset_x.abs() < 4.0, "{preview:?}");
+        assert!(preview.offset_y.abs() < 1.0, "{preview:?}");
+    }
+
+    #[test]
+    fn a_displaced_drawing_reports_its_offset_and_stray_ink() {
+        let reference = CoarseReference::from_mask(&horizontal_line_mask(250, 100, 400));
+        let preview = reference.preview(&[stroke_along(150.0, 100.0, 399.0)]);
+        assert_eq!(preview.coverage, 0.0);
+        assert_eq!(preview.stray, 1.0);
+        assert!((preview.offset_y + 100.0).abs() < 1.0, "{preview:?}");
+    }
+
+    #[test]
+    fn empty_sides_preview_as_zero_without_panicking() {
+        let empty = CoarseReference::from_mask(&Array2::zeros((512, 512)));
+        let preview = empty.preview(&[stroke_along(250.0, 100.0, 399.0)]);
+        assert_eq!(preview.coverage, 0.0);
+        assert_eq!((preview.offset_x, preview.offset_y), (0.0, 0.0));
+        let reference = CoarseReference::from_mask(&horizontal_line_mask(250, 100, 400));
+        let blank = reference.preview(&[]);
+        assert_eq!(blank.coverage, 0.0);
+        assert_eq!(blank.stray, 0.0);
+    }
+}
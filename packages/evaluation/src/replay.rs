@@ -0,0 +1,232 @@
+//! Deterministic stroke replays for streaming regression tests.
+//!
+//! The streaming path is the hardest part of the session layer to test:
+//! scores depend on the order pixels arrive and on when flushes happen.
+//! [`ReplayDriver`] feeds a recorded [`Observation`] into a
+//! [`StreamingEvaluator`] at accelerated simulated time — the injected
+//! [`MockClock`] advances by the recorded stroke deltas (optionally
+//! compressed), a seeded RNG supplies any timing jitter — and records
+//! the score after every stroke. Two replays with the same seed produce
+//! the same [`ReplaySample`] sequence, so regression tests can assert
+//! it exactly.
+
+use evaluator::{EvaluationError, StreamingEvaluator};
+
+use crate::observation::{rasterize_strokes, Observation};
+use crate::time::{Clock, MockClock};
+
+/// The evaluator's state after one replayed stroke.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplaySample {
+    /// Index of the stroke just ingested.
+    pub stroke: usize,
+    /// Simulated time when the stroke finished, in milliseconds.
+    pub at_ms: u64,
+    /// The live top-5 error after the stroke.
+    pub score: f64,
+    /// The coverage-based completion estimate after the stroke.
+    pub completion: f64,
+}
+
+/// Replays recorded strokes against a streaming evaluator under
+/// simulated time.
+#[derive(Debug, Clone)]
+pub struct ReplayDriver {
+    clock: MockClock,
+    /// Recorded stroke deltas are divided by this before advancing the
+    /// clock, so an hour-long session replays in simulated minutes.
+    speedup: u64,
+    /// Upper bound on the random delay added before each stroke.
+    jitter_ms: u64,
+    /// xorshift64 state; seeded, so jitter is reproducible.
+    rng: u64,
+}
+
+impl ReplayDriver {
+    /// A driver advancing `clock` in real recorded time, with no
+    /// jitter. The seed only matters once jitter is enabled.
+    pub fn new(clock: MockClock, seed: u64) -> Self {
+        Self {
+            clock,
+            speedup: 1,
+            jitter_ms: 0,
+            // xorshift has a fixed point at zero; nudge the seed off it.
+            rng: seed.max(1),
+        }
+    }
+
+    /// Compresses recorded time by `factor` (at least 1).
+    pub fn with_speedup(mut self, factor: u64) -> Self {
+        self.speedup = factor.max(1);
+        self
+    }
+
+    /// Adds up to `jitter_ms` of seeded random delay before each
+    /// stroke, for tests that must hold under timing wobble.
+    pub fn with_jitter(mut self, jitter_ms: u64) -> Self {
+        self.jitter_ms = jitter_ms;
+        self
+    }
+
+    /// Replays every stroke of `observation` into `streaming`, one
+    /// batch and flush per stroke, rasterized onto a `width` x `height`
+    /// canvas. The clock advances by each recorded inter-stroke and
+    /// intra-stroke delta (compressed by the speedup, plus jitter)
+    /// before the stroke is scored. Returns one sample per stroke.
+    pub fn replay(
+        &mut self,
+        observation: &Observation,
+        streaming: &mut StreamingEvaluator,
+        width: usize,
+        height: usize,
+    ) -> Result<Vec<ReplaySample>, EvaluationError> {
+        let mut samples = Vec::new();
+        let mut previous_end_ms = observation.started_at_ms();
+        for (index, stroke) in observation.strokes().iter().enumerate() {
+            let start_ms = stroke.points.first().map_or(previous_end_ms, |p| p.t_ms);
+            let end_ms = stroke.points.last().map_or(start_ms, |p| p.t_ms);
+            let jitter = self.next_jitter();
+            self.clock
+                .advance(start_ms.saturating_sub(previous_end_ms) / self.speedup + jitter);
+            let mask = rasterize_strokes(std::slice::from_ref(stroke), width, height);
+            let pixels: Vec<(usize, usize)> = mask
+                .indexed_iter()
+                .filter(|(_, &on)| on != 0)
+                .map(|(position, _)| position)
+                .collect();
+            streaming.add_observation_pixels(&pixels)?;
+            streaming.flush();
+            self.clock
+                .advance(end_ms.saturating_sub(start_ms) / self.speedup);
+            previous_end_ms = end_ms;
+            samples.push(ReplaySample {
+                stroke: index,
+                at_ms: self.clock.now_ms(),
+                score: streaming.current_score(),
+                completion: streaming.completion_estimate(),
+            });
+        }
+        Ok(samples)
+    }
+
+    /// The next jitter delay: xorshift64 over the seeded state, bounded
+    /// by the configured maximum.
+    fn next_jitter(&mut self) -> u64 {
+        if self.jitter_ms == 0 {
+            return 0;
+        }
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        self.rng % (self.jitter_ms + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use evaluator::{EvaluatorConfig, ReferenceModel, StreamingEvaluator};
+    use ndarray::Array2;
+
+    use super::*;
+
+    const SIZE: usize = 100;
+
+    fn reference_line() -> Array2<u8> {
+        let mut mask = Array2::zeros((SIZE, SIZE));
+        for x in 10..90 {
+            mask[(50, x)] = 1;
+        }
+        mask
+    }
+
+    /// A three-stroke session: the left half traced, a stray mark, the
+    /// right half traced — with recorded pauses between strokes.
+    fn recorded_session() -> Observation {
+        let clock = MockClock::new(1_000);
+        let mut observation = Observation::start_with_clock(Arc::new(clock.clone()));
+        let mut stroke = |from: (f64, f64), to: (f64, f64), duration_ms: u64| {
+            observation.begin_stroke();
+            observation.add_point(from.0, from.1);
+            clock.advance(duration_ms);
+            observation.add_point(to.0, to.1);
+            clock.advance(500);
+        };
+        stroke((10.0, 50.0), (50.0, 50.0), 400);
+        stroke((20.0, 80.0), (30.0, 80.0), 200);
+        stroke((50.0, 50.0), (90.0, 50.0), 400);
+        observation
+    }
+
+    fn streaming() -> StreamingEvaluator {
+        let model = ReferenceModel::new(reference_line(), EvaluatorConfig::default()).unwrap();
+        StreamingEvaluator::new(model)
+    }
+
+    #[test]
+    fn replays_with_the_same_seed_match_exactly() {
+        let observation = recorded_session();
+        let mut first_evaluator = streaming();
+        let first = ReplayDriver::new(MockClock::new(0), 42)
+            .with_jitter(20)
+            .replay(&observation, &mut first_evaluator, SIZE, SIZE)
+            .unwrap();
+        let mut second_evaluator = streaming();
+        let second = ReplayDriver::new(MockClock::new(0), 42)
+            .with_jitter(20)
+            .replay(&observation, &mut second_evaluator, SIZE, SIZE)
+            .unwrap();
+        assert_eq!(first, second);
+        // The sequence itself behaves: the stray second stroke raises
+        // the score, tracing never lowers it, completion only grows.
+        assert_eq!(first.len(), 3);
+        assert_eq!(first[0].score, 0.0);
+        assert!(first[1].score > 0.0);
+        assert!(first[2].completion > first[1].completion);
+        assert!(first.windows(2).all(|pair| pair[0].at_ms <= pair[1].at_ms));
+    }
+
+    #[test]
+    fn jitter_moves_timing_but_never_scores() {
+        let observation = recorded_session();
+        let mut first_evaluator = streaming();
+        let first = ReplayDriver::new(MockClock::new(0), 1)
+            .with_jitter(100)
+            .replay(&observation, &mut first_evaluator, SIZE, SIZE)
+            .unwrap();
+        let mut second_evaluator = streaming();
+        let second = ReplayDriver::new(MockClock::new(0), 2)
+            .with_jitter(100)
+            .replay(&observation, &mut second_evaluator, SIZE, SIZE)
+            .unwrap();
+        assert_ne!(
+            first.iter().map(|s| s.at_ms).collect::<Vec<_>>(),
+            second.iter().map(|s| s.at_ms).collect::<Vec<_>>()
+        );
+        for (a, b) in first.iter().zip(&second) {
+            assert_eq!(a.score, b.score);
+            assert_eq!(a.completion, b.completion);
+        }
+    }
+
+    #[test]
+    fn speedup_compresses_simulated_time_tenfold() {
+        let observation = recorded_session();
+        let mut real_evaluator = streaming();
+        let real = ReplayDriver::new(MockClock::new(0), 7)
+            .replay(&observation, &mut real_evaluator, SIZE, SIZE)
+            .unwrap();
+        let mut fast_evaluator = streaming();
+        let fast = ReplayDriver::new(MockClock::new(0), 7)
+            .with_speedup(10)
+            .replay(&observation, &mut fast_evaluator, SIZE, SIZE)
+            .unwrap();
+        // 400 + 500 + 200 + 500 + 400 recorded ms in total.
+        assert_eq!(real.last().unwrap().at_ms, 2_000);
+        assert_eq!(fast.last().unwrap().at_ms, 200);
+        for (a, b) in real.iter().zip(&fast) {
+            assert_eq!(a.score, b.score);
+        }
+    }
+}
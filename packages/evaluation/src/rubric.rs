@@ -0,0 +1,219 @@
+//! Weighted grading rubrics.
+//!
+//! A rubric turns several attempt metrics — top-5 error, coverage, line
+//! quality and time — into one final grade with a per-criterion
+//! breakdown. Rubrics are defined in JSON next to an exercise so
+//! instructors can reweight criteria without a code change.
+
+use serde::{Deserialize, Serialize};
+
+use crate::leaderboard::DrawingReport;
+
+/// Which attempt metric a criterion reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RubricMetric {
+    #[serde(rename = "top_5_error")]
+    Top5Error,
+    Coverage,
+    LineQuality,
+    DurationMs,
+}
+
+impl RubricMetric {
+    /// Reads this metric from a report. `None` when the report predates
+    /// the detail metrics.
+    fn value(self, report: &DrawingReport) -> Option<f64> {
+        match self {
+            Self::Top5Error => report.top_5_error,
+            Self::Coverage => report.coverage,
+            Self::LineQuality => report.line_quality,
+            Self::DurationMs => Some(report.duration_ms as f64),
+        }
+    }
+}
+
+/// One graded criterion: a metric, its weight, and the thresholds for
+/// full and zero credit. Credit falls linearly between the thresholds,
+/// so `full_credit < no_credit` grades lower-is-better metrics and the
+/// reverse ordering grades higher-is-better ones.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RubricCriterion {
+    pub metric: RubricMetric,
+    pub weight: f64,
+    /// Metric value at or better than this earns full credit.
+    pub full_credit: f64,
+    /// Metric value at or worse than this earns no credit.
+    pub no_credit: f64,
+}
+
+impl RubricCriterion {
+    /// Credit in `0..=1` for a metric value.
+    fn credit(&self, value: f64) -> f64 {
+        let fraction = (value - self.no_credit) / (self.full_credit - self.no_credit);
+        fraction.clamp(0.0, 1.0)
+    }
+}
+
+/// A weighted set of grading criteria, as loaded from `rubric.json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rubric {
+    pub criteria: Vec<RubricCriterion>,
+}
+
+/// One criterion's line in a [`RubricGrade`] breakdown.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CriterionGrade {
+    pub metric: RubricMetric,
+    /// The metric value read from the report; `None` when the report
+    /// does not record it, which earns no credit.
+    pub value: Option<f64>,
+    /// Credit earned, in `0..=1`.
+    pub credit: f64,
+    pub weight: f64,
+}
+
+/// Final grade with its per-criterion breakdown.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RubricGrade {
+    /// Weighted average credit, scaled to `0..=100`.
+    pub total: f64,
+    pub breakdown: Vec<CriterionGrade>,
+}
+
+impl Rubric {
+    /// Parses and validates a rubric from its JSON text.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let rubric: Self = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        rubric.validate()?;
+        Ok(rubric)
+    }
+
+    /// Checks the rubric is gradeable: at least one criterion, positive
+    /// weights, and distinct credit thresholds.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.criteria.is_empty() {
+            return Err("rubric needs at least one criterion".to_string());
+        }
+        for criterion in &self.criteria {
+            if criterion.weight <= 0.0 || !criterion.weight.is_finite() {
+                return Err(format!(
+                    "criterion weight must be positive, got {}",
+                    criterion.weight
+                ));
+            }
+            if criterion.full_credit == criterion.no_credit {
+                return Err(
+                    "full_credit and no_credit must differ to interpolate between".to_string(),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Grades one attempt: each criterion reads its metric from the
+    /// report and earns weighted credit; the total is the weighted
+    /// average scaled to `0..=100`.
+    pub fn grade(&self, report: &DrawingReport) -> RubricGrade {
+        let mut breakdown = Vec::with_capacity(self.criteria.len());
+        let mut earned = 0.0;
+        let mut total_weight = 0.0;
+        for criterion in &self.criteria {
+            let value = criterion.metric.value(report);
+            let credit = value.map_or(0.0, |v| criterion.credit(v));
+            earned += credit * criterion.weight;
+            total_weight += criterion.weight;
+            breakdown.push(CriterionGrade {
+                metric: criterion.metric,
+                value,
+                credit,
+                weight: criterion.weight,
+            });
+        }
+        RubricGrade {
+            total: earned / total_weight * 100.0,
+            breakdown,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_rubric() -> Rubric {
+        Rubric::from_json(
+            r#"{
+                "criteria": [
+                    { "metric": "top_5_error", "weight": 2.0, "full_credit": 1.0, "no_credit": 5.0 },
+                    { "metric": "coverage", "weight": 1.0, "full_credit": 1.0, "no_credit": 0.5 },
+                    { "metric": "duration_ms", "weight": 1.0, "full_credit": 30000.0, "no_credit": 120000.0 }
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    fn sample_report() -> DrawingReport {
+        DrawingReport {
+            exercise_id: "cat-01".into(),
+            user_id: "ana".into(),
+            score: 2.0,
+            duration_ms: 75_000,
+            completed_at_ms: 1_000,
+            top_5_error: Some(3.0),
+            coverage: Some(0.9),
+            line_quality: None,
+            speed: None,
+        }
+    }
+
+    #[test]
+    fn grades_interpolate_between_the_credit_thresholds() {
+        let grade = sample_rubric().grade(&sample_report());
+        // top-5 at 3.0 is halfway between 1.0 and 5.0; coverage 0.9 is
+        // 0.8 of the way from 0.5 to 1.0; 75s is halfway in time.
+        assert!((grade.breakdown[0].credit - 0.5).abs() < 1e-9);
+        assert!((grade.breakdown[1].credit - 0.8).abs() < 1e-9);
+        assert!((grade.breakdown[2].credit - 0.5).abs() < 1e-9);
+        let expected = (0.5 * 2.0 + 0.8 + 0.5) / 4.0 * 100.0;
+        assert!((grade.total - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn missing_metrics_earn_no_credit() {
+        let rubric = Rubric::from_json(
+            r#"{ "criteria": [
+                { "metric": "line_quality", "weight": 1.0, "full_credit": 1.0, "no_credit": 0.0 }
+            ] }"#,
+        )
+        .unwrap();
+        let grade = rubric.grade(&sample_report());
+        assert_eq!(grade.total, 0.0);
+        assert_eq!(grade.breakdown[0].value, None);
+    }
+
+    #[test]
+    fn out_of_range_values_are_clamped_to_the_credit_bounds() {
+        let rubric = sample_rubric();
+        let mut report = sample_report();
+        report.top_5_error = Some(0.0);
+        report.coverage = Some(0.1);
+        let grade = rubric.grade(&report);
+        assert_eq!(grade.breakdown[0].credit, 1.0);
+        assert_eq!(grade.breakdown[1].credit, 0.0);
+    }
+
+    #[test]
+    fn invalid_rubrics_are_rejected_with_the_reason() {
+        let error = Rubric::from_json(r#"{ "criteria": [] }"#).unwrap_err();
+        assert!(error.contains("at least one criterion"));
+        let error = Rubric::from_json(
+            r#"{ "criteria": [
+                { "metric": "coverage", "weight": 0.0, "full_credit": 1.0, "no_credit": 0.0 }
+            ] }"#,
+        )
+        .unwrap_err();
+        assert!(error.contains("weight must be positive"));
+    }
+}
@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::time::{default_clock, Clock};
+use crate::observation::Observation;
+
+/// A full exercise run: the user studies the reference, then draws.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    exercise_id: String,
+    created_at_ms: u64,
+    observation: Option<Observation>,
+    /// Time limit applied to each observation this session starts.
+    #[serde(default)]
+    time_limit_ms: Option<u64>,
+    /// Time source shared with the observations this session starts.
+    #[serde(skip, default = "default_clock")]
+    clock: Arc<dyn Clock>,
+}
+
+impl Session {
+    pub fn new(exercise_id: impl Into<String>) -> Self {
+        Self::with_clock(exercise_id, default_clock())
+    }
+
+    /// Creates a timed session: observations it starts carry the limit,
+    /// so overtime strokes can be excluded or penalised at scoring time.
+    pub fn with_time_limit(exercise_id: impl Into<String>, limit_ms: u64) -> Self {
+        let mut session = Self::new(exercise_id);
+        session.time_limit_ms = Some(limit_ms);
+        session
+    }
+
+    /// Creates a session timed from an injected clock, shared with every
+    /// observation it starts.
+    pub fn with_clock(exercise_id: impl Into<String>, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            exercise_id: exercise_id.into(),
+            created_at_ms: clock.now_ms(),
+            observation: None,
+            time_limit_ms: None,
+            clock,
+        }
+    }
+
+    /// Sets the time limit for observations started from now on.
+    pub fn set_time_limit(&mut self, limit_ms: u64) {
+        self.time_limit_ms = Some(limit_ms);
+    }
+
+    pub fn time_limit_ms(&self) -> Option<u64> {
+        self.time_limit_ms
+    }
+
+    pub fn exercise_id(&self) -> &str {
+        &self.exercise_id
+    }
+
+    pub fn created_at_ms(&self) -> u64 {
+        self.created_at_ms
+    }
+
+    /// Begins the drawing phase. Calling again restarts the observation.
+    pub fn start_drawing(&mut self) {
+        let mut observation = Observation::start_with_clock(self.clock.clone());
+        if let Some(limit) = self.time_limit_ms {
+            observation.set_time_limit(limit);
+        }
+        self.observation = Some(observation);
+    }
+
+    pub fn observation(&self) -> Option<&Observation> {
+        self.observation.as_ref()
+    }
+
+    pub fn observation_mut(&mut self) -> Option<&mut Observation> {
+        self.observation.as_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drawing_phase_creates_an_observation() {
+        let mut session = Session::new("cat-01");
+        assert!(session.observation().is_none());
+        session.start_drawing();
+        session.observation_mut().unwrap().add_point(1.0, 1.0);
+        assert_eq!(session.observation().unwrap().total_points(), 1);
+    }
+
+    #[test]
+    fn time_limit_propagates_to_the_observation() {
+        use crate::time::MockClock;
+
+        let clock = MockClock::new(0);
+        let mut session = Session::with_clock("cat-01", Arc::new(clock.clone()));
+        session.set_time_limit(1_000);
+        session.start_drawing();
+        let observation = session.observation().unwrap();
+        assert_eq!(observation.time_limit_ms(), Some(1_000));
+        clock.advance(1_500);
+        session.observation_mut().unwrap().add_point(1.0, 1.0);
+        assert_eq!(session.observation().unwrap().overtime_ms(), 500);
+    }
+}
@@ -0,0 +1,137 @@
+//! The crate's one time source.
+//!
+//! Timing code used to be split between `utils::current_time_ms`
+//! (which breaks on wasm, where `SystemTime` is unsupported) and the
+//! session clock. This module unifies them: [`now_ms`] picks the right
+//! backend per target at compile time, and the [`Clock`] trait stays
+//! injectable so tests can drive time by hand.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::JsCast;
+
+/// Current time in milliseconds from the target's native source: the
+/// system clock since the Unix epoch off wasm, `performance.now()`
+/// (origin-relative) on it. Both are monotonic enough for stroke
+/// timing, which only ever looks at differences.
+pub fn now_ms() -> u64 {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        use std::time::{SystemTime, UNIX_EPOCH};
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0)
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        performance_now_ms()
+    }
+}
+
+/// Source of wall-clock milliseconds, injected into the session layer so
+/// timing is testable without sleeping and portable to targets where
+/// `SystemTime` is unavailable.
+pub trait Clock: Send + Sync + std::fmt::Debug {
+    fn now_ms(&self) -> u64;
+}
+
+/// The target's native clock, backed by [`now_ms`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_ms(&self) -> u64 {
+        now_ms()
+    }
+}
+
+/// Manually advanced clock for deterministic tests. Clones share the
+/// same underlying time, so tests can keep a handle while the session
+/// owns another.
+#[derive(Debug, Clone, Default)]
+pub struct MockClock {
+    now: Arc<AtomicU64>,
+}
+
+impl MockClock {
+    pub fn new(start_ms: u64) -> Self {
+        Self {
+            now: Arc::new(AtomicU64::new(start_ms)),
+        }
+    }
+
+    pub fn advance(&self, ms: u64) {
+        self.now.fetch_add(ms, Ordering::SeqCst);
+    }
+
+    pub fn set(&self, ms: u64) {
+        self.now.store(ms, Ordering::SeqCst);
+    }
+}
+
+impl Clock for MockClock {
+    fn now_ms(&self) -> u64 {
+        self.now.load(Ordering::SeqCst)
+    }
+}
+
+/// Browser clock backed by `performance.now()`; on wasm this is the
+/// same backend [`now_ms`] uses, kept as a named type so bindings can
+/// pass it explicitly.
+#[cfg(target_arch = "wasm32")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PerformanceClock;
+
+#[cfg(target_arch = "wasm32")]
+impl Clock for PerformanceClock {
+    fn now_ms(&self) -> u64 {
+        performance_now_ms()
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn performance_now_ms() -> u64 {
+    // Look up globalThis.performance so this works in both window and
+    // worker contexts without depending on web-sys.
+    js_sys::Reflect::get(&js_sys::global(), &"performance".into())
+        .ok()
+        .and_then(|performance| {
+            js_sys::Reflect::get(&performance, &"now".into())
+                .ok()
+                .and_then(|now| now.dyn_into::<js_sys::Function>().ok())
+                .and_then(|now| now.call0(&performance).ok())
+        })
+        .and_then(|value| value.as_f64())
+        .map(|ms| ms as u64)
+        .unwrap_or(0)
+}
+
+/// The clock used when none is injected explicitly.
+pub(crate) fn default_clock() -> Arc<dyn Clock> {
+    Arc::new(SystemClock)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mock_clock_clones_share_time() {
+        let clock = MockClock::new(100);
+        let handle = clock.clone();
+        handle.advance(50);
+        assert_eq!(clock.now_ms(), 150);
+        handle.set(10);
+        assert_eq!(clock.now_ms(), 10);
+    }
+
+    #[test]
+    fn the_system_clock_matches_the_free_function() {
+        // Both read the same backend, so they agree to well within a
+        // second even across the two calls.
+        assert!(SystemClock.now_ms().abs_diff(now_ms()) < 1_000);
+    }
+}
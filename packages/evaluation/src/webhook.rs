@@ -0,0 +1,310 @@
+//! Webhook delivery for finished sessions.
+//!
+//! LMS integrations currently poll for results; a registered webhook
+//! turns that around — when a session finishes, or its score crosses a
+//! configured threshold, the [`DrawingReport`] is POSTed as JSON to the
+//! registered URL. Delivery uses a hand-rolled HTTP/1.1 client over
+//! `TcpStream` so the server build stays dependency-free; plain `http`
+//! URLs only, which covers the sidecar/localhost endpoints LMS bridges
+//! run. Not compiled for wasm, where the browser owns the network.
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::leaderboard::DrawingReport;
+
+/// When a registered webhook fires.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookTrigger {
+    /// Every finished session.
+    SessionFinished,
+    /// A finished session whose score crossed below the threshold —
+    /// scores are badness-style, so this means "became good enough".
+    ScoreBelow(f64),
+}
+
+/// One registered webhook endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    /// Plain-http URL the report is POSTed to.
+    pub url: String,
+    pub trigger: WebhookTrigger,
+    /// Additional attempts after the first failure.
+    pub max_retries: u32,
+    /// Wait before the first retry; doubles on each further retry.
+    pub initial_backoff_ms: u64,
+}
+
+impl WebhookConfig {
+    /// A finished-session hook with the default 3 retries starting at
+    /// 250ms backoff.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            trigger: WebhookTrigger::SessionFinished,
+            max_retries: 3,
+            initial_backoff_ms: 250,
+        }
+    }
+}
+
+/// Outcome of delivering one report to one endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub url: String,
+    /// Connection attempts made, including the successful one.
+    pub attempts: u32,
+    pub delivered: bool,
+    /// The final attempt's failure, when delivery gave up.
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Holds the registered webhooks and delivers reports to the ones
+/// whose trigger matches.
+#[derive(Debug, Clone, Default)]
+pub struct WebhookEmitter {
+    hooks: Vec<WebhookConfig>,
+}
+
+impl WebhookEmitter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, config: WebhookConfig) {
+        self.hooks.push(config);
+    }
+
+    /// Delivers `report` to every webhook whose trigger matches a
+    /// finished session. `previous_score` is the session's last
+    /// reported score, used to detect threshold crossings; pass `None`
+    /// for a first report. Blocks through retries; callers on a
+    /// latency-sensitive path should emit from their queue worker.
+    pub fn session_finished(
+        &self,
+        report: &DrawingReport,
+        previous_score: Option<f64>,
+    ) -> Vec<WebhookDelivery> {
+        let body = serde_json::to_vec(report).expect("report serializes to JSON");
+        self.hooks
+            .iter()
+            .filter(|hook| match hook.trigger {
+                WebhookTrigger::SessionFinished => true,
+                WebhookTrigger::ScoreBelow(threshold) => {
+                    report.score < threshold
+                        && previous_score.is_none_or(|previous| previous >= threshold)
+                }
+            })
+            .map(|hook| deliver(hook, &body))
+            .collect()
+    }
+}
+
+/// POSTs `body` to the hook's URL, retrying with doubling backoff.
+fn deliver(hook: &WebhookConfig, body: &[u8]) -> WebhookDelivery {
+    let mut attempts = 0;
+    let mut backoff = Duration::from_millis(hook.initial_backoff_ms);
+    loop {
+        attempts += 1;
+        match post_json(&hook.url, body) {
+            Ok(()) => {
+                return WebhookDelivery {
+                    url: hook.url.clone(),
+                    attempts,
+                    delivered: true,
+                    error: None,
+                }
+            }
+            Err(error) => {
+                if attempts > hook.max_retries {
+                    return WebhookDelivery {
+                        url: hook.url.clone(),
+                        attempts,
+                        delivered: false,
+                        error: Some(error),
+                    };
+                }
+                std::thread::sleep(backoff);
+                backoff *= 2;
+            }
+        }
+    }
+}
+
+/// One HTTP/1.1 POST with a JSON body; success is any 2xx status.
+fn post_json(url: &str, body: &[u8]) -> Result<(), String> {
+    let (host, port, path) = parse_http_url(url)?;
+    let mut stream = TcpStream::connect((host.as_str(), port))
+        .map_err(|error| format!("connect to {host}:{port} failed: {error}"))?;
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .and_then(|()| stream.set_write_timeout(Some(Duration::from_secs(10))))
+        .map_err(|error| error.to_string())?;
+    let request = format!(
+        "POST {path} HTTP/1.1\r\nHost: {host}\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    stream
+        .write_all(request.as_bytes())
+        .and_then(|()| stream.write_all(body))
+        .map_err(|error| format!("send failed: {error}"))?;
+    let mut response = String::new();
+    stream
+        .take(512)
+        .read_to_string(&mut response)
+        .map_err(|error| format!("read failed: {error}"))?;
+    let status = response
+        .strip_prefix("HTTP/1.1 ")
+        .or_else(|| response.strip_prefix("HTTP/1.0 "))
+        .and_then(|rest| rest.get(..3))
+        .ok_or_else(|| format!("malformed response: {:?}", response.lines().next()))?;
+    if status.starts_with('2') {
+        Ok(())
+    } else {
+        Err(format!("endpoint returned status {status}"))
+    }
+}
+
+/// Splits a plain-http URL into host, port and path. `https` is
+/// rejected rather than silently sent in clear.
+fn parse_http_url(url: &str) -> Result<(String, u16, String), String> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| format!("unsupported URL (plain http only): {url}"))?;
+    let (authority, path) = match rest.find('/') {
+        Some(index) => (&rest[..index], &rest[index..]),
+        None => (rest, "/"),
+    };
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (
+            host,
+            port.parse()
+                .map_err(|_| format!("invalid port in URL: {url}"))?,
+        ),
+        None => (authority, 80),
+    };
+    if host.is_empty() {
+        return Err(format!("missing host in URL: {url}"));
+    }
+    Ok((host.to_string(), port, path.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufRead;
+    use std::net::TcpListener;
+
+    fn report(score: f64) -> DrawingReport {
+        DrawingReport {
+            exercise_id: "cat-01".to_string(),
+            user_id: "ana".to_string(),
+            score,
+            duration_ms: 60_000,
+            completed_at_ms: 1_700_000_000_000,
+            top_5_error: Some(score),
+            coverage: Some(0.9),
+            line_quality: None,
+            speed: None,
+        }
+    }
+
+    /// Accepts one connection, asserts the request looks like our POST
+    /// and answers with `status`. Returns the received body.
+    fn one_shot_server(status: &'static str) -> (String, std::thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hooks/finished", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = std::io::BufReader::new(&mut stream);
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            assert!(line.starts_with("POST /hooks/finished HTTP/1.1"), "{line}");
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if let Some(value) = header.strip_prefix("Content-Length: ") {
+                    content_length = value.trim().parse().unwrap();
+                }
+                if header == "\r\n" {
+                    break;
+                }
+            }
+            let mut body = vec![0u8; content_length];
+            reader.read_exact(&mut body).unwrap();
+            stream
+                .write_all(format!("HTTP/1.1 {status}\r\nContent-Length: 0\r\n\r\n").as_bytes())
+                .unwrap();
+            String::from_utf8(body).unwrap()
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn finished_sessions_post_the_report_json() {
+        let (url, server) = one_shot_server("200 OK");
+        let mut emitter = WebhookEmitter::new();
+        emitter.register(WebhookConfig::new(url));
+        let deliveries = emitter.session_finished(&report(3.5), None);
+        assert_eq!(deliveries.len(), 1);
+        assert!(deliveries[0].delivered, "{deliveries:?}");
+        assert_eq!(deliveries[0].attempts, 1);
+        let body = server.join().unwrap();
+        let posted: DrawingReport = serde_json::from_str(&body).unwrap();
+        assert_eq!(posted, report(3.5));
+    }
+
+    #[test]
+    fn unreachable_endpoints_exhaust_their_retries() {
+        // Bind-then-drop guarantees nothing listens on the port.
+        let port = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        let mut emitter = WebhookEmitter::new();
+        emitter.register(WebhookConfig {
+            max_retries: 2,
+            initial_backoff_ms: 1,
+            ..WebhookConfig::new(format!("http://127.0.0.1:{port}/hooks"))
+        });
+        let deliveries = emitter.session_finished(&report(3.5), None);
+        assert_eq!(deliveries.len(), 1);
+        assert!(!deliveries[0].delivered);
+        assert_eq!(deliveries[0].attempts, 3);
+        assert!(deliveries[0].error.is_some());
+    }
+
+    #[test]
+    fn score_threshold_hooks_fire_only_on_the_crossing() {
+        let mut emitter = WebhookEmitter::new();
+        emitter.register(WebhookConfig {
+            trigger: WebhookTrigger::ScoreBelow(5.0),
+            ..WebhookConfig::new("http://127.0.0.1:9/unused")
+        });
+        // Still above the threshold: nothing fires.
+        assert!(emitter.session_finished(&report(6.0), None).is_empty());
+        // Already below on the previous report: no re-fire.
+        assert!(emitter.session_finished(&report(3.0), Some(2.0)).is_empty());
+        // Crossing from above to below fires (and fails to deliver,
+        // which is fine — we only assert the trigger here).
+        let deliveries = emitter.session_finished(&report(3.0), Some(6.0));
+        assert_eq!(deliveries.len(), 1);
+    }
+
+    #[test]
+    fn only_plain_http_urls_are_accepted() {
+        assert!(parse_http_url("https://lms.example/hook").is_err());
+        assert!(parse_http_url("http:///missing-host").is_err());
+        let (host, port, path) = parse_http_url("http://lms.example/hook").unwrap();
+        assert_eq!((host.as_str(), port, path.as_str()), ("lms.example", 80, "/hook"));
+        let (_, port, path) = parse_http_url("http://localhost:8080").unwrap();
+        assert_eq!((port, path.as_str()), (8080, "/"));
+    }
+}